@@ -1,5 +1,5 @@
 #![no_std]
-use soroban_sdk::{contract, contractimpl, contracttype, token, Address, Env, log, Symbol};
+use soroban_sdk::{contract, contractimpl, contracttype, token, xdr::ToXdr, Address, BytesN, Env, log, Symbol};
 
 #[contracttype]
 #[derive(Clone)]
@@ -8,6 +8,8 @@ pub enum DataKey {
     HighestBidder,
     HighestBid,
     IsSettle,
+    BidKey(Address), // ed25519 key a bidder signs relayed bids with
+    Nonce(Address),  // Next relayed-bid nonce per bidder
 }
 
 #[contracttype]
@@ -21,6 +23,18 @@ pub struct AuctionDetails {
     pub end_time: u64,
 }
 
+/// Payload a bidder signs off-chain for a relayed bid.
+/// The signature covers sha256 of its XDR encoding.
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct RelayedBid {
+    pub auction: Address, // this contract, so signatures can't be replayed elsewhere
+    pub bidder: Address,
+    pub amount: i128,
+    pub nonce: u64,
+    pub expiry: u64, // last ledger timestamp the bid may be submitted at
+}
+
 #[contract]
 pub struct AuctionContract;
 
@@ -42,7 +56,7 @@ impl AuctionContract {
         }
         seller.require_auth();
 
-        if asset_amount <= 0 || reserve_price < 0 || duration <= 0 {
+        if asset_amount <= 0 || reserve_price < 0 || duration == 0 {
             panic!("Invalid auction parameters");
         }
 
@@ -71,46 +85,52 @@ impl AuctionContract {
     /// Place a bid on the auction.
     pub fn place_bid(env: Env, bidder: Address, amount: i128) {
         bidder.require_auth();
+        Self::accept_bid(&env, &bidder, amount, false);
+    }
 
-        let details: AuctionDetails = env.storage().persistent().get(&DataKey::AuctionInfo).expect("Auction not found");
-        let is_settled: bool = env.storage().persistent().get(&DataKey::IsSettle).unwrap_or(false);
-
-        if is_settled {
-            panic!("Auction already settled");
-        }
-
-        if env.ledger().timestamp() >= details.end_time {
-            panic!("Auction has ended");
-        }
+    /// Register the ed25519 public key a bidder signs relayed bids with.
+    /// The bidder's authorization can itself be submitted by a relayer.
+    pub fn register_bid_key(env: Env, bidder: Address, public_key: BytesN<32>) {
+        bidder.require_auth();
+        env.storage().persistent().set(&DataKey::BidKey(bidder.clone()), &public_key);
+        log!(&env, "Bid key registered for {}", bidder);
+    }
 
-        if amount < details.reserve_price {
-            panic!("Bid lower than reserve price");
-        }
+    /// Submit a bid signed off-chain by the bidder, so bidders without XLM
+    /// for fees can participate. Anyone can relay; the relayer pays the fees.
+    /// Funds are pulled with `transfer_from`, so the bidder must have approved
+    /// this contract for at least `amount` of the bid token.
+    pub fn place_relayed_bid(
+        env: Env,
+        bidder: Address,
+        amount: i128,
+        nonce: u64,
+        expiry: u64,
+        signature: BytesN<64>,
+    ) {
+        let public_key: BytesN<32> = env.storage().persistent().get(&DataKey::BidKey(bidder.clone())).expect("No bid key registered");
 
-        let current_highest_bid: i128 = env.storage().persistent().get(&DataKey::HighestBid).unwrap_or(0);
-        if amount <= current_highest_bid {
-            panic!("Bid must be higher than current highest bid");
+        if env.ledger().timestamp() > expiry {
+            panic!("Signed bid has expired");
         }
 
-        // Transfer funds from bidder to contract
-        let bid_client = token::Client::new(&env, &details.bid_token);
-        bid_client.transfer(&bidder, &env.current_contract_address(), &amount);
-
-        // Refund the previous highest bidder
-        if let Some(previous_bidder) = env.storage().persistent().get::<_, Address>(&DataKey::HighestBidder) {
-            bid_client.transfer(&env.current_contract_address(), &previous_bidder, &current_highest_bid);
+        let expected_nonce: u64 = env.storage().persistent().get(&DataKey::Nonce(bidder.clone())).unwrap_or(0);
+        if nonce != expected_nonce {
+            panic!("Invalid nonce");
         }
 
-        // Store new highest bid
-        env.storage().persistent().set(&DataKey::HighestBidder, &bidder);
-        env.storage().persistent().set(&DataKey::HighestBid, &amount);
-
-        env.events().publish(
-            (Symbol::new(&env, "bid"), bidder.clone()),
-            amount
-        );
+        let payload = RelayedBid {
+            auction: env.current_contract_address(),
+            bidder: bidder.clone(),
+            amount,
+            nonce,
+            expiry,
+        };
+        let digest = env.crypto().sha256(&payload.to_xdr(&env));
+        env.crypto().ed25519_verify(&public_key, &digest.into(), &signature);
 
-        log!(&env, "New highest bid: {} by {}", amount, bidder);
+        env.storage().persistent().set(&DataKey::Nonce(bidder.clone()), &(nonce + 1));
+        Self::accept_bid(&env, &bidder, amount, true);
     }
 
     /// Settle the auction after it has ended.
@@ -153,7 +173,7 @@ impl AuctionContract {
     /// Withdraw funds if the auction failed or user was outbid (alternate pattern).
     /// Note: In this implementation, outbid players are refunded automatically during place_bid.
     /// This function is a placeholder for more complex pull-patterns.
-    pub fn withdraw(env: Env, _user: Address) {
+    pub fn withdraw(_env: Env, _user: Address) {
         panic!("Immediate refund pattern in use. No funds to withdraw manually.");
     }
 
@@ -168,4 +188,53 @@ impl AuctionContract {
         let bid = env.storage().persistent().get(&DataKey::HighestBid).unwrap_or(0);
         (bidder, bid)
     }
+
+    /// Validate and record a bid, refunding the previous highest bidder.
+    /// Relayed bids pull funds through the bidder's allowance.
+    fn accept_bid(env: &Env, bidder: &Address, amount: i128, relayed: bool) {
+        let details: AuctionDetails = env.storage().persistent().get(&DataKey::AuctionInfo).expect("Auction not found");
+        let is_settled: bool = env.storage().persistent().get(&DataKey::IsSettle).unwrap_or(false);
+
+        if is_settled {
+            panic!("Auction already settled");
+        }
+
+        if env.ledger().timestamp() >= details.end_time {
+            panic!("Auction has ended");
+        }
+
+        if amount < details.reserve_price {
+            panic!("Bid lower than reserve price");
+        }
+
+        let current_highest_bid: i128 = env.storage().persistent().get(&DataKey::HighestBid).unwrap_or(0);
+        if amount <= current_highest_bid {
+            panic!("Bid must be higher than current highest bid");
+        }
+
+        // Transfer funds from bidder to contract
+        let bid_client = token::Client::new(env, &details.bid_token);
+        let contract = env.current_contract_address();
+        if relayed {
+            bid_client.transfer_from(&contract, bidder, &contract, &amount);
+        } else {
+            bid_client.transfer(bidder, &contract, &amount);
+        }
+
+        // Refund the previous highest bidder
+        if let Some(previous_bidder) = env.storage().persistent().get::<_, Address>(&DataKey::HighestBidder) {
+            bid_client.transfer(&env.current_contract_address(), &previous_bidder, &current_highest_bid);
+        }
+
+        // Store new highest bid
+        env.storage().persistent().set(&DataKey::HighestBidder, bidder);
+        env.storage().persistent().set(&DataKey::HighestBid, &amount);
+
+        env.events().publish(
+            (Symbol::new(env, "bid"), bidder.clone()),
+            amount
+        );
+
+        log!(env, "New highest bid: {} by {}", amount, bidder);
+    }
 }
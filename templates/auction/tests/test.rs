@@ -1,7 +1,8 @@
 #![cfg(test)]
 
-use auction_contract::{AuctionContract, AuctionContractClient};
-use soroban_sdk::{testutils::{Address as _, Ledger}, token, Address, Env};
+use auction_contract::{AuctionContract, AuctionContractClient, RelayedBid};
+use ed25519_dalek::{Signer, SigningKey};
+use soroban_sdk::{testutils::{Address as _, Ledger}, token, xdr::ToXdr, Address, BytesN, Env};
 
 fn setup_test(
     env: &Env,
@@ -130,3 +131,105 @@ fn test_settle_too_early() {
 
     client.settle();
 }
+
+// Sign a relayed bid the way a bidder's wallet would off-chain
+fn sign_bid(
+    env: &Env,
+    key: &SigningKey,
+    client: &AuctionContractClient,
+    bidder: &Address,
+    amount: i128,
+    nonce: u64,
+    expiry: u64,
+) -> BytesN<64> {
+    let payload = RelayedBid {
+        auction: client.address.clone(),
+        bidder: bidder.clone(),
+        amount,
+        nonce,
+        expiry,
+    };
+    let digest = env.crypto().sha256(&payload.to_xdr(env)).to_array();
+    BytesN::from_array(env, &key.sign(&digest).to_bytes())
+}
+
+// Auction with a bidder who registered a bid key and approved the contract
+fn setup_relayed<'a>(
+    env: &'a Env,
+) -> (Address, SigningKey, token::Client<'a>, AuctionContractClient<'a>) {
+    let (seller, bidder, _, asset_token_id, bid_token_id, _asset_token, bid_token, client) =
+        setup_test(env);
+
+    mint_stellar(env, &asset_token_id, &seller, 1);
+    client.create_auction(&seller, &asset_token_id, &1, &bid_token_id, &10, &3600);
+
+    let key = SigningKey::from_bytes(&[7u8; 32]);
+    client.register_bid_key(&bidder, &BytesN::from_array(env, &key.verifying_key().to_bytes()));
+    mint_stellar(env, &bid_token_id, &bidder, 100);
+    bid_token.approve(&bidder, &client.address, &100, &1000);
+
+    (bidder, key, bid_token, client)
+}
+
+#[test]
+fn test_relayed_bid() {
+    let env = Env::default();
+    let (bidder, key, bid_token, client) = setup_relayed(&env);
+
+    let signature = sign_bid(&env, &key, &client, &bidder, 40, 0, 100);
+    client.place_relayed_bid(&bidder, &40, &0, &100, &signature);
+
+    assert_eq!(client.get_highest_bid(), (Some(bidder.clone()), 40));
+    assert_eq!(bid_token.balance(&bidder), 60);
+    assert_eq!(bid_token.allowance(&bidder, &client.address), 60);
+
+    // The next bid needs the next nonce
+    let signature = sign_bid(&env, &key, &client, &bidder, 50, 1, 100);
+    client.place_relayed_bid(&bidder, &50, &1, &100, &signature);
+    assert_eq!(client.get_highest_bid(), (Some(bidder.clone()), 50));
+    // Bidder got their first bid back when outbidding themselves
+    assert_eq!(bid_token.balance(&bidder), 50);
+}
+
+#[test]
+#[should_panic(expected = "Invalid nonce")]
+fn test_relayed_bid_replay_fails() {
+    let env = Env::default();
+    let (bidder, key, _bid_token, client) = setup_relayed(&env);
+
+    let signature = sign_bid(&env, &key, &client, &bidder, 40, 0, 100);
+    client.place_relayed_bid(&bidder, &40, &0, &100, &signature);
+    client.place_relayed_bid(&bidder, &40, &0, &100, &signature);
+}
+
+#[test]
+#[should_panic(expected = "Signed bid has expired")]
+fn test_expired_relayed_bid_fails() {
+    let env = Env::default();
+    let (bidder, key, _bid_token, client) = setup_relayed(&env);
+
+    let signature = sign_bid(&env, &key, &client, &bidder, 40, 0, 100);
+    env.ledger().with_mut(|li| li.timestamp += 101);
+    client.place_relayed_bid(&bidder, &40, &0, &100, &signature);
+}
+
+#[test]
+#[should_panic]
+fn test_relayed_bid_with_tampered_amount_fails() {
+    let env = Env::default();
+    let (bidder, key, _bid_token, client) = setup_relayed(&env);
+
+    let signature = sign_bid(&env, &key, &client, &bidder, 40, 0, 100);
+    client.place_relayed_bid(&bidder, &90, &0, &100, &signature);
+}
+
+#[test]
+#[should_panic(expected = "No bid key registered")]
+fn test_relayed_bid_without_key_fails() {
+    let env = Env::default();
+    let (_bidder, key, _bid_token, client) = setup_relayed(&env);
+    let stranger = Address::generate(&env);
+
+    let signature = sign_bid(&env, &key, &client, &stranger, 40, 0, 100);
+    client.place_relayed_bid(&stranger, &40, &0, &100, &signature);
+}
@@ -1,18 +1,83 @@
 //! # Escrow Contract Template
 //!
 //! A configurable escrow contract for Soroban supporting:
-//! - Deposits by a payer into escrow cases
-//! - Time-based release constraints
+//! - Deposits by a payer into escrow cases, held in any token contract
+//! - Optional payee acceptance window before a case becomes binding
+//! - Cases bound to an off-chain agreement by hash and optional URI
+//! - Time-based release constraints, or linear vesting after the release time
 //! - Conditional release or refund by authorized parties
 //! - Multi-party approver requirements
+//! - Payee reassignment by mutual consent, or by the arbiter in a dispute
+//! - Disputes settled by the arbiter with an arbitrary payer/payee split, or
+//!   by a split the payer and payee negotiate themselves
+//! - Optional staking of locked funds, with rewards shared out on close
+//! - Storage TTLs that outlive each open case's deadlines, and pruning of
+//!   long-closed cases
+//! - Optional admin-configured protocol fee, with an exemption list
+//! - Lifecycle events for indexers and marketplaces, and an on-chain audit
+//!   trail per case
+//! - Paginated lookup of cases by payer, payee, or arbiter
 //!
 //! Template: escrow
 //! Category: escrow
 //! Version: 0.1.0
 
 #![no_std]
+// `deposit` takes each core escrow term as its own argument, and the
+// generated client mirrors it
+#![allow(clippy::too_many_arguments)]
 
-use soroban_sdk::{contract, contractimpl, contracttype, Address, Env, Vec};
+use soroban_sdk::{
+    auth::{ContractContext, InvokerContractAuthEntry, SubContractInvocation},
+    contract, contractclient, contracterror, contractimpl, contracttype, symbol_short, token, vec,
+    xdr::ToXdr,
+    Address, BytesN, Env, IntoVal, String, Symbol, Vec,
+};
+
+/// The parts of the staking template's interface escrow uses to earn yield
+/// on locked funds. Any contract with these entrypoints works.
+#[contractclient(name = "StakingClient")]
+pub trait Staking {
+    fn stake(env: Env, user: Address, amount: i128, lock_duration: u64);
+    fn unstake(env: Env, user: Address, amount: i128);
+    fn claim_rewards(env: Env, user: Address);
+}
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[repr(u32)]
+pub enum EscrowError {
+    NotFound = 1,
+    NotPending = 2,
+    NotAParty = 3,
+    DuplicateApproval = 4,
+    ReleaseTimeNotReached = 5,
+    InvalidAmount = 6,
+    DuplicateParty = 7,
+    InvalidApprovals = 8,
+    ReleaseTimeInPast = 9,
+    DisputeFeeTooHigh = 10,
+    NotDisputed = 11,
+    NotDisputant = 12,
+    NotArbiter = 13,
+    FeeExceedsAgreed = 14,
+    SplitExceedsAmount = 15,
+    InvalidFee = 16,
+    NotOffered = 17,
+    AcceptWindowClosed = 18,
+    AcceptWindowOpen = 19,
+    NotVesting = 20,
+    NothingVested = 21,
+    TermsUriTooLong = 22,
+    YieldOverflow = 23,
+    InvalidBatch = 24,
+    AlreadyInitialized = 25,
+    NotInitialized = 26,
+    NoSettlement = 27,
+    NotCounterparty = 28,
+    SettlementMismatch = 29,
+    StakingUnavailable = 30,
+}
 
 #[contracttype]
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
@@ -20,6 +85,140 @@ pub enum EscrowStatus {
     Pending,
     Released,
     Refunded,
+    Disputed,
+    Resolved,
+    /// Deposited, waiting for the payee to accept
+    Offered,
+    /// Offer lapsed and the payer took the funds back
+    Withdrawn,
+    /// Payer and payee agreed on a split without the arbiter
+    Settled,
+}
+
+/// Which side of a case a participant lookup covers.
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Role {
+    Payer,
+    Payee,
+    Arbiter,
+}
+
+/// Optional terms for `deposit`. All zero gives a plain escrow: no fees,
+/// binding immediately, and paid out in full on release.
+#[contracttype]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct EscrowOptions {
+    /// Maximum fee the arbiter may take when resolving a dispute
+    pub dispute_fee: u128,
+    /// Arbiter's cut of a release/refund they approved, in basis points
+    pub arbiter_fee_bps: u32,
+    /// Seconds the payee has to `accept`; 0 makes the case binding immediately
+    pub accept_window: u64,
+    /// Seconds over which the amount vests to the payee after `release_after`;
+    /// 0 keeps all-or-nothing release
+    pub vesting_duration: u64,
+    /// Where the agreement behind `terms_hash` can be fetched (IPFS, HTTPS, ...)
+    pub terms_uri: Option<String>,
+    /// Stake the locked funds while the case is open
+    pub yield_mode: YieldMode,
+}
+
+/// Whether a case's funds earn staking rewards while locked.
+#[contracttype]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub enum YieldMode {
+    #[default]
+    Idle,
+    Staked(YieldConfig),
+}
+
+/// Where a yield-bearing case stakes its funds and who gets the rewards.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct YieldConfig {
+    /// Staking contract; it must stake and pay rewards in the case's token
+    pub staking: Address,
+    /// Payee's share of the rewards in basis points; the payer gets the rest
+    pub payee_bps: u32,
+    /// If set, receives all rewards instead of the payer/payee split
+    pub collector: Option<Address>,
+}
+
+/// A case's stake in the shared position this contract holds per staking
+/// contract. All zero for `YieldMode::Idle` cases.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct YieldPosition {
+    pub mode: YieldMode,
+    /// Principal still staked for this case
+    pub staked: u128,
+    /// Pool rewards already accounted for `staked` (`staked * acc_per_share`)
+    pub reward_debt: u128,
+    /// Rewards earned so far, paid out when the case closes
+    pub earned: u128,
+}
+
+/// Operator fee taken from what the payee receives on release.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ProtocolFee {
+    pub bps: u32,
+    pub recipient: Address,
+}
+
+/// This contract's combined position in one staking contract.
+#[contracttype]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct YieldPool {
+    pub principal: u128,
+    /// Rewards per staked unit, scaled by `ACC_SCALE`
+    pub acc_per_share: u128,
+}
+
+/// A step in a case's lifecycle, as kept in its audit trail.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum EscrowAction {
+    Deposited,
+    Accepted,
+    Withdrawn,
+    ReleaseApproved,
+    RefundApproved,
+    Released,
+    Refunded,
+    Disputed,
+    Resolved,
+    /// Proposed payee amount
+    SettlementProposed(u128),
+    Settled,
+    /// Amount paid to the payee
+    Vested(u128),
+    /// New payee
+    PayeeReassigned(Address),
+}
+
+/// One entry of `get_history`: what happened, who did it, and when.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct HistoryEntry {
+    pub action: EscrowAction,
+    pub actor: Address,
+    pub timestamp: u64,
+}
+
+/// Terms of one case in `deposit_batch`; the fields mirror `deposit`'s arguments.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EscrowRequest {
+    pub payee: Address,
+    pub arbiter: Address,
+    pub token: Address,
+    pub amount: u128,
+    pub release_after: u64,
+    pub required_approvals: u32,
+    pub terms_hash: BytesN<32>,
+    pub options: EscrowOptions,
 }
 
 #[contracttype]
@@ -29,12 +228,46 @@ pub struct EscrowCase {
     pub payer: Address,
     pub payee: Address,
     pub arbiter: Address,
+    /// Token contract the escrowed amount is denominated in
+    pub token: Address,
     pub amount: u128,
+    /// Hash of the off-chain agreement; depositing and accepting bind the parties to it
+    pub terms_hash: BytesN<32>,
+    pub terms_uri: Option<String>,
     pub release_after: u64,
+    /// Deadline for the payee to accept an `Offered` case; 0 if no acceptance was required
+    pub accept_by: u64,
     pub required_approvals: u32,
     pub release_approvers: Vec<Address>,
     pub refund_approvers: Vec<Address>,
     pub status: EscrowStatus,
+    /// Most the arbiter may keep when resolving a dispute, agreed at deposit
+    pub dispute_fee: u128,
+    /// Share of the amount paid to the arbiter on release/refund if they approved it
+    pub arbiter_fee_bps: u32,
+    /// Linear vesting period starting at `release_after`; 0 if not vesting
+    pub vesting_duration: u64,
+    /// Vested amount the payee already withdrew, before protocol fees
+    pub vested_paid: u128,
+    /// Protocol fee on the payee's released funds, fixed at deposit; 0 if
+    /// none was configured or a party is exempt
+    pub protocol_fee_bps: u32,
+    pub disputed_by: Option<Address>,
+    pub dispute_reason: Option<BytesN<32>>,
+    /// Party with an open settlement proposal, if any
+    pub settlement_by: Option<Address>,
+    /// Proposed payee share of the unvested balance
+    pub settlement_amount: u128,
+    pub yield_position: YieldPosition,
+    /// Final settlement, paid out once the case closes. `payee_amount`
+    /// includes any `vested_paid` and the protocol fee taken from it.
+    pub payee_amount: u128,
+    pub payer_amount: u128,
+    pub arbiter_amount: u128,
+    /// Part of `payee_amount` owed to the protocol fee recipient
+    pub protocol_amount: u128,
+    /// Ledger timestamp the case closed; 0 while it's open
+    pub closed_at: u64,
 }
 
 #[contracttype]
@@ -42,22 +275,156 @@ pub struct EscrowCase {
 enum StorageKey {
     EscrowCount,
     Escrow(u64),
+    /// Index slots ever handed out for a participant in a role
+    Index(Role, Address),
+    /// Case ID in one index slot; removed when the case leaves the index
+    IndexSlot(Role, Address, u32),
+    /// Slot a case occupies in its participant's index for a role
+    IndexPos(Role, u64),
+    YieldPool(Address),
+    /// ID of the closed case at a queue position, awaiting `prune_closed`
+    Closed(u64),
+    /// Queue position of the oldest closed case not yet pruned
+    ClosedHead,
+    /// Queue position the next closed case will take
+    ClosedTail,
+    /// Admin override of `DEFAULT_CLOSED_RETENTION`
+    ClosedRetention,
+    Admin,
+    ProtocolFee,
+    FeeExempt(Address),
+    /// Entries ever recorded in a case's history
+    HistoryLen(u64),
+    /// One entry of a case's history, by position
+    HistorySlot(u64, u32),
 }
 
+/// Average ledger close time, used to turn deadlines into TTLs.
+pub const LEDGER_SECONDS: u64 = 5;
+
+/// Ledgers an open case is kept alive past its last deadline (~1 day).
+pub const ESCROW_TTL_BUFFER: u32 = 17_280;
+
+/// Seconds a closed case stays queryable before `prune_closed` may delete
+/// it, unless the admin sets another period.
+pub const DEFAULT_CLOSED_RETENTION: u64 = 30 * 86_400;
+
+/// Most cases one `prune_closed` call may delete.
+pub const MAX_PRUNE_SIZE: u32 = 50;
+
+/// Most history entries one `prune_closed` call may delete. A longer trail
+/// is finished over several calls.
+pub const MAX_HISTORY_PRUNE: u32 = 200;
+
+/// Fixed-point scale of `YieldPool::acc_per_share`.
+pub const ACC_SCALE: u128 = 1_000_000_000_000;
+
+/// Longest accepted `terms_uri`, in bytes.
+pub const MAX_TERMS_URI_LEN: u32 = 256;
+
+/// Denominator for basis-point fees.
+pub const BPS_DENOMINATOR: u32 = 10_000;
+
+/// Most cases one `deposit_batch` call may create.
+pub const MAX_BATCH_SIZE: u32 = 20;
+
+/// Largest page returned by the participant lookups.
+pub const MAX_PAGE_SIZE: u32 = 50;
+
 #[contract]
 pub struct EscrowContract;
 
 #[contractimpl]
 impl EscrowContract {
+    /// Set the admin who configures the protocol fee. Optional: without an
+    /// admin the contract simply charges no protocol fee.
+    pub fn initialize(env: Env, admin: Address) -> Result<(), EscrowError> {
+        if env.storage().instance().has(&StorageKey::Admin) {
+            return Err(EscrowError::AlreadyInitialized);
+        }
+        env.storage().instance().set(&StorageKey::Admin, &admin);
+        Ok(())
+    }
+
+    /// Set the protocol fee charged on what payees receive from cases opened
+    /// from now on. `bps = 0` turns it off. Requires admin auth.
+    pub fn set_fee(env: Env, bps: u32, recipient: Address) -> Result<(), EscrowError> {
+        Self::require_admin(&env)?;
+        if bps > BPS_DENOMINATOR {
+            return Err(EscrowError::InvalidFee);
+        }
+        let fee = ProtocolFee { bps, recipient };
+        env.storage().instance().set(&StorageKey::ProtocolFee, &fee);
+
+        env.events()
+            .publish((symbol_short!("fee_set"),), (fee.bps, fee.recipient));
+        Ok(())
+    }
+
+    /// Exempt `account` from the protocol fee (or revoke that). Cases whose
+    /// payer or payee is exempt at deposit pay no fee. Requires admin auth.
+    pub fn set_fee_exempt(env: Env, account: Address, exempt: bool) -> Result<(), EscrowError> {
+        Self::require_admin(&env)?;
+        let key = StorageKey::FeeExempt(account.clone());
+        if exempt {
+            env.storage().persistent().set(&key, &true);
+        } else {
+            env.storage().persistent().remove(&key);
+        }
+
+        env.events()
+            .publish((symbol_short!("exempt"), account), exempt);
+        Ok(())
+    }
+
+    /// Set how many seconds closed cases stay queryable before `prune_closed`
+    /// may delete them. Cases that already closed keep the storage TTL they
+    /// got then, so lengthening the period doesn't stretch their lifetime.
+    /// Requires admin auth.
+    pub fn set_closed_retention(env: Env, seconds: u64) -> Result<(), EscrowError> {
+        Self::require_admin(&env)?;
+        env.storage()
+            .instance()
+            .set(&StorageKey::ClosedRetention, &seconds);
+
+        env.events().publish((symbol_short!("retention"),), seconds);
+        Ok(())
+    }
+
+    /// Seconds closed cases are kept before they can be pruned.
+    pub fn get_closed_retention(env: Env) -> u64 {
+        env.storage()
+            .instance()
+            .get(&StorageKey::ClosedRetention)
+            .unwrap_or(DEFAULT_CLOSED_RETENTION)
+    }
+
+    /// Current protocol fee, if one was ever set.
+    pub fn get_fee(env: Env) -> Option<ProtocolFee> {
+        env.storage().instance().get(&StorageKey::ProtocolFee)
+    }
+
+    /// Whether `account` is on the protocol fee exemption list.
+    pub fn is_fee_exempt(env: Env, account: Address) -> bool {
+        env.storage()
+            .persistent()
+            .has(&StorageKey::FeeExempt(account))
+    }
+
     /// Create a new escrow case and deposit funds into it.
     ///
+    /// Transfers `amount` of `token` from the payer to this contract.
+    ///
     /// # Arguments
     /// * `payer` - Account providing escrowed funds
     /// * `payee` - Account receiving funds on release
     /// * `arbiter` - Neutral account that may approve release/refund
-    /// * `amount` - Escrow amount tracked by this contract
+    /// * `token` - Token contract (e.g. a Stellar Asset Contract) to escrow
+    /// * `amount` - Amount of `token` held until release/refund
     /// * `release_after` - Earliest ledger timestamp for release (seconds)
     /// * `required_approvals` - Minimum approvals from payer/payee/arbiter for release/refund
+    /// * `terms_hash` - Hash of the off-chain agreement the case settles
+    /// * `options` - Fees, acceptance window, vesting, and terms URI; see `EscrowOptions`
     ///
     /// # Returns
     /// * `u64` - New escrow case ID
@@ -66,96 +433,634 @@ impl EscrowContract {
         payer: Address,
         payee: Address,
         arbiter: Address,
+        token: Address,
         amount: u128,
         release_after: u64,
         required_approvals: u32,
-    ) -> u64 {
+        terms_hash: BytesN<32>,
+        options: EscrowOptions,
+    ) -> Result<u64, EscrowError> {
         payer.require_auth();
-        assert!(amount > 0, "amount must be greater than zero");
-        assert!(payer != payee, "payer and payee must differ");
-        assert!(payer != arbiter, "payer and arbiter must differ");
-        assert!(payee != arbiter, "payee and arbiter must differ");
-        assert!(
-            required_approvals > 0 && required_approvals <= 3,
-            "required approvals must be between 1 and 3"
-        );
-
-        let escrow_id = Self::escrow_count(env.clone()) + 1;
-
-        let now = env.ledger().timestamp();
-        assert!(release_after >= now, "release_after cannot be in the past");
-
-        let escrow = EscrowCase {
-            id: escrow_id,
-            payer,
+        let request = EscrowRequest {
             payee,
             arbiter,
+            token,
             amount,
             release_after,
             required_approvals,
-            release_approvers: Vec::new(&env),
-            refund_approvers: Vec::new(&env),
-            status: EscrowStatus::Pending,
+            terms_hash,
+            options,
         };
+        Self::open_case(&env, payer, request)
+    }
 
-        env.storage()
-            .persistent()
-            .set(&StorageKey::Escrow(escrow_id), &escrow);
-        env.storage()
-            .instance()
-            .set(&StorageKey::EscrowCount, &escrow_id);
+    /// Create several escrow cases for one payer in a single transaction,
+    /// e.g. paying a team of contractors from one funding account.
+    ///
+    /// All-or-nothing: if any request is invalid, no case is created.
+    ///
+    /// # Arguments
+    /// * `payer` - Account funding every case
+    /// * `requests` - One entry per case, with the same terms `deposit` takes
+    ///
+    /// # Returns
+    /// * `Vec<u64>` - New escrow case IDs, in request order
+    pub fn deposit_batch(
+        env: Env,
+        payer: Address,
+        requests: Vec<EscrowRequest>,
+    ) -> Result<Vec<u64>, EscrowError> {
+        payer.require_auth();
+        if requests.is_empty() || requests.len() > MAX_BATCH_SIZE {
+            return Err(EscrowError::InvalidBatch);
+        }
+
+        let mut ids = Vec::new(&env);
+        for request in requests.iter() {
+            ids.push_back(Self::open_case(&env, payer.clone(), request)?);
+        }
+        Ok(ids)
+    }
+
+    /// Payee agrees to an `Offered` case's terms before `accept_by`, making it `Pending`.
+    pub fn accept(env: Env, escrow_id: u64) -> Result<(), EscrowError> {
+        let mut escrow = Self::get_escrow(env.clone(), escrow_id)?;
+        escrow.payee.require_auth();
+        if escrow.status != EscrowStatus::Offered {
+            return Err(EscrowError::NotOffered);
+        }
+        if env.ledger().timestamp() > escrow.accept_by {
+            return Err(EscrowError::AcceptWindowClosed);
+        }
+
+        escrow.status = EscrowStatus::Pending;
+        Self::record(&env, &escrow, EscrowAction::Accepted, &escrow.payee);
+        Self::save_escrow(&env, &mut escrow);
+
+        env.events()
+            .publish((symbol_short!("accepted"), escrow_id), escrow.payee);
+        Ok(())
+    }
+
+    /// Payer reclaims the deposit of an `Offered` case the payee didn't accept in time.
+    pub fn withdraw(env: Env, escrow_id: u64) -> Result<(), EscrowError> {
+        let mut escrow = Self::get_escrow(env.clone(), escrow_id)?;
+        escrow.payer.require_auth();
+        if escrow.status != EscrowStatus::Offered {
+            return Err(EscrowError::NotOffered);
+        }
+        if env.ledger().timestamp() <= escrow.accept_by {
+            return Err(EscrowError::AcceptWindowOpen);
+        }
 
-        escrow_id
+        escrow.status = EscrowStatus::Withdrawn;
+        escrow.payer_amount = escrow.amount;
+        Self::settle(&env, &mut escrow)?;
+        Self::record(&env, &escrow, EscrowAction::Withdrawn, &escrow.payer);
+        Self::save_escrow(&env, &mut escrow);
+
+        env.events()
+            .publish((symbol_short!("withdrawn"), escrow_id), escrow.amount);
+        Ok(())
     }
 
     /// Approve and execute release to payee when approval and time conditions are satisfied.
-    pub fn release(env: Env, escrow_id: u64, approver: Address) {
+    pub fn release(env: Env, escrow_id: u64, approver: Address) -> Result<(), EscrowError> {
         approver.require_auth();
 
-        let mut escrow = Self::get_escrow(env.clone(), escrow_id);
-        assert_eq!(escrow.status, EscrowStatus::Pending, "escrow not pending");
-        assert!(
-            env.ledger().timestamp() >= escrow.release_after,
-            "release time not reached"
-        );
+        let mut escrow = Self::get_escrow(env.clone(), escrow_id)?;
+        if escrow.status != EscrowStatus::Pending {
+            return Err(EscrowError::NotPending);
+        }
+        if env.ledger().timestamp() < escrow.release_after {
+            return Err(EscrowError::ReleaseTimeNotReached);
+        }
 
-        Self::assert_is_party(&escrow, &approver);
-        Self::add_unique_approver(&env, &mut escrow.release_approvers, approver);
+        Self::require_party(&escrow, &approver)?;
+        Self::add_unique_approver(&env, &mut escrow.release_approvers, approver.clone())?;
+        Self::record(&env, &escrow, EscrowAction::ReleaseApproved, &approver);
+        env.events().publish(
+            (symbol_short!("rel_appr"), escrow_id, approver.clone()),
+            escrow.release_approvers.len(),
+        );
 
-        if escrow.release_approvers.len() as u32 >= escrow.required_approvals {
+        if escrow.release_approvers.len() >= escrow.required_approvals {
             escrow.status = EscrowStatus::Released;
+            let remaining = escrow.amount - escrow.vested_paid;
+            escrow.arbiter_amount =
+                Self::arbiter_cut(&escrow, remaining, &escrow.release_approvers);
+            escrow.payee_amount = escrow.amount - escrow.arbiter_amount;
+            escrow.protocol_amount = Self::protocol_cut(&escrow, escrow.payee_amount);
+            Self::settle(&env, &mut escrow)?;
+            Self::record(&env, &escrow, EscrowAction::Released, &approver);
+            env.events()
+                .publish((symbol_short!("released"), escrow_id), escrow.amount);
         }
 
-        env.storage()
-            .persistent()
-            .set(&StorageKey::Escrow(escrow_id), &escrow);
+        Self::save_escrow(&env, &mut escrow);
+        Ok(())
     }
 
     /// Approve and execute refund to payer when approval threshold is met.
-    pub fn refund(env: Env, escrow_id: u64, approver: Address) {
+    pub fn refund(env: Env, escrow_id: u64, approver: Address) -> Result<(), EscrowError> {
         approver.require_auth();
 
-        let mut escrow = Self::get_escrow(env.clone(), escrow_id);
-        assert_eq!(escrow.status, EscrowStatus::Pending, "escrow not pending");
+        let mut escrow = Self::get_escrow(env.clone(), escrow_id)?;
+        if escrow.status != EscrowStatus::Pending {
+            return Err(EscrowError::NotPending);
+        }
 
-        Self::assert_is_party(&escrow, &approver);
-        Self::add_unique_approver(&env, &mut escrow.refund_approvers, approver);
+        Self::require_party(&escrow, &approver)?;
+        Self::add_unique_approver(&env, &mut escrow.refund_approvers, approver.clone())?;
+        Self::record(&env, &escrow, EscrowAction::RefundApproved, &approver);
+        env.events().publish(
+            (symbol_short!("ref_appr"), escrow_id, approver.clone()),
+            escrow.refund_approvers.len(),
+        );
 
-        if escrow.refund_approvers.len() as u32 >= escrow.required_approvals {
+        if escrow.refund_approvers.len() >= escrow.required_approvals {
             escrow.status = EscrowStatus::Refunded;
+            let remaining = escrow.amount - escrow.vested_paid;
+            escrow.arbiter_amount = Self::arbiter_cut(&escrow, remaining, &escrow.refund_approvers);
+            escrow.payer_amount = remaining - escrow.arbiter_amount;
+            escrow.payee_amount = escrow.vested_paid;
+            escrow.protocol_amount = Self::protocol_cut(&escrow, escrow.vested_paid);
+            Self::settle(&env, &mut escrow)?;
+            Self::record(&env, &escrow, EscrowAction::Refunded, &approver);
+            env.events()
+                .publish((symbol_short!("refunded"), escrow_id), escrow.amount);
         }
 
-        env.storage()
-            .persistent()
-            .set(&StorageKey::Escrow(escrow_id), &escrow);
+        Self::save_escrow(&env, &mut escrow);
+        Ok(())
+    }
+
+    /// Freeze a pending escrow until the arbiter settles it.
+    ///
+    /// Release and refund approvals stop counting once a case is disputed;
+    /// only `resolve_dispute` can close it.
+    ///
+    /// # Arguments
+    /// * `party` - Payer or payee raising the dispute
+    /// * `escrow_id` - Escrow case to dispute
+    /// * `reason_hash` - Hash of the off-chain dispute statement/evidence
+    pub fn raise_dispute(
+        env: Env,
+        party: Address,
+        escrow_id: u64,
+        reason_hash: BytesN<32>,
+    ) -> Result<(), EscrowError> {
+        party.require_auth();
+
+        let mut escrow = Self::get_escrow(env.clone(), escrow_id)?;
+        if escrow.status != EscrowStatus::Pending {
+            return Err(EscrowError::NotPending);
+        }
+        if party != escrow.payer && party != escrow.payee {
+            return Err(EscrowError::NotDisputant);
+        }
+
+        escrow.status = EscrowStatus::Disputed;
+        escrow.disputed_by = Some(party.clone());
+        escrow.dispute_reason = Some(reason_hash.clone());
+        Self::record(&env, &escrow, EscrowAction::Disputed, &party);
+
+        env.events()
+            .publish((symbol_short!("disputed"), escrow_id, party), reason_hash);
+
+        Self::save_escrow(&env, &mut escrow);
+        Ok(())
+    }
+
+    /// Settle a disputed escrow by splitting the amount between the parties.
+    ///
+    /// # Arguments
+    /// * `arbiter` - The case's arbiter
+    /// * `escrow_id` - Disputed escrow case
+    /// * `payee_amount` - Portion of the escrowed balance awarded to the payee
+    /// * `fee` - Portion kept by the arbiter, at most the agreed `dispute_fee`
+    ///
+    /// Whatever remains after `payee_amount` and `fee` goes back to the payer.
+    /// For vesting cases the split covers only what the payee hasn't withdrawn.
+    pub fn resolve_dispute(
+        env: Env,
+        arbiter: Address,
+        escrow_id: u64,
+        payee_amount: u128,
+        fee: u128,
+    ) -> Result<(), EscrowError> {
+        arbiter.require_auth();
+
+        let mut escrow = Self::get_escrow(env.clone(), escrow_id)?;
+        if escrow.status != EscrowStatus::Disputed {
+            return Err(EscrowError::NotDisputed);
+        }
+        if arbiter != escrow.arbiter {
+            return Err(EscrowError::NotArbiter);
+        }
+        if fee > escrow.dispute_fee {
+            return Err(EscrowError::FeeExceedsAgreed);
+        }
+        let payer_amount = (escrow.amount - escrow.vested_paid)
+            .checked_sub(fee)
+            .and_then(|rest| rest.checked_sub(payee_amount))
+            .ok_or(EscrowError::SplitExceedsAmount)?;
+
+        escrow.status = EscrowStatus::Resolved;
+        escrow.payee_amount = escrow.vested_paid + payee_amount;
+        escrow.payer_amount = payer_amount;
+        escrow.arbiter_amount = fee;
+        escrow.protocol_amount = Self::protocol_cut(&escrow, escrow.vested_paid);
+        Self::settle(&env, &mut escrow)?;
+        Self::record(&env, &escrow, EscrowAction::Resolved, &arbiter);
+
+        env.events().publish(
+            (symbol_short!("resolved"), escrow_id, arbiter),
+            (payee_amount, payer_amount, fee),
+        );
+
+        Self::save_escrow(&env, &mut escrow);
+        Ok(())
+    }
+
+    /// Propose closing a case early with `payee_amount` of the unvested
+    /// balance going to the payee and the rest back to the payer. Works on
+    /// `Pending` and `Disputed` cases; a new proposal from either side
+    /// replaces the previous one.
+    ///
+    /// # Arguments
+    /// * `party` - Payer or payee making the offer
+    /// * `escrow_id` - Escrow case to settle
+    /// * `payee_amount` - Proposed payee share of `amount - vested_paid`
+    pub fn propose_settlement(
+        env: Env,
+        party: Address,
+        escrow_id: u64,
+        payee_amount: u128,
+    ) -> Result<(), EscrowError> {
+        party.require_auth();
+
+        let mut escrow = Self::get_escrow(env.clone(), escrow_id)?;
+        if escrow.status != EscrowStatus::Pending && escrow.status != EscrowStatus::Disputed {
+            return Err(EscrowError::NotPending);
+        }
+        if party != escrow.payer && party != escrow.payee {
+            return Err(EscrowError::NotDisputant);
+        }
+        if payee_amount > escrow.amount - escrow.vested_paid {
+            return Err(EscrowError::SplitExceedsAmount);
+        }
+
+        escrow.settlement_by = Some(party.clone());
+        escrow.settlement_amount = payee_amount;
+        Self::record(
+            &env,
+            &escrow,
+            EscrowAction::SettlementProposed(payee_amount),
+            &party,
+        );
+        Self::save_escrow(&env, &mut escrow);
+
+        env.events()
+            .publish((symbol_short!("proposed"), escrow_id, party), payee_amount);
+        Ok(())
+    }
+
+    /// Accept the other side's settlement proposal, paying out the split and
+    /// marking the case `Settled`. `payee_amount` must match the proposal, so
+    /// a last-moment change can't be accepted unseen. The arbiter takes no
+    /// fee.
+    pub fn accept_settlement(
+        env: Env,
+        party: Address,
+        escrow_id: u64,
+        payee_amount: u128,
+    ) -> Result<(), EscrowError> {
+        party.require_auth();
+
+        let mut escrow = Self::get_escrow(env.clone(), escrow_id)?;
+        if escrow.status != EscrowStatus::Pending && escrow.status != EscrowStatus::Disputed {
+            return Err(EscrowError::NotPending);
+        }
+        let proposer = escrow
+            .settlement_by
+            .clone()
+            .ok_or(EscrowError::NoSettlement)?;
+        if party == proposer || (party != escrow.payer && party != escrow.payee) {
+            return Err(EscrowError::NotCounterparty);
+        }
+        if payee_amount != escrow.settlement_amount {
+            return Err(EscrowError::SettlementMismatch);
+        }
+        // Vesting withdrawals since the proposal may have shrunk the balance
+        let payer_amount = (escrow.amount - escrow.vested_paid)
+            .checked_sub(payee_amount)
+            .ok_or(EscrowError::SplitExceedsAmount)?;
+
+        escrow.status = EscrowStatus::Settled;
+        escrow.payee_amount = escrow.vested_paid + payee_amount;
+        escrow.payer_amount = payer_amount;
+        escrow.protocol_amount = Self::protocol_cut(&escrow, escrow.payee_amount);
+        escrow.settlement_by = None;
+        Self::settle(&env, &mut escrow)?;
+        Self::record(&env, &escrow, EscrowAction::Settled, &party);
+
+        env.events().publish(
+            (symbol_short!("settled"), escrow_id),
+            (payee_amount, payer_amount),
+        );
+
+        Self::save_escrow(&env, &mut escrow);
+        Ok(())
+    }
+
+    /// Pay the payee whatever has vested since their last withdrawal.
+    ///
+    /// Only for cases created with a `vesting_duration`. Vesting runs linearly
+    /// from `release_after` to `release_after + vesting_duration`; the case is
+    /// `Released` once the full amount is withdrawn.
+    ///
+    /// # Returns
+    /// * `u128` - Amount paid by this call
+    pub fn withdraw_vested(env: Env, escrow_id: u64) -> Result<u128, EscrowError> {
+        let mut escrow = Self::get_escrow(env.clone(), escrow_id)?;
+        escrow.payee.require_auth();
+        if escrow.vesting_duration == 0 {
+            return Err(EscrowError::NotVesting);
+        }
+        if escrow.status != EscrowStatus::Pending {
+            return Err(EscrowError::NotPending);
+        }
+        let now = env.ledger().timestamp();
+        if now < escrow.release_after {
+            return Err(EscrowError::ReleaseTimeNotReached);
+        }
+
+        let due = Self::vested(&escrow, now) - escrow.vested_paid;
+        if due == 0 {
+            return Err(EscrowError::NothingVested);
+        }
+        let fee_before = Self::protocol_cut(&escrow, escrow.vested_paid);
+        escrow.vested_paid += due;
+        escrow.payee_amount = escrow.vested_paid;
+        escrow.protocol_amount = Self::protocol_cut(&escrow, escrow.vested_paid);
+        let fee = escrow.protocol_amount - fee_before;
+        let paid = due - fee;
+        Self::unstake(&env, &mut escrow, due)?;
+        token::Client::new(&env, &escrow.token).transfer(
+            &env.current_contract_address(),
+            &escrow.payee,
+            &(paid as i128),
+        );
+        Self::pay_protocol_fee(&env, &escrow, fee);
+
+        Self::record(&env, &escrow, EscrowAction::Vested(paid), &escrow.payee);
+
+        env.events()
+            .publish((symbol_short!("vested"), escrow_id), paid);
+        if escrow.vested_paid == escrow.amount {
+            escrow.status = EscrowStatus::Released;
+            Self::pay_yield(&env, &escrow);
+            Self::record(&env, &escrow, EscrowAction::Released, &escrow.payee);
+            env.events()
+                .publish((symbol_short!("released"), escrow_id), escrow.amount);
+        }
+
+        Self::save_escrow(&env, &mut escrow);
+        Ok(paid)
+    }
+
+    /// Hand a live case over to a new payee, e.g. when the delivering party
+    /// changes mid-engagement.
+    ///
+    /// A `Pending` case needs auth from both the payer and the current payee;
+    /// a `Disputed` one is the arbiter's call alone. The outgoing payee's
+    /// release/refund approvals and any open settlement proposal are
+    /// dropped. Amounts already vested stay with whoever withdrew them.
+    pub fn reassign_payee(env: Env, escrow_id: u64, new_payee: Address) -> Result<(), EscrowError> {
+        let mut escrow = Self::get_escrow(env.clone(), escrow_id)?;
+        match escrow.status {
+            EscrowStatus::Pending => {
+                escrow.payer.require_auth();
+                escrow.payee.require_auth();
+            }
+            EscrowStatus::Disputed => escrow.arbiter.require_auth(),
+            _ => return Err(EscrowError::NotPending),
+        }
+        if new_payee == escrow.payer || new_payee == escrow.payee || new_payee == escrow.arbiter {
+            return Err(EscrowError::DuplicateParty);
+        }
+
+        let old_payee = escrow.payee.clone();
+        // Outside a dispute the payer and payee consented together
+        let actor = match escrow.status {
+            EscrowStatus::Disputed => escrow.arbiter.clone(),
+            _ => escrow.payer.clone(),
+        };
+        Self::record(
+            &env,
+            &escrow,
+            EscrowAction::PayeeReassigned(new_payee.clone()),
+            &actor,
+        );
+        for approvers in [&mut escrow.release_approvers, &mut escrow.refund_approvers] {
+            if let Some(i) = approvers.first_index_of(&old_payee) {
+                approvers.remove(i);
+            }
+        }
+        escrow.payee = new_payee.clone();
+        escrow.settlement_by = None;
+        // Move the index entry before saving, so the save extends it. The
+        // position entry is overwritten rather than removed: an entry removed
+        // and re-created in one call would lose the TTL it already has.
+        let storage = env.storage().persistent();
+        if let Some(slot) = storage.get::<_, u32>(&StorageKey::IndexPos(Role::Payee, escrow_id)) {
+            storage.remove(&StorageKey::IndexSlot(Role::Payee, old_payee.clone(), slot));
+        }
+        Self::index_escrow(&env, Role::Payee, &new_payee, escrow_id, 0);
+        Self::save_escrow(&env, &mut escrow);
+
+        env.events().publish(
+            (symbol_short!("reassign"), escrow_id),
+            (old_payee, new_payee),
+        );
+        Ok(())
+    }
+
+    /// Keep a case's storage, including its participant index entries, alive
+    /// until its last deadline (release time, end of vesting, or acceptance
+    /// window) plus a safety margin. Anyone may call this, e.g. for a
+    /// disputed case waiting on the arbiter.
+    ///
+    /// # Returns
+    /// * `u32` - Ledgers the case is now guaranteed to live
+    pub fn bump_escrow(env: Env, escrow_id: u64) -> Result<u32, EscrowError> {
+        let escrow = Self::get_escrow(env.clone(), escrow_id)?;
+        Ok(Self::extend_escrow_ttl(&env, &escrow))
+    }
+
+    /// Delete up to `limit` cases that closed at least `get_closed_retention`
+    /// seconds ago, oldest first, to keep storage rent bounded. Each case's
+    /// final state is summarized in an `archived` event carrying the SHA-256
+    /// of its XDR encoding before it and its history are removed. Queue
+    /// entries whose case already expired are skipped and count toward
+    /// `limit`. At most `MAX_HISTORY_PRUNE` history entries are deleted per
+    /// call; a case with a longer history is finished by later calls.
+    /// Anyone may call this.
+    ///
+    /// # Returns
+    /// * `u32` - Number of cases pruned
+    pub fn prune_closed(env: Env, limit: u32) -> Result<u32, EscrowError> {
+        let mut head: u64 = env
+            .storage()
+            .instance()
+            .get(&StorageKey::ClosedHead)
+            .unwrap_or(0);
+        let tail: u64 = env
+            .storage()
+            .instance()
+            .get(&StorageKey::ClosedTail)
+            .unwrap_or(0);
+        let limit = limit.min(MAX_PRUNE_SIZE);
+        let retention = Self::get_closed_retention(env.clone());
+        let now = env.ledger().timestamp();
+
+        let mut scanned = 0;
+        let mut pruned = 0;
+        let mut history_budget = MAX_HISTORY_PRUNE;
+        while scanned < limit && head < tail {
+            let slot = StorageKey::Closed(head);
+            let id: Option<u64> = env.storage().persistent().get(&slot);
+            // A case that expired before it was pruned has nothing left to
+            // archive, and stopping at it would stall the queue behind it
+            let escrow = id.and_then(|id| Self::get_escrow(env.clone(), id).ok());
+            if escrow
+                .as_ref()
+                .is_some_and(|escrow| escrow.closed_at.saturating_add(retention) > now)
+            {
+                break;
+            }
+            // A long history is deleted over several calls; the case stays at
+            // the head until it's gone
+            if let Some(id) = id {
+                if !Self::prune_history(&env, id, &mut history_budget) {
+                    break;
+                }
+            }
+
+            if let Some(escrow) = escrow {
+                let id = escrow.id;
+                let digest: BytesN<32> = env.crypto().sha256(&escrow.clone().to_xdr(&env)).into();
+                env.events().publish(
+                    (symbol_short!("archived"), id),
+                    (escrow.status, escrow.closed_at, digest),
+                );
+                env.storage().persistent().remove(&StorageKey::Escrow(id));
+                Self::unindex_escrow(&env, Role::Payer, &escrow.payer, id);
+                Self::unindex_escrow(&env, Role::Payee, &escrow.payee, id);
+                Self::unindex_escrow(&env, Role::Arbiter, &escrow.arbiter, id);
+                pruned += 1;
+            }
+            env.storage().persistent().remove(&slot);
+            head += 1;
+            scanned += 1;
+        }
+
+        if scanned > 0 {
+            env.storage().instance().set(&StorageKey::ClosedHead, &head);
+        }
+        Ok(pruned)
     }
 
     /// Get full escrow case details by ID.
-    pub fn get_escrow(env: Env, escrow_id: u64) -> EscrowCase {
+    pub fn get_escrow(env: Env, escrow_id: u64) -> Result<EscrowCase, EscrowError> {
         env.storage()
             .persistent()
             .get(&StorageKey::Escrow(escrow_id))
-            .expect("escrow not found")
+            .ok_or(EscrowError::NotFound)
+    }
+
+    /// Audit trail of a case: every approval and state change, oldest
+    /// first, with the address behind it and the ledger timestamp.
+    ///
+    /// Returns entries `offset..offset + limit`, with `limit` capped at
+    /// `MAX_PAGE_SIZE`; keep paging until `offset` reaches `get_history_len`.
+    pub fn get_history(
+        env: Env,
+        escrow_id: u64,
+        offset: u32,
+        limit: u32,
+    ) -> Result<Vec<HistoryEntry>, EscrowError> {
+        let len: u32 = env
+            .storage()
+            .persistent()
+            .get(&StorageKey::HistoryLen(escrow_id))
+            .ok_or(EscrowError::NotFound)?;
+        let end = offset.saturating_add(limit.min(MAX_PAGE_SIZE)).min(len);
+
+        let mut entries = Vec::new(&env);
+        for index in offset..end {
+            if let Some(entry) = env
+                .storage()
+                .persistent()
+                .get(&StorageKey::HistorySlot(escrow_id, index))
+            {
+                entries.push_back(entry);
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Number of entries in a case's history; 0 for unknown or pruned cases.
+    pub fn get_history_len(env: Env, escrow_id: u64) -> u32 {
+        env.storage()
+            .persistent()
+            .get(&StorageKey::HistoryLen(escrow_id))
+            .unwrap_or(0)
+    }
+
+    /// List escrow cases paid by `payer`, oldest first.
+    ///
+    /// `offset` and `limit` count index slots, and `limit` is capped at
+    /// `MAX_PAGE_SIZE`. Cases that left the index (pruned, or reassigned to
+    /// another payee) leave gaps, so a page may hold fewer than `limit`
+    /// cases; keep paging until `offset` reaches `get_index_len`.
+    pub fn get_escrows_by_payer(
+        env: Env,
+        payer: Address,
+        offset: u32,
+        limit: u32,
+    ) -> Vec<EscrowCase> {
+        Self::page(&env, Role::Payer, payer, offset, limit)
+    }
+
+    /// List escrow cases paying `payee`, oldest first. Paginated like `get_escrows_by_payer`.
+    pub fn get_escrows_by_payee(
+        env: Env,
+        payee: Address,
+        offset: u32,
+        limit: u32,
+    ) -> Vec<EscrowCase> {
+        Self::page(&env, Role::Payee, payee, offset, limit)
+    }
+
+    /// List escrow cases arbitrated by `arbiter`, oldest first. Paginated like `get_escrows_by_payer`.
+    pub fn get_escrows_by_arbiter(
+        env: Env,
+        arbiter: Address,
+        offset: u32,
+        limit: u32,
+    ) -> Vec<EscrowCase> {
+        Self::page(&env, Role::Arbiter, arbiter, offset, limit)
+    }
+
+    /// Number of index slots `participant` has used in `role`, including the
+    /// gaps left by cases that have since left the index.
+    pub fn get_index_len(env: Env, role: Role, participant: Address) -> u32 {
+        env.storage()
+            .persistent()
+            .get(&StorageKey::Index(role, participant))
+            .unwrap_or(0)
     }
 
     /// Return total number of escrow cases created.
@@ -166,22 +1071,619 @@ impl EscrowContract {
             .unwrap_or(0)
     }
 
-    fn assert_is_party(escrow: &EscrowCase, addr: &Address) {
-        assert!(
-            *addr == escrow.payer || *addr == escrow.payee || *addr == escrow.arbiter,
-            "approver must be payer, payee, or arbiter"
+    /// Validate a request, take the payer's funds, and store the new case.
+    fn open_case(env: &Env, payer: Address, request: EscrowRequest) -> Result<u64, EscrowError> {
+        let EscrowRequest {
+            payee,
+            arbiter,
+            token,
+            amount,
+            release_after,
+            required_approvals,
+            terms_hash,
+            options,
+        } = request;
+        let transfer_amount = i128::try_from(amount).map_err(|_| EscrowError::InvalidAmount)?;
+        if amount == 0 {
+            return Err(EscrowError::InvalidAmount);
+        }
+        if payer == payee || payer == arbiter || payee == arbiter {
+            return Err(EscrowError::DuplicateParty);
+        }
+        if !(1..=3).contains(&required_approvals) {
+            return Err(EscrowError::InvalidApprovals);
+        }
+        if options.dispute_fee > amount {
+            return Err(EscrowError::DisputeFeeTooHigh);
+        }
+        if options.arbiter_fee_bps > BPS_DENOMINATOR {
+            return Err(EscrowError::InvalidFee);
+        }
+        if let YieldMode::Staked(config) = &options.yield_mode {
+            if config.payee_bps > BPS_DENOMINATOR {
+                return Err(EscrowError::InvalidFee);
+            }
+        }
+        if let Some(uri) = &options.terms_uri {
+            if uri.len() > MAX_TERMS_URI_LEN {
+                return Err(EscrowError::TermsUriTooLong);
+            }
+        }
+
+        let escrow_id = Self::escrow_count(env.clone()) + 1;
+
+        let now = env.ledger().timestamp();
+        if release_after < now {
+            return Err(EscrowError::ReleaseTimeInPast);
+        }
+
+        let protocol_fee_bps = match env
+            .storage()
+            .instance()
+            .get::<_, ProtocolFee>(&StorageKey::ProtocolFee)
+        {
+            Some(fee)
+                if !Self::is_fee_exempt(env.clone(), payer.clone())
+                    && !Self::is_fee_exempt(env.clone(), payee.clone()) =>
+            {
+                fee.bps
+            }
+            _ => 0,
+        };
+
+        let (status, accept_by) = if options.accept_window == 0 {
+            (EscrowStatus::Pending, 0)
+        } else {
+            (
+                EscrowStatus::Offered,
+                now.saturating_add(options.accept_window),
+            )
+        };
+
+        let mut escrow = EscrowCase {
+            id: escrow_id,
+            payer,
+            payee,
+            arbiter,
+            token,
+            amount,
+            terms_hash,
+            terms_uri: options.terms_uri,
+            release_after,
+            accept_by,
+            required_approvals,
+            release_approvers: Vec::new(env),
+            refund_approvers: Vec::new(env),
+            status,
+            dispute_fee: options.dispute_fee,
+            arbiter_fee_bps: options.arbiter_fee_bps,
+            vesting_duration: options.vesting_duration,
+            vested_paid: 0,
+            protocol_fee_bps,
+            disputed_by: None,
+            dispute_reason: None,
+            settlement_by: None,
+            settlement_amount: 0,
+            yield_position: YieldPosition {
+                mode: YieldMode::Idle,
+                staked: 0,
+                reward_debt: 0,
+                earned: 0,
+            },
+            payee_amount: 0,
+            payer_amount: 0,
+            arbiter_amount: 0,
+            protocol_amount: 0,
+            closed_at: 0,
+        };
+
+        token::Client::new(env, &escrow.token).transfer(
+            &escrow.payer,
+            &env.current_contract_address(),
+            &transfer_amount,
+        );
+        if let YieldMode::Staked(config) = options.yield_mode {
+            Self::stake(env, &mut escrow, config)?;
+        }
+
+        env.storage()
+            .instance()
+            .set(&StorageKey::EscrowCount, &escrow_id);
+        Self::record(env, &escrow, EscrowAction::Deposited, &escrow.payer);
+        let ttl = Self::save_escrow(env, &mut escrow);
+        Self::index_escrow(env, Role::Payer, &escrow.payer, escrow_id, ttl);
+        Self::index_escrow(env, Role::Payee, &escrow.payee, escrow_id, ttl);
+        Self::index_escrow(env, Role::Arbiter, &escrow.arbiter, escrow_id, ttl);
+
+        env.events().publish(
+            (symbol_short!("deposit"), escrow_id, escrow.payer),
+            (escrow.payee, escrow.arbiter, escrow.amount),
         );
+
+        Ok(escrow_id)
     }
 
-    fn add_unique_approver(env: &Env, approvers: &mut Vec<Address>, approver: Address) {
-        for existing in approvers.iter() {
-            if existing == approver {
-                panic!("duplicate approval");
+    /// Append an action to a case's audit trail. Call before `save_escrow`,
+    /// which keeps the entry count alive alongside the case.
+    ///
+    /// Each action is its own entry, so recording one costs the same however
+    /// long the trail is. An entry is written once and kept through the
+    /// case's last deadline plus the closed-case retention, rather than
+    /// re-extended with the case, which would touch every entry on each
+    /// save; `prune_closed` deletes it with the case.
+    fn record(env: &Env, escrow: &EscrowCase, action: EscrowAction, actor: &Address) {
+        let storage = env.storage().persistent();
+        let len_key = StorageKey::HistoryLen(escrow.id);
+        let index: u32 = storage.get(&len_key).unwrap_or(0);
+        let slot_key = StorageKey::HistorySlot(escrow.id, index);
+
+        let entry = HistoryEntry {
+            action,
+            actor: actor.clone(),
+            timestamp: env.ledger().timestamp(),
+        };
+        storage.set(&slot_key, &entry);
+        storage.set(&len_key, &(index + 1));
+        let ttl = Self::history_ttl(env, escrow);
+        storage.extend_ttl(&slot_key, ttl, ttl);
+    }
+
+    /// Ledgers a history entry recorded now is kept: through the case's last
+    /// deadline and the closed-case retention after it, plus
+    /// `ESCROW_TTL_BUFFER`.
+    fn history_ttl(env: &Env, escrow: &EscrowCase) -> u32 {
+        let deadline = escrow
+            .release_after
+            .saturating_add(escrow.vesting_duration)
+            .max(escrow.accept_by);
+        let seconds = deadline
+            .saturating_sub(env.ledger().timestamp())
+            .saturating_add(Self::get_closed_retention(env.clone()));
+        ((seconds / LEDGER_SECONDS).min(u32::MAX as u64) as u32)
+            .saturating_add(ESCROW_TTL_BUFFER)
+            .min(env.storage().max_ttl())
+    }
+
+    /// Delete a case's history from the newest entry back, at most `budget`
+    /// entries. Returns whether none is left.
+    fn prune_history(env: &Env, escrow_id: u64, budget: &mut u32) -> bool {
+        let storage = env.storage().persistent();
+        let len_key = StorageKey::HistoryLen(escrow_id);
+        let mut len: u32 = storage.get(&len_key).unwrap_or(0);
+        while len > 0 && *budget > 0 {
+            len -= 1;
+            *budget -= 1;
+            storage.remove(&StorageKey::HistorySlot(escrow_id, len));
+        }
+        if len > 0 {
+            storage.set(&len_key, &len);
+            return false;
+        }
+        storage.remove(&len_key);
+        true
+    }
+
+    /// Put a case in the next free slot of a participant index, keeping the
+    /// entries alive at least as long as the case (`ttl` ledgers). Each case
+    /// gets its own slot entry, so the cost doesn't grow with the index.
+    fn index_escrow(env: &Env, role: Role, participant: &Address, escrow_id: u64, ttl: u32) {
+        let storage = env.storage().persistent();
+        let len_key = StorageKey::Index(role, participant.clone());
+        let slot: u32 = storage.get(&len_key).unwrap_or(0);
+        let slot_key = StorageKey::IndexSlot(role, participant.clone(), slot);
+        let pos_key = StorageKey::IndexPos(role, escrow_id);
+
+        storage.set(&slot_key, &escrow_id);
+        storage.set(&pos_key, &slot);
+        storage.set(&len_key, &(slot + 1));
+        for key in [len_key, slot_key, pos_key] {
+            storage.extend_ttl(&key, ttl, ttl);
+        }
+    }
+
+    /// Store a case, keeping it alive through its deadlines while it's open.
+    ///
+    /// The first save after a case closes stamps `closed_at`, queues it for
+    /// `prune_closed`, and keeps it alive through the closed-case retention.
+    ///
+    /// # Returns
+    /// * `u32` - Ledgers the entry is now guaranteed to live; 0 for closed cases
+    fn save_escrow(env: &Env, escrow: &mut EscrowCase) -> u32 {
+        let open = matches!(
+            escrow.status,
+            EscrowStatus::Pending | EscrowStatus::Offered | EscrowStatus::Disputed
+        );
+        let closing = !open && escrow.closed_at == 0;
+        if closing {
+            escrow.closed_at = env.ledger().timestamp();
+        }
+
+        let key = StorageKey::Escrow(escrow.id);
+        env.storage().persistent().set(&key, escrow);
+        if open {
+            return Self::extend_escrow_ttl(env, escrow);
+        }
+        if closing {
+            let tail: u64 = env
+                .storage()
+                .instance()
+                .get(&StorageKey::ClosedTail)
+                .unwrap_or(0);
+            let slot = StorageKey::Closed(tail);
+            env.storage().persistent().set(&slot, &escrow.id);
+            env.storage()
+                .instance()
+                .set(&StorageKey::ClosedTail, &(tail + 1));
+
+            let retention = Self::get_closed_retention(env.clone());
+            let retain = ((retention / LEDGER_SECONDS).min(u32::MAX as u64) as u32)
+                .saturating_add(ESCROW_TTL_BUFFER)
+                .min(env.storage().max_ttl());
+            env.storage().persistent().extend_ttl(&key, retain, retain);
+            env.storage().persistent().extend_ttl(
+                &StorageKey::HistoryLen(escrow.id),
+                retain,
+                retain,
+            );
+            env.storage().persistent().extend_ttl(&slot, retain, retain);
+            Self::extend_index_ttl(env, escrow, retain);
+        }
+        0
+    }
+
+    /// Drop a case from a participant index, leaving its slot empty.
+    fn unindex_escrow(env: &Env, role: Role, participant: &Address, escrow_id: u64) {
+        let storage = env.storage().persistent();
+        let pos_key = StorageKey::IndexPos(role, escrow_id);
+        if let Some(slot) = storage.get::<_, u32>(&pos_key) {
+            storage.remove(&StorageKey::IndexSlot(role, participant.clone(), slot));
+            storage.remove(&pos_key);
+        }
+    }
+
+    /// Extend a case's entry (and the index entries, contract instance and
+    /// yield pool it depends on) to its last deadline plus `ESCROW_TTL_BUFFER`.
+    fn extend_escrow_ttl(env: &Env, escrow: &EscrowCase) -> u32 {
+        let deadline = escrow
+            .release_after
+            .saturating_add(escrow.vesting_duration)
+            .max(escrow.accept_by);
+        let remaining = (deadline.saturating_sub(env.ledger().timestamp()) / LEDGER_SECONDS)
+            .min(u32::MAX as u64) as u32;
+        let extend_to = remaining
+            .saturating_add(ESCROW_TTL_BUFFER)
+            .min(env.storage().max_ttl());
+
+        let storage = env.storage().persistent();
+        storage.extend_ttl(&StorageKey::Escrow(escrow.id), extend_to, extend_to);
+        storage.extend_ttl(&StorageKey::HistoryLen(escrow.id), extend_to, extend_to);
+        Self::extend_index_ttl(env, escrow, extend_to);
+        if let YieldMode::Staked(config) = &escrow.yield_position.mode {
+            let pool = StorageKey::YieldPool(config.staking.clone());
+            if storage.has(&pool) {
+                storage.extend_ttl(&pool, extend_to, extend_to);
+            }
+        }
+        env.storage().instance().extend_ttl(extend_to, extend_to);
+        extend_to
+    }
+
+    /// Extend the index entries pointing at a case: its slot and position in
+    /// each participant's index, and that index's slot counter.
+    fn extend_index_ttl(env: &Env, escrow: &EscrowCase, ttl: u32) {
+        let storage = env.storage().persistent();
+        for (role, participant) in [
+            (Role::Payer, &escrow.payer),
+            (Role::Payee, &escrow.payee),
+            (Role::Arbiter, &escrow.arbiter),
+        ] {
+            let pos_key = StorageKey::IndexPos(role, escrow.id);
+            let Some(slot) = storage.get::<_, u32>(&pos_key) else {
+                continue;
+            };
+            storage.extend_ttl(&pos_key, ttl, ttl);
+            storage.extend_ttl(
+                &StorageKey::IndexSlot(role, participant.clone(), slot),
+                ttl,
+                ttl,
+            );
+            storage.extend_ttl(&StorageKey::Index(role, participant.clone()), ttl, ttl);
+        }
+    }
+
+    /// Cases in index slots `offset..offset + limit`, skipping empty slots.
+    fn page(
+        env: &Env,
+        role: Role,
+        participant: Address,
+        offset: u32,
+        limit: u32,
+    ) -> Vec<EscrowCase> {
+        let len = Self::get_index_len(env.clone(), role, participant.clone());
+        let end = offset.saturating_add(limit.min(MAX_PAGE_SIZE)).min(len);
+
+        let mut result = Vec::new(env);
+        for slot in offset..end {
+            let slot_key = StorageKey::IndexSlot(role, participant.clone(), slot);
+            let Some(id) = env.storage().persistent().get::<_, u64>(&slot_key) else {
+                continue;
+            };
+            if let Ok(escrow) = Self::get_escrow(env.clone(), id) {
+                result.push_back(escrow);
+            }
+        }
+        result
+    }
+
+    /// Protocol fee owed on `base` of the payee's funds at the case's rate.
+    fn protocol_cut(escrow: &EscrowCase, base: u128) -> u128 {
+        Self::mul_div(
+            base,
+            escrow.protocol_fee_bps as u128,
+            BPS_DENOMINATOR as u128,
+        )
+    }
+
+    /// Send `amount` of a case's token to the protocol fee recipient.
+    fn pay_protocol_fee(env: &Env, escrow: &EscrowCase, amount: u128) {
+        if amount == 0 {
+            return;
+        }
+        // A case only carries a fee rate if a fee was configured at deposit
+        let fee: ProtocolFee = env
+            .storage()
+            .instance()
+            .get(&StorageKey::ProtocolFee)
+            .unwrap();
+        token::Client::new(env, &escrow.token).transfer(
+            &env.current_contract_address(),
+            &fee.recipient,
+            &(amount as i128),
+        );
+        env.events()
+            .publish((symbol_short!("proto_fee"), escrow.id), amount);
+    }
+
+    /// Arbiter fee owed on releasing/refunding `base`, zero unless the arbiter is among `approvers`.
+    fn arbiter_cut(escrow: &EscrowCase, base: u128, approvers: &Vec<Address>) -> u128 {
+        if !approvers.contains(&escrow.arbiter) {
+            return 0;
+        }
+        Self::mul_div(
+            base,
+            escrow.arbiter_fee_bps as u128,
+            BPS_DENOMINATOR as u128,
+        )
+    }
+
+    /// Total amount vested to the payee at `now`.
+    fn vested(escrow: &EscrowCase, now: u64) -> u128 {
+        let elapsed = now.saturating_sub(escrow.release_after);
+        if elapsed >= escrow.vesting_duration {
+            return escrow.amount;
+        }
+        Self::mul_div(
+            escrow.amount,
+            elapsed as u128,
+            escrow.vesting_duration as u128,
+        )
+    }
+
+    /// `value * numerator / denominator` for `numerator <= denominator`, split so the
+    /// product can't overflow for amounts near i128::MAX.
+    fn mul_div(value: u128, numerator: u128, denominator: u128) -> u128 {
+        value / denominator * numerator + value % denominator * numerator / denominator
+    }
+
+    /// Pay out a closed case's recorded settlement amounts, less vested
+    /// amounts the payee already withdrew (and the fees taken from them), and
+    /// any staking rewards it earned.
+    fn settle(env: &Env, escrow: &mut EscrowCase) -> Result<(), EscrowError> {
+        Self::unstake(env, escrow, escrow.yield_position.staked)?;
+        Self::pay_yield(env, escrow);
+
+        let fee = escrow.protocol_amount - Self::protocol_cut(escrow, escrow.vested_paid);
+        Self::pay_protocol_fee(env, escrow, fee);
+
+        let client = token::Client::new(env, &escrow.token);
+        let contract = env.current_contract_address();
+        for (to, amount) in [
+            (
+                &escrow.payee,
+                escrow.payee_amount - escrow.vested_paid - fee,
+            ),
+            (&escrow.payer, escrow.payer_amount),
+            (&escrow.arbiter, escrow.arbiter_amount),
+        ] {
+            // Amounts never exceed the deposit, which fit in i128 at deposit time
+            if amount > 0 {
+                client.transfer(&contract, to, &(amount as i128));
+            }
+        }
+        Ok(())
+    }
+
+    /// Stake a new case's whole amount and open its yield position.
+    fn stake(env: &Env, escrow: &mut EscrowCase, config: YieldConfig) -> Result<(), EscrowError> {
+        let mut pool = Self::harvest(env, &config.staking, &escrow.token)?;
+        let contract = env.current_contract_address();
+        let amount = escrow.amount as i128;
+
+        // The staking contract pulls the tokens from us inside its own call,
+        // so that nested transfer needs explicit authorization
+        env.authorize_as_current_contract(vec![
+            env,
+            InvokerContractAuthEntry::Contract(SubContractInvocation {
+                context: ContractContext {
+                    contract: escrow.token.clone(),
+                    fn_name: Symbol::new(env, "transfer"),
+                    args: (contract.clone(), config.staking.clone(), amount).into_val(env),
+                },
+                sub_invocations: vec![env],
+            }),
+        ]);
+        let staked = StakingClient::new(env, &config.staking).try_stake(&contract, &amount, &0);
+        if !matches!(staked, Ok(Ok(()))) {
+            return Err(EscrowError::StakingUnavailable);
+        }
+
+        pool.principal += escrow.amount;
+        env.storage()
+            .persistent()
+            .set(&StorageKey::YieldPool(config.staking.clone()), &pool);
+        escrow.yield_position = YieldPosition {
+            reward_debt: Self::accrued(escrow.amount, &pool)?,
+            staked: escrow.amount,
+            earned: 0,
+            mode: YieldMode::Staked(config),
+        };
+        Ok(())
+    }
+
+    /// Pull `amount` of a case's principal back from staking, crediting the
+    /// rewards it earned so far. No-op for cases that aren't staked. Fails
+    /// with `StakingUnavailable` if staking refuses the unstake (paused, in
+    /// emergency mode, ...), leaving the case as it was to retry later.
+    fn unstake(env: &Env, escrow: &mut EscrowCase, amount: u128) -> Result<(), EscrowError> {
+        let mut position = escrow.yield_position.clone();
+        let YieldMode::Staked(config) = &position.mode else {
+            return Ok(());
+        };
+        let staking = config.staking.clone();
+        let mut pool = Self::harvest(env, &staking, &escrow.token)?;
+
+        position.earned += Self::accrued(position.staked, &pool)? - position.reward_debt;
+        if amount > 0 {
+            let unstaked = StakingClient::new(env, &staking)
+                .try_unstake(&env.current_contract_address(), &(amount as i128));
+            if !matches!(unstaked, Ok(Ok(()))) {
+                return Err(EscrowError::StakingUnavailable);
             }
+            position.staked -= amount;
+            pool.principal -= amount;
+        }
+        position.reward_debt = Self::accrued(position.staked, &pool)?;
+
+        env.storage()
+            .persistent()
+            .set(&StorageKey::YieldPool(staking), &pool);
+        escrow.yield_position = position;
+        Ok(())
+    }
+
+    /// Claim this contract's pending rewards from `staking` and spread them
+    /// over the pool's principal. Only what actually arrives in `token` is
+    /// credited; a claim staking refuses (nothing pending, reward budget
+    /// exhausted, paused, ...) simply yields nothing this time.
+    fn harvest(env: &Env, staking: &Address, token: &Address) -> Result<YieldPool, EscrowError> {
+        let mut pool: YieldPool = env
+            .storage()
+            .persistent()
+            .get(&StorageKey::YieldPool(staking.clone()))
+            .unwrap_or_default();
+        if pool.principal == 0 {
+            return Ok(pool);
+        }
+
+        let contract = env.current_contract_address();
+        let balance = token::Client::new(env, token);
+        let before = balance.balance(&contract);
+        if !matches!(
+            StakingClient::new(env, staking).try_claim_rewards(&contract),
+            Ok(Ok(()))
+        ) {
+            return Ok(pool);
+        }
+        let received = balance.balance(&contract) - before;
+        if received > 0 {
+            let per_share = (received as u128)
+                .checked_mul(ACC_SCALE)
+                .ok_or(EscrowError::YieldOverflow)?
+                / pool.principal;
+            pool.acc_per_share = pool
+                .acc_per_share
+                .checked_add(per_share)
+                .ok_or(EscrowError::YieldOverflow)?;
+        }
+        Ok(pool)
+    }
+
+    /// Pool rewards attributable to `staked` principal since the pool opened.
+    fn accrued(staked: u128, pool: &YieldPool) -> Result<u128, EscrowError> {
+        staked
+            .checked_mul(pool.acc_per_share)
+            .map(|scaled| scaled / ACC_SCALE)
+            .ok_or(EscrowError::YieldOverflow)
+    }
+
+    /// Send a closed case's staking rewards to the collector, or split them
+    /// between payee and payer.
+    fn pay_yield(env: &Env, escrow: &EscrowCase) {
+        let position = &escrow.yield_position;
+        let YieldMode::Staked(config) = &position.mode else {
+            return;
+        };
+        if position.earned == 0 {
+            return;
+        }
+        let client = token::Client::new(env, &escrow.token);
+        let contract = env.current_contract_address();
+        let (payee_part, payer_part) = match &config.collector {
+            Some(collector) => {
+                client.transfer(&contract, collector, &(position.earned as i128));
+                (0, 0)
+            }
+            None => {
+                let payee_part = Self::mul_div(
+                    position.earned,
+                    config.payee_bps as u128,
+                    BPS_DENOMINATOR as u128,
+                );
+                (payee_part, position.earned - payee_part)
+            }
+        };
+        for (to, amount) in [(&escrow.payee, payee_part), (&escrow.payer, payer_part)] {
+            if amount > 0 {
+                client.transfer(&contract, to, &(amount as i128));
+            }
+        }
+
+        env.events().publish(
+            (symbol_short!("yield"), escrow.id),
+            (position.earned, payee_part, payer_part),
+        );
+    }
+
+    fn require_admin(env: &Env) -> Result<(), EscrowError> {
+        let admin: Address = env
+            .storage()
+            .instance()
+            .get(&StorageKey::Admin)
+            .ok_or(EscrowError::NotInitialized)?;
+        admin.require_auth();
+        Ok(())
+    }
+
+    fn require_party(escrow: &EscrowCase, addr: &Address) -> Result<(), EscrowError> {
+        if *addr == escrow.payer || *addr == escrow.payee || *addr == escrow.arbiter {
+            Ok(())
+        } else {
+            Err(EscrowError::NotAParty)
+        }
+    }
+
+    fn add_unique_approver(
+        env: &Env,
+        approvers: &mut Vec<Address>,
+        approver: Address,
+    ) -> Result<(), EscrowError> {
+        if approvers.contains(&approver) {
+            return Err(EscrowError::DuplicateApproval);
         }
         approvers.push_back(approver);
 
         // Extend instance TTL to encourage persistence in longer-running test scenarios.
         env.storage().instance().extend_ttl(100, 1000);
+        Ok(())
     }
 }
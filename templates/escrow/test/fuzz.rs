@@ -0,0 +1,281 @@
+// Randomized calls against every state-changing entrypoint with boundary
+// values (extreme amounts, fees, basis points, windows and ledger times).
+// Every failure must surface as an `EscrowError`; a trap from an overflow or
+// unwrap means an entrypoint is missing a check.
+use escrow_contract::{EscrowContract, EscrowContractClient, EscrowError, EscrowOptions, EscrowRequest, Role};
+use soroban_sdk::{
+    testutils::{Address as _, Ledger as _},
+    token::StellarAssetClient,
+    Address, BytesN, Env, InvokeError, String, Vec,
+};
+
+const ROUNDS: u64 = 24;
+/// Highest ledger sequence a round reaches, under the default minimum entry TTL
+const MAX_SEQUENCE: u32 = 4_000;
+const STEPS: u32 = 60;
+
+// xorshift64: deterministic, so a failing seed reproduces exactly
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: u64) -> u64 {
+        self.next() % n
+    }
+
+    fn flag(&mut self) -> bool {
+        self.below(2) == 0
+    }
+
+    fn amount(&mut self) -> u128 {
+        match self.below(7) {
+            0 => 0,
+            1 => u128::MAX,
+            2 => i128::MAX as u128,
+            3 => i128::MAX as u128 + 1,
+            _ => self.below(2_000) as u128,
+        }
+    }
+
+    fn bps(&mut self) -> u32 {
+        match self.below(5) {
+            0 => 0,
+            1 => u32::MAX,
+            2 => 10_001,
+            _ => self.below(10_001) as u32,
+        }
+    }
+
+    fn approvals(&mut self) -> u32 {
+        match self.below(8) {
+            0 => 0,
+            1 => u32::MAX,
+            _ => 1 + self.below(3) as u32,
+        }
+    }
+
+    fn time(&mut self) -> u64 {
+        match self.below(5) {
+            0 => 0,
+            1 => u64::MAX,
+            2 => u64::MAX - 1,
+            _ => self.below(10_000),
+        }
+    }
+}
+
+struct Harness<'a> {
+    env: &'a Env,
+    client: EscrowContractClient<'a>,
+    token: Address,
+    parties: [Address; 4],
+    rng: Rng,
+}
+
+// Ok, or a typed contract error. `InvokeError::Abort` is a trap.
+fn assert_typed<T, C>(call: &str, result: Result<Result<T, C>, Result<EscrowError, InvokeError>>) {
+    if let Err(Err(err)) = result {
+        panic!("{call} trapped instead of returning an EscrowError: {err:?}");
+    }
+}
+
+impl Harness<'_> {
+    fn address(&mut self) -> Address {
+        self.parties[self.rng.below(4) as usize].clone()
+    }
+
+    fn escrow_id(&mut self) -> u64 {
+        match self.rng.below(6) {
+            0 => u64::MAX,
+            1 => 0,
+            _ => 1 + self.rng.below(3),
+        }
+    }
+
+    fn hash(&mut self) -> BytesN<32> {
+        BytesN::from_array(self.env, &[self.rng.below(4) as u8; 32])
+    }
+
+    // Boundary values are the exception, so enough cases open to reach the
+    // later entrypoints
+    fn options(&mut self) -> EscrowOptions {
+        let terms_uri = match self.rng.below(8) {
+            0 => Some(String::from_str(self.env, &"x".repeat(300))),
+            1 => Some(String::from_str(self.env, "ipfs://terms")),
+            _ => None,
+        };
+        EscrowOptions {
+            dispute_fee: if self.rng.below(4) == 0 { self.rng.amount() } else { self.rng.below(100) as u128 },
+            arbiter_fee_bps: if self.rng.below(4) == 0 { self.rng.bps() } else { self.rng.below(500) as u32 },
+            accept_window: if self.rng.flag() { 0 } else { self.rng.time() },
+            vesting_duration: if self.rng.flag() { 0 } else { self.rng.time() },
+            terms_uri,
+            ..Default::default()
+        }
+    }
+
+    // Payer, payee and arbiter, usually distinct
+    fn roles(&mut self) -> (Address, Address, Address) {
+        if self.rng.below(4) == 0 {
+            return (self.address(), self.address(), self.address());
+        }
+        let first = self.rng.below(4) as usize;
+        let pick = |i: u64| self.parties[(first + i as usize) % 4].clone();
+        (pick(0), pick(1), pick(2))
+    }
+
+    fn release_after(&mut self) -> u64 {
+        let now = self.env.ledger().timestamp();
+        match self.rng.below(4) {
+            0 => now,
+            1 => self.rng.time(),
+            _ => now.saturating_add(self.rng.below(1_000)),
+        }
+    }
+
+    fn step(&mut self) {
+        match self.rng.below(24) {
+            0..=3 => {
+                let (payer, payee, arbiter) = self.roles();
+                let (amount, release_after, approvals) = (self.rng.amount(), self.release_after(), self.rng.approvals());
+                let (terms, options) = (self.hash(), self.options());
+                assert_typed(
+                    "deposit",
+                    self.client.try_deposit(&payer, &payee, &arbiter, &self.token, &amount, &release_after, &approvals, &terms, &options),
+                );
+            }
+            4 => {
+                let (payer, payee, arbiter) = self.roles();
+                let mut requests = Vec::new(self.env);
+                for _ in 0..self.rng.below(3) {
+                    let request = EscrowRequest {
+                        payee: payee.clone(),
+                        arbiter: arbiter.clone(),
+                        token: self.token.clone(),
+                        amount: self.rng.amount(),
+                        release_after: self.release_after(),
+                        required_approvals: self.rng.approvals(),
+                        terms_hash: self.hash(),
+                        options: self.options(),
+                    };
+                    requests.push_back(request);
+                }
+                assert_typed("deposit_batch", self.client.try_deposit_batch(&payer, &requests));
+            }
+            5 => {
+                let id = self.escrow_id();
+                assert_typed("accept", self.client.try_accept(&id));
+                let id = self.escrow_id();
+                assert_typed("withdraw", self.client.try_withdraw(&id));
+            }
+            6..=8 => {
+                let (id, approver) = (self.escrow_id(), self.address());
+                assert_typed("release", self.client.try_release(&id, &approver));
+            }
+            9 | 10 => {
+                let (id, approver) = (self.escrow_id(), self.address());
+                assert_typed("refund", self.client.try_refund(&id, &approver));
+            }
+            11 => {
+                let (party, id, reason) = (self.address(), self.escrow_id(), self.hash());
+                assert_typed("raise_dispute", self.client.try_raise_dispute(&party, &id, &reason));
+            }
+            12 => {
+                let (arbiter, id) = (self.address(), self.escrow_id());
+                let (payee_amount, fee) = (self.rng.amount(), self.rng.amount());
+                assert_typed("resolve_dispute", self.client.try_resolve_dispute(&arbiter, &id, &payee_amount, &fee));
+            }
+            13 => {
+                let (party, id, payee_amount) = (self.address(), self.escrow_id(), self.rng.amount());
+                assert_typed("propose_settlement", self.client.try_propose_settlement(&party, &id, &payee_amount));
+                let (party, id, payee_amount) = (self.address(), self.escrow_id(), self.rng.amount());
+                assert_typed("accept_settlement", self.client.try_accept_settlement(&party, &id, &payee_amount));
+            }
+            14 => {
+                let id = self.escrow_id();
+                assert_typed("withdraw_vested", self.client.try_withdraw_vested(&id));
+            }
+            15 => {
+                let (id, new_payee) = (self.escrow_id(), self.address());
+                assert_typed("reassign_payee", self.client.try_reassign_payee(&id, &new_payee));
+            }
+            16 => {
+                let id = self.escrow_id();
+                assert_typed("bump_escrow", self.client.try_bump_escrow(&id));
+                let limit = match self.rng.below(3) {
+                    0 => u32::MAX,
+                    _ => self.rng.below(5) as u32,
+                };
+                assert_typed("prune_closed", self.client.try_prune_closed(&limit));
+            }
+            17 => {
+                let (bps, recipient) = (self.rng.bps(), self.address());
+                assert_typed("set_fee", self.client.try_set_fee(&bps, &recipient));
+                let (account, exempt) = (self.address(), self.rng.flag());
+                assert_typed("set_fee_exempt", self.client.try_set_fee_exempt(&account, &exempt));
+            }
+            18 => {
+                let seconds = self.rng.time();
+                assert_typed("set_closed_retention", self.client.try_set_closed_retention(&seconds));
+            }
+            19 => {
+                let id = self.escrow_id();
+                assert_typed("get_escrow", self.client.try_get_escrow(&id));
+                let (offset, limit) = (self.rng.approvals(), self.rng.approvals());
+                assert_typed("get_history", self.client.try_get_history(&id, &offset, &limit));
+                let (who, offset, limit) = (self.address(), self.rng.below(4) as u32, self.rng.approvals());
+                // Infallible views: a trap fails the test on its own
+                self.client.get_escrows_by_payer(&who, &offset, &limit);
+                self.client.get_index_len(&Role::Arbiter, &who);
+            }
+            _ => {
+                // Time and sequence only move forward, as on a real ledger; time
+                // jumps may be large. Sequence stays below the minimum entry TTL
+                // so storage isn't archived.
+                let timestamp = match self.rng.below(4) {
+                    0 => u64::MAX,
+                    _ => self.env.ledger().timestamp().saturating_add(self.rng.below(100_000)),
+                };
+                let step = self.rng.below(300) as u32;
+                let sequence = self.env.ledger().sequence().saturating_add(step).min(MAX_SEQUENCE);
+                self.env.ledger().with_mut(|li| {
+                    li.timestamp = timestamp;
+                    li.sequence_number = sequence;
+                });
+            }
+        }
+    }
+}
+
+#[test]
+fn fuzz_entrypoints_fail_with_typed_errors() {
+    for seed in 1..=ROUNDS {
+        let env = Env::default();
+        env.mock_all_auths();
+        env.cost_estimate().budget().reset_unlimited();
+
+        let contract_id = env.register(EscrowContract, ());
+        let client = EscrowContractClient::new(&env, &contract_id);
+        let token = env.register_stellar_asset_contract_v2(Address::generate(&env)).address();
+        let parties = [Address::generate(&env), Address::generate(&env), Address::generate(&env), Address::generate(&env)];
+        let minter = StellarAssetClient::new(&env, &token);
+        for party in parties.iter() {
+            minter.mint(party, &10_000);
+        }
+        // Half the rounds run with an admin, so the fee paths get exercised
+        if seed % 2 == 0 {
+            client.initialize(&parties[0]);
+        }
+
+        let mut harness = Harness { env: &env, client, token, parties, rng: Rng(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15)) };
+        for _ in 0..STEPS {
+            harness.step();
+        }
+    }
+}
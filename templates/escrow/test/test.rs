@@ -1,18 +1,120 @@
 #![cfg(test)]
 
-use escrow_contract::{EscrowContract, EscrowContractClient, EscrowStatus};
-use soroban_sdk::{testutils::{Address as _, Ledger as _}, Address, Env};
+use escrow_contract::{
+    EscrowAction, EscrowContract, EscrowContractClient, EscrowError, EscrowOptions, EscrowRequest, EscrowStatus,
+    HistoryEntry, ProtocolFee, Role, YieldConfig, YieldMode, DEFAULT_CLOSED_RETENTION, ESCROW_TTL_BUFFER, LEDGER_SECONDS,
+    MAX_HISTORY_PRUNE,
+};
+use soroban_sdk::{
+    contract, contractimpl, contracttype, symbol_short, token, vec,
+    testutils::{storage::Persistent as _, Address as _, AuthorizedFunction, Events as _, Ledger as _},
+    token::{StellarAssetClient, TokenClient},
+    xdr::ToXdr,
+    Address, BytesN, Env, IntoVal, String, Symbol, Val, Vec,
+};
+
+// --- Mock staking contract ---
+
+#[contracttype]
+enum MockKey {
+    Token,
+    Staked(Address),
+    Pending(Address),
+    PayoutCap,
+    ClaimsPaused,
+    UnstakingPaused,
+}
+
+/// Minimal stand-in for the staking template: rewards are credited by the
+/// test through `add_rewards` instead of accruing over time.
+#[contract]
+pub struct MockStaking;
+
+#[contractimpl]
+impl MockStaking {
+    pub fn init(env: Env, token: Address) {
+        env.storage().instance().set(&MockKey::Token, &token);
+    }
+
+    pub fn stake(env: Env, user: Address, amount: i128, _lock_duration: u64) {
+        user.require_auth();
+        Self::token(&env).transfer(&user, &env.current_contract_address(), &amount);
+        let staked = Self::staked(env.clone(), user.clone());
+        env.storage().instance().set(&MockKey::Staked(user), &(staked + amount));
+    }
+
+    pub fn unstake(env: Env, user: Address, amount: i128) {
+        user.require_auth();
+        assert!(!env.storage().instance().has(&MockKey::UnstakingPaused), "Unstaking paused");
+        let staked = Self::staked(env.clone(), user.clone());
+        assert!(amount <= staked, "Invalid unstake amount");
+        env.storage().instance().set(&MockKey::Staked(user.clone()), &(staked - amount));
+        Self::token(&env).transfer(&env.current_contract_address(), &user, &amount);
+    }
+
+    pub fn claim_rewards(env: Env, user: Address) {
+        user.require_auth();
+        assert!(!env.storage().instance().has(&MockKey::ClaimsPaused), "Claims paused");
+        let pending = Self::get_pending_rewards(env.clone(), user.clone());
+        assert!(pending > 0, "No rewards to claim");
+        env.storage().instance().set(&MockKey::Pending(user.clone()), &0i128);
+        // Like a drained reward budget: pending overstates what gets paid
+        let cap: i128 = env.storage().instance().get(&MockKey::PayoutCap).unwrap_or(i128::MAX);
+        Self::token(&env).transfer(&env.current_contract_address(), &user, &pending.min(cap));
+    }
+
+    pub fn get_pending_rewards(env: Env, user: Address) -> i128 {
+        env.storage().instance().get(&MockKey::Pending(user)).unwrap_or(0)
+    }
+
+    pub fn staked(env: Env, user: Address) -> i128 {
+        env.storage().instance().get(&MockKey::Staked(user)).unwrap_or(0)
+    }
+
+    /// Pay at most `cap` per claim, whatever is pending.
+    pub fn set_payout_cap(env: Env, cap: i128) {
+        env.storage().instance().set(&MockKey::PayoutCap, &cap);
+    }
+
+    /// Make `claim_rewards` and/or `unstake` fail, as a paused staking contract would.
+    pub fn set_paused(env: Env, claims: bool, unstaking: bool) {
+        for (key, paused) in [(MockKey::ClaimsPaused, claims), (MockKey::UnstakingPaused, unstaking)] {
+            if paused {
+                env.storage().instance().set(&key, &true);
+            } else {
+                env.storage().instance().remove(&key);
+            }
+        }
+    }
+
+    /// Credit `user` with rewards; the test mints the backing tokens.
+    pub fn add_rewards(env: Env, user: Address, amount: i128) {
+        let pending = Self::get_pending_rewards(env.clone(), user.clone());
+        env.storage().instance().set(&MockKey::Pending(user), &(pending + amount));
+    }
+
+    fn token(env: &Env) -> token::Client<'_> {
+        let token: Address = env.storage().instance().get(&MockKey::Token).unwrap();
+        token::Client::new(env, &token)
+    }
+}
 
 // --- Helpers ---
 
-fn setup<'a>(env: &'a Env) -> (EscrowContractClient<'a>, Address, Address, Address) {
+fn setup<'a>(env: &'a Env) -> (EscrowContractClient<'a>, Address, Address, Address, Address) {
     env.mock_all_auths();
-    let contract_id = env.register_contract(None, EscrowContract);
+    let contract_id = env.register(EscrowContract, ());
     let client = EscrowContractClient::new(env, &contract_id);
     let payer   = Address::generate(env);
     let payee   = Address::generate(env);
     let arbiter = Address::generate(env);
-    (client, payer, payee, arbiter)
+    let token = env.register_stellar_asset_contract_v2(Address::generate(env)).address();
+    StellarAssetClient::new(env, &token).mint(&payer, &1_000_000);
+    (client, payer, payee, arbiter, token)
+}
+
+fn balance(env: &Env, token: &Address, who: &Address) -> i128 {
+    TokenClient::new(env, token).balance(who)
 }
 
 /// Advance the ledger timestamp by `seconds`.
@@ -22,8 +124,23 @@ fn advance_time(env: &Env, seconds: u64) {
 }
 
 /// Create a standard escrow (release_after = current time, required_approvals = 1).
-fn create_escrow(client: &EscrowContractClient, payer: &Address, payee: &Address, arbiter: &Address, amount: u128, release_after: u64, approvals: u32) -> u64 {
-    client.deposit(payer, payee, arbiter, &amount, &release_after, &approvals)
+#[allow(clippy::too_many_arguments)]
+fn create_escrow(client: &EscrowContractClient, token: &Address, payer: &Address, payee: &Address, arbiter: &Address, amount: u128, release_after: u64, approvals: u32) -> u64 {
+    client.deposit(payer, payee, arbiter, token, &amount, &release_after, &approvals, &terms(&client.env), &EscrowOptions::default())
+}
+
+fn terms(env: &Env) -> BytesN<32> {
+    BytesN::from_array(env, &[0xab; 32])
+}
+
+/// Whether an event with exactly these topics was published by the last call.
+fn has_event(env: &Env, topics: Vec<Val>) -> bool {
+    env.events().all().iter().any(|(_, t, _)| t == topics)
+}
+
+/// Create a 1-of-3 escrow of 1000 that allows the arbiter a dispute fee of 50.
+fn create_disputable(client: &EscrowContractClient, token: &Address, payer: &Address, payee: &Address, arbiter: &Address, release_after: u64) -> u64 {
+    client.deposit(payer, payee, arbiter, token, &1000, &release_after, &1, &terms(&client.env), &EscrowOptions { dispute_fee: 50, ..Default::default() })
 }
 
 // =====================
@@ -33,10 +150,10 @@ fn create_escrow(client: &EscrowContractClient, payer: &Address, payee: &Address
 #[test]
 fn test_deposit_creates_escrow() {
     let env = Env::default();
-    let (client, payer, payee, arbiter) = setup(&env);
+    let (client, payer, payee, arbiter, token) = setup(&env);
     let now = env.ledger().timestamp();
 
-    let id = create_escrow(&client, &payer, &payee, &arbiter, 1000, now, 1);
+    let id = create_escrow(&client, &token, &payer, &payee, &arbiter, 1000, now, 1);
 
     assert_eq!(id, 1);
     let escrow = client.get_escrow(&id);
@@ -52,59 +169,132 @@ fn test_deposit_creates_escrow() {
 #[test]
 fn test_deposit_increments_count() {
     let env = Env::default();
-    let (client, payer, payee, arbiter) = setup(&env);
+    let (client, payer, payee, arbiter, token) = setup(&env);
     let now = env.ledger().timestamp();
 
-    create_escrow(&client, &payer, &payee, &arbiter, 100, now, 1);
-    create_escrow(&client, &payer, &payee, &arbiter, 200, now, 1);
-    create_escrow(&client, &payer, &payee, &arbiter, 300, now, 1);
+    create_escrow(&client, &token, &payer, &payee, &arbiter, 100, now, 1);
+    create_escrow(&client, &token, &payer, &payee, &arbiter, 200, now, 1);
+    create_escrow(&client, &token, &payer, &payee, &arbiter, 300, now, 1);
 
     assert_eq!(client.escrow_count(), 3);
 }
 
 #[test]
-#[should_panic(expected = "amount must be greater than zero")]
+#[should_panic(expected = "Error(Contract, #6)")]
 fn test_deposit_zero_amount_panics() {
     let env = Env::default();
-    let (client, payer, payee, arbiter) = setup(&env);
+    let (client, payer, payee, arbiter, token) = setup(&env);
     let now = env.ledger().timestamp();
-    create_escrow(&client, &payer, &payee, &arbiter, 0, now, 1);
+    create_escrow(&client, &token, &payer, &payee, &arbiter, 0, now, 1);
 }
 
 #[test]
-#[should_panic(expected = "payer and payee must differ")]
+#[should_panic(expected = "Error(Contract, #7)")]
 fn test_deposit_payer_equals_payee_panics() {
     let env = Env::default();
-    let (client, payer, _, arbiter) = setup(&env);
+    let (client, payer, _, arbiter, token) = setup(&env);
     let now = env.ledger().timestamp();
-    create_escrow(&client, &payer, &payer, &arbiter, 100, now, 1);
+    create_escrow(&client, &token, &payer, &payer, &arbiter, 100, now, 1);
 }
 
 #[test]
-#[should_panic(expected = "payer and arbiter must differ")]
+#[should_panic(expected = "Error(Contract, #7)")]
 fn test_deposit_payer_equals_arbiter_panics() {
     let env = Env::default();
-    let (client, payer, payee, _) = setup(&env);
+    let (client, payer, payee, _, token) = setup(&env);
     let now = env.ledger().timestamp();
-    create_escrow(&client, &payer, &payee, &payer, 100, now, 1);
+    create_escrow(&client, &token, &payer, &payee, &payer, 100, now, 1);
 }
 
 #[test]
-#[should_panic(expected = "required approvals must be between 1 and 3")]
+#[should_panic(expected = "Error(Contract, #8)")]
 fn test_deposit_invalid_approvals_zero_panics() {
     let env = Env::default();
-    let (client, payer, payee, arbiter) = setup(&env);
+    let (client, payer, payee, arbiter, token) = setup(&env);
     let now = env.ledger().timestamp();
-    create_escrow(&client, &payer, &payee, &arbiter, 100, now, 0);
+    create_escrow(&client, &token, &payer, &payee, &arbiter, 100, now, 0);
 }
 
 #[test]
-#[should_panic(expected = "required approvals must be between 1 and 3")]
+#[should_panic(expected = "Error(Contract, #8)")]
 fn test_deposit_invalid_approvals_over_3_panics() {
     let env = Env::default();
-    let (client, payer, payee, arbiter) = setup(&env);
+    let (client, payer, payee, arbiter, token) = setup(&env);
     let now = env.ledger().timestamp();
-    create_escrow(&client, &payer, &payee, &arbiter, 100, now, 4);
+    create_escrow(&client, &token, &payer, &payee, &arbiter, 100, now, 4);
+}
+
+// =====================
+// BATCH DEPOSIT TESTS
+// =====================
+
+fn request(env: &Env, payee: &Address, arbiter: &Address, token: &Address, amount: u128) -> EscrowRequest {
+    EscrowRequest {
+        payee: payee.clone(),
+        arbiter: arbiter.clone(),
+        token: token.clone(),
+        amount,
+        release_after: env.ledger().timestamp(),
+        required_approvals: 1,
+        terms_hash: terms(env),
+        options: EscrowOptions::default(),
+    }
+}
+
+#[test]
+fn test_deposit_batch_creates_each_case() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let second_payee = Address::generate(&env);
+
+    let mut requests = Vec::new(&env);
+    requests.push_back(request(&env, &payee, &arbiter, &token, 300));
+    requests.push_back(request(&env, &second_payee, &arbiter, &token, 700));
+    let ids = client.deposit_batch(&payer, &requests);
+
+    assert_eq!(ids.len(), 2);
+    assert_eq!(client.get_escrow(&ids.get(0).unwrap()).payee, payee);
+    assert_eq!(client.get_escrow(&ids.get(1).unwrap()).amount, 700);
+    assert_eq!(client.escrow_count(), 2);
+    assert_eq!(balance(&env, &token, &client.address), 1000);
+    assert_eq!(client.get_escrows_by_payer(&payer, &0, &10).len(), 2);
+}
+
+#[test]
+fn test_deposit_batch_is_all_or_nothing() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+
+    let mut requests = Vec::new(&env);
+    requests.push_back(request(&env, &payee, &arbiter, &token, 300));
+    requests.push_back(request(&env, &payee, &arbiter, &token, 0));
+    assert_eq!(
+        client.try_deposit_batch(&payer, &requests),
+        Err(Ok(EscrowError::InvalidAmount))
+    );
+
+    assert_eq!(client.escrow_count(), 0);
+    assert_eq!(balance(&env, &token, &payer), 1_000_000);
+}
+
+#[test]
+fn test_deposit_batch_size_limits() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+
+    assert_eq!(
+        client.try_deposit_batch(&payer, &Vec::new(&env)),
+        Err(Ok(EscrowError::InvalidBatch))
+    );
+
+    let mut requests = Vec::new(&env);
+    for _ in 0..21 {
+        requests.push_back(request(&env, &payee, &arbiter, &token, 1));
+    }
+    assert_eq!(
+        client.try_deposit_batch(&payer, &requests),
+        Err(Ok(EscrowError::InvalidBatch))
+    );
 }
 
 // =====================
@@ -114,10 +304,10 @@ fn test_deposit_invalid_approvals_over_3_panics() {
 #[test]
 fn test_release_after_time_with_1_of_1_approval() {
     let env = Env::default();
-    let (client, payer, payee, arbiter) = setup(&env);
+    let (client, payer, payee, arbiter, token) = setup(&env);
     let now = env.ledger().timestamp();
 
-    let id = create_escrow(&client, &payer, &payee, &arbiter, 500, now, 1);
+    let id = create_escrow(&client, &token, &payer, &payee, &arbiter, 500, now, 1);
 
     // release_after = now, so time condition is already met
     client.release(&id, &payer);
@@ -126,14 +316,14 @@ fn test_release_after_time_with_1_of_1_approval() {
 }
 
 #[test]
-#[should_panic(expected = "release time not reached")]
+#[should_panic(expected = "Error(Contract, #5)")]
 fn test_release_requires_time_to_pass() {
     let env = Env::default();
-    let (client, payer, payee, arbiter) = setup(&env);
+    let (client, payer, payee, arbiter, token) = setup(&env);
     let now = env.ledger().timestamp();
 
     // release_after is 1 hour in the future
-    let id = create_escrow(&client, &payer, &payee, &arbiter, 100, now + 3600, 1);
+    let id = create_escrow(&client, &token, &payer, &payee, &arbiter, 100, now + 3600, 1);
 
     // Time hasn't advanced so release should panic
     client.release(&id, &payer);
@@ -142,10 +332,10 @@ fn test_release_requires_time_to_pass() {
 #[test]
 fn test_release_requires_2_of_3_approvals() {
     let env = Env::default();
-    let (client, payer, payee, arbiter) = setup(&env);
+    let (client, payer, payee, arbiter, token) = setup(&env);
     let now = env.ledger().timestamp();
 
-    let id = create_escrow(&client, &payer, &payee, &arbiter, 1000, now, 2);
+    let id = create_escrow(&client, &token, &payer, &payee, &arbiter, 1000, now, 2);
 
     // First approval - still Pending
     client.release(&id, &payer);
@@ -157,26 +347,26 @@ fn test_release_requires_2_of_3_approvals() {
 }
 
 #[test]
-#[should_panic(expected = "approver must be payer, payee, or arbiter")]
+#[should_panic(expected = "Error(Contract, #3)")]
 fn test_release_outsider_cannot_approve() {
     let env = Env::default();
-    let (client, payer, payee, arbiter) = setup(&env);
+    let (client, payer, payee, arbiter, token) = setup(&env);
     let now = env.ledger().timestamp();
 
-    let id = create_escrow(&client, &payer, &payee, &arbiter, 100, now, 1);
+    let id = create_escrow(&client, &token, &payer, &payee, &arbiter, 100, now, 1);
 
     let outsider = Address::generate(&env);
     client.release(&id, &outsider);
 }
 
 #[test]
-#[should_panic(expected = "duplicate approval")]
+#[should_panic(expected = "Error(Contract, #4)")]
 fn test_duplicate_release_approval_panics() {
     let env = Env::default();
-    let (client, payer, payee, arbiter) = setup(&env);
+    let (client, payer, payee, arbiter, token) = setup(&env);
     let now = env.ledger().timestamp();
 
-    let id = create_escrow(&client, &payer, &payee, &arbiter, 100, now, 2);
+    let id = create_escrow(&client, &token, &payer, &payee, &arbiter, 100, now, 2);
 
     client.release(&id, &payer);
     // Payer approves again - should panic
@@ -184,13 +374,13 @@ fn test_duplicate_release_approval_panics() {
 }
 
 #[test]
-#[should_panic(expected = "escrow not pending")]
+#[should_panic(expected = "Error(Contract, #2)")]
 fn test_release_already_released_panics() {
     let env = Env::default();
-    let (client, payer, payee, arbiter) = setup(&env);
+    let (client, payer, payee, arbiter, token) = setup(&env);
     let now = env.ledger().timestamp();
 
-    let id = create_escrow(&client, &payer, &payee, &arbiter, 100, now, 1);
+    let id = create_escrow(&client, &token, &payer, &payee, &arbiter, 100, now, 1);
 
     // Release once (threshold met)
     client.release(&id, &payer);
@@ -207,11 +397,11 @@ fn test_release_already_released_panics() {
 #[test]
 fn test_refund_with_1_of_1_approval() {
     let env = Env::default();
-    let (client, payer, payee, arbiter) = setup(&env);
+    let (client, payer, payee, arbiter, token) = setup(&env);
     let now = env.ledger().timestamp();
 
     // Refund has no time constraint
-    let id = create_escrow(&client, &payer, &payee, &arbiter, 500, now + 9999, 1);
+    let id = create_escrow(&client, &token, &payer, &payee, &arbiter, 500, now + 9999, 1);
 
     client.refund(&id, &payer);
 
@@ -221,10 +411,10 @@ fn test_refund_with_1_of_1_approval() {
 #[test]
 fn test_refund_requires_2_of_3_approvals() {
     let env = Env::default();
-    let (client, payer, payee, arbiter) = setup(&env);
+    let (client, payer, payee, arbiter, token) = setup(&env);
     let now = env.ledger().timestamp();
 
-    let id = create_escrow(&client, &payer, &payee, &arbiter, 1000, now + 9999, 2);
+    let id = create_escrow(&client, &token, &payer, &payee, &arbiter, 1000, now + 9999, 2);
 
     // First refund approval - still Pending
     client.refund(&id, &payer);
@@ -236,26 +426,26 @@ fn test_refund_requires_2_of_3_approvals() {
 }
 
 #[test]
-#[should_panic(expected = "approver must be payer, payee, or arbiter")]
+#[should_panic(expected = "Error(Contract, #3)")]
 fn test_refund_outsider_cannot_approve() {
     let env = Env::default();
-    let (client, payer, payee, arbiter) = setup(&env);
+    let (client, payer, payee, arbiter, token) = setup(&env);
     let now = env.ledger().timestamp();
 
-    let id = create_escrow(&client, &payer, &payee, &arbiter, 100, now + 9999, 1);
+    let id = create_escrow(&client, &token, &payer, &payee, &arbiter, 100, now + 9999, 1);
 
     let outsider = Address::generate(&env);
     client.refund(&id, &outsider);
 }
 
 #[test]
-#[should_panic(expected = "duplicate approval")]
+#[should_panic(expected = "Error(Contract, #4)")]
 fn test_duplicate_refund_approval_panics() {
     let env = Env::default();
-    let (client, payer, payee, arbiter) = setup(&env);
+    let (client, payer, payee, arbiter, token) = setup(&env);
     let now = env.ledger().timestamp();
 
-    let id = create_escrow(&client, &payer, &payee, &arbiter, 100, now + 9999, 2);
+    let id = create_escrow(&client, &token, &payer, &payee, &arbiter, 100, now + 9999, 2);
 
     client.refund(&id, &payer);
     // Payer tries to refund again
@@ -263,13 +453,13 @@ fn test_duplicate_refund_approval_panics() {
 }
 
 #[test]
-#[should_panic(expected = "escrow not pending")]
+#[should_panic(expected = "Error(Contract, #2)")]
 fn test_refund_already_refunded_panics() {
     let env = Env::default();
-    let (client, payer, payee, arbiter) = setup(&env);
+    let (client, payer, payee, arbiter, token) = setup(&env);
     let now = env.ledger().timestamp();
 
-    let id = create_escrow(&client, &payer, &payee, &arbiter, 100, now + 9999, 1);
+    let id = create_escrow(&client, &token, &payer, &payee, &arbiter, 100, now + 9999, 1);
 
     client.refund(&id, &payer);
     assert_eq!(client.get_escrow(&id).status, EscrowStatus::Refunded);
@@ -286,10 +476,10 @@ fn test_refund_already_refunded_panics() {
 fn test_arbiter_breaks_deadlock_for_release() {
     // In a 2-of-3, payer and arbiter can release even without payee.
     let env = Env::default();
-    let (client, payer, payee, arbiter) = setup(&env);
+    let (client, payer, payee, arbiter, token) = setup(&env);
     let now = env.ledger().timestamp();
 
-    let id = create_escrow(&client, &payer, &payee, &arbiter, 500, now, 2);
+    let id = create_escrow(&client, &token, &payer, &payee, &arbiter, 500, now, 2);
 
     // Payer approves release
     client.release(&id, &payer);
@@ -304,10 +494,10 @@ fn test_arbiter_breaks_deadlock_for_release() {
 fn test_arbiter_breaks_deadlock_for_refund() {
     // In a 2-of-3, payee and arbiter can trigger a refund.
     let env = Env::default();
-    let (client, payer, payee, arbiter) = setup(&env);
+    let (client, payer, payee, arbiter, token) = setup(&env);
     let now = env.ledger().timestamp();
 
-    let id = create_escrow(&client, &payer, &payee, &arbiter, 500, now + 9999, 2);
+    let id = create_escrow(&client, &token, &payer, &payee, &arbiter, 500, now + 9999, 2);
 
     // Payee approves refund
     client.refund(&id, &payee);
@@ -319,13 +509,1623 @@ fn test_arbiter_breaks_deadlock_for_refund() {
 }
 
 // =====================
-// EDGE CASES
+// DISPUTE TESTS
+// =====================
+
+#[test]
+fn test_raise_dispute_records_reason() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let now = env.ledger().timestamp();
+
+    let id = create_disputable(&client, &token, &payer, &payee, &arbiter, now + 3600);
+    let reason = BytesN::from_array(&env, &[7; 32]);
+    client.raise_dispute(&payee, &id, &reason);
+
+    let escrow = client.get_escrow(&id);
+    assert_eq!(escrow.status, EscrowStatus::Disputed);
+    assert_eq!(escrow.disputed_by, Some(payee));
+    assert_eq!(escrow.dispute_reason, Some(reason));
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #12)")]
+fn test_arbiter_cannot_raise_dispute() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let now = env.ledger().timestamp();
+
+    let id = create_disputable(&client, &token, &payer, &payee, &arbiter, now);
+    client.raise_dispute(&arbiter, &id, &BytesN::from_array(&env, &[0; 32]));
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #2)")]
+fn test_dispute_blocks_release() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let now = env.ledger().timestamp();
+
+    let id = create_disputable(&client, &token, &payer, &payee, &arbiter, now + 3600);
+    client.raise_dispute(&payer, &id, &BytesN::from_array(&env, &[1; 32]));
+
+    // Release time passing doesn't reopen the case
+    advance_time(&env, 3600);
+    client.release(&id, &payee);
+}
+
+#[test]
+fn test_resolve_dispute_splits_funds() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let now = env.ledger().timestamp();
+
+    let id = create_disputable(&client, &token, &payer, &payee, &arbiter, now);
+    client.raise_dispute(&payer, &id, &BytesN::from_array(&env, &[1; 32]));
+    client.resolve_dispute(&arbiter, &id, &600, &50);
+
+    let escrow = client.get_escrow(&id);
+    assert_eq!(escrow.status, EscrowStatus::Resolved);
+    assert_eq!(escrow.payee_amount, 600);
+    assert_eq!(escrow.arbiter_amount, 50);
+    assert_eq!(escrow.payer_amount, 350);
+}
+
+#[test]
+fn test_resolve_dispute_without_fee() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let now = env.ledger().timestamp();
+
+    let id = create_disputable(&client, &token, &payer, &payee, &arbiter, now);
+    client.raise_dispute(&payee, &id, &BytesN::from_array(&env, &[1; 32]));
+    client.resolve_dispute(&arbiter, &id, &1000, &0);
+
+    let escrow = client.get_escrow(&id);
+    assert_eq!(escrow.payee_amount, 1000);
+    assert_eq!(escrow.payer_amount, 0);
+    assert_eq!(escrow.arbiter_amount, 0);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #13)")]
+fn test_resolve_dispute_non_arbiter_panics() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let now = env.ledger().timestamp();
+
+    let id = create_disputable(&client, &token, &payer, &payee, &arbiter, now);
+    client.raise_dispute(&payer, &id, &BytesN::from_array(&env, &[1; 32]));
+    client.resolve_dispute(&payee, &id, &1000, &0);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #14)")]
+fn test_resolve_dispute_fee_over_agreed_panics() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let now = env.ledger().timestamp();
+
+    let id = create_disputable(&client, &token, &payer, &payee, &arbiter, now);
+    client.raise_dispute(&payer, &id, &BytesN::from_array(&env, &[1; 32]));
+    client.resolve_dispute(&arbiter, &id, &0, &51);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #15)")]
+fn test_resolve_dispute_split_over_amount_panics() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let now = env.ledger().timestamp();
+
+    let id = create_disputable(&client, &token, &payer, &payee, &arbiter, now);
+    client.raise_dispute(&payer, &id, &BytesN::from_array(&env, &[1; 32]));
+    client.resolve_dispute(&arbiter, &id, &960, &50);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #11)")]
+fn test_resolve_undisputed_escrow_panics() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let now = env.ledger().timestamp();
+
+    let id = create_disputable(&client, &token, &payer, &payee, &arbiter, now);
+    client.resolve_dispute(&arbiter, &id, &500, &0);
+}
+
+// =====================
+// SETTLEMENT TESTS
+// =====================
+
+#[test]
+fn test_accepted_settlement_splits_disputed_case() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let now = env.ledger().timestamp();
+
+    let id = create_disputable(&client, &token, &payer, &payee, &arbiter, now);
+    client.raise_dispute(&payer, &id, &BytesN::from_array(&env, &[1; 32]));
+    client.propose_settlement(&payee, &id, &700);
+    assert!(has_event(&env, (symbol_short!("proposed"), id, payee.clone()).into_val(&env)));
+    assert_eq!(client.get_escrow(&id).settlement_by, Some(payee.clone()));
+
+    client.accept_settlement(&payer, &id, &700);
+    let (_, topics, data) = env.events().all().last().unwrap();
+    assert_eq!(topics, (symbol_short!("settled"), id).into_val(&env));
+    let split: (u128, u128) = data.into_val(&env);
+    assert_eq!(split, (700, 300));
+
+    assert_eq!(client.get_escrow(&id).status, EscrowStatus::Settled);
+    assert_eq!(balance(&env, &token, &payee), 700);
+    assert_eq!(balance(&env, &token, &payer), 999_300);
+    assert_eq!(balance(&env, &token, &arbiter), 0);
+}
+
+#[test]
+fn test_counter_offer_replaces_proposal() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let now = env.ledger().timestamp();
+
+    let id = create_escrow(&client, &token, &payer, &payee, &arbiter, 1000, now + 100, 1);
+    client.propose_settlement(&payer, &id, &500);
+    client.propose_settlement(&payee, &id, &800);
+    assert_eq!(client.try_accept_settlement(&payer, &id, &500), Err(Ok(EscrowError::SettlementMismatch)));
+
+    client.accept_settlement(&payer, &id, &800);
+    assert_eq!(balance(&env, &token, &payee), 800);
+    assert_eq!(balance(&env, &token, &payer), 999_200);
+}
+
+#[test]
+fn test_settlement_after_partial_vesting_splits_remainder() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let now = env.ledger().timestamp();
+
+    let id = create_vesting(&client, &token, &payer, &payee, &arbiter, now);
+    client.propose_settlement(&payee, &id, &800);
+    advance_time(&env, 400);
+    client.withdraw_vested(&id);
+    assert_eq!(client.try_accept_settlement(&payer, &id, &800), Err(Ok(EscrowError::SplitExceedsAmount)));
+
+    client.propose_settlement(&payee, &id, &300);
+    client.accept_settlement(&payer, &id, &300);
+    assert_eq!(balance(&env, &token, &payee), 700);
+    assert_eq!(balance(&env, &token, &payer), 999_300);
+    assert_eq!(balance(&env, &token, &client.address), 0);
+}
+
+#[test]
+fn test_settlement_errors() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let now = env.ledger().timestamp();
+
+    let id = create_escrow(&client, &token, &payer, &payee, &arbiter, 1000, now, 1);
+    assert_eq!(client.try_accept_settlement(&payer, &id, &0), Err(Ok(EscrowError::NoSettlement)));
+    assert_eq!(client.try_propose_settlement(&arbiter, &id, &500), Err(Ok(EscrowError::NotDisputant)));
+    assert_eq!(client.try_propose_settlement(&payer, &id, &1001), Err(Ok(EscrowError::SplitExceedsAmount)));
+
+    client.propose_settlement(&payer, &id, &500);
+    assert_eq!(client.try_accept_settlement(&payer, &id, &500), Err(Ok(EscrowError::NotCounterparty)));
+    assert_eq!(client.try_accept_settlement(&arbiter, &id, &500), Err(Ok(EscrowError::NotCounterparty)));
+
+    client.release(&id, &payer);
+    assert_eq!(client.try_propose_settlement(&payer, &id, &500), Err(Ok(EscrowError::NotPending)));
+    assert_eq!(client.try_accept_settlement(&payee, &id, &500), Err(Ok(EscrowError::NotPending)));
+}
+
+// =====================
+// REASSIGNMENT TESTS
+// =====================
+
+#[test]
+fn test_reassign_payee_by_mutual_consent() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let now = env.ledger().timestamp();
+    let new_payee = Address::generate(&env);
+
+    let id = create_escrow(&client, &token, &payer, &payee, &arbiter, 1000, now, 1);
+    client.reassign_payee(&id, &new_payee);
+    let signers: std::vec::Vec<Address> = env.auths().into_iter().map(|(addr, _)| addr).collect();
+    assert_eq!(signers, [payer.clone(), payee.clone()]);
+    let (_, topics, data) = env.events().all().last().unwrap();
+    assert_eq!(topics, (symbol_short!("reassign"), id).into_val(&env));
+    let change: (Address, Address) = data.into_val(&env);
+    assert_eq!(change, (payee.clone(), new_payee.clone()));
+
+    assert_eq!(client.get_escrows_by_payee(&payee, &0, &10).len(), 0);
+    assert_eq!(client.get_escrows_by_payee(&new_payee, &0, &10).get(0).unwrap().id, id);
+
+    client.release(&id, &payer);
+    assert_eq!(balance(&env, &token, &new_payee), 1000);
+    assert_eq!(balance(&env, &token, &payee), 0);
+}
+
+#[test]
+fn test_reassign_payee_near_deadline_keeps_index_ttl() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let now = env.ledger().timestamp();
+    let day = 24 * 60 * 60;
+    let new_payee = Address::generate(&env);
+
+    // The index entries were extended to the release deadline at deposit;
+    // reassigning near it must not shorten them
+    let id = create_escrow(&client, &token, &payer, &payee, &arbiter, 1000, now + 10 * day, 1);
+    advance_time(&env, 9 * day);
+    client.reassign_payee(&id, &new_payee);
+    assert_eq!(client.get_escrows_by_payee(&new_payee, &0, &10).get(0).unwrap().id, id);
+}
+
+#[test]
+fn test_reassign_payee_drops_old_payee_approvals() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let now = env.ledger().timestamp();
+
+    let id = create_escrow(&client, &token, &payer, &payee, &arbiter, 1000, now, 2);
+    client.release(&id, &payee);
+    client.reassign_payee(&id, &Address::generate(&env));
+
+    assert_eq!(client.get_escrow(&id).release_approvers.len(), 0);
+    client.release(&id, &payer);
+    assert_eq!(client.get_escrow(&id).status, EscrowStatus::Pending);
+}
+
+#[test]
+fn test_arbiter_reassigns_payee_in_dispute() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let now = env.ledger().timestamp();
+    let new_payee = Address::generate(&env);
+
+    let id = create_disputable(&client, &token, &payer, &payee, &arbiter, now);
+    client.raise_dispute(&payer, &id, &BytesN::from_array(&env, &[1; 32]));
+    client.reassign_payee(&id, &new_payee);
+    let (signer, _) = env.auths().pop().unwrap();
+    assert_eq!(signer, arbiter);
+
+    client.resolve_dispute(&arbiter, &id, &600, &0);
+    assert_eq!(balance(&env, &token, &new_payee), 600);
+}
+
+#[test]
+fn test_reassign_payee_errors() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let now = env.ledger().timestamp();
+
+    let id = create_escrow(&client, &token, &payer, &payee, &arbiter, 1000, now, 1);
+    assert_eq!(client.try_reassign_payee(&id, &payer), Err(Ok(EscrowError::DuplicateParty)));
+    assert_eq!(client.try_reassign_payee(&id, &arbiter), Err(Ok(EscrowError::DuplicateParty)));
+
+    client.release(&id, &payer);
+    let other = Address::generate(&env);
+    assert_eq!(client.try_reassign_payee(&id, &other), Err(Ok(EscrowError::NotPending)));
+    assert_eq!(client.try_reassign_payee(&99, &other), Err(Ok(EscrowError::NotFound)));
+}
+
+// =====================
+// TOKEN CUSTODY TESTS
+// =====================
+
+#[test]
+fn test_deposit_moves_tokens_into_escrow() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let now = env.ledger().timestamp();
+
+    let id = create_escrow(&client, &token, &payer, &payee, &arbiter, 1000, now, 1);
+
+    assert_eq!(client.get_escrow(&id).token, token);
+    assert_eq!(balance(&env, &token, &client.address), 1000);
+    assert_eq!(balance(&env, &token, &payer), 999_000);
+}
+
+#[test]
+fn test_release_pays_payee() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let now = env.ledger().timestamp();
+
+    let id = create_escrow(&client, &token, &payer, &payee, &arbiter, 1000, now, 1);
+    client.release(&id, &payer);
+
+    assert_eq!(balance(&env, &token, &payee), 1000);
+    assert_eq!(balance(&env, &token, &client.address), 0);
+}
+
+#[test]
+fn test_refund_returns_to_payer() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let now = env.ledger().timestamp();
+
+    let id = create_escrow(&client, &token, &payer, &payee, &arbiter, 1000, now, 2);
+    client.refund(&id, &payee);
+    assert_eq!(balance(&env, &token, &client.address), 1000);
+
+    client.refund(&id, &arbiter);
+    assert_eq!(balance(&env, &token, &payer), 1_000_000);
+    assert_eq!(balance(&env, &token, &client.address), 0);
+}
+
+#[test]
+fn test_resolve_dispute_pays_split() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let now = env.ledger().timestamp();
+
+    let id = create_disputable(&client, &token, &payer, &payee, &arbiter, now);
+    client.raise_dispute(&payer, &id, &BytesN::from_array(&env, &[1; 32]));
+    client.resolve_dispute(&arbiter, &id, &600, &50);
+
+    assert_eq!(balance(&env, &token, &payee), 600);
+    assert_eq!(balance(&env, &token, &arbiter), 50);
+    assert_eq!(balance(&env, &token, &payer), 999_350);
+    assert_eq!(balance(&env, &token, &client.address), 0);
+}
+
+#[test]
+fn test_cases_in_different_tokens() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let now = env.ledger().timestamp();
+    let other = env.register_stellar_asset_contract_v2(Address::generate(&env)).address();
+    StellarAssetClient::new(&env, &other).mint(&payer, &500);
+
+    let first = create_escrow(&client, &token, &payer, &payee, &arbiter, 100, now, 1);
+    let second = create_escrow(&client, &other, &payer, &payee, &arbiter, 500, now, 1);
+    client.release(&second, &payer);
+
+    assert_eq!(balance(&env, &token, &client.address), 100);
+    assert_eq!(balance(&env, &other, &payee), 500);
+    assert_eq!(client.get_escrow(&first).status, EscrowStatus::Pending);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #6)")]
+fn test_deposit_amount_over_i128_panics() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let now = env.ledger().timestamp();
+    create_escrow(&client, &token, &payer, &payee, &arbiter, i128::MAX as u128 + 1, now, 1);
+}
+
 // =====================
+// ARBITER FEE TESTS
+// =====================
+
+/// Create a 2-of-3 escrow of 10_000 with a 2.5% arbiter fee.
+fn create_with_arbiter_fee(client: &EscrowContractClient, token: &Address, payer: &Address, payee: &Address, arbiter: &Address, release_after: u64) -> u64 {
+    client.deposit(payer, payee, arbiter, token, &10_000, &release_after, &2, &terms(&client.env), &EscrowOptions { arbiter_fee_bps: 250, ..Default::default() })
+}
+
+#[test]
+fn test_arbiter_fee_paid_on_release_arbiter_approved() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let now = env.ledger().timestamp();
+
+    let id = create_with_arbiter_fee(&client, &token, &payer, &payee, &arbiter, now);
+    client.release(&id, &payer);
+    client.release(&id, &arbiter);
+
+    let escrow = client.get_escrow(&id);
+    assert_eq!(escrow.arbiter_amount, 250);
+    assert_eq!(escrow.payee_amount, 9_750);
+    assert_eq!(balance(&env, &token, &arbiter), 250);
+    assert_eq!(balance(&env, &token, &payee), 9_750);
+}
+
+#[test]
+fn test_arbiter_fee_paid_on_refund_arbiter_approved() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let now = env.ledger().timestamp();
+
+    let id = create_with_arbiter_fee(&client, &token, &payer, &payee, &arbiter, now + 9999);
+    client.refund(&id, &arbiter);
+    client.refund(&id, &payee);
+
+    assert_eq!(balance(&env, &token, &arbiter), 250);
+    assert_eq!(balance(&env, &token, &payer), 999_750);
+}
+
+#[test]
+fn test_no_arbiter_fee_without_arbiter_approval() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let now = env.ledger().timestamp();
+
+    let id = create_with_arbiter_fee(&client, &token, &payer, &payee, &arbiter, now);
+    client.release(&id, &payer);
+    client.release(&id, &payee);
+
+    assert_eq!(client.get_escrow(&id).arbiter_amount, 0);
+    assert_eq!(balance(&env, &token, &payee), 10_000);
+    assert_eq!(balance(&env, &token, &arbiter), 0);
+}
+
+#[test]
+fn test_arbiter_fee_on_max_amount_does_not_overflow() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let now = env.ledger().timestamp();
+    let amount = i128::MAX as u128;
+    StellarAssetClient::new(&env, &token).mint(&payer, &(i128::MAX - 1_000_000));
+
+    let id = client.deposit(&payer, &payee, &arbiter, &token, &amount, &now, &1, &terms(&client.env), &EscrowOptions { arbiter_fee_bps: 10_000, ..Default::default() });
+    client.release(&id, &arbiter);
+
+    let escrow = client.get_escrow(&id);
+    assert_eq!(escrow.arbiter_amount, amount);
+    assert_eq!(escrow.payee_amount, 0);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #16)")]
+fn test_arbiter_fee_over_100_percent_panics() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let now = env.ledger().timestamp();
+    client.deposit(&payer, &payee, &arbiter, &token, &100, &now, &1, &terms(&client.env), &EscrowOptions { arbiter_fee_bps: 10_001, ..Default::default() });
+}
+
+// =====================
+// PROTOCOL FEE TESTS
+// =====================
+
+/// Make the contract charge `bps` on releases, paid to a fresh recipient.
+fn enable_fee(env: &Env, client: &EscrowContractClient, bps: u32) -> Address {
+    let recipient = Address::generate(env);
+    client.initialize(&Address::generate(env));
+    client.set_fee(&bps, &recipient);
+    recipient
+}
+
+#[test]
+fn test_protocol_fee_deducted_on_release() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let now = env.ledger().timestamp();
+    let recipient = enable_fee(&env, &client, 250);
+
+    let id = create_with_arbiter_fee(&client, &token, &payer, &payee, &arbiter, now);
+    client.release(&id, &payer);
+    client.release(&id, &arbiter);
+    assert!(has_event(&env, (symbol_short!("proto_fee"), id).into_val(&env)));
+
+    // 2.5% of the 9_750 left after the arbiter's cut
+    let case = client.get_escrow(&id);
+    assert_eq!((case.payee_amount, case.arbiter_amount, case.protocol_amount), (9_750, 250, 243));
+    assert_eq!(balance(&env, &token, &payee), 9_507);
+    assert_eq!(balance(&env, &token, &arbiter), 250);
+    assert_eq!(balance(&env, &token, &recipient), 243);
+}
+
+#[test]
+fn test_protocol_fee_not_charged_on_refund() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let now = env.ledger().timestamp();
+    let recipient = enable_fee(&env, &client, 250);
+
+    let id = create_escrow(&client, &token, &payer, &payee, &arbiter, 1000, now, 1);
+    client.refund(&id, &payee);
+    assert_eq!(balance(&env, &token, &payer), 1_000_000);
+    assert_eq!(balance(&env, &token, &recipient), 0);
+}
+
+#[test]
+fn test_protocol_fee_on_vested_withdrawals() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let now = env.ledger().timestamp();
+    let recipient = enable_fee(&env, &client, 1000);
+
+    let id = create_vesting(&client, &token, &payer, &payee, &arbiter, now);
+    advance_time(&env, 250);
+    assert_eq!(client.withdraw_vested(&id), 225);
+    assert_eq!(balance(&env, &token, &recipient), 25);
+
+    client.refund(&id, &payer);
+    assert_eq!(balance(&env, &token, &payee), 225);
+    assert_eq!(balance(&env, &token, &recipient), 25);
+    assert_eq!(balance(&env, &token, &payer), 999_750);
+    assert_eq!(balance(&env, &token, &client.address), 0);
+}
+
+#[test]
+fn test_protocol_fee_fixed_at_deposit() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let now = env.ledger().timestamp();
+    let recipient = enable_fee(&env, &client, 100);
+
+    let id = create_escrow(&client, &token, &payer, &payee, &arbiter, 1000, now, 1);
+    client.set_fee(&5000, &recipient);
+    client.release(&id, &payer);
+    assert_eq!(balance(&env, &token, &payee), 990);
+    assert_eq!(balance(&env, &token, &recipient), 10);
+}
+
+#[test]
+fn test_fee_exempt_party_pays_no_fee() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let now = env.ledger().timestamp();
+    let recipient = enable_fee(&env, &client, 250);
+
+    client.set_fee_exempt(&payee, &true);
+    assert!(client.is_fee_exempt(&payee));
+    let exempt = create_escrow(&client, &token, &payer, &payee, &arbiter, 1000, now, 1);
+    client.set_fee_exempt(&payee, &false);
+    assert!(!client.is_fee_exempt(&payee));
+    let charged = create_escrow(&client, &token, &payer, &payee, &arbiter, 1000, now, 1);
+
+    assert_eq!(client.get_escrow(&exempt).protocol_fee_bps, 0);
+    assert_eq!(client.get_escrow(&charged).protocol_fee_bps, 250);
+    client.release(&exempt, &payer);
+    assert_eq!(balance(&env, &token, &recipient), 0);
+    client.release(&charged, &payer);
+    assert_eq!(balance(&env, &token, &recipient), 25);
+}
+
+#[test]
+fn test_fee_admin_errors() {
+    let env = Env::default();
+    let (client, _, payee, _, _) = setup(&env);
+    let recipient = Address::generate(&env);
+    assert_eq!(client.get_fee(), None);
+    assert_eq!(client.try_set_fee(&100, &recipient), Err(Ok(EscrowError::NotInitialized)));
+    assert_eq!(client.try_set_fee_exempt(&payee, &true), Err(Ok(EscrowError::NotInitialized)));
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    assert_eq!(client.try_initialize(&admin), Err(Ok(EscrowError::AlreadyInitialized)));
+    assert_eq!(client.try_set_fee(&10_001, &recipient), Err(Ok(EscrowError::InvalidFee)));
+
+    client.set_fee(&100, &recipient);
+    let (signer, _) = env.auths().pop().unwrap();
+    assert_eq!(signer, admin);
+    assert_eq!(client.get_fee(), Some(ProtocolFee { bps: 100, recipient }));
+}
+
+// =====================
+// ACCEPTANCE TESTS
+// =====================
+
+/// Create a 1-of-3 escrow of 1000 the payee must accept within an hour.
+fn create_offer(client: &EscrowContractClient, token: &Address, payer: &Address, payee: &Address, arbiter: &Address, release_after: u64) -> u64 {
+    client.deposit(payer, payee, arbiter, token, &1000, &release_after, &1, &terms(&client.env), &EscrowOptions { accept_window: 3600, ..Default::default() })
+}
+
+#[test]
+fn test_offer_starts_offered_until_accepted() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let now = env.ledger().timestamp();
+
+    let id = create_offer(&client, &token, &payer, &payee, &arbiter, now);
+    let escrow = client.get_escrow(&id);
+    assert_eq!(escrow.status, EscrowStatus::Offered);
+    assert_eq!(escrow.accept_by, now + 3600);
+
+    advance_time(&env, 3600);
+    client.accept(&id);
+    assert_eq!(client.get_escrow(&id).status, EscrowStatus::Pending);
+
+    client.release(&id, &payer);
+    assert_eq!(balance(&env, &token, &payee), 1000);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #2)")]
+fn test_offered_escrow_cannot_be_released() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let now = env.ledger().timestamp();
+
+    let id = create_offer(&client, &token, &payer, &payee, &arbiter, now);
+    client.release(&id, &payer);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #18)")]
+fn test_accept_after_window_panics() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let now = env.ledger().timestamp();
+
+    let id = create_offer(&client, &token, &payer, &payee, &arbiter, now);
+    advance_time(&env, 3601);
+    client.accept(&id);
+}
+
+#[test]
+fn test_payer_withdraws_lapsed_offer() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let now = env.ledger().timestamp();
+
+    let id = create_offer(&client, &token, &payer, &payee, &arbiter, now);
+    assert_eq!(
+        client.try_withdraw(&id),
+        Err(Ok(EscrowError::AcceptWindowOpen))
+    );
+
+    advance_time(&env, 3601);
+    client.withdraw(&id);
+
+    let escrow = client.get_escrow(&id);
+    assert_eq!(escrow.status, EscrowStatus::Withdrawn);
+    assert_eq!(escrow.payer_amount, 1000);
+    assert_eq!(balance(&env, &token, &payer), 1_000_000);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #17)")]
+fn test_accept_binding_escrow_panics() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let now = env.ledger().timestamp();
+
+    let id = create_escrow(&client, &token, &payer, &payee, &arbiter, 100, now, 1);
+    client.accept(&id);
+}
+
+#[test]
+fn test_accept_requires_payee_auth() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let now = env.ledger().timestamp();
+
+    let id = create_offer(&client, &token, &payer, &payee, &arbiter, now);
+    client.accept(&id);
+
+    let (signer, invocation) = env.auths().pop().unwrap();
+    assert_eq!(signer, payee);
+    assert_eq!(
+        invocation.function,
+        AuthorizedFunction::Contract((client.address.clone(), Symbol::new(&env, "accept"), (id,).into_val(&env)))
+    );
+}
+
+// =====================
+// VESTING TESTS
+// =====================
+
+/// Create a 1-of-3 escrow of 1000 vesting over 1000 seconds from `release_after`.
+fn create_vesting(client: &EscrowContractClient, token: &Address, payer: &Address, payee: &Address, arbiter: &Address, release_after: u64) -> u64 {
+    let options = EscrowOptions { vesting_duration: 1000, dispute_fee: 10, ..Default::default() };
+    client.deposit(payer, payee, arbiter, token, &1000, &release_after, &1, &terms(&client.env), &options)
+}
+
+#[test]
+fn test_withdraw_vested_pays_linear_portion() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let now = env.ledger().timestamp();
+
+    let id = create_vesting(&client, &token, &payer, &payee, &arbiter, now + 100);
+
+    advance_time(&env, 100 + 250);
+    assert_eq!(client.withdraw_vested(&id), 250);
+    advance_time(&env, 100);
+    assert_eq!(client.withdraw_vested(&id), 100);
+
+    let escrow = client.get_escrow(&id);
+    assert_eq!(escrow.vested_paid, 350);
+    assert_eq!(escrow.status, EscrowStatus::Pending);
+    assert_eq!(balance(&env, &token, &payee), 350);
+}
+
+#[test]
+fn test_fully_vested_escrow_is_released() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let now = env.ledger().timestamp();
+
+    let id = create_vesting(&client, &token, &payer, &payee, &arbiter, now);
+
+    advance_time(&env, 400);
+    client.withdraw_vested(&id);
+    advance_time(&env, 5000);
+    assert_eq!(client.withdraw_vested(&id), 600);
+
+    let escrow = client.get_escrow(&id);
+    assert_eq!(escrow.status, EscrowStatus::Released);
+    assert_eq!(escrow.payee_amount, 1000);
+    assert_eq!(balance(&env, &token, &payee), 1000);
+    assert_eq!(balance(&env, &token, &client.address), 0);
+}
+
+#[test]
+fn test_withdraw_vested_errors() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let now = env.ledger().timestamp();
+
+    let plain = create_escrow(&client, &token, &payer, &payee, &arbiter, 100, now, 1);
+    assert_eq!(client.try_withdraw_vested(&plain), Err(Ok(EscrowError::NotVesting)));
+
+    let id = create_vesting(&client, &token, &payer, &payee, &arbiter, now + 10);
+    assert_eq!(
+        client.try_withdraw_vested(&id),
+        Err(Ok(EscrowError::ReleaseTimeNotReached))
+    );
+    advance_time(&env, 10);
+    assert_eq!(client.try_withdraw_vested(&id), Err(Ok(EscrowError::NothingVested)));
+}
+
+#[test]
+fn test_refund_after_partial_vesting_returns_unvested() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let now = env.ledger().timestamp();
+
+    let id = create_vesting(&client, &token, &payer, &payee, &arbiter, now);
+    advance_time(&env, 300);
+    client.withdraw_vested(&id);
+    client.refund(&id, &payer);
+
+    let escrow = client.get_escrow(&id);
+    assert_eq!(escrow.status, EscrowStatus::Refunded);
+    assert_eq!(escrow.payee_amount, 300);
+    assert_eq!(escrow.payer_amount, 700);
+    assert_eq!(balance(&env, &token, &payee), 300);
+    assert_eq!(balance(&env, &token, &payer), 999_700);
+}
+
+#[test]
+fn test_dispute_splits_only_unvested_balance() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let now = env.ledger().timestamp();
+
+    let id = create_vesting(&client, &token, &payer, &payee, &arbiter, now);
+    advance_time(&env, 500);
+    client.withdraw_vested(&id);
+    client.raise_dispute(&payer, &id, &BytesN::from_array(&env, &[1; 32]));
+    assert_eq!(client.try_withdraw_vested(&id), Err(Ok(EscrowError::NotPending)));
+    assert_eq!(
+        client.try_resolve_dispute(&arbiter, &id, &600, &0),
+        Err(Ok(EscrowError::SplitExceedsAmount))
+    );
+
+    client.resolve_dispute(&arbiter, &id, &200, &10);
+    let escrow = client.get_escrow(&id);
+    assert_eq!(escrow.payee_amount, 700);
+    assert_eq!(escrow.payer_amount, 290);
+    assert_eq!(balance(&env, &token, &payee), 700);
+    assert_eq!(balance(&env, &token, &client.address), 0);
+}
+
+// =====================
+// YIELD TESTS
+// =====================
+
+fn setup_staking<'a>(env: &'a Env, token: &Address) -> MockStakingClient<'a> {
+    let staking = MockStakingClient::new(env, &env.register(MockStaking, ()));
+    staking.init(token);
+    staking
+}
+
+/// Mint `amount` to the staking contract and credit it as the escrow contract's rewards.
+fn accrue(env: &Env, staking: &MockStakingClient, token: &Address, escrow: &Address, amount: i128) {
+    StellarAssetClient::new(env, token).mint(&staking.address, &amount);
+    staking.add_rewards(escrow, &amount);
+}
+
+fn yield_options(staking: &Address, payee_bps: u32, collector: Option<Address>) -> EscrowOptions {
+    let yield_config = YieldConfig { staking: staking.clone(), payee_bps, collector };
+    EscrowOptions { yield_mode: YieldMode::Staked(yield_config), ..Default::default() }
+}
+
+#[test]
+fn test_yield_deposit_stakes_funds() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let staking = setup_staking(&env, &token);
+    let now = env.ledger().timestamp();
+
+    let options = yield_options(&staking.address, 5_000, None);
+    let id = client.deposit(&payer, &payee, &arbiter, &token, &1000, &now, &1, &terms(&env), &options);
+
+    assert_eq!(staking.staked(&client.address), 1000);
+    assert_eq!(balance(&env, &token, &client.address), 0);
+    let position = client.get_escrow(&id).yield_position;
+    assert_eq!(position.staked, 1000);
+    assert_eq!(position.earned, 0);
+}
+
+#[test]
+fn test_yield_split_on_release() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let staking = setup_staking(&env, &token);
+    let now = env.ledger().timestamp();
+
+    let options = yield_options(&staking.address, 2_500, None);
+    let id = client.deposit(&payer, &payee, &arbiter, &token, &1000, &now, &1, &terms(&env), &options);
+    accrue(&env, &staking, &token, &client.address, 100);
+    client.release(&id, &payer);
+    assert!(has_event(&env, (symbol_short!("yield"), id).into_val(&env)));
+
+    assert_eq!(client.get_escrow(&id).yield_position.earned, 100);
+    assert_eq!(staking.staked(&client.address), 0);
+    assert_eq!(balance(&env, &token, &payee), 1025);
+    assert_eq!(balance(&env, &token, &payer), 999_075);
+    assert_eq!(balance(&env, &token, &client.address), 0);
+}
+
+#[test]
+fn test_yield_to_collector_on_refund() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let staking = setup_staking(&env, &token);
+    let collector = Address::generate(&env);
+    let now = env.ledger().timestamp();
+
+    let options = yield_options(&staking.address, 5_000, Some(collector.clone()));
+    let id = client.deposit(&payer, &payee, &arbiter, &token, &1000, &now, &1, &terms(&env), &options);
+    accrue(&env, &staking, &token, &client.address, 40);
+    client.refund(&id, &payee);
+
+    assert_eq!(balance(&env, &token, &collector), 40);
+    assert_eq!(balance(&env, &token, &payer), 1_000_000);
+    assert_eq!(balance(&env, &token, &payee), 0);
+}
+
+#[test]
+fn test_yield_shared_pro_rata_between_cases() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let staking = setup_staking(&env, &token);
+    let now = env.ledger().timestamp();
+    let options = yield_options(&staking.address, 10_000, None);
+
+    let first = client.deposit(&payer, &payee, &arbiter, &token, &1000, &now, &1, &terms(&env), &options);
+    accrue(&env, &staking, &token, &client.address, 100);
+    let second = client.deposit(&payer, &payee, &arbiter, &token, &3000, &now, &1, &terms(&env), &options);
+    accrue(&env, &staking, &token, &client.address, 400);
+
+    client.release(&first, &payer);
+    assert_eq!(client.get_escrow(&first).yield_position.earned, 200);
+    client.release(&second, &payer);
+    assert_eq!(client.get_escrow(&second).yield_position.earned, 300);
+    assert_eq!(balance(&env, &token, &payee), 4500);
+}
+
+#[test]
+fn test_yield_vesting_withdrawal_unstakes_due() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let staking = setup_staking(&env, &token);
+    let now = env.ledger().timestamp();
+
+    let mut options = yield_options(&staking.address, 10_000, None);
+    options.vesting_duration = 1000;
+    let id = client.deposit(&payer, &payee, &arbiter, &token, &1000, &now, &1, &terms(&env), &options);
+
+    advance_time(&env, 400);
+    accrue(&env, &staking, &token, &client.address, 10);
+    client.withdraw_vested(&id);
+    assert_eq!(staking.staked(&client.address), 600);
+    assert_eq!(client.get_escrow(&id).yield_position.earned, 10);
+
+    advance_time(&env, 600);
+    client.withdraw_vested(&id);
+    assert_eq!(client.get_escrow(&id).status, EscrowStatus::Released);
+    assert_eq!(balance(&env, &token, &payee), 1010);
+}
+
+#[test]
+fn test_yield_credits_only_rewards_received() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let staking = setup_staking(&env, &token);
+    let now = env.ledger().timestamp();
+    let options = yield_options(&staking.address, 10_000, None);
+
+    let first = client.deposit(&payer, &payee, &arbiter, &token, &1000, &now, &1, &terms(&env), &options);
+    let idle = create_escrow(&client, &token, &payer, &payee, &arbiter, 500, now, 1);
+    accrue(&env, &staking, &token, &client.address, 100);
+    staking.set_payout_cap(&30);
+    client.release(&first, &payer);
+
+    // Crediting the reported 100 would have paid out of the idle case's funds
+    assert_eq!(client.get_escrow(&first).yield_position.earned, 30);
+    assert_eq!(balance(&env, &token, &payee), 1030);
+    assert_eq!(balance(&env, &token, &client.address), 500);
+    client.release(&idle, &payer);
+    assert_eq!(balance(&env, &token, &payee), 1530);
+}
+
+#[test]
+fn test_yield_failed_claim_pays_out_without_yield() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let staking = setup_staking(&env, &token);
+    let now = env.ledger().timestamp();
+
+    let options = yield_options(&staking.address, 10_000, None);
+    let id = client.deposit(&payer, &payee, &arbiter, &token, &1000, &now, &1, &terms(&env), &options);
+    accrue(&env, &staking, &token, &client.address, 100);
+    staking.set_paused(&true, &false);
+    client.release(&id, &payer);
+
+    assert_eq!(client.get_escrow(&id).status, EscrowStatus::Released);
+    assert_eq!(client.get_escrow(&id).yield_position.earned, 0);
+    assert_eq!(balance(&env, &token, &payee), 1000);
+}
+
+#[test]
+fn test_yield_failed_unstake_is_typed_and_retryable() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let staking = setup_staking(&env, &token);
+    let now = env.ledger().timestamp();
+
+    let options = yield_options(&staking.address, 10_000, None);
+    let id = client.deposit(&payer, &payee, &arbiter, &token, &1000, &now, &1, &terms(&env), &options);
+    staking.set_paused(&false, &true);
+    assert_eq!(client.try_release(&id, &payer), Err(Ok(EscrowError::StakingUnavailable)));
+    assert_eq!(client.try_refund(&id, &payee), Err(Ok(EscrowError::StakingUnavailable)));
+    assert_eq!(client.get_escrow(&id).status, EscrowStatus::Pending);
+
+    staking.set_paused(&false, &false);
+    client.release(&id, &payer);
+    assert_eq!(balance(&env, &token, &payee), 1000);
+}
+
+#[test]
+fn test_yield_payee_share_over_100_percent_rejected() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let staking = setup_staking(&env, &token);
+    let now = env.ledger().timestamp();
+
+    let options = yield_options(&staking.address, 10_001, None);
+    assert_eq!(
+        client.try_deposit(&payer, &payee, &arbiter, &token, &1000, &now, &1, &terms(&env), &options),
+        Err(Ok(EscrowError::InvalidFee))
+    );
+}
+
+// =====================
+// TERMS TESTS
+// =====================
+
+#[test]
+fn test_deposit_binds_terms() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let now = env.ledger().timestamp();
+
+    let uri = String::from_str(&env, "ipfs://bafybeigdyrzt5sfp7udm7hu76uh7y26nf3efuylqabf3oclgtqy55fbzdi");
+    let options = EscrowOptions { terms_uri: Some(uri.clone()), ..Default::default() };
+    let hash = BytesN::from_array(&env, &[3; 32]);
+    let id = client.deposit(&payer, &payee, &arbiter, &token, &100, &now, &1, &hash, &options);
+
+    let escrow = client.get_escrow(&id);
+    assert_eq!(escrow.terms_hash, hash);
+    assert_eq!(escrow.terms_uri, Some(uri));
+}
+
+#[test]
+fn test_terms_uri_is_optional() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let now = env.ledger().timestamp();
+
+    let id = create_escrow(&client, &token, &payer, &payee, &arbiter, 100, now, 1);
+
+    let escrow = client.get_escrow(&id);
+    assert_eq!(escrow.terms_hash, terms(&env));
+    assert_eq!(escrow.terms_uri, None);
+}
+
+#[test]
+fn test_terms_uri_too_long_rejected() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let now = env.ledger().timestamp();
+
+    let uri = String::from_bytes(&env, &[b'a'; 257]);
+    let options = EscrowOptions { terms_uri: Some(uri), ..Default::default() };
+    assert_eq!(
+        client.try_deposit(&payer, &payee, &arbiter, &token, &100, &now, &1, &terms(&env), &options),
+        Err(Ok(EscrowError::TermsUriTooLong))
+    );
+}
+
+// =====================
+// TTL TESTS
+// =====================
+
+fn escrow_ttl(env: &Env, client: &EscrowContractClient, id: u64) -> u32 {
+    env.as_contract(&client.address, || {
+        env.storage()
+            .persistent()
+            .get_ttl(&(Symbol::new(env, "Escrow"), id))
+    })
+}
+
+#[test]
+fn test_deposit_keeps_ttl_through_release_time() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let release_after = env.ledger().timestamp() + 30 * 86_400;
+    let id = create_escrow(&client, &token, &payer, &payee, &arbiter, 1_000, release_after, 1);
+
+    let ledgers = (30 * 86_400 / LEDGER_SECONDS) as u32;
+    assert!(escrow_ttl(&env, &client, id) >= ledgers + ESCROW_TTL_BUFFER);
+}
+
+#[test]
+fn test_bump_escrow_extends_to_deadline() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let release_after = env.ledger().timestamp() + 86_400;
+    let id = create_escrow(&client, &token, &payer, &payee, &arbiter, 1_000, release_after, 1);
+
+    advance_time(&env, 43_200);
+    let expected = (43_200 / LEDGER_SECONDS) as u32 + ESCROW_TTL_BUFFER;
+    assert_eq!(client.bump_escrow(&id), expected);
+    assert!(escrow_ttl(&env, &client, id) >= expected);
+}
+
+#[test]
+fn test_bump_escrow_extends_participant_index() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let release_after = env.ledger().timestamp() + 86_400;
+    let id = create_escrow(&client, &token, &payer, &payee, &arbiter, 1_000, release_after, 1);
+
+    // Let the entries run down below what the bump asks for
+    env.ledger().with_mut(|li| li.sequence_number += ESCROW_TTL_BUFFER);
+    advance_time(&env, 43_200);
+    let ttl = client.bump_escrow(&id);
+    env.as_contract(&client.address, || {
+        let storage = env.storage().persistent();
+        for (role, party) in [(Role::Payer, &payer), (Role::Payee, &payee), (Role::Arbiter, &arbiter)] {
+            let len_key = (Symbol::new(&env, "Index"), role, party.clone());
+            let slot_key = (Symbol::new(&env, "IndexSlot"), role, party.clone(), 0u32);
+            let pos_key = (Symbol::new(&env, "IndexPos"), role, id);
+            assert_eq!(storage.get_ttl(&len_key), ttl);
+            assert_eq!(storage.get_ttl(&slot_key), ttl);
+            assert_eq!(storage.get_ttl(&pos_key), ttl);
+        }
+    });
+}
+
+#[test]
+fn test_bump_escrow_past_deadline_keeps_buffer() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let now = env.ledger().timestamp();
+    let id = create_escrow(&client, &token, &payer, &payee, &arbiter, 1_000, now, 1);
+
+    advance_time(&env, 86_400);
+    assert_eq!(client.bump_escrow(&id), ESCROW_TTL_BUFFER);
+}
+
+#[test]
+fn test_bump_escrow_caps_at_max_ttl() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let id = create_escrow(&client, &token, &payer, &payee, &arbiter, 1_000, u64::MAX, 1);
+
+    let max_ttl = env.as_contract(&client.address, || env.storage().max_ttl());
+    assert_eq!(client.bump_escrow(&id), max_ttl);
+}
+
+#[test]
+fn test_bump_escrow_unknown_id_fails() {
+    let env = Env::default();
+    let (client, _, _, _, _) = setup(&env);
+    assert_eq!(client.try_bump_escrow(&99), Err(Ok(EscrowError::NotFound)));
+}
+
+// =====================
+// PRUNING TESTS
+// =====================
+
+#[test]
+fn test_prune_closed_waits_for_retention() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let now = env.ledger().timestamp();
+    let id = create_escrow(&client, &token, &payer, &payee, &arbiter, 100, now, 1);
+    client.release(&id, &arbiter);
+    assert_eq!(client.get_escrow(&id).closed_at, now);
+
+    advance_time(&env, DEFAULT_CLOSED_RETENTION - 1);
+    assert_eq!(client.prune_closed(&10), 0);
+    advance_time(&env, 1);
+    assert_eq!(client.prune_closed(&10), 1);
+
+    assert_eq!(client.try_get_escrow(&id), Err(Ok(EscrowError::NotFound)));
+    assert_eq!(client.get_escrows_by_payer(&payer, &0, &10).len(), 0);
+    assert_eq!(client.get_escrows_by_payee(&payee, &0, &10).len(), 0);
+    assert_eq!(client.get_escrows_by_arbiter(&arbiter, &0, &10).len(), 0);
+}
+
+#[test]
+fn test_prune_closed_emits_archival_digest() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let now = env.ledger().timestamp();
+    let id = create_escrow(&client, &token, &payer, &payee, &arbiter, 100, now, 1);
+    client.refund(&id, &payer);
+    let closed = client.get_escrow(&id);
+
+    advance_time(&env, DEFAULT_CLOSED_RETENTION);
+    client.prune_closed(&1);
+    let (_, topics, data) = env.events().all().last().unwrap();
+    assert_eq!(topics, (symbol_short!("archived"), id).into_val(&env));
+    let summary: (EscrowStatus, u64, BytesN<32>) = data.into_val(&env);
+    let digest: BytesN<32> = env.crypto().sha256(&closed.to_xdr(&env)).into();
+    assert_eq!(summary, (EscrowStatus::Refunded, now, digest));
+}
+
+#[test]
+fn test_prune_closed_skips_open_cases_and_respects_limit() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let now = env.ledger().timestamp();
+    let open = create_escrow(&client, &token, &payer, &payee, &arbiter, 100, now, 1);
+    for _ in 0..3 {
+        let id = create_escrow(&client, &token, &payer, &payee, &arbiter, 100, now, 1);
+        client.release(&id, &payee);
+    }
+
+    advance_time(&env, DEFAULT_CLOSED_RETENTION);
+    assert_eq!(client.prune_closed(&2), 2);
+    assert_eq!(client.prune_closed(&2), 1);
+    assert_eq!(client.prune_closed(&2), 0);
+
+    let remaining = client.get_escrows_by_payer(&payer, &0, &10);
+    assert_eq!(remaining.len(), 1);
+    assert_eq!(remaining.get(0).unwrap().id, open);
+    assert_eq!(client.get_escrow(&open).status, EscrowStatus::Pending);
+}
+
+#[test]
+fn test_admin_sets_closed_retention() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    assert_eq!(client.get_closed_retention(), DEFAULT_CLOSED_RETENTION);
+    assert_eq!(client.try_set_closed_retention(&3600), Err(Ok(EscrowError::NotInitialized)));
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    client.set_closed_retention(&3600);
+    let (signer, _) = env.auths().pop().unwrap();
+    assert_eq!(signer, admin);
+    assert_eq!(client.get_closed_retention(), 3600);
+
+    let now = env.ledger().timestamp();
+    let id = create_escrow(&client, &token, &payer, &payee, &arbiter, 100, now, 1);
+    client.release(&id, &arbiter);
+    assert_eq!(escrow_ttl(&env, &client, id), (3600 / LEDGER_SECONDS) as u32 + ESCROW_TTL_BUFFER);
+
+    advance_time(&env, 3599);
+    assert_eq!(client.prune_closed(&10), 0);
+    advance_time(&env, 1);
+    assert_eq!(client.prune_closed(&10), 1);
+    assert_eq!(client.try_get_escrow(&id), Err(Ok(EscrowError::NotFound)));
+}
+
+#[test]
+fn test_prune_closed_walks_queue_across_calls() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let now = env.ledger().timestamp();
+    for _ in 0..3 {
+        let id = create_escrow(&client, &token, &payer, &payee, &arbiter, 100, now, 1);
+        client.release(&id, &payee);
+    }
+    advance_time(&env, DEFAULT_CLOSED_RETENTION);
+    assert_eq!(client.prune_closed(&2), 2);
+
+    // Cases closing after a prune join the back of the queue
+    let later = create_escrow(&client, &token, &payer, &payee, &arbiter, 100, env.ledger().timestamp(), 1);
+    client.refund(&later, &payer);
+    assert_eq!(client.prune_closed(&10), 1);
+    advance_time(&env, DEFAULT_CLOSED_RETENTION);
+    assert_eq!(client.prune_closed(&10), 1);
+    assert_eq!(client.prune_closed(&10), 0);
+    assert_eq!(client.try_get_escrow(&later), Err(Ok(EscrowError::NotFound)));
+}
+
+#[test]
+fn test_prune_closed_skips_expired_cases() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let now = env.ledger().timestamp();
+    let gone = create_escrow(&client, &token, &payer, &payee, &arbiter, 100, now, 1);
+    client.release(&gone, &payee);
+    let kept = create_escrow(&client, &token, &payer, &payee, &arbiter, 100, now, 1);
+    client.release(&kept, &payee);
+
+    // The head case's entry lapsed before anyone pruned it
+    env.as_contract(&client.address, || {
+        env.storage()
+            .persistent()
+            .remove(&(Symbol::new(&env, "Escrow"), gone));
+    });
+    advance_time(&env, DEFAULT_CLOSED_RETENTION);
+    assert_eq!(client.prune_closed(&10), 1);
+    assert_eq!(client.try_get_escrow(&kept), Err(Ok(EscrowError::NotFound)));
+    assert_eq!(client.prune_closed(&10), 0);
+}
+
+// =====================
+// PARTICIPANT LOOKUP TESTS
+// =====================
+
+#[test]
+fn test_escrows_indexed_by_each_participant() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let now = env.ledger().timestamp();
+    let other_payee = Address::generate(&env);
+
+    let first = create_escrow(&client, &token, &payer, &payee, &arbiter, 100, now, 1);
+    let second = create_escrow(&client, &token, &payer, &other_payee, &arbiter, 200, now, 1);
+
+    let by_payer = client.get_escrows_by_payer(&payer, &0, &10);
+    assert_eq!(by_payer.len(), 2);
+    assert_eq!(by_payer.get(0).unwrap().id, first);
+    assert_eq!(by_payer.get(1).unwrap().id, second);
+
+    let by_payee = client.get_escrows_by_payee(&other_payee, &0, &10);
+    assert_eq!(by_payee.len(), 1);
+    assert_eq!(by_payee.get(0).unwrap().amount, 200);
+
+    assert_eq!(client.get_escrows_by_arbiter(&arbiter, &0, &10).len(), 2);
+    assert_eq!(client.get_escrows_by_payee(&payer, &0, &10).len(), 0);
+}
+
+#[test]
+fn test_participant_lookup_paginates() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let now = env.ledger().timestamp();
+
+    for amount in 1..=5u128 {
+        create_escrow(&client, &token, &payer, &payee, &arbiter, amount, now, 1);
+    }
+
+    let page = client.get_escrows_by_payer(&payer, &2, &2);
+    assert_eq!(page.len(), 2);
+    assert_eq!(page.get(0).unwrap().id, 3);
+    assert_eq!(page.get(1).unwrap().id, 4);
+    assert_eq!(client.get_escrows_by_payer(&payer, &4, &10).len(), 1);
+    assert_eq!(client.get_escrows_by_payer(&payer, &5, &10).len(), 0);
+}
+
+#[test]
+fn test_participant_lookup_reflects_current_state() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let now = env.ledger().timestamp();
+
+    let id = create_escrow(&client, &token, &payer, &payee, &arbiter, 100, now, 1);
+    client.release(&id, &arbiter);
+
+    let cases = client.get_escrows_by_payee(&payee, &0, &1);
+    assert_eq!(cases.get(0).unwrap().status, EscrowStatus::Released);
+}
+
+#[test]
+fn test_participant_index_leaves_gaps() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let now = env.ledger().timestamp();
+    let new_payee = Address::generate(&env);
+
+    let first = create_escrow(&client, &token, &payer, &payee, &arbiter, 100, now, 1);
+    let second = create_escrow(&client, &token, &payer, &payee, &arbiter, 100, now, 1);
+    let third = create_escrow(&client, &token, &payer, &payee, &arbiter, 100, now, 1);
+    client.reassign_payee(&second, &new_payee);
+
+    assert_eq!(client.get_index_len(&Role::Payee, &payee), 3);
+    let cases = client.get_escrows_by_payee(&payee, &0, &10);
+    assert_eq!(cases.len(), 2);
+    assert_eq!(cases.get(0).unwrap().id, first);
+    assert_eq!(cases.get(1).unwrap().id, third);
+    assert_eq!(client.get_escrows_by_payee(&payee, &1, &1).len(), 0);
+
+    assert_eq!(client.get_index_len(&Role::Payee, &new_payee), 1);
+    assert_eq!(client.get_escrows_by_payee(&new_payee, &0, &10).get(0).unwrap().id, second);
+    assert_eq!(client.get_index_len(&Role::Payer, &payer), 3);
+}
+
+#[test]
+fn test_deposit_write_size_independent_of_index_size() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let now = env.ledger().timestamp();
+
+    create_escrow(&client, &token, &payer, &payee, &arbiter, 1, now, 1);
+    let first = env.cost_estimate().resources().write_bytes;
+    for _ in 0..50 {
+        create_escrow(&client, &token, &payer, &payee, &arbiter, 1, now, 1);
+    }
+    create_escrow(&client, &token, &payer, &payee, &arbiter, 1, now, 1);
+    assert_eq!(env.cost_estimate().resources().write_bytes, first);
+    assert_eq!(client.get_index_len(&Role::Arbiter, &arbiter), 52);
+}
+
+// =====================
+// EVENT TESTS
+// =====================
+
+#[test]
+fn test_deposit_emits_event() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let now = env.ledger().timestamp();
+
+    let id = create_escrow(&client, &token, &payer, &payee, &arbiter, 1000, now, 1);
+
+    let (_, topics, data) = env.events().all().last().unwrap();
+    assert_eq!(topics, (symbol_short!("deposit"), id, payer).into_val(&env));
+    let (to, arb, amount): (Address, Address, u128) = data.into_val(&env);
+    assert_eq!((to, arb, amount), (payee, arbiter, 1000));
+}
+
+#[test]
+fn test_release_emits_approval_and_released_events() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let now = env.ledger().timestamp();
+
+    let id = create_escrow(&client, &token, &payer, &payee, &arbiter, 500, now, 2);
+
+    client.release(&id, &payer);
+    assert!(has_event(&env, (symbol_short!("rel_appr"), id, payer.clone()).into_val(&env)));
+    assert!(!has_event(&env, (symbol_short!("released"), id).into_val(&env)));
+
+    client.release(&id, &payee);
+    let (_, topics, data) = env.events().all().last().unwrap();
+    assert_eq!(topics, (symbol_short!("released"), id).into_val(&env));
+    let amount: u128 = data.into_val(&env);
+    assert_eq!(amount, 500);
+}
+
+#[test]
+fn test_refund_emits_approval_and_refunded_events() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let now = env.ledger().timestamp();
+
+    let id = create_escrow(&client, &token, &payer, &payee, &arbiter, 500, now + 9999, 1);
+    client.refund(&id, &arbiter);
+
+    let (_, approval_topics, approvals) = env.events().all().first().unwrap();
+    assert_eq!(approval_topics, (symbol_short!("ref_appr"), id, arbiter).into_val(&env));
+    let count: u32 = approvals.into_val(&env);
+    assert_eq!(count, 1);
+    assert!(has_event(&env, (symbol_short!("refunded"), id).into_val(&env)));
+}
+
+#[test]
+fn test_dispute_lifecycle_emits_events() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let now = env.ledger().timestamp();
+
+    let id = create_disputable(&client, &token, &payer, &payee, &arbiter, now);
+    let reason = BytesN::from_array(&env, &[9; 32]);
+    client.raise_dispute(&payee, &id, &reason);
+
+    let (_, topics, data) = env.events().all().last().unwrap();
+    assert_eq!(topics, (symbol_short!("disputed"), id, payee).into_val(&env));
+    let emitted: BytesN<32> = data.into_val(&env);
+    assert_eq!(emitted, reason);
+
+    client.resolve_dispute(&arbiter, &id, &700, &20);
+    let (_, topics, data) = env.events().all().last().unwrap();
+    assert_eq!(topics, (symbol_short!("resolved"), id, arbiter).into_val(&env));
+    let split: (u128, u128, u128) = data.into_val(&env);
+    assert_eq!(split, (700, 280, 20));
+}
+
+// =====================
+// HISTORY TESTS
+// =====================
+
+#[test]
+fn test_history_records_approvals_in_order() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let start = env.ledger().timestamp();
+
+    let id = create_escrow(&client, &token, &payer, &payee, &arbiter, 1000, start, 2);
+    client.release(&id, &payee);
+    advance_time(&env, 60);
+    client.release(&id, &arbiter);
+
+    let entry = |action, actor: &Address, timestamp| HistoryEntry { action, actor: actor.clone(), timestamp };
+    assert_eq!(
+        client.get_history(&id, &0, &50),
+        vec![
+            &env,
+            entry(EscrowAction::Deposited, &payer, start),
+            entry(EscrowAction::ReleaseApproved, &payee, start),
+            entry(EscrowAction::ReleaseApproved, &arbiter, start + 60),
+            entry(EscrowAction::Released, &arbiter, start + 60),
+        ]
+    );
+}
+
+#[test]
+fn test_history_records_dispute_and_settlement() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let now = env.ledger().timestamp();
+    let new_payee = Address::generate(&env);
+
+    let id = create_disputable(&client, &token, &payer, &payee, &arbiter, now);
+    client.raise_dispute(&payer, &id, &BytesN::from_array(&env, &[1; 32]));
+    client.reassign_payee(&id, &new_payee);
+    client.propose_settlement(&new_payee, &id, &400);
+    client.accept_settlement(&payer, &id, &400);
+
+    let history = client.get_history(&id, &0, &50);
+    let steps: std::vec::Vec<(EscrowAction, Address)> = history.iter().map(|e| (e.action, e.actor)).collect();
+    assert_eq!(
+        steps,
+        [
+            (EscrowAction::Deposited, payer.clone()),
+            (EscrowAction::Disputed, payer.clone()),
+            (EscrowAction::PayeeReassigned(new_payee.clone()), arbiter.clone()),
+            (EscrowAction::SettlementProposed(400), new_payee.clone()),
+            (EscrowAction::Settled, payer.clone()),
+        ]
+    );
+}
+
+#[test]
+fn test_history_missing_for_unknown_or_pruned_case() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let now = env.ledger().timestamp();
+    assert_eq!(client.try_get_history(&1, &0, &50), Err(Ok(EscrowError::NotFound)));
+
+    let id = create_escrow(&client, &token, &payer, &payee, &arbiter, 1000, now, 1);
+    client.refund(&id, &payee);
+    assert_eq!(client.get_history(&id, &0, &50).len(), 3);
+
+    advance_time(&env, DEFAULT_CLOSED_RETENTION);
+    client.prune_closed(&1);
+    assert_eq!(client.try_get_history(&id, &0, &50), Err(Ok(EscrowError::NotFound)));
+}
+
+#[test]
+fn test_history_pages_by_entry() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let now = env.ledger().timestamp();
+
+    let id = create_disputable(&client, &token, &payer, &payee, &arbiter, now);
+    client.raise_dispute(&payer, &id, &BytesN::from_array(&env, &[1; 32]));
+    for amount in 0..60u128 {
+        client.propose_settlement(&payee, &id, &amount);
+    }
+    assert_eq!(client.get_history_len(&id), 62);
+
+    // Pages are capped at MAX_PAGE_SIZE, then continue from the offset
+    let first = client.get_history(&id, &0, &100);
+    assert_eq!(first.len(), 50);
+    assert_eq!(first.get(0).unwrap().action, EscrowAction::Deposited);
+    let rest = client.get_history(&id, &50, &100);
+    assert_eq!(rest.len(), 12);
+    assert_eq!(rest.last().unwrap().action, EscrowAction::SettlementProposed(59));
+    assert_eq!(client.get_history(&id, &62, &10).len(), 0);
+    assert_eq!(client.get_history_len(&999), 0);
+}
+
+#[test]
+fn test_history_write_size_independent_of_length() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let now = env.ledger().timestamp();
+
+    let id = create_disputable(&client, &token, &payer, &payee, &arbiter, now);
+    client.raise_dispute(&payer, &id, &BytesN::from_array(&env, &[1; 32]));
+    client.propose_settlement(&payee, &id, &1);
+    let first = env.cost_estimate().resources().write_bytes;
+    for amount in 2..40u128 {
+        client.propose_settlement(&payee, &id, &amount);
+    }
+    assert_eq!(env.cost_estimate().resources().write_bytes, first);
+}
+
+#[test]
+fn test_history_entries_kept_through_deadline_and_retention() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let release_after = env.ledger().timestamp() + 86_400;
+    let id = create_escrow(&client, &token, &payer, &payee, &arbiter, 1_000, release_after, 1);
+
+    let expected = ((86_400 + DEFAULT_CLOSED_RETENTION) / LEDGER_SECONDS) as u32 + ESCROW_TTL_BUFFER;
+    let ttl = env.as_contract(&client.address, || {
+        env.storage()
+            .persistent()
+            .get_ttl(&(Symbol::new(&env, "HistorySlot"), id, 0u32))
+    });
+    assert_eq!(ttl, expected);
+}
+
+#[test]
+fn test_prune_closed_deletes_long_history_over_calls() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let now = env.ledger().timestamp();
+
+    let id = create_disputable(&client, &token, &payer, &payee, &arbiter, now);
+    client.raise_dispute(&payer, &id, &BytesN::from_array(&env, &[1; 32]));
+    for amount in 0..MAX_HISTORY_PRUNE as u128 {
+        client.propose_settlement(&payee, &id, &amount);
+    }
+    client.resolve_dispute(&arbiter, &id, &600, &0);
+    assert_eq!(client.get_history_len(&id), MAX_HISTORY_PRUNE + 3);
+
+    // The first call runs out of history budget and leaves the case queued
+    advance_time(&env, DEFAULT_CLOSED_RETENTION);
+    assert_eq!(client.prune_closed(&10), 0);
+    assert_eq!(client.get_history_len(&id), 3);
+    assert_eq!(client.prune_closed(&10), 1);
+    assert_eq!(client.try_get_escrow(&id), Err(Ok(EscrowError::NotFound)));
+    let left = env.as_contract(&client.address, || {
+        env.storage()
+            .persistent()
+            .has(&(Symbol::new(&env, "HistorySlot"), id, 0u32))
+    });
+    assert!(!left);
+}
+
+// =====================
+// EDGE CASES
+// =====================
+
+#[test]
+fn test_errors_surface_as_escrow_error() {
+    let env = Env::default();
+    let (client, payer, payee, arbiter, token) = setup(&env);
+    let now = env.ledger().timestamp();
+
+    assert_eq!(
+        client.try_deposit(&payer, &payee, &arbiter, &token, &100, &now, &1, &terms(&client.env), &EscrowOptions { dispute_fee: 101, ..Default::default() }),
+        Err(Ok(EscrowError::DisputeFeeTooHigh))
+    );
+
+    let id = create_escrow(&client, &token, &payer, &payee, &arbiter, 100, now + 3600, 1);
+    assert_eq!(
+        client.try_release(&id, &payer),
+        Err(Ok(EscrowError::ReleaseTimeNotReached))
+    );
+    assert_eq!(
+        client.try_refund(&id, &Address::generate(&env)),
+        Err(Ok(EscrowError::NotAParty))
+    );
+    assert_eq!(client.try_get_escrow(&999), Err(Ok(EscrowError::NotFound)));
+}
 
 #[test]
-#[should_panic(expected = "escrow not found")]
+#[should_panic(expected = "Error(Contract, #1)")]
 fn test_get_escrow_nonexistent_panics() {
     let env = Env::default();
-    let (client, _, _, _) = setup(&env);
+    let (client, _, _, _, _) = setup(&env);
     client.get_escrow(&999u64);
 }
@@ -1,8 +1,8 @@
 #![no_std]
 
 use soroban_sdk::{
-    contract, contracterror, contractimpl, contracttype, symbol_short, Address, Env, Map, Symbol,
-    Vec,
+    contract, contracterror, contractimpl, contracttype, symbol_short, xdr::ToXdr, Address,
+    Bytes, BytesN, Env, IntoVal, InvokeError, Map, String, Symbol, Val, Vec,
 };
 
 // Storage keys
@@ -10,6 +10,30 @@ const SIGNERS: Symbol = symbol_short!("signers");
 const THRESHOLD: Symbol = symbol_short!("threshold");
 const PROP_COUNT: Symbol = symbol_short!("prop_cnt");
 const INITIALIZED: Symbol = symbol_short!("init");
+const AUTO_EXEC: Symbol = symbol_short!("auto_exec");
+const TIMELOCK: Symbol = symbol_short!("timelock");
+const LINKED: Symbol = symbol_short!("linked");
+const RECOVERY: Symbol = symbol_short!("recovery");
+const PENDING_RECOVERY: Symbol = symbol_short!("rec_pend");
+const SIG_NONCE: Symbol = symbol_short!("sig_nonce");
+const POLICY: Symbol = symbol_short!("policy");
+const PRUNE_CURSOR: Symbol = symbol_short!("prune_at");
+const PRUNE_SCAN: Symbol = symbol_short!("prune_scn");
+const EXECUTORS: Symbol = symbol_short!("executors");
+const SUCCESSOR: Symbol = symbol_short!("successor");
+const LIMITED: Symbol = symbol_short!("limited");
+const SIGNER_EPOCH: Symbol = symbol_short!("sgn_epoch");
+
+// Upper bound on proposal IDs one listing or prune call reads
+const MAX_PAGE_SIZE: u32 = 50;
+// Ledgers an open proposal's entries outlive its expiration by
+const PROPOSAL_TTL_BUFFER: u32 = 17_280; // ~1 day
+// Upper bound on a proposal description, in bytes
+const MAX_DESCRIPTION_LEN: u32 = 256;
+// Upper bound on a metadata hash, in bytes. Fits a sha256 digest or a binary CID.
+const MAX_METADATA_LEN: u32 = 64;
+// Upper bound on the tokens one `MigrateTo` sweeps
+const MAX_SWEEP_TOKENS: u32 = 20;
 
 #[contracterror]
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
@@ -27,23 +51,203 @@ pub enum MultisigError {
     ProposalExpired = 10,
     DuplicateSigner = 11,
     EmptySigners = 12,
+    NotProposer = 13,
+    ProposalCancelled = 14,
+    InvalidAmount = 15,
+    NoSpendingLimit = 16,
+    SpendingLimitExceeded = 17,
+    InvalidSpendingLimit = 18,
+    NotQueued = 19,
+    TimelockNotElapsed = 20,
+    AlreadyRejected = 21,
+    ProposalRejected = 22,
+    InvalidBatch = 23,
+    WalletNotLinked = 24,
+    WalletAlreadyLinked = 25,
+    WalletNotRequired = 26,
+    DuplicateProposal = 27,
+    NoRecoveryConfig = 28,
+    NotAGuardian = 29,
+    RecoveryPending = 30,
+    NoPendingRecovery = 31,
+    RecoveryNotReady = 32,
+    KeyAlreadyRegistered = 33,
+    UnknownSignerKey = 34,
+    DescriptionTooLong = 35,
+    MetadataTooLong = 36,
+    ProposalFailed = 37,
+    WalletRetired = 38,
+    InvalidSuccessor = 39,
+    TooManyTokens = 40,
+    SweepFailed = 41,
 }
 
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum ProposalStatus {
     Active,
+    Queued, // threshold reached, waiting out the timelock delay
     Executed,
+    Cancelled,
+    Rejected, // enough signers rejected that the threshold is unreachable
+    Failed,   // executed, but the token or contract call it made failed
 }
 
 // What action the proposal performs
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum ProposalAction {
-    // Transfer native token to recipient
-    Transfer(Address, i128),
+    // Transfer `amount` of `token` to recipient: (token, to, amount)
+    Transfer(Address, Address, i128),
     // Update the signer set and threshold
     UpdateSigners(Vec<Address>, u32),
+    // Add one signer, optionally setting a new threshold
+    AddSigner(Address, Option<u32>),
+    // Remove one signer, optionally setting a new threshold. Fails if the
+    // current (or given) threshold would exceed the remaining signers.
+    RemoveSigner(Address, Option<u32>),
+    // Call `function` on another contract with the given arguments,
+    // authorized as this wallet
+    Invoke(Address, Symbol, Vec<Val>),
+    // Turn auto-execution on the threshold-reaching approval on or off
+    SetAutoExecute(bool),
+    // Let any single signer spend up to `limit` of `token` per `period`
+    // seconds without a proposal: (token, limit, period). A limit of 0
+    // removes the allowance.
+    SetSpendingLimit(Address, i128, u64),
+    // Delay in seconds between reaching the threshold and execution.
+    // 0 disables the timelock.
+    SetTimelock(u64),
+    // Run several actions in order within one approval cycle. If any
+    // action fails, none of them take effect. Batches cannot be empty
+    // or nested.
+    Batch(Vec<ProposalAction>),
+    // Link a child wallet whose sign-off mirrored proposals can require
+    LinkWallet(Address),
+    // Unlink a child wallet. Pending proposals stop waiting on it.
+    UnlinkWallet(Address),
+    // Configure social recovery: (guardians, guardian threshold, delay in
+    // seconds). An empty guardian list disables recovery.
+    SetRecovery(Vec<Address>, u32, u64),
+    // Require a different number of approvals for one kind of action.
+    // 0 falls back to the wallet-wide threshold.
+    SetActionThreshold(ActionKind, u32),
+    // Let a non-signer (e.g. an automation bot) call `execute` until the
+    // given ledger timestamp. It can't propose or approve. 0 revokes it.
+    SetExecutor(Address, u64),
+    // Send the whole balance of each listed token (at most MAX_SWEEP_TOKENS)
+    // to a successor wallet and retire this one: no new proposals or
+    // deposits afterwards
+    MigrateTo(Address, Vec<Address>),
+}
+
+// Action categories a per-kind threshold can be attached to
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ActionKind {
+    Transfer,
+    UpdateSigners,
+    AddSigner,
+    RemoveSigner,
+    Invoke,
+    SetAutoExecute,
+    SetSpendingLimit,
+    SetTimelock,
+    LinkWallet,
+    UnlinkWallet,
+    SetRecovery,
+    SetActionThreshold,
+    SetExecutor,
+    MigrateTo,
+}
+
+// Where a proposal stands, in one call
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ProposalSummary {
+    pub status: ProposalStatus,
+    pub approvals: u32,
+    pub threshold: u32,        // approvals its action requires
+    pub pending: Vec<Address>, // signers who haven't approved or rejected
+    pub expiration: u64,       // ledger sequence number
+    pub eta: u64,              // queued proposals only
+    pub executable: bool,      // `execute` would be accepted now
+}
+
+// One page of a proposal listing
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ProposalPage {
+    pub proposals: Vec<Proposal>,
+    pub next_id: u32, // pass as `start_id` to continue; 0 once the listing is done
+}
+
+// Why `execute` would fail right now, as reported by `simulate`
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum SimulationIssue {
+    // Executed, cancelled, rejected or expired; carries the error code
+    NotOpen(u32),
+    // Approvals so far, approvals the action requires
+    ThresholdNotMet(u32, u32),
+    // Required linked wallets that haven't signed off yet
+    WalletsPending(Vec<Address>),
+    // A timelock is set and the proposal hasn't been queued
+    NotQueued,
+    // Queued; executable from this ledger timestamp
+    TimelockPending(u64),
+    // Token, amount the action moves out, current wallet balance
+    InsufficientBalance(Address, i128, i128),
+    // Token or invoke target that isn't a reachable contract
+    TargetUnavailable(Address),
+    // An action would fail validation with this error code
+    InvalidAction(u32),
+}
+
+// Outcome of a dry run. `executable` is true when there are no issues.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Simulation {
+    pub executable: bool,
+    pub issues: Vec<SimulationIssue>,
+}
+
+// Wallet state a dry run updates as it walks through a proposal's actions
+struct DryRun {
+    signers: Vec<Address>,
+    threshold: u32,
+    policy: Map<ActionKind, u32>,
+    linked: Vec<Address>,
+    outflows: Map<Address, i128>, // token -> total amount transferred out
+}
+
+// Per-token allowance for single-signer spending
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SpendingLimit {
+    pub limit: i128,
+    pub period: u64,       // window length in seconds
+    pub spent: i128,       // amount spent in the current window
+    pub window_start: u64, // ledger timestamp the current window opened
+}
+
+// Guardians who can replace the signer set if the signers go silent
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RecoveryConfig {
+    pub guardians: Vec<Address>,
+    pub threshold: u32, // guardian approvals needed to start the delay
+    pub delay: u64,     // seconds the signers have to cancel
+}
+
+// A signer set replacement proposed by guardians
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PendingRecovery {
+    pub new_signers: Vec<Address>,
+    pub new_threshold: u32,
+    pub approvals: Vec<Address>, // guardians backing this recovery
+    pub eta: u64, // ledger timestamp it may finalize from; 0 until the guardian threshold is met
 }
 
 #[contracttype]
@@ -53,8 +257,17 @@ pub struct Proposal {
     pub proposer: Address,
     pub action: ProposalAction,
     pub approvals: Vec<Address>,
+    pub rejections: Vec<Address>,
+    pub required_wallets: Vec<Address>, // linked wallets that must also approve
+    pub wallet_approvals: Vec<Address>,
+    pub content_hash: BytesN<32>, // sha256 of (action, expiration, salt)
+    pub description: String,      // short human-readable intent, may be empty
+    pub metadata_hash: Option<Bytes>, // hash or CID of an off-chain document, e.g. an invoice
     pub status: ProposalStatus,
     pub expiration: u64, // ledger sequence number
+    pub eta: u64,        // ledger timestamp a queued proposal may execute from
+    pub error_code: u32, // contract error code of a failed call; 0 if none or it trapped
+    pub epoch: u32,      // signer-set epoch its votes were cast in; recovery starts a new one
 }
 
 // Helper to build per-proposal storage key
@@ -62,6 +275,33 @@ fn proposal_key(id: u32) -> (Symbol, u32) {
     (symbol_short!("proposal"), id)
 }
 
+// Helper to build the content hash -> proposal ID key
+fn hash_key(hash: BytesN<32>) -> (Symbol, BytesN<32>) {
+    (symbol_short!("hash"), hash)
+}
+
+// Helpers to map signers to their ed25519 keys and back
+fn signer_key_key(signer: Address) -> (Symbol, Address) {
+    (symbol_short!("sgn_key"), signer)
+}
+
+fn key_signer_key(key: BytesN<32>) -> (Symbol, BytesN<32>) {
+    (symbol_short!("key_sgn"), key)
+}
+
+// Contract addresses are the ones whose strkey starts with 'C'
+fn is_contract(address: &Address) -> bool {
+    let strkey = address.to_string();
+    let mut buf = [0u8; 56];
+    strkey.copy_into_slice(&mut buf);
+    buf[0] == b'C'
+}
+
+// Helper to build per-token spending limit key
+fn limit_key(token: Address) -> (Symbol, Address) {
+    (symbol_short!("limit"), token)
+}
+
 #[contract]
 pub struct MultisigWallet;
 
@@ -107,29 +347,114 @@ impl MultisigWallet {
         proposer: Address,
         action: ProposalAction,
         expiration_ledger: u64,
+    ) -> Result<u32, MultisigError> {
+        Self::new_proposal(&env, proposer, action, expiration_ledger, 0, Vec::new(&env))
+    }
+
+    // Create a proposal identical in content to an open one. Proposals with
+    // the same action and expiration are rejected as duplicates unless
+    // their salts differ, e.g. for a deliberate second identical payment.
+    pub fn create_salted_proposal(
+        env: Env,
+        proposer: Address,
+        action: ProposalAction,
+        expiration_ledger: u64,
+        salt: u64,
+    ) -> Result<u32, MultisigError> {
+        Self::new_proposal(&env, proposer, action, expiration_ledger, salt, Vec::new(&env))
+    }
+
+    // Create a proposal carrying a short description of its intent and,
+    // optionally, the hash or CID of a longer off-chain document. Neither
+    // is part of the content hash, so they don't affect duplicate detection.
+    pub fn create_described_proposal(
+        env: Env,
+        proposer: Address,
+        action: ProposalAction,
+        expiration_ledger: u64,
+        description: String,
+        metadata_hash: Option<Bytes>,
+    ) -> Result<u32, MultisigError> {
+        if description.len() > MAX_DESCRIPTION_LEN {
+            return Err(MultisigError::DescriptionTooLong);
+        }
+        if let Some(hash) = &metadata_hash {
+            if hash.len() > MAX_METADATA_LEN {
+                return Err(MultisigError::MetadataTooLong);
+            }
+        }
+        let id = Self::new_proposal(&env, proposer, action, expiration_ledger, 0, Vec::new(&env))?;
+        let mut proposal = Self::load_proposal(&env, id)?;
+        proposal.description = description;
+        proposal.metadata_hash = metadata_hash;
+        Self::save_proposal(&env, &proposal);
+        Ok(id)
+    }
+
+    // Create a proposal that, besides the threshold, needs sign-off from each
+    // of the given linked wallets. A child wallet signs off by executing its
+    // own proposal that calls `approve_from_wallet` here, so each wallet
+    // contributes the result of its own threshold.
+    pub fn create_mirrored_proposal(
+        env: Env,
+        proposer: Address,
+        action: ProposalAction,
+        expiration_ledger: u64,
+        wallets: Vec<Address>,
     ) -> Result<u32, MultisigError> {
         Self::require_initialized(&env)?;
-        proposer.require_auth();
-        Self::require_signer(&env, &proposer)?;
+        let linked = Self::linked_wallets(&env);
+        let mut required = Vec::new(&env);
+        for wallet in wallets.iter() {
+            if !linked.contains(&wallet) {
+                return Err(MultisigError::WalletNotLinked);
+            }
+            if !required.contains(&wallet) {
+                required.push_back(wallet);
+            }
+        }
+        Self::new_proposal(&env, proposer, action, expiration_ledger, 0, required)
+    }
 
-        let id: u32 = env.storage().instance().get(&PROP_COUNT).unwrap();
-        let next_id = id + 1;
+    // Record a linked wallet's sign-off on a mirrored proposal. Must be
+    // called by the wallet contract itself. Triggers queueing or
+    // auto-execution like `approve` if it completes the requirements.
+    pub fn approve_from_wallet(
+        env: Env,
+        wallet: Address,
+        proposal_id: u32,
+    ) -> Result<(), MultisigError> {
+        Self::require_initialized(&env)?;
+        wallet.require_auth();
 
-        let proposal = Proposal {
-            id: next_id,
-            proposer: proposer.clone(),
-            action,
-            approvals: Vec::new(&env),
-            status: ProposalStatus::Active,
-            expiration: expiration_ledger,
-        };
+        let mut proposal = Self::load_proposal(&env, proposal_id)?;
+        Self::require_active(&env, &proposal)?;
+        if !proposal.required_wallets.contains(&wallet) {
+            return Err(MultisigError::WalletNotRequired);
+        }
+        if !Self::linked_wallets(&env).contains(&wallet) {
+            return Err(MultisigError::WalletNotLinked);
+        }
+        if proposal.wallet_approvals.contains(&wallet) {
+            return Err(MultisigError::AlreadyApproved);
+        }
 
-        env.storage().persistent().set(&proposal_key(next_id), &proposal);
-        env.storage().instance().set(&PROP_COUNT, &next_id);
-        Ok(next_id)
+        proposal.wallet_approvals.push_back(wallet.clone());
+        Self::save_proposal(&env, &proposal);
+
+        env.events().publish(
+            (symbol_short!("w_approve"), proposal_id, wallet.clone()),
+            proposal.wallet_approvals.len(),
+        );
+
+        Self::on_approval(&env, &wallet, proposal)
     }
 
     // Approve a proposal. Each signer can approve once.
+    // With a timelock set, the approval that reaches the threshold queues
+    // the proposal. Otherwise, with auto-execution enabled, it executes the
+    // proposal. A Transfer or Invoke that fails marks the proposal Failed and
+    // the approval stays recorded; any other action that fails reverts it.
     pub fn approve(env: Env, signer: Address, proposal_id: u32) -> Result<(), MultisigError> {
         Self::require_initialized(&env)?;
         signer.require_auth();
@@ -144,10 +469,19 @@ impl MultisigWallet {
                 return Err(MultisigError::AlreadyApproved);
             }
         }
+        if proposal.rejections.contains(&signer) {
+            return Err(MultisigError::AlreadyRejected);
+        }
 
-        proposal.approvals.push_back(signer);
-        env.storage().persistent().set(&proposal_key(proposal_id), &proposal);
-        Ok(())
+        proposal.approvals.push_back(signer.clone());
+        Self::save_proposal(&env, &proposal);
+
+        env.events().publish(
+            (symbol_short!("approved"), proposal_id, signer.clone()),
+            proposal.approvals.len(),
+        );
+
+        Self::on_approval(&env, &signer, proposal)
     }
 
     // Revoke a previous approval. Signer can only revoke their own.
@@ -178,116 +512,1528 @@ impl MultisigWallet {
         }
 
         proposal.approvals = new_approvals;
-        env.storage().persistent().set(&proposal_key(proposal_id), &proposal);
+
+        // Dropping below the threshold takes a queued proposal out of the queue
+        if proposal.status == ProposalStatus::Queued && !Self::approvals_met(&env, &proposal) {
+            proposal.status = ProposalStatus::Active;
+            proposal.eta = 0;
+        }
+        Self::save_proposal(&env, &proposal);
+
+        env.events().publish(
+            (symbol_short!("revoked"), proposal_id, signer),
+            proposal.approvals.len(),
+        );
         Ok(())
     }
 
-    // Execute a proposal once enough approvals are collected.
-    // Any signer can trigger execution.
-    pub fn execute(env: Env, signer: Address, proposal_id: u32) -> Result<(), MultisigError> {
+    // Vote against a proposal. Once rejections leave too few signers to
+    // reach the threshold, the proposal closes as Rejected.
+    // A signer who approved must revoke before rejecting.
+    pub fn reject(env: Env, signer: Address, proposal_id: u32) -> Result<(), MultisigError> {
         Self::require_initialized(&env)?;
         signer.require_auth();
         Self::require_signer(&env, &signer)?;
 
         let mut proposal = Self::load_proposal(&env, proposal_id)?;
         Self::require_active(&env, &proposal)?;
-
-        let threshold: u32 = env.storage().instance().get(&THRESHOLD).unwrap();
-        if proposal.approvals.len() < threshold {
-            return Err(MultisigError::ThresholdNotMet);
+        if proposal.rejections.contains(&signer) {
+            return Err(MultisigError::AlreadyRejected);
+        }
+        if proposal.approvals.contains(&signer) {
+            return Err(MultisigError::AlreadyApproved);
         }
 
-        // Execute the action
-        match &proposal.action {
-            ProposalAction::Transfer(to, amount) => {
-                // Transfer native token from contract to recipient
-                let contract_addr = env.current_contract_address();
-                let token = soroban_sdk::token::Client::new(
-                    &env,
-                    &env.storage()
-                        .instance()
-                        .get::<Symbol, Address>(&symbol_short!("token"))
-                        .unwrap_or(contract_addr.clone()),
-                );
-                token.transfer(&contract_addr, to, amount);
-            }
-            ProposalAction::UpdateSigners(new_signers, new_threshold) => {
-                // Validate new config
-                if new_signers.is_empty() {
-                    return Err(MultisigError::EmptySigners);
-                }
-                if *new_threshold == 0 || *new_threshold > new_signers.len() {
-                    return Err(MultisigError::InvalidThreshold);
-                }
-                env.storage().instance().set(&SIGNERS, new_signers);
-                env.storage().instance().set(&THRESHOLD, new_threshold);
-            }
+        proposal.rejections.push_back(signer.clone());
+
+        let signers: Vec<Address> = env.storage().instance().get(&SIGNERS).unwrap();
+        let threshold = Self::required_approvals(&env, &proposal.action);
+        let rejections = Self::signer_votes(&env, &proposal.rejections);
+        if signers.len().saturating_sub(rejections) < threshold {
+            proposal.status = ProposalStatus::Rejected;
         }
+        Self::save_proposal(&env, &proposal);
 
-        proposal.status = ProposalStatus::Executed;
-        env.storage().persistent().set(&proposal_key(proposal_id), &proposal);
+        env.events().publish(
+            (symbol_short!("rejected"), proposal_id, signer),
+            proposal.rejections.len(),
+        );
         Ok(())
     }
 
-    // Set the token address used for Transfer proposals
-    pub fn set_token(env: Env, signer: Address, token: Address) -> Result<(), MultisigError> {
+    // Queue a proposal that met the threshold without being queued, e.g.
+    // because the timelock was enabled or the threshold lowered afterwards.
+    pub fn queue(env: Env, signer: Address, proposal_id: u32) -> Result<u64, MultisigError> {
         Self::require_initialized(&env)?;
         signer.require_auth();
         Self::require_signer(&env, &signer)?;
-        env.storage().instance().set(&symbol_short!("token"), &token);
+
+        let mut proposal = Self::load_proposal(&env, proposal_id)?;
+        Self::require_active(&env, &proposal)?;
+        if proposal.status == ProposalStatus::Queued {
+            return Ok(proposal.eta);
+        }
+
+        if !Self::approvals_met(&env, &proposal) {
+            return Err(MultisigError::ThresholdNotMet);
+        }
+
+        Self::enqueue(&env, &mut proposal);
+        Ok(proposal.eta)
+    }
+
+    // Execute a proposal once enough approvals are collected.
+    // Any signer or authorized executor can trigger execution. With a
+    // timelock set, the proposal must be queued and its delay elapsed.
+    pub fn execute(env: Env, signer: Address, proposal_id: u32) -> Result<(), MultisigError> {
+        Self::require_initialized(&env)?;
+        Self::require_not_retired(&env)?;
+        signer.require_auth();
+        if !Self::is_executor(&env, &signer) {
+            Self::require_signer(&env, &signer)?;
+        }
+
+        let proposal = Self::load_proposal(&env, proposal_id)?;
+        Self::require_executable(&env, &proposal)?;
+        Self::run_proposal(&env, &signer, proposal)
+    }
+
+    // Cancel an active proposal. Only the original proposer can cancel.
+    pub fn cancel_proposal(
+        env: Env,
+        proposer: Address,
+        proposal_id: u32,
+    ) -> Result<(), MultisigError> {
+        Self::require_initialized(&env)?;
+        proposer.require_auth();
+
+        let mut proposal = Self::load_proposal(&env, proposal_id)?;
+        Self::require_active(&env, &proposal)?;
+        if proposal.proposer != proposer {
+            return Err(MultisigError::NotProposer);
+        }
+
+        proposal.status = ProposalStatus::Cancelled;
+        Self::save_proposal(&env, &proposal);
+
+        env.events()
+            .publish((symbol_short!("cancelled"), proposal_id, proposer), ());
         Ok(())
     }
 
-    // --- View functions ---
+    // Start a recovery replacing the signer set. Only guardians can
+    // initiate, and only one recovery can be pending at a time.
+    pub fn initiate_recovery(
+        env: Env,
+        guardian: Address,
+        new_signers: Vec<Address>,
+        new_threshold: u32,
+    ) -> Result<(), MultisigError> {
+        Self::require_initialized(&env)?;
+        guardian.require_auth();
+        let config = Self::require_guardian(&env, &guardian)?;
+        if env.storage().instance().has(&PENDING_RECOVERY) {
+            return Err(MultisigError::RecoveryPending);
+        }
+        if new_signers.is_empty() {
+            return Err(MultisigError::EmptySigners);
+        }
+        if new_threshold == 0 || new_threshold > new_signers.len() {
+            return Err(MultisigError::InvalidThreshold);
+        }
 
-    pub fn get_proposal(env: Env, proposal_id: u32) -> Result<Proposal, MultisigError> {
-        Self::load_proposal(&env, proposal_id)
+        let mut recovery = PendingRecovery {
+            new_signers,
+            new_threshold,
+            approvals: Vec::new(&env),
+            eta: 0,
+        };
+        env.events().publish(
+            (symbol_short!("rec_init"), guardian.clone()),
+            (recovery.new_signers.clone(), new_threshold),
+        );
+        Self::back_recovery(&env, &config, &guardian, &mut recovery);
+        Ok(())
     }
 
-    pub fn get_signers(env: Env) -> Result<Vec<Address>, MultisigError> {
+    // Back the pending recovery as another guardian. The approval that
+    // reaches the guardian threshold starts the delay.
+    pub fn support_recovery(env: Env, guardian: Address) -> Result<(), MultisigError> {
         Self::require_initialized(&env)?;
-        Ok(env.storage().instance().get(&SIGNERS).unwrap())
+        guardian.require_auth();
+        let config = Self::require_guardian(&env, &guardian)?;
+        let mut recovery = Self::pending_recovery(&env)?;
+        if recovery.approvals.contains(&guardian) {
+            return Err(MultisigError::AlreadyApproved);
+        }
+        Self::back_recovery(&env, &config, &guardian, &mut recovery);
+        Ok(())
     }
 
-    pub fn get_threshold(env: Env) -> Result<u32, MultisigError> {
+    // Cancel the pending recovery. Any current signer can cancel, which is
+    // how responsive signers fend off an unwanted recovery.
+    pub fn cancel_recovery(env: Env, signer: Address) -> Result<(), MultisigError> {
         Self::require_initialized(&env)?;
-        Ok(env.storage().instance().get(&THRESHOLD).unwrap())
+        signer.require_auth();
+        Self::require_signer(&env, &signer)?;
+        Self::pending_recovery(&env)?;
+
+        env.storage().instance().remove(&PENDING_RECOVERY);
+        env.events()
+            .publish((symbol_short!("rec_cncl"), signer), ());
+        Ok(())
     }
 
-    pub fn get_proposal_count(env: Env) -> Result<u32, MultisigError> {
+    // Install the recovered signer set once the delay has elapsed.
+    // Anyone can finalize; the guardians already authorized it.
+    // Everything the old keys could still act through is cleared: votes on
+    // open proposals, executors and spending limits.
+    pub fn finalize_recovery(env: Env) -> Result<(), MultisigError> {
         Self::require_initialized(&env)?;
-        Ok(env.storage().instance().get(&PROP_COUNT).unwrap())
+        let recovery = Self::pending_recovery(&env)?;
+        if recovery.eta == 0 || env.ledger().timestamp() < recovery.eta {
+            return Err(MultisigError::RecoveryNotReady);
+        }
+
+        let storage = env.storage().instance();
+        storage.remove(&PENDING_RECOVERY);
+        // The old per-action thresholds may not fit the new signer set
+        storage.remove(&POLICY);
+        storage.remove(&EXECUTORS);
+        for token in Self::limited_tokens(&env).iter() {
+            storage.remove(&limit_key(token));
+        }
+        storage.remove(&LIMITED);
+        // Votes cast before this point lapse as each proposal is next loaded
+        storage.set(&SIGNER_EPOCH, &(Self::signer_epoch(&env) + 1));
+        // Recovery is not a proposal, so signer events carry ID 0
+        Self::replace_signers(&env, 0, recovery.new_signers, recovery.new_threshold)
     }
 
-    // --- Internal helpers ---
+    // Register the ed25519 key a signer uses for off-chain approvals.
+    // Re-registering replaces the signer's previous key.
+    pub fn register_signer_key(
+        env: Env,
+        signer: Address,
+        public_key: BytesN<32>,
+    ) -> Result<(), MultisigError> {
+        Self::require_initialized(&env)?;
+        signer.require_auth();
+        Self::require_signer(&env, &signer)?;
 
-    fn require_initialized(env: &Env) -> Result<(), MultisigError> {
-        if !env.storage().instance().has(&INITIALIZED) {
-            return Err(MultisigError::NotInitialized);
+        let storage = env.storage().persistent();
+        if let Some(owner) = storage.get::<_, Address>(&key_signer_key(public_key.clone())) {
+            if owner != signer {
+                return Err(MultisigError::KeyAlreadyRegistered);
+            }
+        }
+        if let Some(old_key) = storage.get::<_, BytesN<32>>(&signer_key_key(signer.clone())) {
+            storage.remove(&key_signer_key(old_key));
         }
+        storage.set(&signer_key_key(signer.clone()), &public_key);
+        storage.set(&key_signer_key(public_key.clone()), &signer);
+
+        env.events()
+            .publish((symbol_short!("key_set"), signer), public_key);
         Ok(())
     }
 
-    fn require_signer(env: &Env, addr: &Address) -> Result<(), MultisigError> {
-        let signers: Vec<Address> = env.storage().instance().get(&SIGNERS).unwrap();
-        for s in signers.iter() {
-            if s == *addr {
-                return Ok(());
+    // Execute an action approved off-chain in a single transaction.
+    // Each (public key, signature) pair must be a registered signer key
+    // signing `signature_payload(action, expiration_ledger)`. The action is
+    // recorded as a proposal approved by those signers; with a timelock set
+    // it is queued instead of executed. Returns the proposal ID.
+    pub fn execute_with_signatures(
+        env: Env,
+        action: ProposalAction,
+        expiration_ledger: u64,
+        signatures: Vec<(BytesN<32>, BytesN<64>)>,
+    ) -> Result<u32, MultisigError> {
+        Self::require_initialized(&env)?;
+        if env.ledger().sequence() as u64 > expiration_ledger {
+            return Err(MultisigError::ProposalExpired);
+        }
+
+        let digest = Self::signature_payload(env.clone(), action.clone(), expiration_ledger)?;
+        let mut approvals: Vec<Address> = Vec::new(&env);
+        for (public_key, signature) in signatures.iter() {
+            let signer: Address = env
+                .storage()
+                .persistent()
+                .get(&key_signer_key(public_key.clone()))
+                .ok_or(MultisigError::UnknownSignerKey)?;
+            Self::require_signer(&env, &signer)?;
+            if approvals.contains(&signer) {
+                return Err(MultisigError::DuplicateSigner);
             }
+            env.crypto()
+                .ed25519_verify(&public_key, &digest.clone().into(), &signature);
+            approvals.push_back(signer);
         }
-        Err(MultisigError::NotASigner)
+
+        // An empty batch needs no approvals, so reject it before the threshold check
+        if let ProposalAction::Batch(actions) = &action {
+            if actions.is_empty() {
+                return Err(MultisigError::InvalidBatch);
+            }
+        }
+        if approvals.is_empty() || approvals.len() < Self::required_approvals(&env, &action) {
+            return Err(MultisigError::ThresholdNotMet);
+        }
+
+        // Consume the nonce so the same signatures can't be replayed
+        let nonce: u64 = env.storage().instance().get(&SIG_NONCE).unwrap_or(0);
+        env.storage().instance().set(&SIG_NONCE, &(nonce + 1));
+
+        let proposer = approvals.get(0).ok_or(MultisigError::ThresholdNotMet)?;
+        let mut proposal = Self::insert_proposal(
+            &env,
+            proposer.clone(),
+            action,
+            expiration_ledger,
+            0,
+            Vec::new(&env),
+            approvals,
+        )?;
+        let id = proposal.id;
+        if Self::timelock_delay(&env) > 0 {
+            Self::enqueue(&env, &mut proposal);
+        } else {
+            Self::run_proposal(&env, &proposer, proposal)?;
+        }
+        Ok(id)
     }
 
-    fn load_proposal(env: &Env, id: u32) -> Result<Proposal, MultisigError> {
-        env.storage()
-            .persistent()
-            .get(&proposal_key(id))
-            .ok_or(MultisigError::ProposalNotFound)
+    // Digest signers sign for `execute_with_signatures`:
+    // sha256(xdr((wallet address, action, expiration_ledger, nonce)))
+    pub fn signature_payload(
+        env: Env,
+        action: ProposalAction,
+        expiration_ledger: u64,
+    ) -> Result<BytesN<32>, MultisigError> {
+        Self::require_initialized(&env)?;
+        let nonce: u64 = env.storage().instance().get(&SIG_NONCE).unwrap_or(0);
+        let payload = (env.current_contract_address(), action, expiration_ledger, nonce).to_xdr(&env);
+        Ok(env.crypto().sha256(&payload).into())
+    }
+
+    // Deposit `amount` of `token` from `from` into the wallet.
+    // Anyone can fund the wallet; the deposit is recorded as an event.
+    pub fn deposit(
+        env: Env,
+        from: Address,
+        token: Address,
+        amount: i128,
+    ) -> Result<(), MultisigError> {
+        Self::require_initialized(&env)?;
+        from.require_auth();
+        if amount <= 0 {
+            return Err(MultisigError::InvalidAmount);
+        }
+
+        Self::require_not_retired(&env)?;
+
+        soroban_sdk::token::Client::new(&env, &token).transfer(
+            &from,
+            &env.current_contract_address(),
+            &amount,
+        );
+
+        env.events()
+            .publish((symbol_short!("deposit"), token, from), amount);
+        Ok(())
+    }
+
+    // Spend from a token's spending limit without a proposal.
+    // Any single signer can call this while the current window has room.
+    pub fn spend(
+        env: Env,
+        signer: Address,
+        token: Address,
+        to: Address,
+        amount: i128,
+    ) -> Result<(), MultisigError> {
+        Self::require_initialized(&env)?;
+        Self::require_not_retired(&env)?;
+        signer.require_auth();
+        Self::require_signer(&env, &signer)?;
+        if amount <= 0 {
+            return Err(MultisigError::InvalidAmount);
+        }
+
+        let mut limit = Self::current_window(&env, &token)?;
+        let spent = limit
+            .spent
+            .checked_add(amount)
+            .filter(|spent| *spent <= limit.limit)
+            .ok_or(MultisigError::SpendingLimitExceeded)?;
+        limit.spent = spent;
+        env.storage().instance().set(&limit_key(token.clone()), &limit);
+
+        soroban_sdk::token::Client::new(&env, &token).transfer(
+            &env.current_contract_address(),
+            &to,
+            &amount,
+        );
+
+        env.events()
+            .publish((symbol_short!("spent"), token, signer), (to, amount));
+        Ok(())
+    }
+
+    // Delete closed proposals with IDs below `before_id` from persistent
+    // storage so the wallet stops paying rent on them. Executed, cancelled
+    // and rejected proposals are closed; so are ones that expired before
+    // `before_ledger`. Each deletion emits an `archived` event carrying the
+    // sha256 of the proposal record. Open proposals in the scanned range
+    // get their TTL extended to their expiration instead.
+    // Scans at most MAX_PAGE_SIZE IDs per call and returns how many were
+    // pruned; call again to continue. Each call resumes where the last scan
+    // stopped and wraps back to the oldest unpruned ID once it reaches
+    // `before_id`, so a long-lived open proposal doesn't stall the rest.
+    pub fn prune(
+        env: Env,
+        signer: Address,
+        before_id: u32,
+        before_ledger: u64,
+    ) -> Result<u32, MultisigError> {
+        Self::require_initialized(&env)?;
+        signer.require_auth();
+        Self::require_signer(&env, &signer)?;
+
+        let count: u32 = env.storage().instance().get(&PROP_COUNT).unwrap();
+        // Lowest ID that may still hold a proposal; everything below is gone
+        let floor: u32 = env.storage().instance().get(&PRUNE_CURSOR).unwrap_or(1);
+        let limit = before_id.min(count + 1);
+        let mut start: u32 = env.storage().instance().get(&PRUNE_SCAN).unwrap_or(1).max(floor);
+        if start >= limit {
+            start = floor;
+        }
+        let end = limit.max(start).min(start.saturating_add(MAX_PAGE_SIZE));
+        let current_ledger = env.ledger().sequence() as u64;
+
+        let mut pruned = 0u32;
+        let mut cursor = floor;
+        for id in start..end {
+            let Ok(proposal) = Self::load_proposal(&env, id) else {
+                if cursor == id {
+                    cursor = id + 1;
+                }
+                continue;
+            };
+            let closed = match proposal.status {
+                ProposalStatus::Executed
+                | ProposalStatus::Cancelled
+                | ProposalStatus::Rejected
+                | ProposalStatus::Failed => true,
+                ProposalStatus::Active | ProposalStatus::Queued => {
+                    proposal.expiration < current_ledger && proposal.expiration < before_ledger
+                }
+            };
+            if !closed {
+                Self::extend_proposal_ttl(&env, &proposal);
+                continue;
+            }
+
+            let summary: BytesN<32> = env.crypto().sha256(&proposal.clone().to_xdr(&env)).into();
+            let storage = env.storage().persistent();
+            storage.remove(&proposal_key(id));
+            // Only drop the hash index if a later proposal hasn't taken it over
+            if storage.get::<_, u32>(&hash_key(proposal.content_hash.clone())) == Some(id) {
+                storage.remove(&hash_key(proposal.content_hash));
+            }
+            env.events().publish((symbol_short!("archived"), id), summary);
+            pruned += 1;
+            if cursor == id {
+                cursor = id + 1;
+            }
+        }
+        env.storage().instance().set(&PRUNE_CURSOR, &cursor);
+        env.storage().instance().set(&PRUNE_SCAN, &end);
+        Ok(pruned)
+    }
+
+    // Extend a proposal's storage TTL to its expiration (plus a day's
+    // margin), so it can't be archived mid-approval. Anyone can pay for
+    // this. Returns the TTL in ledgers the entry now has at least.
+    pub fn bump_proposal(env: Env, proposal_id: u32) -> Result<u32, MultisigError> {
+        Self::require_initialized(&env)?;
+        let proposal = Self::load_proposal(&env, proposal_id)?;
+        Ok(Self::extend_proposal_ttl(&env, &proposal))
+    }
+
+    // --- View functions ---
+
+    // Reading an open proposal also extends its TTL
+    pub fn get_proposal(env: Env, proposal_id: u32) -> Result<Proposal, MultisigError> {
+        let proposal = Self::load_proposal(&env, proposal_id)?;
+        if Self::require_active(&env, &proposal).is_ok() {
+            Self::extend_proposal_ttl(&env, &proposal);
+        }
+        Ok(proposal)
+    }
+
+    // List proposals in ID order, optionally filtered by status, starting
+    // at `start_id`. Reads at most MAX_PAGE_SIZE IDs per call and returns up
+    // to `limit` matches, so a filtered page can come back short or empty;
+    // keep calling with `next_id` until it is 0.
+    pub fn get_proposals(
+        env: Env,
+        status_filter: Option<ProposalStatus>,
+        start_id: u32,
+        limit: u32,
+    ) -> Result<ProposalPage, MultisigError> {
+        Self::require_initialized(&env)?;
+        Ok(Self::page_proposals(&env, start_id, limit, |_, proposal| {
+            status_filter.as_ref().is_none_or(|status| proposal.status == *status)
+        }))
+    }
+
+    // Approval progress and executability of one proposal. `executable`
+    // covers status, approvals and timelock; use `simulate` to also check
+    // the action itself.
+    pub fn get_proposal_summary(
+        env: Env,
+        proposal_id: u32,
+    ) -> Result<ProposalSummary, MultisigError> {
+        Self::require_initialized(&env)?;
+        let proposal = Self::load_proposal(&env, proposal_id)?;
+
+        let signers: Vec<Address> = env.storage().instance().get(&SIGNERS).unwrap();
+        let mut pending = Vec::new(&env);
+        for signer in signers.iter() {
+            if !proposal.approvals.contains(&signer) && !proposal.rejections.contains(&signer) {
+                pending.push_back(signer);
+            }
+        }
+
+        Ok(ProposalSummary {
+            status: proposal.status.clone(),
+            approvals: Self::signer_votes(&env, &proposal.approvals),
+            threshold: Self::required_approvals(&env, &proposal.action),
+            pending,
+            expiration: proposal.expiration,
+            eta: proposal.eta,
+            executable: Self::require_executable(&env, &proposal).is_ok(),
+        })
+    }
+
+    // Open, unexpired proposals the signer has neither approved nor rejected,
+    // in ID order. Paged like `get_proposals`.
+    pub fn get_pending_for_signer(
+        env: Env,
+        signer: Address,
+        start_id: u32,
+        limit: u32,
+    ) -> Result<ProposalPage, MultisigError> {
+        Self::require_initialized(&env)?;
+        Ok(Self::page_proposals(&env, start_id, limit, |env, proposal| {
+            Self::require_active(env, proposal).is_ok()
+                && !proposal.approvals.contains(&signer)
+                && !proposal.rejections.contains(&signer)
+        }))
+    }
+
+    // Dry-run a proposal without changing state: check its status,
+    // approvals and timelock, then validate each action against the wallet
+    // as it would be when that action runs (balances, signer set changes,
+    // invoke targets). Contract code behind an Invoke target isn't run, so
+    // a clean result doesn't guarantee the call itself succeeds.
+    pub fn simulate(env: Env, proposal_id: u32) -> Result<Simulation, MultisigError> {
+        Self::require_initialized(&env)?;
+        let proposal = Self::load_proposal(&env, proposal_id)?;
+
+        let mut issues = Vec::new(&env);
+        if let Err(err) = Self::require_active(&env, &proposal) {
+            issues.push_back(SimulationIssue::NotOpen(err as u32));
+        }
+        let required = Self::required_approvals(&env, &proposal.action);
+        let approvals = Self::signer_votes(&env, &proposal.approvals);
+        if approvals < required {
+            issues.push_back(SimulationIssue::ThresholdNotMet(approvals, required));
+        }
+        let linked = Self::linked_wallets(&env);
+        let mut pending_wallets = Vec::new(&env);
+        for wallet in proposal.required_wallets.iter() {
+            if linked.contains(&wallet) && !proposal.wallet_approvals.contains(&wallet) {
+                pending_wallets.push_back(wallet);
+            }
+        }
+        if !pending_wallets.is_empty() {
+            issues.push_back(SimulationIssue::WalletsPending(pending_wallets));
+        }
+        if Self::timelock_delay(&env) > 0 {
+            if proposal.status != ProposalStatus::Queued {
+                issues.push_back(SimulationIssue::NotQueued);
+            } else if env.ledger().timestamp() < proposal.eta {
+                issues.push_back(SimulationIssue::TimelockPending(proposal.eta));
+            }
+        }
+
+        let mut state = DryRun {
+            signers: env.storage().instance().get(&SIGNERS).unwrap(),
+            threshold: env.storage().instance().get(&THRESHOLD).unwrap(),
+            policy: Self::policy(&env),
+            linked,
+            outflows: Map::new(&env),
+        };
+        Self::simulate_action(&env, &proposal.action, &mut state, &mut issues);
+
+        let wallet = env.current_contract_address();
+        for (token, needed) in state.outflows.iter() {
+            match soroban_sdk::token::Client::new(&env, &token).try_balance(&wallet) {
+                Ok(Ok(balance)) if balance < needed => issues.push_back(
+                    SimulationIssue::InsufficientBalance(token, needed, balance),
+                ),
+                Ok(Ok(_)) => {}
+                _ => issues.push_back(SimulationIssue::TargetUnavailable(token)),
+            }
+        }
+
+        Ok(Simulation {
+            executable: issues.is_empty(),
+            issues,
+        })
+    }
+
+    // ID of the proposal last created with this content hash, if any
+    pub fn find_proposal_by_hash(env: Env, hash: BytesN<32>) -> Option<u32> {
+        env.storage().persistent().get(&hash_key(hash))
+    }
+
+    // Content hash a proposal with these parameters would get
+    pub fn compute_proposal_hash(
+        env: Env,
+        action: ProposalAction,
+        expiration_ledger: u64,
+        salt: u64,
+    ) -> BytesN<32> {
+        Self::content_hash(&env, &action, expiration_ledger, salt)
+    }
+
+    pub fn get_signers(env: Env) -> Result<Vec<Address>, MultisigError> {
+        Self::require_initialized(&env)?;
+        Ok(env.storage().instance().get(&SIGNERS).unwrap())
+    }
+
+    pub fn get_threshold(env: Env) -> Result<u32, MultisigError> {
+        Self::require_initialized(&env)?;
+        Ok(env.storage().instance().get(&THRESHOLD).unwrap())
+    }
+
+    // Approvals required for one kind of action: its own threshold if
+    // set, otherwise the wallet-wide one
+    pub fn get_action_threshold(env: Env, kind: ActionKind) -> Result<u32, MultisigError> {
+        Self::require_initialized(&env)?;
+        Ok(Self::kind_threshold(&env, kind))
+    }
+
+    pub fn get_proposal_count(env: Env) -> Result<u32, MultisigError> {
+        Self::require_initialized(&env)?;
+        Ok(env.storage().instance().get(&PROP_COUNT).unwrap())
+    }
+
+    pub fn get_balance(env: Env, token: Address) -> i128 {
+        soroban_sdk::token::Client::new(&env, &token).balance(&env.current_contract_address())
+    }
+
+    // Spending limit for a token, with `spent` reset if the window has rolled over
+    pub fn get_spending_limit(env: Env, token: Address) -> Result<SpendingLimit, MultisigError> {
+        Self::require_initialized(&env)?;
+        Self::current_window(&env, &token)
+    }
+
+    pub fn get_timelock(env: Env) -> Result<u64, MultisigError> {
+        Self::require_initialized(&env)?;
+        Ok(Self::timelock_delay(&env))
+    }
+
+    pub fn get_auto_execute(env: Env) -> Result<bool, MultisigError> {
+        Self::require_initialized(&env)?;
+        Ok(Self::auto_execute_enabled(&env))
+    }
+
+    pub fn get_signer_key(env: Env, signer: Address) -> Option<BytesN<32>> {
+        env.storage().persistent().get(&signer_key_key(signer))
+    }
+
+    pub fn get_recovery_config(env: Env) -> Result<Option<RecoveryConfig>, MultisigError> {
+        Self::require_initialized(&env)?;
+        Ok(env.storage().instance().get(&RECOVERY))
+    }
+
+    pub fn get_pending_recovery(env: Env) -> Result<Option<PendingRecovery>, MultisigError> {
+        Self::require_initialized(&env)?;
+        Ok(env.storage().instance().get(&PENDING_RECOVERY))
+    }
+
+    // Wallet this one migrated to, if it has been retired
+    pub fn get_successor(env: Env) -> Result<Option<Address>, MultisigError> {
+        Self::require_initialized(&env)?;
+        Ok(env.storage().instance().get(&SUCCESSOR))
+    }
+
+    // Authorized executors and the timestamp each one's authorization ends
+    pub fn get_executors(env: Env) -> Result<Map<Address, u64>, MultisigError> {
+        Self::require_initialized(&env)?;
+        Ok(Self::executors(&env))
+    }
+
+    pub fn get_linked_wallets(env: Env) -> Result<Vec<Address>, MultisigError> {
+        Self::require_initialized(&env)?;
+        Ok(Self::linked_wallets(&env))
+    }
+
+    // --- Internal helpers ---
+
+    fn require_initialized(env: &Env) -> Result<(), MultisigError> {
+        if !env.storage().instance().has(&INITIALIZED) {
+            return Err(MultisigError::NotInitialized);
+        }
+        Ok(())
+    }
+
+    fn require_not_retired(env: &Env) -> Result<(), MultisigError> {
+        if env.storage().instance().has(&SUCCESSOR) {
+            return Err(MultisigError::WalletRetired);
+        }
+        Ok(())
+    }
+
+    // A wallet can migrate once, and not to itself
+    fn check_successor(
+        env: &Env,
+        successor: &Address,
+        tokens: &Vec<Address>,
+    ) -> Result<(), MultisigError> {
+        Self::require_not_retired(env)?;
+        if *successor == env.current_contract_address() {
+            return Err(MultisigError::InvalidSuccessor);
+        }
+        if tokens.len() > MAX_SWEEP_TOKENS {
+            return Err(MultisigError::TooManyTokens);
+        }
+        Ok(())
+    }
+
+    fn signer_epoch(env: &Env) -> u32 {
+        env.storage().instance().get(&SIGNER_EPOCH).unwrap_or(0)
+    }
+
+    // Tokens that currently have a spending limit
+    fn limited_tokens(env: &Env) -> Vec<Address> {
+        env.storage()
+            .instance()
+            .get(&LIMITED)
+            .unwrap_or_else(|| Vec::new(env))
+    }
+
+    fn executors(env: &Env) -> Map<Address, u64> {
+        env.storage()
+            .instance()
+            .get(&EXECUTORS)
+            .unwrap_or_else(|| Map::new(env))
+    }
+
+    fn is_executor(env: &Env, addr: &Address) -> bool {
+        match Self::executors(env).get(addr.clone()) {
+            Some(until) => env.ledger().timestamp() < until,
+            None => false,
+        }
+    }
+
+    fn require_signer(env: &Env, addr: &Address) -> Result<(), MultisigError> {
+        let signers: Vec<Address> = env.storage().instance().get(&SIGNERS).unwrap();
+        for s in signers.iter() {
+            if s == *addr {
+                return Ok(());
+            }
+        }
+        Err(MultisigError::NotASigner)
+    }
+
+    // Load a token's spending limit, opening a fresh window once the
+    // current one has elapsed
+    fn current_window(env: &Env, token: &Address) -> Result<SpendingLimit, MultisigError> {
+        let mut limit: SpendingLimit = env
+            .storage()
+            .instance()
+            .get(&limit_key(token.clone()))
+            .ok_or(MultisigError::NoSpendingLimit)?;
+        let now = env.ledger().timestamp();
+        if now >= limit.window_start.saturating_add(limit.period) {
+            limit.spent = 0;
+            limit.window_start = now;
+        }
+        Ok(limit)
+    }
+
+    fn timelock_delay(env: &Env) -> u64 {
+        env.storage().instance().get(&TIMELOCK).unwrap_or(0)
+    }
+
+    // Move a proposal into the queue with its execution time set
+    fn enqueue(env: &Env, proposal: &mut Proposal) {
+        proposal.status = ProposalStatus::Queued;
+        proposal.eta = env
+            .ledger()
+            .timestamp()
+            .saturating_add(Self::timelock_delay(env));
+        Self::save_proposal(env, proposal);
+        env.events()
+            .publish((symbol_short!("queued"), proposal.id), proposal.eta);
+    }
+
+    fn require_guardian(env: &Env, addr: &Address) -> Result<RecoveryConfig, MultisigError> {
+        let config: RecoveryConfig = env
+            .storage()
+            .instance()
+            .get(&RECOVERY)
+            .ok_or(MultisigError::NoRecoveryConfig)?;
+        if !config.guardians.contains(addr) {
+            return Err(MultisigError::NotAGuardian);
+        }
+        Ok(config)
+    }
+
+    fn pending_recovery(env: &Env) -> Result<PendingRecovery, MultisigError> {
+        env.storage()
+            .instance()
+            .get(&PENDING_RECOVERY)
+            .ok_or(MultisigError::NoPendingRecovery)
+    }
+
+    // Record a guardian's backing, starting the delay at the threshold
+    fn back_recovery(
+        env: &Env,
+        config: &RecoveryConfig,
+        guardian: &Address,
+        recovery: &mut PendingRecovery,
+    ) {
+        recovery.approvals.push_back(guardian.clone());
+        if recovery.eta == 0 && recovery.approvals.len() >= config.threshold {
+            recovery.eta = env.ledger().timestamp().saturating_add(config.delay);
+        }
+        env.storage().instance().set(&PENDING_RECOVERY, recovery);
+        env.events().publish(
+            (symbol_short!("rec_back"), guardian.clone()),
+            (recovery.approvals.len(), recovery.eta),
+        );
+    }
+
+    fn linked_wallets(env: &Env) -> Vec<Address> {
+        env.storage()
+            .instance()
+            .get(&LINKED)
+            .unwrap_or_else(|| Vec::new(env))
+    }
+
+    fn policy(env: &Env) -> Map<ActionKind, u32> {
+        env.storage()
+            .instance()
+            .get(&POLICY)
+            .unwrap_or_else(|| Map::new(env))
+    }
+
+    fn kind_threshold(env: &Env, kind: ActionKind) -> u32 {
+        Self::policy(env)
+            .get(kind)
+            .unwrap_or_else(|| env.storage().instance().get(&THRESHOLD).unwrap())
+    }
+
+    // A batch needs as many approvals as its most demanding action
+    fn required_approvals(env: &Env, action: &ProposalAction) -> u32 {
+        let kind = match action {
+            ProposalAction::Transfer(..) => ActionKind::Transfer,
+            ProposalAction::UpdateSigners(..) => ActionKind::UpdateSigners,
+            ProposalAction::AddSigner(..) => ActionKind::AddSigner,
+            ProposalAction::RemoveSigner(..) => ActionKind::RemoveSigner,
+            ProposalAction::Invoke(..) => ActionKind::Invoke,
+            ProposalAction::SetAutoExecute(..) => ActionKind::SetAutoExecute,
+            ProposalAction::SetSpendingLimit(..) => ActionKind::SetSpendingLimit,
+            ProposalAction::SetTimelock(..) => ActionKind::SetTimelock,
+            ProposalAction::LinkWallet(..) => ActionKind::LinkWallet,
+            ProposalAction::UnlinkWallet(..) => ActionKind::UnlinkWallet,
+            ProposalAction::SetRecovery(..) => ActionKind::SetRecovery,
+            ProposalAction::SetActionThreshold(..) => ActionKind::SetActionThreshold,
+            ProposalAction::SetExecutor(..) => ActionKind::SetExecutor,
+            ProposalAction::MigrateTo(..) => ActionKind::MigrateTo,
+            ProposalAction::Batch(actions) => {
+                let mut required = 0;
+                for inner in actions.iter() {
+                    required = required.max(Self::required_approvals(env, &inner));
+                }
+                return required;
+            }
+        };
+        Self::kind_threshold(env, kind)
+    }
+
+    // How many of `votes` come from current signers. Votes cast by signers
+    // removed since don't count.
+    fn signer_votes(env: &Env, votes: &Vec<Address>) -> u32 {
+        let signers: Vec<Address> = env.storage().instance().get(&SIGNERS).unwrap();
+        let mut count = 0;
+        for addr in votes.iter() {
+            if signers.contains(&addr) {
+                count += 1;
+            }
+        }
+        count
+    }
+
+    // Threshold reached and every still-linked required wallet signed off
+    fn approvals_met(env: &Env, proposal: &Proposal) -> bool {
+        let required = Self::required_approvals(env, &proposal.action);
+        if Self::signer_votes(env, &proposal.approvals) < required {
+            return false;
+        }
+        let linked = Self::linked_wallets(env);
+        for wallet in proposal.required_wallets.iter() {
+            if linked.contains(&wallet) && !proposal.wallet_approvals.contains(&wallet) {
+                return false;
+            }
+        }
+        true
+    }
+
+    // Queue or auto-execute an active proposal whose requirements were just
+    // completed by `approver`
+    fn on_approval(
+        env: &Env,
+        approver: &Address,
+        mut proposal: Proposal,
+    ) -> Result<(), MultisigError> {
+        if proposal.status == ProposalStatus::Active && Self::approvals_met(env, &proposal) {
+            if Self::timelock_delay(env) > 0 {
+                Self::enqueue(env, &mut proposal);
+            } else if Self::auto_execute_enabled(env) {
+                Self::run_proposal(env, approver, proposal)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn auto_execute_enabled(env: &Env) -> bool {
+        env.storage().instance().get(&AUTO_EXEC).unwrap_or(false)
+    }
+
+    // Store a new proposal. Only signers can propose.
+    fn new_proposal(
+        env: &Env,
+        proposer: Address,
+        action: ProposalAction,
+        expiration_ledger: u64,
+        salt: u64,
+        required_wallets: Vec<Address>,
+    ) -> Result<u32, MultisigError> {
+        Self::require_initialized(env)?;
+        proposer.require_auth();
+        Self::require_signer(env, &proposer)?;
+
+        let proposal = Self::insert_proposal(
+            env,
+            proposer,
+            action,
+            expiration_ledger,
+            salt,
+            required_wallets,
+            Vec::new(env),
+        )?;
+        Ok(proposal.id)
+    }
+
+    // Validate and store a proposal whose proposer and approvals the caller
+    // has already authenticated
+    fn insert_proposal(
+        env: &Env,
+        proposer: Address,
+        action: ProposalAction,
+        expiration_ledger: u64,
+        salt: u64,
+        required_wallets: Vec<Address>,
+        approvals: Vec<Address>,
+    ) -> Result<Proposal, MultisigError> {
+        Self::require_not_retired(env)?;
+
+        // Refuse a second open proposal with the same content
+        let content_hash = Self::content_hash(env, &action, expiration_ledger, salt);
+        if let Some(existing) = Self::find_proposal_by_hash(env.clone(), content_hash.clone()) {
+            let proposal = Self::load_proposal(env, existing)?;
+            if Self::require_active(env, &proposal).is_ok() {
+                return Err(MultisigError::DuplicateProposal);
+            }
+        }
+
+        if let ProposalAction::Batch(actions) = &action {
+            if actions.is_empty() {
+                return Err(MultisigError::InvalidBatch);
+            }
+            for inner in actions.iter() {
+                if let ProposalAction::Batch(_) = inner {
+                    return Err(MultisigError::InvalidBatch);
+                }
+            }
+        }
+
+        let id: u32 = env.storage().instance().get(&PROP_COUNT).unwrap();
+        let next_id = id + 1;
+
+        let proposal = Proposal {
+            id: next_id,
+            proposer: proposer.clone(),
+            action,
+            approvals,
+            rejections: Vec::new(env),
+            required_wallets,
+            wallet_approvals: Vec::new(env),
+            content_hash: content_hash.clone(),
+            description: String::from_str(env, ""),
+            metadata_hash: None,
+            status: ProposalStatus::Active,
+            expiration: expiration_ledger,
+            eta: 0,
+            error_code: 0,
+            epoch: Self::signer_epoch(env),
+        };
+
+        env.storage().persistent().set(&hash_key(content_hash), &next_id);
+        Self::save_proposal(env, &proposal);
+        env.storage().instance().set(&PROP_COUNT, &next_id);
+
+        env.events().publish(
+            (symbol_short!("proposed"), next_id, proposer),
+            proposal.action.clone(),
+        );
+        Ok(proposal)
+    }
+
+    fn content_hash(
+        env: &Env,
+        action: &ProposalAction,
+        expiration_ledger: u64,
+        salt: u64,
+    ) -> BytesN<32> {
+        let payload = (action.clone(), expiration_ledger, salt).to_xdr(env);
+        env.crypto().sha256(&payload).into()
+    }
+
+    // Keep an open proposal's entries alive until it expires
+    fn extend_proposal_ttl(env: &Env, proposal: &Proposal) -> u32 {
+        let remaining = proposal
+            .expiration
+            .saturating_sub(env.ledger().sequence() as u64)
+            .min(u32::MAX as u64) as u32;
+        let extend_to = remaining
+            .saturating_add(PROPOSAL_TTL_BUFFER)
+            .min(env.storage().max_ttl());
+        let storage = env.storage().persistent();
+        storage.extend_ttl(&proposal_key(proposal.id), extend_to, extend_to);
+        storage.extend_ttl(&hash_key(proposal.content_hash.clone()), extend_to, extend_to);
+        extend_to
+    }
+
+    // Store a proposal, keeping it alive until it expires while it's open
+    fn save_proposal(env: &Env, proposal: &Proposal) {
+        env.storage()
+            .persistent()
+            .set(&proposal_key(proposal.id), proposal);
+        if let ProposalStatus::Active | ProposalStatus::Queued = proposal.status {
+            Self::extend_proposal_ttl(env, proposal);
+        }
+    }
+
+    // An open proposal from before the last recovery comes back with its
+    // signer votes cleared, and unqueued since it no longer meets any threshold
+    // Scan IDs from `start_id` (never below the prune cursor) for proposals
+    // `keep` accepts. Stops after MAX_PAGE_SIZE IDs or `limit` matches.
+    fn page_proposals(
+        env: &Env,
+        start_id: u32,
+        limit: u32,
+        keep: impl Fn(&Env, &Proposal) -> bool,
+    ) -> ProposalPage {
+        let count: u32 = env.storage().instance().get(&PROP_COUNT).unwrap();
+        let floor: u32 = env.storage().instance().get(&PRUNE_CURSOR).unwrap_or(1);
+        let limit = limit.min(MAX_PAGE_SIZE);
+        let start = start_id.max(floor);
+        let end = (count + 1).max(start).min(start.saturating_add(MAX_PAGE_SIZE));
+
+        let mut proposals = Vec::new(env);
+        let mut next_id = end;
+        for id in start..end {
+            if proposals.len() >= limit {
+                next_id = id;
+                break;
+            }
+            let Ok(proposal) = Self::load_proposal(env, id) else {
+                continue;
+            };
+            if keep(env, &proposal) {
+                proposals.push_back(proposal);
+            }
+        }
+        if next_id > count {
+            next_id = 0;
+        }
+        ProposalPage { proposals, next_id }
+    }
+
+    fn load_proposal(env: &Env, id: u32) -> Result<Proposal, MultisigError> {
+        let mut proposal: Proposal = env
+            .storage()
+            .persistent()
+            .get(&proposal_key(id))
+            .ok_or(MultisigError::ProposalNotFound)?;
+        let epoch = Self::signer_epoch(env);
+        if proposal.epoch < epoch {
+            if let ProposalStatus::Active | ProposalStatus::Queued = proposal.status {
+                proposal.approvals = Vec::new(env);
+                proposal.rejections = Vec::new(env);
+                proposal.status = ProposalStatus::Active;
+                proposal.eta = 0;
+            }
+            proposal.epoch = epoch;
+        }
+        Ok(proposal)
+    }
+
+    // Perform the proposal's action and mark it executed. Callers are
+    // responsible for checking auth, status and threshold first.
+    // A single Transfer or Invoke whose call fails doesn't revert: the
+    // proposal is closed as Failed with the error code, so its approvals
+    // aren't left in limbo. Batches and wallet-internal actions still
+    // revert as a whole.
+    fn run_proposal(
+        env: &Env,
+        signer: &Address,
+        mut proposal: Proposal,
+    ) -> Result<(), MultisigError> {
+        // Covers auto-execution on approval as well as `execute`
+        Self::require_not_retired(env)?;
+        let outcome = match &proposal.action {
+            ProposalAction::Transfer(token, to, amount) => Self::try_call(
+                env,
+                token,
+                &symbol_short!("transfer"),
+                (env.current_contract_address(), to.clone(), *amount).into_val(env),
+            ),
+            ProposalAction::Invoke(contract, function, args) => {
+                Self::try_call(env, contract, function, args.clone())
+            }
+            action => {
+                Self::apply_action(env, proposal.id, action)?;
+                Ok(())
+            }
+        };
+
+        if let Err(error_code) = outcome {
+            proposal.status = ProposalStatus::Failed;
+            proposal.error_code = error_code;
+            Self::save_proposal(env, &proposal);
+            env.events().publish(
+                (symbol_short!("failed"), proposal.id, signer.clone()),
+                error_code,
+            );
+            return Ok(());
+        }
+
+        proposal.status = ProposalStatus::Executed;
+        Self::save_proposal(env, &proposal);
+
+        env.events()
+            .publish((symbol_short!("executed"), proposal.id, signer.clone()), ());
+        Ok(())
+    }
+
+    // Mirror `apply_action`'s validation against the dry-run state,
+    // recording an issue instead of failing
+    fn simulate_action(
+        env: &Env,
+        action: &ProposalAction,
+        state: &mut DryRun,
+        issues: &mut Vec<SimulationIssue>,
+    ) {
+        let result = match action {
+            ProposalAction::Transfer(token, _, amount) => {
+                if *amount < 0 {
+                    Err(MultisigError::InvalidAmount)
+                } else {
+                    let total = state
+                        .outflows
+                        .get(token.clone())
+                        .unwrap_or(0)
+                        .saturating_add(*amount);
+                    state.outflows.set(token.clone(), total);
+                    Ok(())
+                }
+            }
+            ProposalAction::UpdateSigners(new_signers, new_threshold) => {
+                Self::dry_run_signers(state, new_signers.clone(), *new_threshold)
+            }
+            ProposalAction::AddSigner(signer, new_threshold) => {
+                if state.signers.contains(signer) {
+                    Err(MultisigError::DuplicateSigner)
+                } else {
+                    let mut signers = state.signers.clone();
+                    signers.push_back(signer.clone());
+                    let threshold = new_threshold.unwrap_or(state.threshold);
+                    Self::dry_run_signers(state, signers, threshold)
+                }
+            }
+            ProposalAction::RemoveSigner(signer, new_threshold) => {
+                match state.signers.first_index_of(signer) {
+                    None => Err(MultisigError::NotASigner),
+                    Some(index) => {
+                        let mut signers = state.signers.clone();
+                        signers.remove(index);
+                        let threshold = new_threshold.unwrap_or(state.threshold);
+                        Self::dry_run_signers(state, signers, threshold)
+                    }
+                }
+            }
+            ProposalAction::Invoke(contract, _, _) => {
+                // Calling the target here would run it, and the host reports a
+                // missing contract with the same error as a missing function, so
+                // only the address kind can be checked without side effects
+                if !is_contract(contract) {
+                    issues.push_back(SimulationIssue::TargetUnavailable(contract.clone()));
+                }
+                Ok(())
+            }
+            ProposalAction::SetAutoExecute(_)
+            | ProposalAction::SetTimelock(_)
+            | ProposalAction::SetExecutor(..) => Ok(()),
+            ProposalAction::MigrateTo(successor, tokens) => {
+                // Zero outflow, so only tokens that can't report a balance get flagged
+                for token in tokens.iter() {
+                    let needed = state.outflows.get(token.clone()).unwrap_or(0);
+                    state.outflows.set(token, needed);
+                }
+                Self::check_successor(env, successor, tokens)
+            }
+            ProposalAction::SetSpendingLimit(_, limit, period) => {
+                if *limit < 0 || (*limit > 0 && *period == 0) {
+                    Err(MultisigError::InvalidSpendingLimit)
+                } else {
+                    Ok(())
+                }
+            }
+            ProposalAction::LinkWallet(wallet) => {
+                if state.linked.contains(wallet) {
+                    Err(MultisigError::WalletAlreadyLinked)
+                } else {
+                    state.linked.push_back(wallet.clone());
+                    Ok(())
+                }
+            }
+            ProposalAction::UnlinkWallet(wallet) => match state.linked.first_index_of(wallet) {
+                None => Err(MultisigError::WalletNotLinked),
+                Some(index) => {
+                    state.linked.remove(index);
+                    Ok(())
+                }
+            },
+            ProposalAction::SetRecovery(guardians, threshold, _) => {
+                Self::check_guardians(env, guardians, *threshold)
+            }
+            ProposalAction::SetActionThreshold(kind, threshold) => {
+                if *threshold > state.signers.len() {
+                    Err(MultisigError::InvalidThreshold)
+                } else {
+                    if *threshold == 0 {
+                        state.policy.remove(*kind);
+                    } else {
+                        state.policy.set(*kind, *threshold);
+                    }
+                    Ok(())
+                }
+            }
+            ProposalAction::Batch(actions) => {
+                for inner in actions.iter() {
+                    Self::simulate_action(env, &inner, state, issues);
+                }
+                Ok(())
+            }
+        };
+        if let Err(err) = result {
+            issues.push_back(SimulationIssue::InvalidAction(err as u32));
+        }
+    }
+
+    fn dry_run_signers(
+        state: &mut DryRun,
+        signers: Vec<Address>,
+        threshold: u32,
+    ) -> Result<(), MultisigError> {
+        Self::check_signer_set(&signers, threshold, &state.policy)?;
+        state.signers = signers;
+        state.threshold = threshold;
+        Ok(())
+    }
+
+    // Call another contract without letting its failure abort this one.
+    // Returns the contract error code, or 0 if the call trapped.
+    fn try_call(
+        env: &Env,
+        contract: &Address,
+        function: &Symbol,
+        args: Vec<Val>,
+    ) -> Result<(), u32> {
+        match env.try_invoke_contract::<Val, InvokeError>(contract, function, args) {
+            Ok(_) => Ok(()),
+            Err(Ok(InvokeError::Contract(code))) => Err(code),
+            Err(_) => Err(0),
+        }
+    }
+
+    // Perform a single action. An error aborts the whole invocation, so a
+    // failing action in a batch rolls back the ones before it.
+    fn apply_action(
+        env: &Env,
+        proposal_id: u32,
+        action: &ProposalAction,
+    ) -> Result<(), MultisigError> {
+        match action {
+            ProposalAction::Transfer(token, to, amount) => {
+                soroban_sdk::token::Client::new(env, token).transfer(
+                    &env.current_contract_address(),
+                    to,
+                    amount,
+                );
+            }
+            ProposalAction::UpdateSigners(new_signers, new_threshold) => {
+                Self::replace_signers(env, proposal_id, new_signers.clone(), *new_threshold)?;
+            }
+            ProposalAction::AddSigner(signer, new_threshold) => {
+                let mut signers: Vec<Address> = env.storage().instance().get(&SIGNERS).unwrap();
+                if signers.contains(signer) {
+                    return Err(MultisigError::DuplicateSigner);
+                }
+                signers.push_back(signer.clone());
+                let threshold = new_threshold
+                    .unwrap_or_else(|| env.storage().instance().get(&THRESHOLD).unwrap());
+                Self::replace_signers(env, proposal_id, signers, threshold)?;
+            }
+            ProposalAction::RemoveSigner(signer, new_threshold) => {
+                let mut signers: Vec<Address> = env.storage().instance().get(&SIGNERS).unwrap();
+                let index = signers
+                    .first_index_of(signer)
+                    .ok_or(MultisigError::NotASigner)?;
+                signers.remove(index);
+                let threshold = new_threshold
+                    .unwrap_or_else(|| env.storage().instance().get(&THRESHOLD).unwrap());
+                Self::replace_signers(env, proposal_id, signers, threshold)?;
+            }
+            ProposalAction::Invoke(contract, function, args) => {
+                env.invoke_contract::<Val>(contract, function, args.clone());
+            }
+            ProposalAction::SetAutoExecute(enabled) => {
+                env.storage().instance().set(&AUTO_EXEC, enabled);
+            }
+            ProposalAction::SetTimelock(delay) => {
+                env.storage().instance().set(&TIMELOCK, delay);
+            }
+            ProposalAction::SetSpendingLimit(token, limit, period) => {
+                if *limit < 0 || (*limit > 0 && *period == 0) {
+                    return Err(MultisigError::InvalidSpendingLimit);
+                }
+                let key = limit_key(token.clone());
+                let mut limited = Self::limited_tokens(env);
+                if let Some(index) = limited.first_index_of(token) {
+                    limited.remove(index);
+                }
+                if *limit == 0 {
+                    env.storage().instance().remove(&key);
+                } else {
+                    limited.push_back(token.clone());
+                    let spending_limit = SpendingLimit {
+                        limit: *limit,
+                        period: *period,
+                        spent: 0,
+                        window_start: env.ledger().timestamp(),
+                    };
+                    env.storage().instance().set(&key, &spending_limit);
+                }
+                env.storage().instance().set(&LIMITED, &limited);
+                env.events().publish(
+                    (symbol_short!("limit_set"), token.clone()),
+                    (*limit, *period),
+                );
+            }
+            ProposalAction::LinkWallet(wallet) => {
+                let mut linked = Self::linked_wallets(env);
+                if linked.contains(wallet) {
+                    return Err(MultisigError::WalletAlreadyLinked);
+                }
+                linked.push_back(wallet.clone());
+                env.storage().instance().set(&LINKED, &linked);
+            }
+            ProposalAction::UnlinkWallet(wallet) => {
+                let mut linked = Self::linked_wallets(env);
+                let index = linked
+                    .first_index_of(wallet)
+                    .ok_or(MultisigError::WalletNotLinked)?;
+                linked.remove(index);
+                env.storage().instance().set(&LINKED, &linked);
+            }
+            ProposalAction::SetRecovery(guardians, threshold, delay) => {
+                // Any reconfiguration voids a recovery started under the old one
+                env.storage().instance().remove(&PENDING_RECOVERY);
+                Self::check_guardians(env, guardians, *threshold)?;
+                if guardians.is_empty() {
+                    env.storage().instance().remove(&RECOVERY);
+                } else {
+                    let config = RecoveryConfig {
+                        guardians: guardians.clone(),
+                        threshold: *threshold,
+                        delay: *delay,
+                    };
+                    env.storage().instance().set(&RECOVERY, &config);
+                }
+            }
+            ProposalAction::SetActionThreshold(kind, threshold) => {
+                let signers: Vec<Address> = env.storage().instance().get(&SIGNERS).unwrap();
+                if *threshold > signers.len() {
+                    return Err(MultisigError::InvalidThreshold);
+                }
+                let mut policy = Self::policy(env);
+                if *threshold == 0 {
+                    policy.remove(*kind);
+                } else {
+                    policy.set(*kind, *threshold);
+                }
+                env.storage().instance().set(&POLICY, &policy);
+                env.events()
+                    .publish((symbol_short!("policy"), proposal_id), (*kind, *threshold));
+            }
+            ProposalAction::SetExecutor(executor, until) => {
+                let mut executors = Self::executors(env);
+                if *until == 0 {
+                    executors.remove(executor.clone());
+                } else {
+                    executors.set(executor.clone(), *until);
+                }
+                env.storage().instance().set(&EXECUTORS, &executors);
+                env.events()
+                    .publish((symbol_short!("executor"), executor.clone()), *until);
+            }
+            ProposalAction::MigrateTo(successor, tokens) => {
+                Self::check_successor(env, successor, tokens)?;
+                let wallet = env.current_contract_address();
+                // A token that fails aborts the migration instead of trapping,
+                // so signers can propose again without it
+                for token in tokens.iter() {
+                    let client = soroban_sdk::token::Client::new(env, &token);
+                    let balance = match client.try_balance(&wallet) {
+                        Ok(Ok(balance)) => balance,
+                        _ => return Err(MultisigError::SweepFailed),
+                    };
+                    if balance > 0
+                        && !matches!(
+                            client.try_transfer(&wallet, successor, &balance),
+                            Ok(Ok(()))
+                        )
+                    {
+                        return Err(MultisigError::SweepFailed);
+                    }
+                }
+                env.storage().instance().set(&SUCCESSOR, successor);
+                env.events()
+                    .publish((symbol_short!("migrated"), proposal_id), successor.clone());
+            }
+            ProposalAction::Batch(actions) => {
+                for inner in actions.iter() {
+                    Self::apply_action(env, proposal_id, &inner)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Validate and store a new signer set and threshold
+    fn replace_signers(
+        env: &Env,
+        proposal_id: u32,
+        signers: Vec<Address>,
+        threshold: u32,
+    ) -> Result<(), MultisigError> {
+        Self::check_signer_set(&signers, threshold, &Self::policy(env))?;
+        env.storage().instance().set(&SIGNERS, &signers);
+        env.storage().instance().set(&THRESHOLD, &threshold);
+        env.events()
+            .publish((symbol_short!("signers"), proposal_id), (signers, threshold));
+        Ok(())
+    }
+
+    fn check_signer_set(
+        signers: &Vec<Address>,
+        threshold: u32,
+        policy: &Map<ActionKind, u32>,
+    ) -> Result<(), MultisigError> {
+        if signers.is_empty() {
+            return Err(MultisigError::EmptySigners);
+        }
+        if threshold == 0 || threshold > signers.len() {
+            return Err(MultisigError::InvalidThreshold);
+        }
+        // Per-action thresholds must stay reachable too
+        for (_, kind_threshold) in policy.iter() {
+            if kind_threshold > signers.len() {
+                return Err(MultisigError::InvalidThreshold);
+            }
+        }
+        Ok(())
+    }
+
+    // An empty guardian list is valid: it disables recovery
+    fn check_guardians(
+        env: &Env,
+        guardians: &Vec<Address>,
+        threshold: u32,
+    ) -> Result<(), MultisigError> {
+        if guardians.is_empty() {
+            return Ok(());
+        }
+        if threshold == 0 || threshold > guardians.len() {
+            return Err(MultisigError::InvalidThreshold);
+        }
+        let mut seen: Map<Address, bool> = Map::new(env);
+        for guardian in guardians.iter() {
+            if seen.contains_key(guardian.clone()) {
+                return Err(MultisigError::DuplicateSigner);
+            }
+            seen.set(guardian, true);
+        }
+        Ok(())
+    }
+
+    // Everything `execute` checks before running the action
+    fn require_executable(env: &Env, proposal: &Proposal) -> Result<(), MultisigError> {
+        Self::require_active(env, proposal)?;
+
+        if !Self::approvals_met(env, proposal) {
+            return Err(MultisigError::ThresholdNotMet);
+        }
+
+        if Self::timelock_delay(env) > 0 {
+            if proposal.status != ProposalStatus::Queued {
+                return Err(MultisigError::NotQueued);
+            }
+            if env.ledger().timestamp() < proposal.eta {
+                return Err(MultisigError::TimelockNotElapsed);
+            }
+        }
+        Ok(())
     }
 
     fn require_active(env: &Env, proposal: &Proposal) -> Result<(), MultisigError> {
-        if proposal.status == ProposalStatus::Executed {
-            return Err(MultisigError::AlreadyExecuted);
+        match proposal.status {
+            ProposalStatus::Executed => return Err(MultisigError::AlreadyExecuted),
+            ProposalStatus::Cancelled => return Err(MultisigError::ProposalCancelled),
+            ProposalStatus::Rejected => return Err(MultisigError::ProposalRejected),
+            ProposalStatus::Failed => return Err(MultisigError::ProposalFailed),
+            ProposalStatus::Active | ProposalStatus::Queued => {}
         }
         let current_ledger = env.ledger().sequence() as u64;
         if current_ledger > proposal.expiration {
@@ -0,0 +1,441 @@
+// Randomized calls against every state-changing entrypoint with boundary
+// values (extreme amounts, IDs, thresholds, ledger times and empty batches),
+// including off-chain signed execution and linked-wallet sign-off. Every failure
+// must surface as a `MultisigError`; a trap from an overflow or unwrap
+// means an entrypoint is missing a check.
+use multisig_wallet::{
+    ActionKind, MultisigError, MultisigWallet, MultisigWalletClient, ProposalAction,
+    ProposalStatus,
+};
+use ed25519_dalek::{Signer, SigningKey};
+use soroban_sdk::{
+    symbol_short,
+    testutils::{Address as _, Ledger as _},
+    token, Address, Bytes, BytesN, Env, IntoVal, InvokeError, String, Symbol, Vec,
+};
+
+const ROUNDS: u64 = 24;
+const STEPS: u32 = 60;
+
+// xorshift64: deterministic, so a failing seed reproduces exactly
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: u64) -> u64 {
+        self.next() % n
+    }
+
+    fn flag(&mut self) -> bool {
+        self.below(2) == 0
+    }
+
+    fn amount(&mut self) -> i128 {
+        match self.below(7) {
+            0 => 0,
+            1 => -1,
+            2 => i128::MAX,
+            3 => i128::MIN,
+            4 => i128::MAX / 2 + 1,
+            _ => self.below(2_000) as i128,
+        }
+    }
+
+    fn small_u32(&mut self) -> u32 {
+        match self.below(5) {
+            0 => 0,
+            1 => u32::MAX,
+            _ => self.below(5) as u32,
+        }
+    }
+
+    fn time(&mut self) -> u64 {
+        match self.below(5) {
+            0 => 0,
+            1 => u64::MAX,
+            2 => u64::MAX - 1,
+            _ => self.below(10_000),
+        }
+    }
+}
+
+struct Harness<'a> {
+    env: &'a Env,
+    client: MultisigWalletClient<'a>,
+    signers: [Address; 3],
+    keys: [SigningKey; 3],
+    outsider: Address,
+    // Wallet that mirrored proposals can wait on once linked
+    child: Address,
+    token: Address,
+    rng: Rng,
+}
+
+// Ok, or a typed contract error. `InvokeError::Abort` is a trap.
+// Returns the call's value if it succeeded.
+fn assert_typed<T, C>(
+    call: &str,
+    result: Result<Result<T, C>, Result<MultisigError, InvokeError>>,
+) -> Option<T> {
+    match result {
+        Ok(Ok(value)) => Some(value),
+        Err(Err(err)) => panic!("{call} trapped instead of returning a MultisigError: {err:?}"),
+        _ => None,
+    }
+}
+
+impl Harness<'_> {
+    fn address(&mut self) -> Address {
+        match self.rng.below(4) {
+            3 => self.outsider.clone(),
+            i => self.signers[i as usize].clone(),
+        }
+    }
+
+    fn signer_subset(&mut self) -> Vec<Address> {
+        let mut set = Vec::new(self.env);
+        for _ in 0..self.rng.below(5) {
+            let addr = self.address();
+            set.push_back(addr);
+        }
+        set
+    }
+
+    fn proposal_id(&mut self) -> u32 {
+        match self.rng.below(5) {
+            0 => 0,
+            1 => u32::MAX,
+            _ => {
+                let count = self.client.get_proposal_count();
+                1 + self.rng.below(count as u64 + 1) as u32
+            }
+        }
+    }
+
+    fn wallets(&mut self) -> Vec<Address> {
+        let mut wallets = Vec::new(self.env);
+        for _ in 0..self.rng.below(3) {
+            let wallet = if self.rng.flag() { self.child.clone() } else { self.outsider.clone() };
+            wallets.push_back(wallet);
+        }
+        wallets
+    }
+
+    // Sign with a random (possibly empty or repeated) choice of signer keys
+    fn signatures(&mut self, action: &ProposalAction, expiration: u64) -> Vec<(BytesN<32>, BytesN<64>)> {
+        let mut signatures = Vec::new(self.env);
+        let Some(digest) = assert_typed(
+            "signature_payload",
+            self.client.try_signature_payload(action, &expiration),
+        ) else {
+            return signatures;
+        };
+        for _ in 0..self.rng.below(4) {
+            let key = &self.keys[self.rng.below(3) as usize];
+            signatures.push_back((
+                BytesN::from_array(self.env, &key.verifying_key().to_bytes()),
+                BytesN::from_array(self.env, &key.sign(&digest.to_array()).to_bytes()),
+            ));
+        }
+        signatures
+    }
+
+    fn action(&mut self, allow_batch: bool) -> ProposalAction {
+        match self.rng.below(if allow_batch { 14 } else { 13 }) {
+            0 => {
+                let to = self.address();
+                ProposalAction::Transfer(self.token.clone(), to, self.rng.amount())
+            }
+            1 => ProposalAction::UpdateSigners(self.signer_subset(), self.rng.small_u32()),
+            2 => {
+                let threshold = self.rng.flag().then(|| self.rng.small_u32());
+                ProposalAction::AddSigner(Address::generate(self.env), threshold)
+            }
+            3 => {
+                let threshold = self.rng.flag().then(|| self.rng.small_u32());
+                ProposalAction::RemoveSigner(self.address(), threshold)
+            }
+            4 => ProposalAction::SetSpendingLimit(
+                self.token.clone(),
+                self.rng.amount(),
+                self.rng.time(),
+            ),
+            5 => ProposalAction::SetTimelock(self.rng.time()),
+            6 => ProposalAction::SetAutoExecute(self.rng.flag()),
+            7 => {
+                let kind = if self.rng.flag() {
+                    ActionKind::Transfer
+                } else {
+                    ActionKind::UpdateSigners
+                };
+                ProposalAction::SetActionThreshold(kind, self.rng.small_u32())
+            }
+            8 => ProposalAction::SetRecovery(
+                self.signer_subset(),
+                self.rng.small_u32(),
+                self.rng.time(),
+            ),
+            9 => ProposalAction::SetExecutor(self.outsider.clone(), self.rng.time()),
+            10 => {
+                let wallet = self.client.address.clone();
+                match self.rng.below(3) {
+                    0 => ProposalAction::Invoke(
+                        self.token.clone(),
+                        Symbol::new(self.env, "balance"),
+                        (wallet,).into_val(self.env),
+                    ),
+                    1 => {
+                        let (to, amount) = (self.address(), self.rng.amount());
+                        ProposalAction::Invoke(
+                            self.token.clone(),
+                            Symbol::new(self.env, "transfer"),
+                            (wallet, to, amount).into_val(self.env),
+                        )
+                    }
+                    // An account, not a contract
+                    _ => ProposalAction::Invoke(self.outsider.clone(), symbol_short!("ping"), Vec::new(self.env)),
+                }
+            }
+            11 => ProposalAction::LinkWallet(self.child.clone()),
+            12 => {
+                let wallet = if self.rng.flag() { self.child.clone() } else { self.outsider.clone() };
+                ProposalAction::UnlinkWallet(wallet)
+            }
+            _ => {
+                // Zero to two actions, so empty batches come up too
+                let mut actions = Vec::new(self.env);
+                for _ in 0..self.rng.below(3) {
+                    actions.push_back(self.action(false));
+                }
+                ProposalAction::Batch(actions)
+            }
+        }
+    }
+
+    fn step(&mut self) {
+        match self.rng.below(28) {
+            18 | 19 => {
+                // Push a random action through every signer so state changes
+                // actually land, not just the validation paths
+                let action = self.action(true);
+                let expiration = self.rng.time();
+                let proposer = self.signers[0].clone();
+                let created = self.client.try_create_proposal(&proposer, &action, &expiration);
+                if let Some(id) = assert_typed("create_proposal", created) {
+                    for signer in self.signers.clone().iter() {
+                        assert_typed("approve", self.client.try_approve(signer, &id));
+                    }
+                    assert_typed("execute", self.client.try_execute(&proposer, &id));
+                }
+            }
+            0 | 1 => {
+                let (proposer, action) = (self.address(), self.action(true));
+                let expiration = self.rng.time();
+                assert_typed("create_proposal", self.client.try_create_proposal(&proposer, &action, &expiration));
+            }
+            2 | 3 => {
+                let (signer, id) = (self.address(), self.proposal_id());
+                assert_typed("approve", self.client.try_approve(&signer, &id));
+            }
+            4 => {
+                let (signer, id) = (self.address(), self.proposal_id());
+                assert_typed("revoke_approval", self.client.try_revoke_approval(&signer, &id));
+            }
+            5 => {
+                let (signer, id) = (self.address(), self.proposal_id());
+                assert_typed("reject", self.client.try_reject(&signer, &id));
+            }
+            6 => {
+                let (signer, id) = (self.address(), self.proposal_id());
+                assert_typed("queue", self.client.try_queue(&signer, &id));
+            }
+            7 | 8 => {
+                let (signer, id) = (self.address(), self.proposal_id());
+                assert_typed("execute", self.client.try_execute(&signer, &id));
+            }
+            9 => {
+                let (signer, id) = (self.address(), self.proposal_id());
+                assert_typed("cancel_proposal", self.client.try_cancel_proposal(&signer, &id));
+            }
+            10 => {
+                let from = self.address();
+                let amount = self.rng.amount();
+                assert_typed("deposit", self.client.try_deposit(&from, &self.token, &amount));
+            }
+            11 => {
+                let (signer, to) = (self.address(), self.address());
+                let amount = self.rng.amount();
+                assert_typed("spend", self.client.try_spend(&signer, &self.token, &to, &amount));
+            }
+            12 => {
+                let signer = self.address();
+                let (before_id, before_ledger) = (self.rng.small_u32(), self.rng.time());
+                assert_typed("prune", self.client.try_prune(&signer, &before_id, &before_ledger));
+            }
+            13 => {
+                let filter = self.rng.flag().then_some(ProposalStatus::Active);
+                let (start_id, limit) = (self.rng.small_u32(), self.rng.small_u32());
+                assert_typed("get_proposals", self.client.try_get_proposals(&filter, &start_id, &limit));
+                let id = self.proposal_id();
+                assert_typed("simulate", self.client.try_simulate(&id));
+            }
+            14 => {
+                let (guardian, new_signers) = (self.address(), self.signer_subset());
+                let threshold = self.rng.small_u32();
+                assert_typed(
+                    "initiate_recovery",
+                    self.client.try_initiate_recovery(&guardian, &new_signers, &threshold),
+                );
+            }
+            15 => {
+                let guardian = self.address();
+                assert_typed("support_recovery", self.client.try_support_recovery(&guardian));
+                assert_typed("finalize_recovery", self.client.try_finalize_recovery());
+            }
+            20 | 21 => {
+                // Empty batches need no approvals, so weight them as a boundary
+                let action = match self.rng.below(4) {
+                    0 => ProposalAction::Batch(Vec::new(self.env)),
+                    _ => self.action(true),
+                };
+                let expiration = self.rng.time();
+                let signatures = self.signatures(&action, expiration);
+                assert_typed(
+                    "execute_with_signatures",
+                    self.client.try_execute_with_signatures(&action, &expiration, &signatures),
+                );
+            }
+            22 => {
+                let signer = self.address();
+                // A harness key, so signing keeps working, or a stray one
+                let public_key = match self.rng.below(4) {
+                    3 => BytesN::from_array(self.env, &[self.rng.below(256) as u8; 32]),
+                    i => BytesN::from_array(self.env, &self.keys[i as usize].verifying_key().to_bytes()),
+                };
+                assert_typed("register_signer_key", self.client.try_register_signer_key(&signer, &public_key));
+            }
+            23 => {
+                let (proposer, action) = (self.address(), self.action(true));
+                let (expiration, wallets) = (self.rng.time(), self.wallets());
+                assert_typed(
+                    "create_mirrored_proposal",
+                    self.client.try_create_mirrored_proposal(&proposer, &action, &expiration, &wallets),
+                );
+            }
+            24 => {
+                let wallet = if self.rng.flag() { self.child.clone() } else { self.outsider.clone() };
+                let id = self.proposal_id();
+                assert_typed("approve_from_wallet", self.client.try_approve_from_wallet(&wallet, &id));
+            }
+            25 => {
+                let (proposer, action) = (self.address(), self.action(true));
+                let expiration = self.rng.time();
+                let description = String::from_str(self.env, if self.rng.flag() { "" } else { "rotate keys" });
+                let metadata = match self.rng.below(3) {
+                    0 => None,
+                    1 => Some(Bytes::from_array(self.env, &[7u8; 32])),
+                    _ => Some(Bytes::from_array(self.env, &[7u8; 65])),
+                };
+                assert_typed(
+                    "create_described_proposal",
+                    self.client.try_create_described_proposal(&proposer, &action, &expiration, &description, &metadata),
+                );
+                let salt = self.rng.time();
+                assert_typed(
+                    "create_salted_proposal",
+                    self.client.try_create_salted_proposal(&proposer, &action, &expiration, &salt),
+                );
+            }
+            26 => {
+                let id = self.proposal_id();
+                assert_typed("bump_proposal", self.client.try_bump_proposal(&id));
+            }
+            27 => {
+                let signer = self.address();
+                assert_typed("cancel_recovery", self.client.try_cancel_recovery(&signer));
+            }
+            _ => {
+                // Sequence stays below the default entry TTL so storage isn't archived
+                let (timestamp, sequence) = (self.rng.time(), self.rng.below(3_000) as u32);
+                self.env.ledger().with_mut(|li| {
+                    li.timestamp = timestamp;
+                    li.sequence_number = sequence;
+                });
+            }
+        }
+    }
+}
+
+#[test]
+fn fuzz_entrypoints_fail_with_typed_errors() {
+    for seed in 1..=ROUNDS {
+        let env = Env::default();
+        env.mock_all_auths();
+        env.budget().reset_unlimited();
+
+        let contract_id = env.register_contract(None, MultisigWallet);
+        let client = MultisigWalletClient::new(&env, &contract_id);
+        let signers = [Address::generate(&env), Address::generate(&env), Address::generate(&env)];
+        let mut initial = Vec::new(&env);
+        for signer in signers.iter() {
+            initial.push_back(signer.clone());
+        }
+        client.initialize(&initial, &2);
+        let keys = [
+            SigningKey::from_bytes(&[1u8; 32]),
+            SigningKey::from_bytes(&[2u8; 32]),
+            SigningKey::from_bytes(&[3u8; 32]),
+        ];
+        for (signer, key) in signers.iter().zip(keys.iter()) {
+            client.register_signer_key(signer, &BytesN::from_array(&env, &key.verifying_key().to_bytes()));
+        }
+
+        let token = env
+            .register_stellar_asset_contract_v2(Address::generate(&env))
+            .address();
+        let minter = token::StellarAssetClient::new(&env, &token);
+        minter.mint(&contract_id, &1_000);
+        for signer in signers.iter() {
+            minter.mint(signer, &1_000);
+        }
+
+        let mut harness = Harness {
+            env: &env,
+            client,
+            signers,
+            keys,
+            outsider: Address::generate(&env),
+            child: Address::generate(&env),
+            token,
+            rng: Rng(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15)),
+        };
+        for _ in 0..STEPS {
+            harness.step();
+        }
+    }
+}
+
+#[test]
+fn fuzz_initialize_fails_with_typed_errors() {
+    let mut rng = Rng(0xDEAD_BEEF);
+    for _ in 0..64 {
+        let env = Env::default();
+        env.mock_all_auths();
+        let contract_id = env.register_contract(None, MultisigWallet);
+        let client = MultisigWalletClient::new(&env, &contract_id);
+
+        let pool = [Address::generate(&env), Address::generate(&env)];
+        let mut signers = Vec::new(&env);
+        for _ in 0..rng.below(4) {
+            signers.push_back(pool[rng.below(2) as usize].clone());
+        }
+        let threshold = rng.small_u32();
+        assert_typed("initialize", client.try_initialize(&signers, &threshold));
+    }
+}
@@ -1,5 +1,33 @@
-use multisig_wallet::{MultisigWallet, MultisigWalletClient, ProposalAction, ProposalStatus};
-use soroban_sdk::{testutils::{Address as _, Ledger as _}, Address, Env, Vec};
+use multisig_wallet::{
+    ActionKind, MultisigError, MultisigWallet, MultisigWalletClient, ProposalAction,
+    ProposalStatus, SimulationIssue,
+};
+use ed25519_dalek::{Signer, SigningKey};
+use soroban_sdk::{
+    symbol_short,
+    testutils::{storage::Persistent as _, Address as _, Events as _, Ledger as _},
+    token, Address, Bytes, BytesN, Env, IntoVal, String, Symbol, Val, Vec,
+};
+
+// Minimal contract the wallet can administer through Invoke proposals.
+mod target {
+    use soroban_sdk::{contract, contractimpl, symbol_short, Address, Env};
+
+    #[contract]
+    pub struct Target;
+
+    #[contractimpl]
+    impl Target {
+        pub fn set_value(env: Env, owner: Address, value: u32) {
+            owner.require_auth();
+            env.storage().instance().set(&symbol_short!("value"), &value);
+        }
+
+        pub fn value(env: Env) -> u32 {
+            env.storage().instance().get(&symbol_short!("value")).unwrap_or(0)
+        }
+    }
+}
 
 fn setup_env<'a>(env: &'a Env) -> (MultisigWalletClient<'a>, Address, Address, Address) {
     env.mock_all_auths();
@@ -11,6 +39,20 @@ fn setup_env<'a>(env: &'a Env) -> (MultisigWalletClient<'a>, Address, Address, A
     (client, s1, s2, s3)
 }
 
+// Register a Stellar asset and mint `amount` to `holder`
+fn create_token(env: &Env, holder: &Address, amount: i128) -> Address {
+    let admin = Address::generate(env);
+    let token_id = env.register_stellar_asset_contract_v2(admin).address();
+    token::StellarAssetClient::new(env, &token_id).mint(holder, &amount);
+    token_id
+}
+
+// Topics of the most recent event published by the wallet
+fn last_event_topics(env: &Env) -> Vec<Val> {
+    let (_, topics, _) = env.events().all().last().unwrap();
+    topics
+}
+
 fn make_signers(env: &Env, addrs: &[Address]) -> Vec<Address> {
     let mut v = Vec::new(env);
     for a in addrs {
@@ -92,7 +134,7 @@ fn test_create_proposal() {
     client.initialize(&make_signers(&env, &[s1.clone(), s2, s3]), &2);
 
     let recipient = Address::generate(&env);
-    let action = ProposalAction::Transfer(recipient, 1000);
+    let action = ProposalAction::Transfer(Address::generate(&env), recipient, 1000);
     let id = client.create_proposal(&s1, &action, &1000u64);
 
     assert_eq!(id, 1);
@@ -108,9 +150,9 @@ fn test_proposal_count_increments() {
     let (client, s1, s2, _) = setup_env(&env);
     client.initialize(&make_signers(&env, &[s1.clone(), s2.clone()]), &1);
 
-    let action = ProposalAction::Transfer(Address::generate(&env), 100);
+    let action = ProposalAction::Transfer(Address::generate(&env), Address::generate(&env), 100);
     client.create_proposal(&s1, &action, &1000u64);
-    client.create_proposal(&s2, &action, &1000u64);
+    client.create_proposal(&s2, &action, &1001u64);
 
     assert_eq!(client.get_proposal_count(), 2);
 }
@@ -123,7 +165,7 @@ fn test_non_signer_cannot_propose() {
     client.initialize(&make_signers(&env, &[s1, s2]), &2);
 
     let outsider = Address::generate(&env);
-    let action = ProposalAction::Transfer(Address::generate(&env), 100);
+    let action = ProposalAction::Transfer(Address::generate(&env), Address::generate(&env), 100);
     client.create_proposal(&outsider, &action, &1000u64);
 }
 
@@ -135,7 +177,7 @@ fn test_approve_proposal() {
     let (client, s1, s2, s3) = setup_env(&env);
     client.initialize(&make_signers(&env, &[s1.clone(), s2.clone(), s3]), &2);
 
-    let action = ProposalAction::Transfer(Address::generate(&env), 500);
+    let action = ProposalAction::Transfer(Address::generate(&env), Address::generate(&env), 500);
     let id = client.create_proposal(&s1, &action, &1000u64);
 
     client.approve(&s1, &id);
@@ -151,7 +193,7 @@ fn test_double_approval_fails() {
     let (client, s1, s2, _) = setup_env(&env);
     client.initialize(&make_signers(&env, &[s1.clone(), s2]), &2);
 
-    let action = ProposalAction::Transfer(Address::generate(&env), 100);
+    let action = ProposalAction::Transfer(Address::generate(&env), Address::generate(&env), 100);
     let id = client.create_proposal(&s1, &action, &1000u64);
 
     client.approve(&s1, &id);
@@ -165,7 +207,7 @@ fn test_non_signer_cannot_approve() {
     let (client, s1, s2, _) = setup_env(&env);
     client.initialize(&make_signers(&env, &[s1.clone(), s2]), &2);
 
-    let action = ProposalAction::Transfer(Address::generate(&env), 100);
+    let action = ProposalAction::Transfer(Address::generate(&env), Address::generate(&env), 100);
     let id = client.create_proposal(&s1, &action, &1000u64);
 
     let outsider = Address::generate(&env);
@@ -180,7 +222,7 @@ fn test_revoke_approval() {
     let (client, s1, s2, s3) = setup_env(&env);
     client.initialize(&make_signers(&env, &[s1.clone(), s2, s3]), &2);
 
-    let action = ProposalAction::Transfer(Address::generate(&env), 100);
+    let action = ProposalAction::Transfer(Address::generate(&env), Address::generate(&env), 100);
     let id = client.create_proposal(&s1, &action, &1000u64);
 
     client.approve(&s1, &id);
@@ -197,7 +239,7 @@ fn test_revoke_without_approval_fails() {
     let (client, s1, s2, _) = setup_env(&env);
     client.initialize(&make_signers(&env, &[s1.clone(), s2.clone()]), &2);
 
-    let action = ProposalAction::Transfer(Address::generate(&env), 100);
+    let action = ProposalAction::Transfer(Address::generate(&env), Address::generate(&env), 100);
     let id = client.create_proposal(&s1, &action, &1000u64);
 
     client.revoke_approval(&s2, &id);
@@ -212,7 +254,7 @@ fn test_execute_below_threshold_fails() {
     let (client, s1, s2, s3) = setup_env(&env);
     client.initialize(&make_signers(&env, &[s1.clone(), s2, s3]), &2);
 
-    let action = ProposalAction::Transfer(Address::generate(&env), 100);
+    let action = ProposalAction::Transfer(Address::generate(&env), Address::generate(&env), 100);
     let id = client.create_proposal(&s1, &action, &1000u64);
 
     client.approve(&s1, &id);
@@ -245,7 +287,7 @@ fn test_execute_already_executed_fails() {
     let (client, s1, s2, s3) = setup_env(&env);
     client.initialize(&make_signers(&env, &[s1.clone(), s2.clone(), s3]), &2);
 
-    let new_signers = make_signers(&env, &[s1.clone()]);
+    let new_signers = make_signers(&env, std::slice::from_ref(&s1));
     let action = ProposalAction::UpdateSigners(new_signers, 1);
     let id = client.create_proposal(&s1, &action, &1000u64);
 
@@ -272,7 +314,7 @@ fn test_non_signer_cannot_execute() {
     let (client, s1, s2, _) = setup_env(&env);
     client.initialize(&make_signers(&env, &[s1.clone(), s2]), &1);
 
-    let action = ProposalAction::Transfer(Address::generate(&env), 100);
+    let action = ProposalAction::Transfer(Address::generate(&env), Address::generate(&env), 100);
     let id = client.create_proposal(&s1, &action, &1000u64);
     client.approve(&s1, &id);
 
@@ -374,7 +416,7 @@ fn test_all_signers_revoking_prevents_execution() {
     let (client, s1, s2, _) = setup_env(&env);
     client.initialize(&make_signers(&env, &[s1.clone(), s2.clone()]), &2);
 
-    let action = ProposalAction::Transfer(Address::generate(&env), 500);
+    let action = ProposalAction::Transfer(Address::generate(&env), Address::generate(&env), 500);
     let id = client.create_proposal(&s1, &action, &1000u64);
 
     client.approve(&s1, &id);
@@ -395,7 +437,7 @@ fn test_outsider_cannot_revoke_others_approval() {
     let (client, s1, s2, _) = setup_env(&env);
     client.initialize(&make_signers(&env, &[s1.clone(), s2]), &2);
 
-    let action = ProposalAction::Transfer(Address::generate(&env), 200);
+    let action = ProposalAction::Transfer(Address::generate(&env), Address::generate(&env), 200);
     let id = client.create_proposal(&s1, &action, &1000u64);
     client.approve(&s1, &id);
 
@@ -404,19 +446,6 @@ fn test_outsider_cannot_revoke_others_approval() {
     client.revoke_approval(&outsider, &id);
 }
 
-#[test]
-#[should_panic(expected = "Error(Contract, #4)")]
-fn test_outsider_cannot_call_set_token() {
-    let env = Env::default();
-    let (client, s1, s2, _) = setup_env(&env);
-    client.initialize(&make_signers(&env, &[s1, s2]), &2);
-
-    // A random outsider tries to set the token address
-    let outsider = Address::generate(&env);
-    let token_addr = Address::generate(&env);
-    client.set_token(&outsider, &token_addr);
-}
-
 // --- Expired Proposal Edge Cases ---
 
 #[test]
@@ -427,7 +456,7 @@ fn test_expired_proposal_cannot_be_approved() {
     client.initialize(&make_signers(&env, &[s1.clone(), s2]), &2);
 
     // Create proposal that expires at ledger sequence 5
-    let action = ProposalAction::Transfer(Address::generate(&env), 100);
+    let action = ProposalAction::Transfer(Address::generate(&env), Address::generate(&env), 100);
     let id = client.create_proposal(&s1, &action, &5u64);
 
     // Advance ledger sequence past expiry
@@ -445,7 +474,7 @@ fn test_expired_proposal_cannot_be_executed() {
     client.initialize(&make_signers(&env, &[s1.clone(), s2.clone()]), &2);
 
     // Create proposal that expires at ledger sequence 5
-    let action = ProposalAction::Transfer(Address::generate(&env), 100);
+    let action = ProposalAction::Transfer(Address::generate(&env), Address::generate(&env), 100);
     let id = client.create_proposal(&s1, &action, &5u64);
 
     // Approve before expiry
@@ -485,7 +514,7 @@ fn test_update_invalid_threshold_fails() {
     let (client, s1, s2, _) = setup_env(&env);
     client.initialize(&make_signers(&env, &[s1.clone(), s2.clone()]), &1);
 
-    let new_signers = make_signers(&env, &[s1.clone()]);
+    let new_signers = make_signers(&env, std::slice::from_ref(&s1));
     // threshold of 5 with only 1 signer is invalid
     let action = ProposalAction::UpdateSigners(new_signers, 5);
     let id = client.create_proposal(&s1, &action, &1000u64);
@@ -501,8 +530,8 @@ fn test_multiple_proposals_independent() {
     let (client, s1, s2, s3) = setup_env(&env);
     client.initialize(&make_signers(&env, &[s1.clone(), s2.clone(), s3]), &1);
 
-    let action_a = ProposalAction::Transfer(Address::generate(&env), 100);
-    let action_b = ProposalAction::Transfer(Address::generate(&env), 200);
+    let action_a = ProposalAction::Transfer(Address::generate(&env), Address::generate(&env), 100);
+    let action_b = ProposalAction::Transfer(Address::generate(&env), Address::generate(&env), 200);
 
     let id_a = client.create_proposal(&s1, &action_a, &1000u64);
     let id_b = client.create_proposal(&s2, &action_b, &1000u64);
@@ -515,3 +544,2396 @@ fn test_multiple_proposals_independent() {
     assert_eq!(client.get_proposal(&id_b).approvals.len(), 0);
     assert_eq!(client.get_proposal_count(), 2);
 }
+
+// --- Invoke Action ---
+
+#[test]
+fn test_invoke_action_calls_target_contract() {
+    let env = Env::default();
+    let (client, s1, s2, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2.clone()]), &2);
+
+    let target_id = env.register_contract(None, target::Target);
+    let target = target::TargetClient::new(&env, &target_id);
+
+    let args = (client.address.clone(), 42u32).into_val(&env);
+    let action = ProposalAction::Invoke(target_id.clone(), symbol_short!("set_value"), args);
+    let id = client.create_proposal(&s1, &action, &1000u64);
+
+    client.approve(&s1, &id);
+    client.approve(&s2, &id);
+    client.execute(&s1, &id);
+
+    assert_eq!(target.value(), 42);
+    assert_eq!(client.get_proposal(&id).status, ProposalStatus::Executed);
+}
+
+#[test]
+fn test_failed_invoke_marks_proposal_failed() {
+    let env = Env::default();
+    let (client, s1, _, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, std::slice::from_ref(&s1)), &1);
+
+    let target_id = env.register_contract(None, target::Target);
+
+    // `missing` is not a function on the target contract
+    let action = ProposalAction::Invoke(target_id, symbol_short!("missing"), Vec::new(&env));
+    let id = client.create_proposal(&s1, &action, &1000u64);
+    client.approve(&s1, &id);
+
+    client.execute(&s1, &id);
+    let proposal = client.get_proposal(&id);
+    assert_eq!(proposal.status, ProposalStatus::Failed);
+    // A missing function traps rather than returning a contract error
+    assert_eq!(proposal.error_code, 0);
+}
+
+// --- Events ---
+
+#[test]
+fn test_proposal_lifecycle_emits_events() {
+    let env = Env::default();
+    let (client, s1, s2, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2.clone()]), &2);
+
+    let action = ProposalAction::UpdateSigners(make_signers(&env, &[s1.clone(), s2.clone()]), 1);
+    let id = client.create_proposal(&s1, &action, &1000u64);
+    assert_eq!(
+        last_event_topics(&env),
+        (symbol_short!("proposed"), id, s1.clone()).into_val(&env)
+    );
+
+    client.approve(&s1, &id);
+    assert_eq!(
+        last_event_topics(&env),
+        (symbol_short!("approved"), id, s1.clone()).into_val(&env)
+    );
+
+    client.revoke_approval(&s1, &id);
+    assert_eq!(
+        last_event_topics(&env),
+        (symbol_short!("revoked"), id, s1.clone()).into_val(&env)
+    );
+
+    client.approve(&s1, &id);
+    client.approve(&s2, &id);
+    client.execute(&s2, &id);
+
+    // Execution publishes the signer update followed by the executed event
+    let events = env.events().all();
+    let len = events.len();
+    let (_, signers_topics, _) = events.get(len - 2).unwrap();
+    let (_, executed_topics, _) = events.get(len - 1).unwrap();
+    assert_eq!(signers_topics, (symbol_short!("signers"), id).into_val(&env));
+    assert_eq!(
+        executed_topics,
+        (symbol_short!("executed"), id, s2.clone()).into_val(&env)
+    );
+}
+
+#[test]
+fn test_approval_event_carries_approval_count() {
+    let env = Env::default();
+    let (client, s1, s2, s3) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2.clone(), s3]), &2);
+
+    let action = ProposalAction::Transfer(Address::generate(&env), Address::generate(&env), 100);
+    let id = client.create_proposal(&s1, &action, &1000u64);
+    client.approve(&s1, &id);
+    client.approve(&s2, &id);
+
+    let (_, topics, data) = env.events().all().last().unwrap();
+    let name: Symbol = topics.get(0).unwrap().into_val(&env);
+    let count: u32 = data.into_val(&env);
+    assert_eq!(name, symbol_short!("approved"));
+    assert_eq!(count, 2);
+}
+
+// --- Cancellation ---
+
+#[test]
+fn test_proposer_can_cancel_proposal() {
+    let env = Env::default();
+    let (client, s1, s2, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2.clone()]), &2);
+
+    let action = ProposalAction::Transfer(Address::generate(&env), Address::generate(&env), 100);
+    let id = client.create_proposal(&s1, &action, &1000u64);
+    client.approve(&s2, &id);
+
+    client.cancel_proposal(&s1, &id);
+
+    assert_eq!(client.get_proposal(&id).status, ProposalStatus::Cancelled);
+    assert_eq!(
+        last_event_topics(&env),
+        (symbol_short!("cancelled"), id, s1.clone()).into_val(&env)
+    );
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #13)")]
+fn test_non_proposer_cannot_cancel() {
+    let env = Env::default();
+    let (client, s1, s2, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2.clone()]), &2);
+
+    let action = ProposalAction::Transfer(Address::generate(&env), Address::generate(&env), 100);
+    let id = client.create_proposal(&s1, &action, &1000u64);
+
+    client.cancel_proposal(&s2, &id);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #14)")]
+fn test_cancelled_proposal_cannot_be_approved() {
+    let env = Env::default();
+    let (client, s1, s2, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2.clone()]), &2);
+
+    let action = ProposalAction::Transfer(Address::generate(&env), Address::generate(&env), 100);
+    let id = client.create_proposal(&s1, &action, &1000u64);
+    client.cancel_proposal(&s1, &id);
+
+    client.approve(&s2, &id);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #9)")]
+fn test_executed_proposal_cannot_be_cancelled() {
+    let env = Env::default();
+    let (client, s1, _, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, std::slice::from_ref(&s1)), &1);
+
+    let action = ProposalAction::UpdateSigners(make_signers(&env, std::slice::from_ref(&s1)), 1);
+    let id = client.create_proposal(&s1, &action, &1000u64);
+    client.approve(&s1, &id);
+    client.execute(&s1, &id);
+
+    client.cancel_proposal(&s1, &id);
+}
+
+// --- Auto-Execution ---
+
+// Enable auto-execution through a regular proposal
+fn enable_auto_execute(client: &MultisigWalletClient, signers: &[Address]) {
+    let id = client.create_proposal(&signers[0], &ProposalAction::SetAutoExecute(true), &1000u64);
+    for s in signers {
+        client.approve(s, &id);
+    }
+    client.execute(&signers[0], &id);
+}
+
+#[test]
+fn test_auto_execute_disabled_by_default() {
+    let env = Env::default();
+    let (client, s1, s2, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2.clone()]), &2);
+
+    assert!(!client.get_auto_execute());
+
+    let action = ProposalAction::UpdateSigners(make_signers(&env, &[s1.clone(), s2.clone()]), 1);
+    let id = client.create_proposal(&s1, &action, &1000u64);
+    client.approve(&s1, &id);
+    client.approve(&s2, &id);
+
+    assert_eq!(client.get_proposal(&id).status, ProposalStatus::Active);
+    assert_eq!(client.get_threshold(), 2);
+}
+
+#[test]
+fn test_auto_execute_on_threshold_approval() {
+    let env = Env::default();
+    let (client, s1, s2, s3) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2.clone(), s3.clone()]), &2);
+    enable_auto_execute(&client, &[s1.clone(), s2.clone()]);
+    assert!(client.get_auto_execute());
+
+    let action = ProposalAction::UpdateSigners(make_signers(&env, &[s1.clone(), s2.clone()]), 1);
+    let id = client.create_proposal(&s1, &action, &1000u64);
+
+    client.approve(&s1, &id);
+    assert_eq!(client.get_proposal(&id).status, ProposalStatus::Active);
+
+    // Second approval reaches the threshold and executes in the same call
+    client.approve(&s2, &id);
+    assert_eq!(client.get_proposal(&id).status, ProposalStatus::Executed);
+    assert_eq!(client.get_threshold(), 1);
+    assert_eq!(
+        last_event_topics(&env),
+        (symbol_short!("executed"), id, s2.clone()).into_val(&env)
+    );
+}
+
+#[test]
+fn test_failed_auto_execute_reverts_approval() {
+    let env = Env::default();
+    let (client, s1, s2, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2.clone()]), &2);
+    enable_auto_execute(&client, &[s1.clone(), s2.clone()]);
+
+    // Threshold of 5 with one signer is invalid, so execution fails
+    let action = ProposalAction::UpdateSigners(make_signers(&env, std::slice::from_ref(&s1)), 5);
+    let id = client.create_proposal(&s1, &action, &1000u64);
+    client.approve(&s1, &id);
+
+    assert!(client.try_approve(&s2, &id).is_err());
+    let proposal = client.get_proposal(&id);
+    assert_eq!(proposal.status, ProposalStatus::Active);
+    assert_eq!(proposal.approvals.len(), 1);
+}
+
+// --- Deposits ---
+
+#[test]
+fn test_deposit_and_get_balance() {
+    let env = Env::default();
+    let (client, s1, s2, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1, s2]), &2);
+
+    let funder = Address::generate(&env);
+    let token_id = create_token(&env, &funder, 1_000);
+    assert_eq!(client.get_balance(&token_id), 0);
+
+    client.deposit(&funder, &token_id, &400);
+
+    assert_eq!(client.get_balance(&token_id), 400);
+    assert_eq!(token::Client::new(&env, &token_id).balance(&funder), 600);
+    let (_, topics, data) = env.events().all().last().unwrap();
+    assert_eq!(
+        topics,
+        (symbol_short!("deposit"), token_id.clone(), funder.clone()).into_val(&env)
+    );
+    let amount: i128 = data.into_val(&env);
+    assert_eq!(amount, 400);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #15)")]
+fn test_deposit_zero_amount_fails() {
+    let env = Env::default();
+    let (client, s1, s2, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1, s2]), &2);
+
+    let funder = Address::generate(&env);
+    let token_id = create_token(&env, &funder, 1_000);
+    client.deposit(&funder, &token_id, &0);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #2)")]
+fn test_deposit_before_init_fails() {
+    let env = Env::default();
+    let (client, _, _, _) = setup_env(&env);
+
+    let funder = Address::generate(&env);
+    let token_id = create_token(&env, &funder, 1_000);
+    client.deposit(&funder, &token_id, &100);
+}
+
+// --- Token Transfers ---
+
+#[test]
+fn test_transfer_proposal_sends_chosen_token() {
+    let env = Env::default();
+    let (client, s1, s2, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2.clone()]), &2);
+
+    // The wallet holds two different assets
+    let usdc = create_token(&env, &client.address, 1_000);
+    let eurc = create_token(&env, &client.address, 500);
+    let recipient = Address::generate(&env);
+
+    let id_a = client.create_proposal(
+        &s1,
+        &ProposalAction::Transfer(usdc.clone(), recipient.clone(), 300),
+        &1000u64,
+    );
+    let id_b = client.create_proposal(
+        &s1,
+        &ProposalAction::Transfer(eurc.clone(), recipient.clone(), 200),
+        &1000u64,
+    );
+    for id in [id_a, id_b] {
+        client.approve(&s1, &id);
+        client.approve(&s2, &id);
+        client.execute(&s1, &id);
+    }
+
+    assert_eq!(client.get_balance(&usdc), 700);
+    assert_eq!(client.get_balance(&eurc), 300);
+    assert_eq!(token::Client::new(&env, &usdc).balance(&recipient), 300);
+    assert_eq!(token::Client::new(&env, &eurc).balance(&recipient), 200);
+}
+
+#[test]
+fn test_transfer_exceeding_balance_fails() {
+    let env = Env::default();
+    let (client, s1, _, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, std::slice::from_ref(&s1)), &1);
+
+    let usdc = create_token(&env, &client.address, 100);
+    let action = ProposalAction::Transfer(usdc.clone(), Address::generate(&env), 101);
+    let id = client.create_proposal(&s1, &action, &1000u64);
+    client.approve(&s1, &id);
+
+    client.execute(&s1, &id);
+    let proposal = client.get_proposal(&id);
+    assert_eq!(proposal.status, ProposalStatus::Failed);
+    // The asset contract's insufficient-balance error
+    assert_eq!(proposal.error_code, 10);
+    assert_eq!(client.get_balance(&usdc), 100);
+}
+
+// --- Spending Limits ---
+
+// Configure a spending limit through a 2-of-2 proposal
+fn set_spending_limit(
+    client: &MultisigWalletClient,
+    signers: &[Address],
+    token: &Address,
+    limit: i128,
+    period: u64,
+) {
+    let action = ProposalAction::SetSpendingLimit(token.clone(), limit, period);
+    let id = client.create_proposal(&signers[0], &action, &1000u64);
+    for s in signers {
+        client.approve(s, &id);
+    }
+    client.execute(&signers[0], &id);
+}
+
+#[test]
+fn test_single_signer_spends_within_limit() {
+    let env = Env::default();
+    let (client, s1, s2, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2.clone()]), &2);
+    let usdc = create_token(&env, &client.address, 1_000);
+    set_spending_limit(&client, &[s1.clone(), s2.clone()], &usdc, 100, 86_400);
+
+    let recipient = Address::generate(&env);
+    client.spend(&s1, &usdc, &recipient, &60);
+    client.spend(&s2, &usdc, &recipient, &40);
+
+    assert_eq!(token::Client::new(&env, &usdc).balance(&recipient), 100);
+    assert_eq!(client.get_spending_limit(&usdc).spent, 100);
+    assert_eq!(
+        last_event_topics(&env),
+        (symbol_short!("spent"), usdc.clone(), s2.clone()).into_val(&env)
+    );
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #17)")]
+fn test_spend_over_limit_fails() {
+    let env = Env::default();
+    let (client, s1, s2, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2.clone()]), &2);
+    let usdc = create_token(&env, &client.address, 1_000);
+    set_spending_limit(&client, &[s1.clone(), s2.clone()], &usdc, 100, 86_400);
+
+    client.spend(&s1, &usdc, &Address::generate(&env), &60);
+    client.spend(&s1, &usdc, &Address::generate(&env), &41);
+}
+
+#[test]
+fn test_spending_window_resets_after_period() {
+    let env = Env::default();
+    let (client, s1, s2, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2.clone()]), &2);
+    let usdc = create_token(&env, &client.address, 1_000);
+    set_spending_limit(&client, &[s1.clone(), s2.clone()], &usdc, 100, 86_400);
+
+    client.spend(&s1, &usdc, &Address::generate(&env), &100);
+    assert!(client.try_spend(&s1, &usdc, &Address::generate(&env), &1).is_err());
+
+    env.ledger().with_mut(|li| li.timestamp += 86_400);
+    assert_eq!(client.get_spending_limit(&usdc).spent, 0);
+    client.spend(&s1, &usdc, &Address::generate(&env), &100);
+    assert_eq!(client.get_balance(&usdc), 800);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #16)")]
+fn test_spend_without_limit_fails() {
+    let env = Env::default();
+    let (client, s1, s2, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2]), &2);
+    let usdc = create_token(&env, &client.address, 1_000);
+
+    client.spend(&s1, &usdc, &Address::generate(&env), &10);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #4)")]
+fn test_non_signer_cannot_spend() {
+    let env = Env::default();
+    let (client, s1, s2, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2.clone()]), &2);
+    let usdc = create_token(&env, &client.address, 1_000);
+    set_spending_limit(&client, &[s1, s2], &usdc, 100, 86_400);
+
+    let outsider = Address::generate(&env);
+    client.spend(&outsider, &usdc, &outsider, &10);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #16)")]
+fn test_zero_limit_removes_allowance() {
+    let env = Env::default();
+    let (client, s1, s2, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2.clone()]), &2);
+    let usdc = create_token(&env, &client.address, 1_000);
+    set_spending_limit(&client, &[s1.clone(), s2.clone()], &usdc, 100, 86_400);
+    set_spending_limit(&client, &[s1.clone(), s2.clone()], &usdc, 0, 0);
+
+    client.spend(&s1, &usdc, &Address::generate(&env), &10);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #18)")]
+fn test_invalid_spending_limit_fails() {
+    let env = Env::default();
+    let (client, s1, s2, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2.clone()]), &2);
+    let usdc = create_token(&env, &client.address, 1_000);
+
+    // A positive limit needs a non-zero window
+    set_spending_limit(&client, &[s1, s2], &usdc, 100, 0);
+}
+
+// --- Incremental Signer Changes ---
+
+// Approve and execute `action` with every signer in `signers`
+fn pass_proposal(client: &MultisigWalletClient, signers: &[Address], action: &ProposalAction) -> u32 {
+    let id = client.create_proposal(&signers[0], action, &1000u64);
+    for s in signers {
+        client.approve(s, &id);
+    }
+    client.execute(&signers[0], &id);
+    id
+}
+
+#[test]
+fn test_add_signer_keeps_threshold() {
+    let env = Env::default();
+    let (client, s1, s2, s3) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2.clone()]), &2);
+
+    pass_proposal(&client, &[s1, s2], &ProposalAction::AddSigner(s3.clone(), None));
+
+    let signers = client.get_signers();
+    assert_eq!(signers.len(), 3);
+    assert!(signers.contains(&s3));
+    assert_eq!(client.get_threshold(), 2);
+}
+
+#[test]
+fn test_add_signer_with_threshold_adjustment() {
+    let env = Env::default();
+    let (client, s1, s2, s3) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2.clone()]), &2);
+
+    pass_proposal(&client, &[s1, s2], &ProposalAction::AddSigner(s3, Some(3)));
+
+    assert_eq!(client.get_signers().len(), 3);
+    assert_eq!(client.get_threshold(), 3);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #11)")]
+fn test_add_existing_signer_fails() {
+    let env = Env::default();
+    let (client, s1, s2, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2.clone()]), &1);
+
+    pass_proposal(&client, std::slice::from_ref(&s1), &ProposalAction::AddSigner(s2, None));
+}
+
+#[test]
+fn test_remove_signer() {
+    let env = Env::default();
+    let (client, s1, s2, s3) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2.clone(), s3.clone()]), &2);
+
+    pass_proposal(&client, &[s1, s2], &ProposalAction::RemoveSigner(s3.clone(), None));
+
+    let signers = client.get_signers();
+    assert_eq!(signers.len(), 2);
+    assert!(!signers.contains(&s3));
+    assert_eq!(client.get_threshold(), 2);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #3)")]
+fn test_remove_signer_below_threshold_fails() {
+    let env = Env::default();
+    let (client, s1, s2, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2.clone()]), &2);
+
+    // 2-of-1 would lock the wallet
+    pass_proposal(&client, &[s1, s2.clone()], &ProposalAction::RemoveSigner(s2, None));
+}
+
+#[test]
+fn test_remove_signer_with_threshold_adjustment() {
+    let env = Env::default();
+    let (client, s1, s2, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2.clone()]), &2);
+
+    pass_proposal(&client, &[s1, s2.clone()], &ProposalAction::RemoveSigner(s2, Some(1)));
+
+    assert_eq!(client.get_signers().len(), 1);
+    assert_eq!(client.get_threshold(), 1);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #4)")]
+fn test_remove_unknown_signer_fails() {
+    let env = Env::default();
+    let (client, s1, s2, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2]), &1);
+
+    let outsider = Address::generate(&env);
+    pass_proposal(&client, std::slice::from_ref(&s1), &ProposalAction::RemoveSigner(outsider, None));
+}
+
+#[test]
+fn test_removed_signer_approval_stops_counting() {
+    let env = Env::default();
+    let (client, s1, s2, s3) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2.clone(), s3.clone()]), &2);
+
+    // s3 approves an open proposal, then gets removed
+    let pending = client.create_proposal(&s1, &ProposalAction::SetTimelock(60), &1000u64);
+    client.approve(&s3, &pending);
+    pass_proposal(&client, &[s1.clone(), s2], &ProposalAction::RemoveSigner(s3, None));
+
+    assert_eq!(client.get_proposal_summary(&pending).approvals, 0);
+    assert_eq!(client.try_execute(&s1, &pending), Err(Ok(MultisigError::ThresholdNotMet)));
+
+    // One current signer's approval isn't enough on its own
+    client.approve(&s1, &pending);
+    assert_eq!(client.try_execute(&s1, &pending), Err(Ok(MultisigError::ThresholdNotMet)));
+}
+
+// --- Timelock ---
+
+#[test]
+fn test_threshold_approval_queues_with_timelock() {
+    let env = Env::default();
+    let (client, s1, s2, s3) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2.clone(), s3]), &2);
+    pass_proposal(&client, &[s1.clone(), s2.clone()], &ProposalAction::SetTimelock(3_600));
+    assert_eq!(client.get_timelock(), 3_600);
+
+    let action = ProposalAction::UpdateSigners(make_signers(&env, &[s1.clone(), s2.clone()]), 1);
+    let id = client.create_proposal(&s1, &action, &1000u64);
+    client.approve(&s1, &id);
+    assert_eq!(client.get_proposal(&id).status, ProposalStatus::Active);
+
+    client.approve(&s2, &id);
+    let proposal = client.get_proposal(&id);
+    assert_eq!(proposal.status, ProposalStatus::Queued);
+    assert_eq!(proposal.eta, env.ledger().timestamp() + 3_600);
+    assert_eq!(last_event_topics(&env), (symbol_short!("queued"), id).into_val(&env));
+
+    env.ledger().with_mut(|li| li.timestamp += 3_600);
+    client.execute(&s1, &id);
+    assert_eq!(client.get_proposal(&id).status, ProposalStatus::Executed);
+    assert_eq!(client.get_threshold(), 1);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #20)")]
+fn test_execute_before_timelock_elapsed_fails() {
+    let env = Env::default();
+    let (client, s1, s2, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2.clone()]), &2);
+    pass_proposal(&client, &[s1.clone(), s2.clone()], &ProposalAction::SetTimelock(3_600));
+
+    let action = ProposalAction::Transfer(Address::generate(&env), Address::generate(&env), 100);
+    let id = client.create_proposal(&s1, &action, &1000u64);
+    client.approve(&s1, &id);
+    client.approve(&s2, &id);
+
+    env.ledger().with_mut(|li| li.timestamp += 3_599);
+    client.execute(&s1, &id);
+}
+
+#[test]
+fn test_revoke_during_timelock_dequeues() {
+    let env = Env::default();
+    let (client, s1, s2, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2.clone()]), &2);
+    pass_proposal(&client, &[s1.clone(), s2.clone()], &ProposalAction::SetTimelock(3_600));
+
+    let action = ProposalAction::Transfer(Address::generate(&env), Address::generate(&env), 100);
+    let id = client.create_proposal(&s1, &action, &1000u64);
+    client.approve(&s1, &id);
+    client.approve(&s2, &id);
+    assert_eq!(client.get_proposal(&id).status, ProposalStatus::Queued);
+
+    // A signer suspects a compromised key and pulls their approval
+    client.revoke_approval(&s2, &id);
+    let proposal = client.get_proposal(&id);
+    assert_eq!(proposal.status, ProposalStatus::Active);
+    assert_eq!(proposal.eta, 0);
+
+    env.ledger().with_mut(|li| li.timestamp += 3_600);
+    assert!(client.try_execute(&s1, &id).is_err());
+}
+
+#[test]
+fn test_queue_proposal_approved_before_timelock() {
+    let env = Env::default();
+    let (client, s1, s2, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2.clone()]), &2);
+
+    // Fully approved while no timelock was configured
+    let action = ProposalAction::UpdateSigners(make_signers(&env, &[s1.clone(), s2.clone()]), 1);
+    let id = client.create_proposal(&s1, &action, &1000u64);
+    client.approve(&s1, &id);
+    client.approve(&s2, &id);
+
+    pass_proposal(&client, &[s1.clone(), s2.clone()], &ProposalAction::SetTimelock(60));
+    assert!(client.try_execute(&s1, &id).is_err());
+
+    let eta = client.queue(&s1, &id);
+    assert_eq!(eta, env.ledger().timestamp() + 60);
+    env.ledger().with_mut(|li| li.timestamp += 60);
+    client.execute(&s1, &id);
+    assert_eq!(client.get_threshold(), 1);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #19)")]
+fn test_execute_unqueued_with_timelock_fails() {
+    let env = Env::default();
+    let (client, s1, s2, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2.clone()]), &2);
+
+    let action = ProposalAction::Transfer(Address::generate(&env), Address::generate(&env), 100);
+    let id = client.create_proposal(&s1, &action, &1000u64);
+    client.approve(&s1, &id);
+    client.approve(&s2, &id);
+
+    pass_proposal(&client, &[s1.clone(), s2.clone()], &ProposalAction::SetTimelock(60));
+    client.execute(&s1, &id);
+}
+
+// --- Rejections ---
+
+#[test]
+fn test_reject_records_vote() {
+    let env = Env::default();
+    let (client, s1, s2, s3) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2.clone(), s3]), &2);
+
+    let action = ProposalAction::Transfer(Address::generate(&env), Address::generate(&env), 100);
+    let id = client.create_proposal(&s1, &action, &1000u64);
+    client.reject(&s2, &id);
+
+    // 3 signers - 1 rejection still leaves 2 possible approvals
+    let proposal = client.get_proposal(&id);
+    assert_eq!(proposal.rejections.len(), 1);
+    assert_eq!(proposal.status, ProposalStatus::Active);
+    assert_eq!(
+        last_event_topics(&env),
+        (symbol_short!("rejected"), id, s2.clone()).into_val(&env)
+    );
+}
+
+#[test]
+fn test_rejections_close_proposal_early() {
+    let env = Env::default();
+    let (client, s1, s2, s3) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2.clone(), s3.clone()]), &2);
+
+    let action = ProposalAction::Transfer(Address::generate(&env), Address::generate(&env), 100);
+    let id = client.create_proposal(&s1, &action, &1000u64);
+    client.approve(&s1, &id);
+    client.reject(&s2, &id);
+    client.reject(&s3, &id);
+
+    assert_eq!(client.get_proposal(&id).status, ProposalStatus::Rejected);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #22)")]
+fn test_rejected_proposal_cannot_be_approved() {
+    let env = Env::default();
+    let (client, s1, s2, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2.clone()]), &2);
+
+    let action = ProposalAction::Transfer(Address::generate(&env), Address::generate(&env), 100);
+    let id = client.create_proposal(&s1, &action, &1000u64);
+
+    // With 2-of-2, a single rejection makes the threshold unreachable
+    client.reject(&s2, &id);
+    client.approve(&s1, &id);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #21)")]
+fn test_double_rejection_fails() {
+    let env = Env::default();
+    let (client, s1, s2, s3) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2.clone(), s3]), &2);
+
+    let action = ProposalAction::Transfer(Address::generate(&env), Address::generate(&env), 100);
+    let id = client.create_proposal(&s1, &action, &1000u64);
+    client.reject(&s2, &id);
+    client.reject(&s2, &id);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #21)")]
+fn test_rejecter_cannot_approve() {
+    let env = Env::default();
+    let (client, s1, s2, s3) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2.clone(), s3]), &2);
+
+    let action = ProposalAction::Transfer(Address::generate(&env), Address::generate(&env), 100);
+    let id = client.create_proposal(&s1, &action, &1000u64);
+    client.reject(&s2, &id);
+    client.approve(&s2, &id);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #6)")]
+fn test_approver_cannot_reject_without_revoking() {
+    let env = Env::default();
+    let (client, s1, s2, s3) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2, s3]), &2);
+
+    let action = ProposalAction::Transfer(Address::generate(&env), Address::generate(&env), 100);
+    let id = client.create_proposal(&s1, &action, &1000u64);
+    client.approve(&s1, &id);
+    client.reject(&s1, &id);
+}
+
+// --- Proposal Listing ---
+
+#[test]
+fn test_get_proposals_paginates() {
+    let env = Env::default();
+    let (client, s1, s2, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2]), &2);
+
+    let action = ProposalAction::Transfer(Address::generate(&env), Address::generate(&env), 100);
+    for i in 0..5u64 {
+        client.create_proposal(&s1, &action, &(1000 + i));
+    }
+
+    let page = client.get_proposals(&None, &0, &2);
+    assert_eq!(page.proposals.len(), 2);
+    assert_eq!(page.proposals.get(0).unwrap().id, 1);
+    assert_eq!(page.proposals.get(1).unwrap().id, 2);
+    assert_eq!(page.next_id, 3);
+
+    let page = client.get_proposals(&None, &page.next_id, &10);
+    assert_eq!(page.proposals.len(), 3);
+    assert_eq!(page.proposals.get(0).unwrap().id, 3);
+    assert_eq!(page.next_id, 0);
+
+    let page = client.get_proposals(&None, &6, &10);
+    assert!(page.proposals.is_empty());
+    assert_eq!(page.next_id, 0);
+}
+
+#[test]
+fn test_get_proposals_filters_by_status() {
+    let env = Env::default();
+    let (client, s1, s2, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2]), &2);
+
+    let action = ProposalAction::Transfer(Address::generate(&env), Address::generate(&env), 100);
+    let id1 = client.create_proposal(&s1, &action, &1000u64);
+    let id2 = client.create_proposal(&s1, &action, &1001u64);
+    let id3 = client.create_proposal(&s1, &action, &1002u64);
+    client.cancel_proposal(&s1, &id2);
+
+    let active = client.get_proposals(&Some(ProposalStatus::Active), &0, &10).proposals;
+    assert_eq!(active.len(), 2);
+    assert_eq!(active.get(0).unwrap().id, id1);
+    assert_eq!(active.get(1).unwrap().id, id3);
+
+    let cancelled = client.get_proposals(&Some(ProposalStatus::Cancelled), &0, &10).proposals;
+    assert_eq!(cancelled.len(), 1);
+    assert_eq!(cancelled.get(0).unwrap().id, id2);
+
+    // The cursor is a proposal ID, not a count of matches
+    let page = client.get_proposals(&Some(ProposalStatus::Active), &0, &1);
+    assert_eq!(page.next_id, id2);
+    let page = client.get_proposals(&Some(ProposalStatus::Active), &page.next_id, &1);
+    assert_eq!(page.proposals.get(0).unwrap().id, id3);
+    assert_eq!(page.next_id, 0);
+}
+
+#[test]
+fn test_get_proposals_caps_ids_scanned_per_call() {
+    let env = Env::default();
+    let (client, s1, s2, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2]), &2);
+
+    let action = ProposalAction::Transfer(Address::generate(&env), Address::generate(&env), 100);
+    for _ in 0..60 {
+        let id = client.create_proposal(&s1, &action, &1000u64);
+        client.cancel_proposal(&s1, &id);
+    }
+    let last = client.create_proposal(&s1, &action, &1000u64);
+
+    // The first 50 IDs hold no match: an empty page, but not the end
+    let filter = Some(ProposalStatus::Active);
+    let page = client.get_proposals(&filter, &1, &10);
+    assert!(page.proposals.is_empty());
+    assert_eq!(page.next_id, 51);
+
+    let page = client.get_proposals(&filter, &page.next_id, &10);
+    assert_eq!(page.proposals.len(), 1);
+    assert_eq!(page.proposals.get(0).unwrap().id, last);
+    assert_eq!(page.next_id, 0);
+}
+
+#[test]
+fn test_get_pending_for_signer() {
+    let env = Env::default();
+    let (client, s1, s2, s3) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2.clone(), s3]), &2);
+
+    let action = ProposalAction::Transfer(Address::generate(&env), Address::generate(&env), 100);
+    let approved = client.create_proposal(&s1, &action, &1000u64);
+    let rejected = client.create_proposal(&s1, &action, &1001u64);
+    let cancelled = client.create_proposal(&s1, &action, &1002u64);
+    let expiring = client.create_proposal(&s1, &action, &5u64);
+    let open = client.create_proposal(&s1, &action, &1003u64);
+
+    client.approve(&s2, &approved);
+    client.reject(&s2, &rejected);
+    client.cancel_proposal(&s1, &cancelled);
+
+    assert_eq!(client.get_pending_for_signer(&s2, &0, &10).proposals.len(), 2);
+
+    // Page through the matches by ID cursor
+    let first = client.get_pending_for_signer(&s2, &0, &1);
+    assert_eq!(first.proposals.len(), 1);
+    assert_eq!(first.proposals.get(0).unwrap().id, expiring);
+    let second = client.get_pending_for_signer(&s2, &first.next_id, &1);
+    assert_eq!(second.proposals.get(0).unwrap().id, open);
+    assert_eq!(second.next_id, 0);
+
+    env.ledger().with_mut(|li| li.sequence_number = 6);
+    let pending = client.get_pending_for_signer(&s2, &0, &10).proposals;
+    assert_eq!(pending.len(), 1);
+    assert_eq!(pending.get(0).unwrap().id, open);
+    assert_ne!(pending.get(0).unwrap().id, expiring);
+}
+
+// --- Batched Actions ---
+
+#[test]
+fn test_batch_runs_actions_in_order() {
+    let env = Env::default();
+    let (client, s1, s2, s3) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2.clone()]), &2);
+
+    let usdc = create_token(&env, &client.address, 1_000);
+    let recipient = Address::generate(&env);
+
+    let mut actions = Vec::new(&env);
+    actions.push_back(ProposalAction::Transfer(usdc.clone(), recipient.clone(), 400));
+    actions.push_back(ProposalAction::AddSigner(s3.clone(), None));
+    // Runs after AddSigner, so a 3-signer threshold is valid
+    actions.push_back(ProposalAction::UpdateSigners(
+        make_signers(&env, &[s1.clone(), s2.clone(), s3.clone()]),
+        3,
+    ));
+    let id = pass_proposal(&client, &[s1, s2], &ProposalAction::Batch(actions));
+
+    assert_eq!(client.get_proposal(&id).status, ProposalStatus::Executed);
+    assert_eq!(token::Client::new(&env, &usdc).balance(&recipient), 400);
+    assert!(client.get_signers().contains(&s3));
+    assert_eq!(client.get_threshold(), 3);
+}
+
+#[test]
+fn test_batch_failure_reverts_earlier_actions() {
+    let env = Env::default();
+    let (client, s1, s2, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2.clone()]), &2);
+
+    let usdc = create_token(&env, &client.address, 1_000);
+    let recipient = Address::generate(&env);
+
+    let mut actions = Vec::new(&env);
+    actions.push_back(ProposalAction::Transfer(usdc.clone(), recipient.clone(), 400));
+    actions.push_back(ProposalAction::SetTimelock(3600));
+    // Removing a non-signer fails, so nothing above may stick
+    actions.push_back(ProposalAction::RemoveSigner(Address::generate(&env), None));
+    let id = client.create_proposal(&s1, &ProposalAction::Batch(actions), &1000u64);
+    client.approve(&s1, &id);
+    client.approve(&s2, &id);
+
+    assert!(client.try_execute(&s1, &id).is_err());
+    assert_eq!(client.get_proposal(&id).status, ProposalStatus::Active);
+    assert_eq!(client.get_balance(&usdc), 1_000);
+    assert_eq!(token::Client::new(&env, &usdc).balance(&recipient), 0);
+    assert_eq!(client.get_timelock(), 0);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #23)")]
+fn test_empty_batch_fails() {
+    let env = Env::default();
+    let (client, s1, _, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, std::slice::from_ref(&s1)), &1);
+
+    client.create_proposal(&s1, &ProposalAction::Batch(Vec::new(&env)), &1000u64);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #23)")]
+fn test_nested_batch_fails() {
+    let env = Env::default();
+    let (client, s1, _, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, std::slice::from_ref(&s1)), &1);
+
+    let mut inner = Vec::new(&env);
+    inner.push_back(ProposalAction::SetAutoExecute(true));
+    let mut outer = Vec::new(&env);
+    outer.push_back(ProposalAction::Batch(inner));
+    client.create_proposal(&s1, &ProposalAction::Batch(outer), &1000u64);
+}
+
+// --- Linked Wallets ---
+
+// A 1-of-1 child wallet linked to a 1-of-1 parent. Returns (parent, parent
+// signer, child, child signer).
+fn setup_linked_wallets<'a>(
+    env: &'a Env,
+) -> (MultisigWalletClient<'a>, Address, MultisigWalletClient<'a>, Address) {
+    let (parent, p1, c1, _) = setup_env(env);
+    parent.initialize(&make_signers(env, std::slice::from_ref(&p1)), &1);
+
+    let child = MultisigWalletClient::new(env, &env.register_contract(None, MultisigWallet));
+    child.initialize(&make_signers(env, std::slice::from_ref(&c1)), &1);
+
+    pass_proposal(
+        &parent,
+        std::slice::from_ref(&p1),
+        &ProposalAction::LinkWallet(child.address.clone()),
+    );
+    (parent, p1, child, c1)
+}
+
+// Have the child wallet sign off on a parent proposal through its own proposal
+fn child_sign_off(
+    env: &Env,
+    parent: &MultisigWalletClient,
+    child: &MultisigWalletClient,
+    child_signer: &Address,
+    proposal_id: u32,
+) {
+    let args = (child.address.clone(), proposal_id).into_val(env);
+    let action = ProposalAction::Invoke(
+        parent.address.clone(),
+        Symbol::new(env, "approve_from_wallet"),
+        args,
+    );
+    pass_proposal(child, std::slice::from_ref(child_signer), &action);
+}
+
+#[test]
+fn test_link_and_unlink_wallet() {
+    let env = Env::default();
+    let (parent, p1, child, _) = setup_linked_wallets(&env);
+    assert_eq!(parent.get_linked_wallets(), make_signers(&env, std::slice::from_ref(&child.address)));
+
+    pass_proposal(
+        &parent,
+        std::slice::from_ref(&p1),
+        &ProposalAction::UnlinkWallet(child.address.clone()),
+    );
+    assert_eq!(parent.get_linked_wallets().len(), 0);
+}
+
+#[test]
+fn test_link_wallet_twice_fails() {
+    let env = Env::default();
+    let (parent, p1, child, _) = setup_linked_wallets(&env);
+
+    let action = ProposalAction::LinkWallet(child.address.clone());
+    let id = parent.create_proposal(&p1, &action, &1000u64);
+    parent.approve(&p1, &id);
+    assert!(parent.try_execute(&p1, &id).is_err());
+    assert_eq!(parent.get_linked_wallets().len(), 1);
+}
+
+#[test]
+fn test_mirrored_proposal_waits_for_child_wallet() {
+    let env = Env::default();
+    let (parent, p1, child, c1) = setup_linked_wallets(&env);
+
+    let wallets = make_signers(&env, std::slice::from_ref(&child.address));
+    let id = parent.create_mirrored_proposal(
+        &p1,
+        &ProposalAction::SetTimelock(60),
+        &1000u64,
+        &wallets,
+    );
+    parent.approve(&p1, &id);
+
+    // Parent threshold alone is not enough
+    let result = parent.try_execute(&p1, &id);
+    assert_eq!(result, Err(Ok(MultisigError::ThresholdNotMet)));
+
+    child_sign_off(&env, &parent, &child, &c1, id);
+    assert_eq!(parent.get_proposal(&id).wallet_approvals, wallets);
+
+    parent.execute(&p1, &id);
+    assert_eq!(parent.get_timelock(), 60);
+}
+
+#[test]
+fn test_child_sign_off_completing_approvals_auto_executes() {
+    let env = Env::default();
+    let (parent, p1, child, c1) = setup_linked_wallets(&env);
+    enable_auto_execute(&parent, std::slice::from_ref(&p1));
+
+    let wallets = make_signers(&env, std::slice::from_ref(&child.address));
+    let id = parent.create_mirrored_proposal(
+        &p1,
+        &ProposalAction::SetTimelock(60),
+        &1000u64,
+        &wallets,
+    );
+    parent.approve(&p1, &id);
+    assert_eq!(parent.get_proposal(&id).status, ProposalStatus::Active);
+
+    child_sign_off(&env, &parent, &child, &c1, id);
+    assert_eq!(parent.get_proposal(&id).status, ProposalStatus::Executed);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #24)")]
+fn test_mirrored_proposal_requires_linked_wallet() {
+    let env = Env::default();
+    let (parent, p1, _, _) = setup_linked_wallets(&env);
+
+    let wallets = make_signers(&env, &[Address::generate(&env)]);
+    parent.create_mirrored_proposal(&p1, &ProposalAction::SetTimelock(60), &1000u64, &wallets);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #26)")]
+fn test_unrequired_wallet_cannot_approve() {
+    let env = Env::default();
+    let (parent, p1, child, _) = setup_linked_wallets(&env);
+
+    let id = parent.create_proposal(&p1, &ProposalAction::SetTimelock(60), &1000u64);
+    parent.approve_from_wallet(&child.address, &id);
+}
+
+#[test]
+fn test_unlinking_wallet_drops_requirement() {
+    let env = Env::default();
+    let (parent, p1, child, _) = setup_linked_wallets(&env);
+
+    let wallets = make_signers(&env, std::slice::from_ref(&child.address));
+    let id = parent.create_mirrored_proposal(
+        &p1,
+        &ProposalAction::SetTimelock(60),
+        &1000u64,
+        &wallets,
+    );
+    parent.approve(&p1, &id);
+
+    pass_proposal(
+        &parent,
+        std::slice::from_ref(&p1),
+        &ProposalAction::UnlinkWallet(child.address.clone()),
+    );
+    parent.execute(&p1, &id);
+    assert_eq!(parent.get_timelock(), 60);
+}
+
+// --- Duplicate Protection ---
+
+#[test]
+#[should_panic(expected = "Error(Contract, #27)")]
+fn test_duplicate_open_proposal_fails() {
+    let env = Env::default();
+    let (client, s1, s2, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2.clone()]), &2);
+
+    let action = ProposalAction::Transfer(Address::generate(&env), Address::generate(&env), 100);
+    client.create_proposal(&s1, &action, &1000u64);
+    client.create_proposal(&s2, &action, &1000u64);
+}
+
+#[test]
+fn test_salted_proposal_allows_identical_content() {
+    let env = Env::default();
+    let (client, s1, s2, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2]), &2);
+
+    let action = ProposalAction::Transfer(Address::generate(&env), Address::generate(&env), 100);
+    let id1 = client.create_proposal(&s1, &action, &1000u64);
+    let id2 = client.create_salted_proposal(&s1, &action, &1000u64, &1);
+
+    assert_ne!(client.get_proposal(&id1).content_hash, client.get_proposal(&id2).content_hash);
+    assert_eq!(
+        client.try_create_salted_proposal(&s1, &action, &1000u64, &1),
+        Err(Ok(MultisigError::DuplicateProposal))
+    );
+}
+
+#[test]
+fn test_duplicate_allowed_once_original_closed() {
+    let env = Env::default();
+    let (client, s1, s2, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2]), &2);
+
+    let action = ProposalAction::Transfer(Address::generate(&env), Address::generate(&env), 100);
+    let id1 = client.create_proposal(&s1, &action, &1000u64);
+    client.cancel_proposal(&s1, &id1);
+    let id2 = client.create_proposal(&s1, &action, &1000u64);
+
+    let hash = client.get_proposal(&id2).content_hash;
+    assert_eq!(client.find_proposal_by_hash(&hash), Some(id2));
+}
+
+#[test]
+fn test_find_proposal_by_hash() {
+    let env = Env::default();
+    let (client, s1, _, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, std::slice::from_ref(&s1)), &1);
+
+    let action = ProposalAction::SetTimelock(60);
+    let hash = client.compute_proposal_hash(&action, &1000u64, &0);
+    assert_eq!(client.find_proposal_by_hash(&hash), None);
+
+    let id = client.create_proposal(&s1, &action, &1000u64);
+    assert_eq!(client.get_proposal(&id).content_hash, hash);
+    assert_eq!(client.find_proposal_by_hash(&hash), Some(id));
+}
+
+// --- Social Recovery ---
+
+// 2-of-2 wallet with a 2-of-3 guardian set and a one-day delay.
+// Returns (client, signers, guardians).
+fn setup_recovery<'a>(env: &'a Env) -> (MultisigWalletClient<'a>, [Address; 2], [Address; 3]) {
+    let (client, s1, s2, _) = setup_env(env);
+    client.initialize(&make_signers(env, &[s1.clone(), s2.clone()]), &2);
+
+    let guardians = [
+        Address::generate(env),
+        Address::generate(env),
+        Address::generate(env),
+    ];
+    pass_proposal(
+        &client,
+        &[s1.clone(), s2.clone()],
+        &ProposalAction::SetRecovery(make_signers(env, &guardians), 2, 86_400),
+    );
+    (client, [s1, s2], guardians)
+}
+
+#[test]
+fn test_recovery_replaces_signers_after_delay() {
+    let env = Env::default();
+    let (client, _, guardians) = setup_recovery(&env);
+    let new_signer = Address::generate(&env);
+    let new_signers = make_signers(&env, std::slice::from_ref(&new_signer));
+
+    client.initiate_recovery(&guardians[0], &new_signers, &1);
+    assert_eq!(client.get_pending_recovery().unwrap().eta, 0);
+
+    client.support_recovery(&guardians[1]);
+    assert_eq!(client.get_pending_recovery().unwrap().eta, 86_400);
+
+    env.ledger().with_mut(|li| li.timestamp = 86_400);
+    client.finalize_recovery();
+
+    assert_eq!(client.get_signers(), new_signers);
+    assert_eq!(client.get_threshold(), 1);
+    assert!(client.get_pending_recovery().is_none());
+}
+
+#[test]
+fn test_recovery_clears_old_votes_executors_and_limits() {
+    let env = Env::default();
+    let (client, [s1, s2], guardians) = setup_recovery(&env);
+    let token = create_token(&env, &client.address, 1_000);
+    let bot = Address::generate(&env);
+    pass_proposal(&client, &[s1.clone(), s2.clone()], &ProposalAction::SetExecutor(bot.clone(), u64::MAX));
+    pass_proposal(&client, &[s1.clone(), s2.clone()], &ProposalAction::SetSpendingLimit(token.clone(), 100, 86_400));
+
+    let pending = client.create_proposal(&s1, &ProposalAction::SetTimelock(60), &1000u64);
+    client.approve(&s1, &pending);
+
+    // s1 stays on as the sole signer of a 1-of-2 set
+    let new_signers = make_signers(&env, &[s1.clone(), Address::generate(&env)]);
+    client.initiate_recovery(&guardians[0], &new_signers, &1);
+    client.support_recovery(&guardians[1]);
+    env.ledger().with_mut(|li| li.timestamp = 86_400);
+    client.finalize_recovery();
+
+    // The approval from before the recovery no longer counts
+    assert!(client.get_proposal(&pending).approvals.is_empty());
+    assert_eq!(client.try_execute(&s1, &pending), Err(Ok(MultisigError::ThresholdNotMet)));
+    assert!(client.get_executors().is_empty());
+    assert_eq!(client.try_get_spending_limit(&token), Err(Ok(MultisigError::NoSpendingLimit)));
+    assert_eq!(
+        client.try_spend(&s1, &token, &bot, &10),
+        Err(Ok(MultisigError::NoSpendingLimit))
+    );
+
+    // Approving again under the new signer set works
+    client.approve(&s1, &pending);
+    client.execute(&s1, &pending);
+    assert_eq!(client.get_timelock(), 60);
+}
+
+#[test]
+fn test_recovery_not_ready_before_delay() {
+    let env = Env::default();
+    let (client, _, guardians) = setup_recovery(&env);
+    let new_signers = make_signers(&env, &[Address::generate(&env)]);
+
+    client.initiate_recovery(&guardians[0], &new_signers, &1);
+    // Below the guardian threshold the delay never starts
+    assert_eq!(client.try_finalize_recovery(), Err(Ok(MultisigError::RecoveryNotReady)));
+
+    client.support_recovery(&guardians[2]);
+    env.ledger().with_mut(|li| li.timestamp = 86_399);
+    assert_eq!(client.try_finalize_recovery(), Err(Ok(MultisigError::RecoveryNotReady)));
+}
+
+#[test]
+fn test_signer_cancels_recovery() {
+    let env = Env::default();
+    let (client, [s1, s2], guardians) = setup_recovery(&env);
+    let new_signers = make_signers(&env, &[Address::generate(&env)]);
+
+    client.initiate_recovery(&guardians[0], &new_signers, &1);
+    client.support_recovery(&guardians[1]);
+    client.cancel_recovery(&s2);
+
+    assert!(client.get_pending_recovery().is_none());
+    env.ledger().with_mut(|li| li.timestamp = 86_400);
+    assert_eq!(client.try_finalize_recovery(), Err(Ok(MultisigError::NoPendingRecovery)));
+    assert_eq!(client.get_signers(), make_signers(&env, &[s1, s2]));
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #29)")]
+fn test_non_guardian_cannot_initiate_recovery() {
+    let env = Env::default();
+    let (client, [s1, _], _) = setup_recovery(&env);
+    client.initiate_recovery(&s1, &make_signers(&env, std::slice::from_ref(&s1)), &1);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #28)")]
+fn test_recovery_requires_config() {
+    let env = Env::default();
+    let (client, s1, _, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, std::slice::from_ref(&s1)), &1);
+    client.initiate_recovery(&s1, &make_signers(&env, std::slice::from_ref(&s1)), &1);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #30)")]
+fn test_second_recovery_while_pending_fails() {
+    let env = Env::default();
+    let (client, _, guardians) = setup_recovery(&env);
+    let new_signers = make_signers(&env, &[Address::generate(&env)]);
+    client.initiate_recovery(&guardians[0], &new_signers, &1);
+    client.initiate_recovery(&guardians[1], &new_signers, &1);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #6)")]
+fn test_guardian_cannot_back_recovery_twice() {
+    let env = Env::default();
+    let (client, _, guardians) = setup_recovery(&env);
+    let new_signers = make_signers(&env, &[Address::generate(&env)]);
+    client.initiate_recovery(&guardians[0], &new_signers, &1);
+    client.support_recovery(&guardians[0]);
+}
+
+#[test]
+fn test_reconfiguring_recovery_voids_pending() {
+    let env = Env::default();
+    let (client, [s1, s2], guardians) = setup_recovery(&env);
+    client.initiate_recovery(&guardians[0], &make_signers(&env, &[Address::generate(&env)]), &1);
+
+    pass_proposal(
+        &client,
+        &[s1, s2],
+        &ProposalAction::SetRecovery(Vec::new(&env), 0, 0),
+    );
+    assert!(client.get_pending_recovery().is_none());
+    assert!(client.get_recovery_config().is_none());
+}
+
+// --- Off-chain Signatures ---
+
+// 2-of-3 wallet where every signer registered an ed25519 key.
+// Returns (client, signers, signing keys).
+fn setup_signer_keys<'a>(env: &'a Env) -> (MultisigWalletClient<'a>, [Address; 3], [SigningKey; 3]) {
+    let (client, s1, s2, s3) = setup_env(env);
+    client.initialize(&make_signers(env, &[s1.clone(), s2.clone(), s3.clone()]), &2);
+
+    let keys = [
+        SigningKey::from_bytes(&[1u8; 32]),
+        SigningKey::from_bytes(&[2u8; 32]),
+        SigningKey::from_bytes(&[3u8; 32]),
+    ];
+    let signers = [s1, s2, s3];
+    for (signer, key) in signers.iter().zip(keys.iter()) {
+        client.register_signer_key(signer, &BytesN::from_array(env, &key.verifying_key().to_bytes()));
+    }
+    (client, signers, keys)
+}
+
+// Collect (public key, signature) pairs over the wallet's current payload
+fn sign_action(
+    env: &Env,
+    client: &MultisigWalletClient,
+    keys: &[&SigningKey],
+    action: &ProposalAction,
+    expiration_ledger: u64,
+) -> Vec<(BytesN<32>, BytesN<64>)> {
+    let digest = client.signature_payload(action, &expiration_ledger).to_array();
+    let mut signatures = Vec::new(env);
+    for key in keys {
+        signatures.push_back((
+            BytesN::from_array(env, &key.verifying_key().to_bytes()),
+            BytesN::from_array(env, &key.sign(&digest).to_bytes()),
+        ));
+    }
+    signatures
+}
+
+#[test]
+fn test_execute_with_signatures() {
+    let env = Env::default();
+    let (client, [s1, _, s3], keys) = setup_signer_keys(&env);
+
+    let action = ProposalAction::SetTimelock(60);
+    let signatures = sign_action(&env, &client, &[&keys[0], &keys[2]], &action, 1000);
+    let id = client.execute_with_signatures(&action, &1000u64, &signatures);
+
+    assert_eq!(client.get_timelock(), 60);
+    let proposal = client.get_proposal(&id);
+    assert_eq!(proposal.status, ProposalStatus::Executed);
+    assert_eq!(proposal.approvals, make_signers(&env, &[s1, s3]));
+}
+
+#[test]
+fn test_execute_with_signatures_below_threshold_fails() {
+    let env = Env::default();
+    let (client, _, keys) = setup_signer_keys(&env);
+
+    let action = ProposalAction::SetTimelock(60);
+    let signatures = sign_action(&env, &client, &[&keys[0]], &action, 1000);
+    let result = client.try_execute_with_signatures(&action, &1000u64, &signatures);
+    assert_eq!(result, Err(Ok(MultisigError::ThresholdNotMet)));
+
+    // The same key twice does not count as two signers
+    let signatures = sign_action(&env, &client, &[&keys[0], &keys[0]], &action, 1000);
+    let result = client.try_execute_with_signatures(&action, &1000u64, &signatures);
+    assert_eq!(result, Err(Ok(MultisigError::DuplicateSigner)));
+}
+
+#[test]
+fn test_execute_with_signatures_rejects_empty_batch_and_no_signers() {
+    let env = Env::default();
+    let (client, _, _) = setup_signer_keys(&env);
+
+    let empty = ProposalAction::Batch(Vec::new(&env));
+    let result = client.try_execute_with_signatures(&empty, &1000u64, &Vec::new(&env));
+    assert_eq!(result, Err(Ok(MultisigError::InvalidBatch)));
+
+    let action = ProposalAction::SetTimelock(60);
+    let result = client.try_execute_with_signatures(&action, &1000u64, &Vec::new(&env));
+    assert_eq!(result, Err(Ok(MultisigError::ThresholdNotMet)));
+}
+
+#[test]
+fn test_signatures_cannot_be_replayed() {
+    let env = Env::default();
+    let (client, _, keys) = setup_signer_keys(&env);
+
+    let action = ProposalAction::SetAutoExecute(true);
+    let signatures = sign_action(&env, &client, &[&keys[0], &keys[1]], &action, 1000);
+    client.execute_with_signatures(&action, &1000u64, &signatures);
+
+    // The nonce moved on, so the old signatures no longer verify
+    assert!(client.try_execute_with_signatures(&action, &1000u64, &signatures).is_err());
+    assert_eq!(client.get_proposal_count(), 1);
+}
+
+#[test]
+fn test_signature_over_different_action_fails() {
+    let env = Env::default();
+    let (client, _, keys) = setup_signer_keys(&env);
+
+    let signed = ProposalAction::SetTimelock(60);
+    let signatures = sign_action(&env, &client, &[&keys[0], &keys[1]], &signed, 1000);
+    let submitted = ProposalAction::SetTimelock(0);
+    assert!(client.try_execute_with_signatures(&submitted, &1000u64, &signatures).is_err());
+    assert_eq!(client.get_proposal_count(), 0);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #34)")]
+fn test_unregistered_key_fails() {
+    let env = Env::default();
+    let (client, _, keys) = setup_signer_keys(&env);
+
+    let stranger = SigningKey::from_bytes(&[9u8; 32]);
+    let action = ProposalAction::SetTimelock(60);
+    let signatures = sign_action(&env, &client, &[&keys[0], &stranger], &action, 1000);
+    client.execute_with_signatures(&action, &1000u64, &signatures);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #33)")]
+fn test_key_registered_to_another_signer_fails() {
+    let env = Env::default();
+    let (client, [_, s2, _], keys) = setup_signer_keys(&env);
+    client.register_signer_key(&s2, &BytesN::from_array(&env, &keys[0].verifying_key().to_bytes()));
+}
+
+#[test]
+fn test_execute_with_signatures_respects_timelock() {
+    let env = Env::default();
+    let (client, [s1, s2, _], keys) = setup_signer_keys(&env);
+    pass_proposal(&client, &[s1.clone(), s2], &ProposalAction::SetTimelock(3600));
+
+    let action = ProposalAction::SetAutoExecute(true);
+    let signatures = sign_action(&env, &client, &[&keys[0], &keys[1]], &action, 1000);
+    let id = client.execute_with_signatures(&action, &1000u64, &signatures);
+
+    assert_eq!(client.get_proposal(&id).status, ProposalStatus::Queued);
+    env.ledger().with_mut(|li| li.timestamp += 3600);
+    client.execute(&s1, &id);
+    assert!(client.get_auto_execute());
+}
+
+// --- Per-action thresholds ---
+
+#[test]
+fn test_action_threshold_defaults_to_global() {
+    let env = Env::default();
+    let (client, s1, s2, s3) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1, s2, s3]), &2);
+
+    assert_eq!(client.get_action_threshold(&ActionKind::Transfer), 2);
+    assert_eq!(client.get_action_threshold(&ActionKind::UpdateSigners), 2);
+}
+
+#[test]
+fn test_stricter_action_threshold_blocks_execution() {
+    let env = Env::default();
+    let (client, s1, s2, s3) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2.clone(), s3.clone()]), &2);
+    pass_proposal(
+        &client,
+        &[s1.clone(), s2.clone()],
+        &ProposalAction::SetActionThreshold(ActionKind::UpdateSigners, 3),
+    );
+    assert_eq!(client.get_action_threshold(&ActionKind::UpdateSigners), 3);
+
+    let action = ProposalAction::UpdateSigners(make_signers(&env, &[s1.clone(), s2.clone(), s3.clone()]), 1);    let id = client.create_proposal(&s1, &action, &1000u64);
+    client.approve(&s1, &id);
+    client.approve(&s2, &id);
+    assert_eq!(
+        client.try_execute(&s1, &id),
+        Err(Ok(MultisigError::ThresholdNotMet))
+    );
+
+    client.approve(&s3, &id);
+    client.execute(&s1, &id);
+    assert_eq!(client.get_threshold(), 1);
+}
+
+#[test]
+fn test_looser_action_threshold_allows_fewer_approvals() {
+    let env = Env::default();
+    let (client, s1, s2, s3) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2.clone(), s3]), &2);
+    pass_proposal(
+        &client,
+        &[s1.clone(), s2],
+        &ProposalAction::SetActionThreshold(ActionKind::Transfer, 1),
+    );
+
+    let usdc = create_token(&env, &client.address, 1_000);
+    let recipient = Address::generate(&env);
+    pass_proposal(
+        &client,
+        std::slice::from_ref(&s1),
+        &ProposalAction::Transfer(usdc.clone(), recipient.clone(), 250),
+    );
+    assert_eq!(token::Client::new(&env, &usdc).balance(&recipient), 250);
+
+    // Other kinds still need the wallet-wide threshold
+    let id = client.create_proposal(&s1, &ProposalAction::SetTimelock(60), &1000u64);
+    client.approve(&s1, &id);
+    assert!(client.try_execute(&s1, &id).is_err());
+}
+
+#[test]
+fn test_batch_uses_strictest_action_threshold() {
+    let env = Env::default();
+    let (client, s1, s2, s3) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2.clone(), s3.clone()]), &2);
+    pass_proposal(
+        &client,
+        &[s1.clone(), s2.clone()],
+        &ProposalAction::SetActionThreshold(ActionKind::SetRecovery, 3),
+    );
+
+    let mut actions = Vec::new(&env);
+    actions.push_back(ProposalAction::SetTimelock(60));
+    actions.push_back(ProposalAction::SetRecovery(Vec::new(&env), 0, 0));
+    let id = client.create_proposal(&s1, &ProposalAction::Batch(actions), &1000u64);
+    client.approve(&s1, &id);
+    client.approve(&s2, &id);
+    assert_eq!(
+        client.try_execute(&s1, &id),
+        Err(Ok(MultisigError::ThresholdNotMet))
+    );
+
+    client.approve(&s3, &id);
+    client.execute(&s1, &id);
+    assert_eq!(client.get_timelock(), 60);
+}
+
+#[test]
+fn test_clearing_action_threshold_restores_global() {
+    let env = Env::default();
+    let (client, s1, s2, s3) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2.clone(), s3.clone()]), &2);
+    pass_proposal(
+        &client,
+        &[s1.clone(), s2.clone(), s3.clone()],
+        &ProposalAction::SetActionThreshold(ActionKind::Invoke, 3),
+    );
+    assert_eq!(client.get_action_threshold(&ActionKind::Invoke), 3);
+
+    pass_proposal(
+        &client,
+        &[s1, s2, s3],
+        &ProposalAction::SetActionThreshold(ActionKind::Invoke, 0),
+    );
+    assert_eq!(client.get_action_threshold(&ActionKind::Invoke), 2);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #3)")]
+fn test_action_threshold_above_signer_count_fails() {
+    let env = Env::default();
+    let (client, s1, s2, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2.clone()]), &2);
+    pass_proposal(
+        &client,
+        &[s1, s2],
+        &ProposalAction::SetActionThreshold(ActionKind::Transfer, 3),
+    );
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #3)")]
+fn test_removing_signer_below_action_threshold_fails() {
+    let env = Env::default();
+    let (client, s1, s2, s3) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2.clone(), s3.clone()]), &2);
+    pass_proposal(
+        &client,
+        &[s1.clone(), s2.clone()],
+        &ProposalAction::SetActionThreshold(ActionKind::Transfer, 3),
+    );
+
+    // A 2-signer wallet could never meet the 3-approval transfer policy
+    pass_proposal(&client, &[s1, s2], &ProposalAction::RemoveSigner(s3, None));
+}
+
+// --- Descriptions ---
+
+#[test]
+fn test_described_proposal_stores_intent() {
+    let env = Env::default();
+    let (client, s1, s2, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2]), &2);
+
+    let invoice = Bytes::from_array(&env, &[7u8; 32]);
+    let description = String::from_str(&env, "Pay March audit invoice");
+    let action = ProposalAction::Transfer(Address::generate(&env), Address::generate(&env), 500);
+    let id = client.create_described_proposal(&s1, &action, &1000u64, &description, &Some(invoice.clone()));
+
+    let proposal = client.get_proposal(&id);
+    assert_eq!(proposal.description, description);
+    assert_eq!(proposal.metadata_hash, Some(invoice));
+}
+
+#[test]
+fn test_plain_proposal_has_empty_description() {
+    let env = Env::default();
+    let (client, s1, s2, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2]), &2);
+
+    let id = client.create_proposal(&s1, &ProposalAction::SetTimelock(60), &1000u64);
+    let proposal = client.get_proposal(&id);
+    assert_eq!(proposal.description.len(), 0);
+    assert_eq!(proposal.metadata_hash, None);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #35)")]
+fn test_description_too_long_fails() {
+    let env = Env::default();
+    let (client, s1, s2, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2]), &2);
+
+    let description = String::from_str(&env, &"x".repeat(257));
+    client.create_described_proposal(&s1, &ProposalAction::SetTimelock(60), &1000u64, &description, &None);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #27)")]
+fn test_description_does_not_bypass_duplicate_check() {
+    let env = Env::default();
+    let (client, s1, s2, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2]), &2);
+
+    let action = ProposalAction::SetTimelock(60);
+    client.create_proposal(&s1, &action, &1000u64);
+    let description = String::from_str(&env, "Same action, new words");
+    client.create_described_proposal(&s1, &action, &1000u64, &description, &None);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #36)")]
+fn test_metadata_too_long_fails() {
+    let env = Env::default();
+    let (client, s1, s2, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2]), &2);
+
+    let description = String::from_str(&env, "Oversized metadata");
+    let metadata = Bytes::from_array(&env, &[1u8; 65]);
+    client.create_described_proposal(&s1, &ProposalAction::SetTimelock(60), &1000u64, &description, &Some(metadata));
+}
+
+// --- Pruning ---
+
+#[test]
+fn test_prune_removes_closed_proposals() {
+    let env = Env::default();
+    let (client, s1, s2, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2.clone()]), &2);
+
+    let executed = pass_proposal(&client, &[s1.clone(), s2], &ProposalAction::SetTimelock(0));
+    let cancelled = client.create_proposal(&s1, &ProposalAction::SetAutoExecute(true), &1000u64);
+    client.cancel_proposal(&s1, &cancelled);
+    let open = client.create_proposal(&s1, &ProposalAction::SetAutoExecute(false), &1000u64);
+
+    assert_eq!(client.prune(&s1, &10, &0), 2);
+    assert_eq!(client.try_get_proposal(&executed), Err(Ok(MultisigError::ProposalNotFound)));
+    assert_eq!(client.try_get_proposal(&cancelled), Err(Ok(MultisigError::ProposalNotFound)));
+    assert_eq!(client.get_proposal(&open).status, ProposalStatus::Active);
+    assert_eq!(client.get_proposals(&None, &0, &10).proposals.len(), 1);
+}
+
+#[test]
+fn test_prune_emits_archive_event() {
+    let env = Env::default();
+    let (client, s1, s2, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2.clone()]), &2);
+    let id = pass_proposal(&client, &[s1.clone(), s2], &ProposalAction::SetTimelock(0));
+
+    client.prune(&s1, &10, &0);
+    let expected: Vec<Val> = (symbol_short!("archived"), id).into_val(&env);
+    assert_eq!(last_event_topics(&env), expected);
+}
+
+#[test]
+fn test_prune_respects_before_id() {
+    let env = Env::default();
+    let (client, s1, s2, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2.clone()]), &2);
+    let first = pass_proposal(&client, &[s1.clone(), s2.clone()], &ProposalAction::SetTimelock(0));
+    let second = pass_proposal(&client, &[s1.clone(), s2], &ProposalAction::SetTimelock(60));
+
+    assert_eq!(client.prune(&s1, &second, &0), 1);
+    assert!(client.try_get_proposal(&first).is_err());
+    assert_eq!(client.get_proposal(&second).status, ProposalStatus::Executed);
+
+    assert_eq!(client.prune(&s1, &(second + 1), &0), 1);
+    assert!(client.try_get_proposal(&second).is_err());
+}
+
+#[test]
+fn test_prune_expired_only_before_ledger() {
+    let env = Env::default();
+    let (client, s1, s2, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2]), &2);
+    let early = client.create_proposal(&s1, &ProposalAction::SetTimelock(60), &100u64);
+    let late = client.create_proposal(&s1, &ProposalAction::SetTimelock(120), &200u64);
+    let open = client.create_proposal(&s1, &ProposalAction::SetTimelock(180), &1000u64);
+
+    env.ledger().with_mut(|li| li.sequence_number = 500);
+    assert_eq!(client.prune(&s1, &10, &150), 1);
+    assert!(client.try_get_proposal(&early).is_err());
+    assert!(client.try_get_proposal(&late).is_ok());
+
+    assert_eq!(client.prune(&s1, &10, &1_000_000), 1);
+    assert!(client.try_get_proposal(&late).is_err());
+    // Not expired yet, whatever the cutoff
+    assert!(client.try_get_proposal(&open).is_ok());
+}
+
+#[test]
+fn test_pruned_content_can_be_proposed_again() {
+    let env = Env::default();
+    let (client, s1, s2, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2]), &2);
+    let action = ProposalAction::SetTimelock(60);
+    let id = client.create_proposal(&s1, &action, &1000u64);
+    client.cancel_proposal(&s1, &id);
+
+    client.prune(&s1, &10, &0);
+    let hash = client.compute_proposal_hash(&action, &1000u64, &0);
+    assert_eq!(client.find_proposal_by_hash(&hash), None);
+    assert_eq!(client.create_proposal(&s1, &action, &1000u64), id + 1);
+}
+
+#[test]
+fn test_prune_extends_open_proposal_ttl() {
+    let env = Env::default();
+    let (client, s1, s2, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2]), &2);
+    let id = client.create_proposal(&s1, &ProposalAction::SetTimelock(60), &100_000u64);
+
+    client.prune(&s1, &10, &0);
+    let ttl = env.as_contract(&client.address, || {
+        env.storage()
+            .persistent()
+            .get_ttl(&(symbol_short!("proposal"), id))
+    });
+    assert!(ttl >= 100_000);
+}
+
+#[test]
+fn test_prune_moves_past_long_lived_open_proposal() {
+    let env = Env::default();
+    let (client, s1, s2, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2]), &2);
+    let open = client.create_proposal(&s1, &ProposalAction::SetTimelock(1), &100_000u64);
+    let mut last = open;
+    for i in 0..60u64 {
+        last = client.create_proposal(&s1, &ProposalAction::SetTimelock(100 + i), &1000u64);
+        client.cancel_proposal(&s1, &last);
+    }
+
+    assert_eq!(client.prune(&s1, &(last + 1), &0), 49);
+    assert_eq!(client.prune(&s1, &(last + 1), &0), 11);
+    assert!(client.try_get_proposal(&last).is_err());
+    assert_eq!(client.get_proposal(&open).status, ProposalStatus::Active);
+    // Wraps back to the open proposal and finds nothing else to delete
+    assert_eq!(client.prune(&s1, &(last + 1), &0), 0);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #4)")]
+fn test_non_signer_cannot_prune() {
+    let env = Env::default();
+    let (client, s1, s2, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1, s2]), &2);
+    client.prune(&Address::generate(&env), &10, &0);
+}
+
+// --- Simulation ---
+
+#[test]
+fn test_simulate_ready_transfer() {
+    let env = Env::default();
+    let (client, s1, s2, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2.clone()]), &2);
+    let usdc = create_token(&env, &client.address, 1_000);
+
+    let action = ProposalAction::Transfer(usdc, Address::generate(&env), 600);
+    let id = client.create_proposal(&s1, &action, &1000u64);
+    client.approve(&s1, &id);
+    client.approve(&s2, &id);
+
+    let result = client.simulate(&id);
+    assert!(result.executable);
+    assert!(result.issues.is_empty());
+}
+
+#[test]
+fn test_simulate_reports_missing_approvals_and_balance() {
+    let env = Env::default();
+    let (client, s1, s2, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2]), &2);
+    let usdc = create_token(&env, &client.address, 1_000);
+
+    let mut actions = Vec::new(&env);
+    actions.push_back(ProposalAction::Transfer(usdc.clone(), Address::generate(&env), 700));
+    actions.push_back(ProposalAction::Transfer(usdc.clone(), Address::generate(&env), 700));
+    let id = client.create_proposal(&s1, &ProposalAction::Batch(actions), &1000u64);
+    client.approve(&s1, &id);
+
+    let result = client.simulate(&id);
+    assert!(!result.executable);
+    assert!(result.issues.contains(SimulationIssue::ThresholdNotMet(1, 2)));
+    // Both transfers draw on the same balance
+    assert!(result
+        .issues
+        .contains(SimulationIssue::InsufficientBalance(usdc, 1_400, 1_000)));
+}
+
+#[test]
+fn test_simulate_tracks_signer_changes_through_batch() {
+    let env = Env::default();
+    let (client, s1, s2, s3) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2.clone()]), &2);
+
+    // Valid only because AddSigner runs first
+    let mut actions = Vec::new(&env);
+    actions.push_back(ProposalAction::AddSigner(s3.clone(), None));
+    actions.push_back(ProposalAction::UpdateSigners(
+        make_signers(&env, &[s1.clone(), s2.clone(), s3.clone()]),
+        3,
+    ));
+    let id = client.create_proposal(&s1, &ProposalAction::Batch(actions), &1000u64);
+    client.approve(&s1, &id);
+    client.approve(&s2, &id);
+    assert!(client.simulate(&id).executable);
+
+    let doomed = ProposalAction::RemoveSigner(s2.clone(), None);
+    let id = client.create_proposal(&s1, &doomed, &1000u64);
+    let result = client.simulate(&id);
+    assert!(result
+        .issues
+        .contains(SimulationIssue::InvalidAction(MultisigError::InvalidThreshold as u32)));
+}
+
+#[test]
+fn test_simulate_flags_non_contract_invoke_target() {
+    let env = Env::default();
+    let (client, s1, s2, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2.clone()]), &1);
+
+    // `Address::generate` yields contract addresses, so spell out an account
+    let account = Address::from_string(&String::from_str(
+        &env,
+        "GAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAWHF",
+    ));
+    let action = ProposalAction::Invoke(account.clone(), symbol_short!("set_value"), Vec::new(&env));
+    let id = client.create_proposal(&s1, &action, &1000u64);
+    client.approve(&s1, &id);
+
+    let result = client.simulate(&id);
+    assert_eq!(result.issues.len(), 1);
+    assert_eq!(result.issues.get(0), Some(SimulationIssue::TargetUnavailable(account)));
+}
+
+#[test]
+fn test_simulate_reports_timelock_and_closed_status() {
+    let env = Env::default();
+    let (client, s1, s2, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2.clone()]), &2);
+    let setup = pass_proposal(&client, &[s1.clone(), s2.clone()], &ProposalAction::SetTimelock(3600));
+
+    let closed = client.simulate(&setup);
+    assert!(closed
+        .issues
+        .contains(SimulationIssue::NotOpen(MultisigError::AlreadyExecuted as u32)));
+
+    let id = client.create_proposal(&s1, &ProposalAction::SetAutoExecute(true), &1000u64);
+    client.approve(&s1, &id);
+    client.approve(&s2, &id);
+    let eta = client.get_proposal(&id).eta;
+    assert_eq!(client.simulate(&id).issues, Vec::from_array(&env, [SimulationIssue::TimelockPending(eta)]));
+
+    env.ledger().with_mut(|li| li.timestamp += 3600);
+    assert!(client.simulate(&id).executable);
+}
+
+#[test]
+fn test_simulate_flags_missing_token_contract() {
+    let env = Env::default();
+    let (client, s1, s2, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2]), &1);
+
+    let missing = Address::generate(&env);
+    let action = ProposalAction::Transfer(missing.clone(), Address::generate(&env), 10);
+    let id = client.create_proposal(&s1, &action, &1000u64);
+    client.approve(&s1, &id);
+
+    let result = client.simulate(&id);
+    assert_eq!(result.issues, Vec::from_array(&env, [SimulationIssue::TargetUnavailable(missing)]));
+}
+
+// --- Arithmetic bounds ---
+
+#[test]
+fn test_max_timelock_queues_without_overflow() {
+    let env = Env::default();
+    let (client, s1, s2, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2.clone()]), &2);
+    pass_proposal(&client, &[s1.clone(), s2.clone()], &ProposalAction::SetTimelock(u64::MAX));
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+
+    let id = client.create_proposal(&s1, &ProposalAction::SetTimelock(0), &1000u64);
+    client.approve(&s1, &id);
+    client.approve(&s2, &id);
+    assert_eq!(client.get_proposal(&id).eta, u64::MAX);
+    assert_eq!(
+        client.try_execute(&s1, &id),
+        Err(Ok(MultisigError::TimelockNotElapsed))
+    );
+}
+
+#[test]
+fn test_spend_overflow_is_rejected() {
+    let env = Env::default();
+    let (client, s1, s2, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2.clone()]), &2);
+    let usdc = create_token(&env, &client.address, 1_000);
+    pass_proposal(&client, &[s1.clone(), s2], &ProposalAction::SetSpendingLimit(usdc.clone(), i128::MAX, 86_400));
+
+    let to = Address::generate(&env);
+    client.spend(&s1, &usdc, &to, &10);
+    assert_eq!(
+        client.try_spend(&s1, &usdc, &to, &i128::MAX),
+        Err(Ok(MultisigError::SpendingLimitExceeded))
+    );
+}
+
+// --- Executors ---
+
+#[test]
+fn test_executor_executes_approved_proposal() {
+    let env = Env::default();
+    let (client, s1, s2, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2.clone()]), &2);
+    let bot = Address::generate(&env);
+    pass_proposal(&client, &[s1.clone(), s2.clone()], &ProposalAction::SetExecutor(bot.clone(), u64::MAX));
+    assert_eq!(client.get_executors().get(bot.clone()), Some(u64::MAX));
+
+    let id = client.create_proposal(&s1, &ProposalAction::SetTimelock(60), &1000u64);
+    client.approve(&s1, &id);
+    client.approve(&s2, &id);
+    client.execute(&bot, &id);
+
+    assert_eq!(client.get_proposal(&id).status, ProposalStatus::Executed);
+    assert_eq!(client.get_timelock(), 60);
+}
+
+#[test]
+fn test_executor_cannot_propose_or_approve() {
+    let env = Env::default();
+    let (client, s1, s2, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2.clone()]), &2);
+    let bot = Address::generate(&env);
+    pass_proposal(&client, &[s1.clone(), s2], &ProposalAction::SetExecutor(bot.clone(), u64::MAX));
+
+    let action = ProposalAction::SetTimelock(60);
+    assert_eq!(
+        client.try_create_proposal(&bot, &action, &1000u64),
+        Err(Ok(MultisigError::NotASigner))
+    );
+    let id = client.create_proposal(&s1, &action, &1000u64);
+    assert_eq!(client.try_approve(&bot, &id), Err(Ok(MultisigError::NotASigner)));
+    assert_eq!(client.try_execute(&bot, &id), Err(Ok(MultisigError::ThresholdNotMet)));
+}
+
+#[test]
+fn test_executor_authorization_expires() {
+    let env = Env::default();
+    let (client, s1, s2, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2.clone()]), &2);
+    let bot = Address::generate(&env);
+    pass_proposal(&client, &[s1.clone(), s2.clone()], &ProposalAction::SetExecutor(bot.clone(), 500));
+
+    let id = client.create_proposal(&s1, &ProposalAction::SetTimelock(60), &1000u64);
+    client.approve(&s1, &id);
+    client.approve(&s2, &id);
+    env.ledger().with_mut(|li| li.timestamp = 500);
+    assert_eq!(client.try_execute(&bot, &id), Err(Ok(MultisigError::NotASigner)));
+}
+
+#[test]
+fn test_revoked_executor_cannot_execute() {
+    let env = Env::default();
+    let (client, s1, s2, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2.clone()]), &2);
+    let bot = Address::generate(&env);
+    pass_proposal(&client, &[s1.clone(), s2.clone()], &ProposalAction::SetExecutor(bot.clone(), u64::MAX));
+    pass_proposal(&client, &[s1.clone(), s2.clone()], &ProposalAction::SetExecutor(bot.clone(), 0));
+    assert!(client.get_executors().is_empty());
+
+    let id = client.create_proposal(&s1, &ProposalAction::SetTimelock(60), &1000u64);
+    client.approve(&s1, &id);
+    client.approve(&s2, &id);
+    assert_eq!(client.try_execute(&bot, &id), Err(Ok(MultisigError::NotASigner)));
+}
+
+// --- Summary ---
+
+#[test]
+fn test_proposal_summary_tracks_progress() {
+    let env = Env::default();
+    let (client, s1, s2, s3) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2.clone(), s3.clone()]), &2);
+
+    let id = client.create_proposal(&s1, &ProposalAction::SetTimelock(60), &1000u64);
+    client.approve(&s1, &id);
+    client.reject(&s3, &id);
+
+    let summary = client.get_proposal_summary(&id);
+    assert_eq!(summary.status, ProposalStatus::Active);
+    assert_eq!(summary.approvals, 1);
+    assert_eq!(summary.threshold, 2);
+    assert_eq!(summary.pending, make_signers(&env, std::slice::from_ref(&s2)));
+    assert_eq!(summary.expiration, 1000);
+    assert!(!summary.executable);
+
+    client.approve(&s2, &id);
+    let summary = client.get_proposal_summary(&id);
+    assert!(summary.pending.is_empty());
+    assert!(summary.executable);
+}
+
+#[test]
+fn test_proposal_summary_uses_action_threshold() {
+    let env = Env::default();
+    let (client, s1, s2, s3) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2.clone(), s3]), &2);
+    pass_proposal(
+        &client,
+        &[s1.clone(), s2],
+        &ProposalAction::SetActionThreshold(ActionKind::SetAutoExecute, 3),
+    );
+
+    let id = client.create_proposal(&s1, &ProposalAction::SetAutoExecute(true), &1000u64);
+    assert_eq!(client.get_proposal_summary(&id).threshold, 3);
+}
+
+#[test]
+fn test_proposal_summary_waits_for_timelock() {
+    let env = Env::default();
+    let (client, s1, s2, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2.clone()]), &2);
+    pass_proposal(&client, &[s1.clone(), s2.clone()], &ProposalAction::SetTimelock(3600));
+
+    let id = client.create_proposal(&s1, &ProposalAction::SetAutoExecute(true), &1000u64);
+    client.approve(&s1, &id);
+    client.approve(&s2, &id);
+    let summary = client.get_proposal_summary(&id);
+    assert_eq!(summary.status, ProposalStatus::Queued);
+    assert_eq!(summary.eta, 3600);
+    assert!(!summary.executable);
+
+    env.ledger().with_mut(|li| li.timestamp += 3600);
+    assert!(client.get_proposal_summary(&id).executable);
+}
+
+// --- Failed execution ---
+
+#[test]
+fn test_failed_transfer_emits_event() {
+    let env = Env::default();
+    let (client, s1, _, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, std::slice::from_ref(&s1)), &1);
+    let usdc = create_token(&env, &client.address, 100);
+
+    let action = ProposalAction::Transfer(usdc, Address::generate(&env), 500);
+    let id = client.create_proposal(&s1, &action, &1000u64);
+    client.approve(&s1, &id);
+    client.execute(&s1, &id);
+
+    let expected: Vec<Val> = (symbol_short!("failed"), id, s1).into_val(&env);
+    assert_eq!(last_event_topics(&env), expected);
+}
+
+#[test]
+fn test_failed_proposal_is_closed() {
+    let env = Env::default();
+    let (client, s1, _, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, std::slice::from_ref(&s1)), &1);
+    let usdc = create_token(&env, &client.address, 100);
+
+    let action = ProposalAction::Transfer(usdc.clone(), Address::generate(&env), 500);
+    let id = client.create_proposal(&s1, &action, &1000u64);
+    client.approve(&s1, &id);
+    client.execute(&s1, &id);
+
+    assert_eq!(client.try_execute(&s1, &id), Err(Ok(MultisigError::ProposalFailed)));
+    assert_eq!(client.try_approve(&s1, &id), Err(Ok(MultisigError::ProposalFailed)));
+    // Once funded, the same payment can be proposed again
+    token::StellarAssetClient::new(&env, &usdc).mint(&client.address, &400);
+    let retry = client.create_proposal(&s1, &action, &1000u64);
+    client.approve(&s1, &retry);
+    client.execute(&s1, &retry);
+    assert_eq!(client.get_proposal(&retry).status, ProposalStatus::Executed);
+}
+
+#[test]
+fn test_auto_executed_failure_keeps_approval() {
+    let env = Env::default();
+    let (client, s1, s2, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2.clone()]), &2);
+    pass_proposal(&client, &[s1.clone(), s2.clone()], &ProposalAction::SetAutoExecute(true));
+    let usdc = create_token(&env, &client.address, 100);
+
+    let action = ProposalAction::Transfer(usdc, Address::generate(&env), 500);
+    let id = client.create_proposal(&s1, &action, &1000u64);
+    client.approve(&s1, &id);
+    client.approve(&s2, &id);
+
+    let proposal = client.get_proposal(&id);
+    assert_eq!(proposal.status, ProposalStatus::Failed);
+    assert_eq!(proposal.approvals.len(), 2);
+}
+
+#[test]
+fn test_failed_transfer_in_batch_still_reverts() {
+    let env = Env::default();
+    let (client, s1, _, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, std::slice::from_ref(&s1)), &1);
+    let usdc = create_token(&env, &client.address, 100);
+
+    let mut actions = Vec::new(&env);
+    actions.push_back(ProposalAction::SetTimelock(60));
+    actions.push_back(ProposalAction::Transfer(usdc, Address::generate(&env), 500));
+    let id = client.create_proposal(&s1, &ProposalAction::Batch(actions), &1000u64);
+    client.approve(&s1, &id);
+
+    assert!(client.try_execute(&s1, &id).is_err());
+    assert_eq!(client.get_proposal(&id).status, ProposalStatus::Active);
+    assert_eq!(client.get_timelock(), 0);
+}
+
+// --- Storage TTL ---
+
+fn proposal_ttl(env: &Env, client: &MultisigWalletClient, id: u32) -> u32 {
+    env.as_contract(&client.address, || {
+        env.storage()
+            .persistent()
+            .get_ttl(&(symbol_short!("proposal"), id))
+    })
+}
+
+#[test]
+fn test_new_proposal_lives_until_expiration() {
+    let env = Env::default();
+    let (client, s1, s2, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2]), &2);
+
+    let id = client.create_proposal(&s1, &ProposalAction::SetTimelock(60), &200_000u64);
+    assert!(proposal_ttl(&env, &client, id) >= 200_000);
+}
+
+#[test]
+fn test_approval_keeps_ttl_through_expiration() {
+    let env = Env::default();
+    let (client, s1, s2, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2]), &2);
+    let id = client.create_proposal(&s1, &ProposalAction::SetTimelock(60), &200_000u64);
+
+    env.ledger().with_mut(|li| li.sequence_number = 3_000);
+    client.approve(&s1, &id);
+    // Covers the remaining 197_000 ledgers plus the margin
+    assert!(proposal_ttl(&env, &client, id) >= 197_000 + 17_280);
+}
+
+#[test]
+fn test_bump_proposal() {
+    let env = Env::default();
+    let (client, s1, s2, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2]), &2);
+    let id = client.create_proposal(&s1, &ProposalAction::SetTimelock(60), &50_000u64);
+
+    env.ledger().with_mut(|li| li.sequence_number = 3_000);
+    let ttl = client.bump_proposal(&id);
+    assert_eq!(ttl, 47_000 + 17_280);
+    assert!(proposal_ttl(&env, &client, id) >= ttl);
+}
+
+#[test]
+fn test_bump_caps_at_max_ttl() {
+    let env = Env::default();
+    let (client, s1, s2, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2]), &2);
+    let id = client.create_proposal(&s1, &ProposalAction::SetTimelock(60), &u64::MAX);
+
+    let max_ttl = env.as_contract(&client.address, || env.storage().max_ttl());
+    assert_eq!(client.bump_proposal(&id), max_ttl);
+}
+
+#[test]
+fn test_bump_missing_proposal_fails() {
+    let env = Env::default();
+    let (client, s1, s2, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1, s2]), &2);
+    assert_eq!(client.try_bump_proposal(&7), Err(Ok(MultisigError::ProposalNotFound)));
+}
+
+// --- Migration ---
+
+// Mint `amount` of a new token to `holder` and deposit it into the wallet
+fn deposit_token(env: &Env, client: &MultisigWalletClient, holder: &Address, amount: i128) -> Address {
+    let token_id = create_token(env, holder, amount);
+    client.deposit(holder, &token_id, &amount);
+    token_id
+}
+
+#[test]
+fn test_migrate_sweeps_listed_tokens() {
+    let env = Env::default();
+    let (client, s1, s2, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2.clone()]), &2);
+    let usdc = deposit_token(&env, &client, &s1, 700);
+    // Sent straight to the wallet; listing it is enough
+    let eurc = create_token(&env, &client.address, 300);
+
+    let successor = Address::generate(&env);
+    let tokens = Vec::from_array(&env, [usdc.clone(), eurc.clone()]);
+    let id = pass_proposal(&client, &[s1, s2], &ProposalAction::MigrateTo(successor.clone(), tokens));
+
+    assert_eq!(token::Client::new(&env, &usdc).balance(&successor), 700);
+    assert_eq!(token::Client::new(&env, &eurc).balance(&successor), 300);
+    assert_eq!(client.get_balance(&usdc), 0);
+    assert_eq!(client.get_successor(), Some(successor.clone()));
+    let expected: Vec<Val> = (symbol_short!("migrated"), id).into_val(&env);
+    assert!(env.events().all().iter().any(|(_, topics, _)| topics == expected));
+}
+
+#[test]
+fn test_retired_wallet_rejects_proposals_and_deposits() {
+    let env = Env::default();
+    let (client, s1, s2, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2.clone()]), &2);
+    let usdc = deposit_token(&env, &client, &s1, 100);
+    let action = ProposalAction::MigrateTo(Address::generate(&env), Vec::from_array(&env, [usdc.clone()]));
+    pass_proposal(&client, &[s1.clone(), s2], &action);
+
+    assert_eq!(
+        client.try_create_proposal(&s1, &ProposalAction::SetTimelock(60), &1000u64),
+        Err(Ok(MultisigError::WalletRetired))
+    );
+    token::StellarAssetClient::new(&env, &usdc).mint(&s1, &50);
+    assert_eq!(client.try_deposit(&s1, &usdc, &50), Err(Ok(MultisigError::WalletRetired)));
+}
+
+#[test]
+fn test_retired_wallet_rejects_execute_and_spend() {
+    let env = Env::default();
+    let (client, s1, s2, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2.clone()]), &2);
+    let usdc = deposit_token(&env, &client, &s1, 100);
+    pass_proposal(&client, &[s1.clone(), s2.clone()], &ProposalAction::SetSpendingLimit(usdc.clone(), 50, 86_400));
+
+    // Approved before the migration, executed after it
+    let pending = client.create_proposal(&s1, &ProposalAction::SetTimelock(60), &1000u64);
+    client.approve(&s1, &pending);
+    client.approve(&s2, &pending);
+    // Migrate without sweeping so funds stay behind for `spend` to reach
+    pass_proposal(&client, &[s1.clone(), s2], &ProposalAction::MigrateTo(Address::generate(&env), Vec::new(&env)));
+
+    assert_eq!(client.try_execute(&s1, &pending), Err(Ok(MultisigError::WalletRetired)));
+    assert_eq!(
+        client.try_spend(&s1, &usdc, &Address::generate(&env), &10),
+        Err(Ok(MultisigError::WalletRetired))
+    );
+    assert_eq!(client.get_balance(&usdc), 100);
+}
+
+#[test]
+fn test_retired_wallet_rejects_auto_execute_on_approval() {
+    let env = Env::default();
+    let (client, s1, s2, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2.clone()]), &2);
+    let usdc = deposit_token(&env, &client, &s1, 100);
+    enable_auto_execute(&client, &[s1.clone(), s2.clone()]);
+
+    // Opened before the migration, completed after it
+    let to = Address::generate(&env);
+    let pending = client.create_proposal(&s1, &ProposalAction::Transfer(usdc.clone(), to.clone(), 100), &1000u64);
+    client.approve(&s1, &pending);
+    let migrate = client.create_proposal(
+        &s1,
+        &ProposalAction::MigrateTo(Address::generate(&env), Vec::new(&env)),
+        &1000u64,
+    );
+    client.approve(&s1, &migrate);
+    client.approve(&s2, &migrate);
+    assert!(client.get_successor().is_some());
+
+    assert_eq!(client.try_approve(&s2, &pending), Err(Ok(MultisigError::WalletRetired)));
+    assert_eq!(client.get_balance(&usdc), 100);
+    assert_eq!(token::Client::new(&env, &usdc).balance(&to), 0);
+}
+
+#[test]
+fn test_migrate_with_broken_token_aborts() {
+    let env = Env::default();
+    let (client, s1, s2, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2.clone()]), &2);
+    let usdc = deposit_token(&env, &client, &s1, 100);
+    // Not a token contract, so its balance call fails
+    let action = ProposalAction::MigrateTo(Address::generate(&env), Vec::from_array(&env, [usdc.clone(), s1.clone()]));
+    let id = client.create_proposal(&s1, &action, &1000u64);
+    client.approve(&s1, &id);
+    client.approve(&s2, &id);
+
+    assert_eq!(client.try_execute(&s1, &id), Err(Ok(MultisigError::SweepFailed)));
+    assert_eq!(client.get_successor(), None);
+    assert_eq!(client.get_balance(&usdc), 100);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #40)")]
+fn test_migrate_rejects_too_many_tokens() {
+    let env = Env::default();
+    let (client, s1, s2, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2.clone()]), &2);
+    let mut tokens = Vec::new(&env);
+    for _ in 0..21 {
+        tokens.push_back(Address::generate(&env));
+    }
+    pass_proposal(&client, &[s1, s2], &ProposalAction::MigrateTo(Address::generate(&env), tokens));
+}
+
+#[test]
+fn test_second_migration_fails() {
+    let env = Env::default();
+    let (client, s1, s2, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2.clone()]), &2);
+
+    let first = client.create_proposal(&s1, &ProposalAction::MigrateTo(Address::generate(&env), Vec::new(&env)), &1000u64);
+    let second = client.create_proposal(&s1, &ProposalAction::MigrateTo(Address::generate(&env), Vec::new(&env)), &1000u64);
+    for id in [first, second] {
+        client.approve(&s1, &id);
+        client.approve(&s2, &id);
+    }
+    client.execute(&s1, &first);
+    assert_eq!(client.try_execute(&s1, &second), Err(Ok(MultisigError::WalletRetired)));
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #39)")]
+fn test_migrate_to_self_fails() {
+    let env = Env::default();
+    let (client, s1, s2, _) = setup_env(&env);
+    client.initialize(&make_signers(&env, &[s1.clone(), s2.clone()]), &2);
+    pass_proposal(&client, &[s1, s2], &ProposalAction::MigrateTo(client.address.clone(), Vec::new(&env)));
+}
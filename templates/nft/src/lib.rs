@@ -1,9 +1,45 @@
 #![no_std]
 
 use soroban_sdk::{
-    contract, contractimpl, contracttype, Address, Env, String, Symbol, Map,
+    contract, contracterror, contractimpl, contracttype, symbol_short, token, xdr::ToXdr, Address,
+    Bytes, BytesN, Env, String, Vec,
 };
 
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[repr(u32)]
+pub enum NftError {
+    AlreadyInitialized = 1,
+    NotInitialized = 2,
+    TokenNotFound = 3,
+    NotOwner = 4,
+    NotAuthorized = 5,
+    RoyaltyTooHigh = 6,
+    TraitsAlreadyCommitted = 7,
+    TraitsAfterMint = 8,
+    InvalidTraitWeights = 9,
+    TraitsNotCommitted = 10,
+    Paused = 11,
+    MaxSupplyReached = 12,
+    Soulbound = 13,
+    MetadataFrozen = 14,
+    InvalidClaimWindow = 15,
+    ClaimNotActive = 16,
+    NotEligible = 17,
+    ClaimLimitReached = 18,
+    InvalidSalePhases = 19,
+    SaleNotActive = 20,
+    WalletLimitReached = 21,
+    NoTreasury = 22,
+    InvalidQuantity = 23,
+}
+
+/// Most sale phases the admin may configure
+const MAX_SALE_PHASES: u32 = 10;
+
+/// Most token IDs `tokens_of_owner` returns per call
+pub const MAX_PAGE_SIZE: u32 = 50;
+
 /// Token Metadata standard structure
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -21,6 +57,61 @@ pub struct RoyaltyData {
     pub amount: u32, // represents percentage in basis points (e.g., 500 = 5%)
 }
 
+/// A trait category and its weighted possible values
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TraitCategory {
+    pub name: String,
+    pub values: Vec<String>,
+    pub weights: Vec<u32>, // relative rarity of each value, same length as `values`
+}
+
+/// A token's value for one trait category
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Trait {
+    pub name: String,
+    pub value: String,
+}
+
+/// A single-token approval, valid through ledger `expiration`
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Approval {
+    pub spender: Address,
+    pub expiration: u32,
+}
+
+/// When claims are open, and the optional Merkle root of `(claimant, limit)` leaves
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ClaimConfig {
+    pub start_time: u64,
+    pub end_time: u64, // exclusive
+    pub merkle_root: Option<BytesN<32>>,
+}
+
+/// A paid mint window. `price` is per token in `payment_token`. An
+/// `allowlist_only` phase (presale) is open only to the claim allowlist.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SalePhase {
+    pub price: i128,
+    pub payment_token: Address,
+    pub max_per_wallet: u32,
+    pub start_time: u64,
+    pub end_time: u64, // exclusive
+    pub allowlist_only: bool,
+}
+
+/// How many tokens one address may claim, for the direct allowlist
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ClaimAllowance {
+    pub claimant: Address,
+    pub limit: u32,
+}
+
 #[contracttype]
 pub enum DataKey {
     Admin,
@@ -32,6 +123,25 @@ pub enum DataKey {
     Metadata(u64),   // Token ID to URI/Metadata mapping
     Royalty,         // Global royalty mapping
     TokenRoyalty(u64), // Per-token royalty mapping
+    TraitSeed,       // Collection seed for generative traits
+    TraitCategories, // Trait categories for generative traits
+    Approval(u64),   // Token ID to single-token Approval
+    OperatorApproval(Address, Address), // (owner, operator) to approved-for-all flag
+    OwnedCount(Address),  // Number of tokens an owner holds
+    OwnedToken(Address, u32), // (owner, slot) to a token ID in the owner's index
+    OwnedIndex(u64),      // Token ID to its slot in its owner's index
+    Paused,          // Halts minting, transfers and burns while true
+    MaxSupply,       // Cap on tokens ever minted; absent when uncapped
+    Soulbound(u64),  // Set for tokens that can't be transferred
+    MetadataFrozen(u64), // Set once a token's URI is permanently fixed
+    AllMetadataFrozen,   // Set once every token's URI is permanently fixed
+    OwnerUriUpdates,     // Lets token owners, not just the admin, set their URIs
+    ClaimConfig,         // Claim window and optional Merkle root
+    ClaimLimit(Address), // Direct allowlist: tokens an address may claim
+    Claimed(Address),    // Tokens an address has claimed so far
+    SalePhases,          // Vec<SalePhase>, ascending and non-overlapping
+    Treasury,            // Receives sale proceeds
+    PhaseMinted(u32, Address), // (phase index, buyer) to tokens bought in that phase
 }
 
 #[contract]
@@ -39,18 +149,19 @@ pub struct NftContract;
 
 #[contractimpl]
 impl NftContract {
-    /// Initialize the NFT contract
+    /// Initialize the NFT contract. `max_supply` caps how many tokens can ever
+    /// be minted; `None` leaves the collection open-ended.
     pub fn initialize(
         env: Env,
         admin: Address,
         name: String,
         symbol: String,
         base_uri: String,
-    ) {
-        assert!(
-            !env.storage().instance().has(&DataKey::Admin),
-            "Already initialized"
-        );
+        max_supply: Option<u64>,
+    ) -> Result<(), NftError> {
+        if env.storage().instance().has(&DataKey::Admin) {
+            return Err(NftError::AlreadyInitialized);
+        }
         admin.require_auth();
 
         env.storage().instance().set(&DataKey::Admin, &admin);
@@ -58,6 +169,10 @@ impl NftContract {
         env.storage().instance().set(&DataKey::Symbol, &symbol);
         env.storage().instance().set(&DataKey::BaseUri, &base_uri);
         env.storage().instance().set(&DataKey::TotalSupply, &0u64);
+        if let Some(max_supply) = max_supply {
+            env.storage().instance().set(&DataKey::MaxSupply, &max_supply);
+        }
+        Ok(())
     }
 
     /// Mint a new NFT
@@ -65,20 +180,214 @@ impl NftContract {
         env: Env,
         to: Address,
         uri: String,
-    ) -> u64 {
-        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
-        admin.require_auth();
+    ) -> Result<u64, NftError> {
+        Self::require_admin(&env)?;
+        Self::mint_to(&env, &to, uri, false)
+    }
 
-        let mut total_supply: u64 = env.storage().instance().get(&DataKey::TotalSupply).unwrap_or(0);
-        let token_id = total_supply + 1;
-        
-        total_supply = token_id;
-        env.storage().instance().set(&DataKey::TotalSupply, &total_supply);
+    /// Mint a soulbound NFT, e.g. a credential or membership badge. It can
+    /// never be transferred, but its holder may burn it.
+    pub fn mint_soulbound(env: Env, to: Address, uri: String) -> Result<u64, NftError> {
+        Self::require_admin(&env)?;
+        Self::mint_to(&env, &to, uri, true)
+    }
 
-        env.storage().persistent().set(&DataKey::Owner(token_id), &to);
-        env.storage().persistent().set(&DataKey::Metadata(token_id), &uri);
+    /// Open claims for `config.start_time..config.end_time`. Eligibility comes
+    /// from the direct allowlist, or else a Merkle proof of
+    /// `sha256(claimant_xdr || limit_be_bytes)` against `config.merkle_root`.
+    /// Admin only.
+    pub fn set_claim_config(env: Env, config: ClaimConfig) -> Result<(), NftError> {
+        Self::require_admin(&env)?;
+        if config.start_time >= config.end_time {
+            return Err(NftError::InvalidClaimWindow);
+        }
+        env.storage().instance().set(&DataKey::ClaimConfig, &config);
+        env.events().publish((symbol_short!("claim_cfg"),), config);
+        Ok(())
+    }
+
+    /// Set per-address claim limits on the direct allowlist. A limit of zero
+    /// removes the address. Admin only.
+    pub fn set_claim_limits(env: Env, allowances: Vec<ClaimAllowance>) -> Result<(), NftError> {
+        Self::require_admin(&env)?;
+        for allowance in allowances.iter() {
+            let key = DataKey::ClaimLimit(allowance.claimant.clone());
+            if allowance.limit == 0 {
+                env.storage().persistent().remove(&key);
+            } else {
+                env.storage().persistent().set(&key, &allowance.limit);
+            }
+            env.events().publish((symbol_short!("claim_lim"), allowance.claimant), allowance.limit);
+        }
+        Ok(())
+    }
+
+    /// Mint one token to an eligible `claimant` while the claim window is open,
+    /// with the collection's base URI. Addresses on the direct allowlist pass
+    /// an empty proof and their `limit` is ignored; others prove their Merkle
+    /// leaf. Returns the new token ID.
+    pub fn claim(env: Env, claimant: Address, limit: u32, proof: Vec<BytesN<32>>) -> Result<u64, NftError> {
+        claimant.require_auth();
+
+        let config = Self::get_claim_config(env.clone()).ok_or(NftError::ClaimNotActive)?;
+        let now = env.ledger().timestamp();
+        if now < config.start_time || now >= config.end_time {
+            return Err(NftError::ClaimNotActive);
+        }
+
+        let allowed = Self::allowance(&env, &claimant, config.merkle_root, limit, &proof)?;
+
+        let claimed = Self::claimed(env.clone(), claimant.clone());
+        if claimed >= allowed {
+            return Err(NftError::ClaimLimitReached);
+        }
+        env.storage().persistent().set(&DataKey::Claimed(claimant.clone()), &(claimed + 1));
+
+        let uri: String = env.storage().instance().get(&DataKey::BaseUri).ok_or(NftError::NotInitialized)?;
+        Self::mint_to(&env, &claimant, uri, false)
+    }
 
-        token_id
+    /// Get the claim window and Merkle root, if claims were configured
+    pub fn get_claim_config(env: Env) -> Option<ClaimConfig> {
+        env.storage().instance().get(&DataKey::ClaimConfig)
+    }
+
+    /// Get an address's limit on the direct allowlist; zero if it isn't listed
+    pub fn claim_limit(env: Env, claimant: Address) -> u32 {
+        env.storage().persistent().get(&DataKey::ClaimLimit(claimant)).unwrap_or(0)
+    }
+
+    /// Get how many tokens an address has claimed
+    pub fn claimed(env: Env, claimant: Address) -> u32 {
+        env.storage().persistent().get(&DataKey::Claimed(claimant)).unwrap_or(0)
+    }
+
+    /// Replace the sale phases. Phases must each end after they start, be in
+    /// time order and not overlap. Per-wallet counts are kept by phase index.
+    /// Admin only.
+    pub fn set_sale_phases(env: Env, phases: Vec<SalePhase>) -> Result<(), NftError> {
+        Self::require_admin(&env)?;
+        if phases.len() > MAX_SALE_PHASES {
+            return Err(NftError::InvalidSalePhases);
+        }
+        let mut previous_end = 0;
+        for phase in phases.iter() {
+            if phase.price < 0 || phase.start_time >= phase.end_time || phase.start_time < previous_end {
+                return Err(NftError::InvalidSalePhases);
+            }
+            previous_end = phase.end_time;
+        }
+        env.storage().instance().set(&DataKey::SalePhases, &phases);
+        env.events().publish((symbol_short!("phases"),), phases);
+        Ok(())
+    }
+
+    /// Set the address that receives sale proceeds. Admin only.
+    pub fn set_treasury(env: Env, treasury: Address) -> Result<(), NftError> {
+        Self::require_admin(&env)?;
+        env.storage().instance().set(&DataKey::Treasury, &treasury);
+        env.events().publish((symbol_short!("treasury"),), treasury);
+        Ok(())
+    }
+
+    /// Buy `quantity` tokens in the active sale phase, with the collection's
+    /// base URI. The price is pulled from `buyer` straight to the treasury.
+    /// In an `allowlist_only` phase the buyer must be eligible to claim, by
+    /// the direct allowlist or a `(limit, proof)` Merkle proof as in `claim`;
+    /// otherwise `limit` and `proof` are ignored. Returns the new token IDs.
+    pub fn buy_mint(
+        env: Env,
+        buyer: Address,
+        quantity: u32,
+        limit: u32,
+        proof: Vec<BytesN<32>>,
+    ) -> Result<Vec<u64>, NftError> {
+        buyer.require_auth();
+        if quantity == 0 {
+            return Err(NftError::InvalidQuantity);
+        }
+
+        let index = Self::active_phase(env.clone()).ok_or(NftError::SaleNotActive)?;
+        let phase = Self::get_sale_phases(env.clone()).get(index).ok_or(NftError::SaleNotActive)?;
+        if phase.allowlist_only {
+            let root = Self::get_claim_config(env.clone()).and_then(|config| config.merkle_root);
+            if Self::allowance(&env, &buyer, root, limit, &proof)? == 0 {
+                return Err(NftError::NotEligible);
+            }
+        }
+        let bought = Self::phase_minted(env.clone(), index, buyer.clone());
+        let total = bought.checked_add(quantity).ok_or(NftError::InvalidQuantity)?;
+        if total > phase.max_per_wallet {
+            return Err(NftError::WalletLimitReached);
+        }
+        if Self::remaining_supply(env.clone()).is_some_and(|remaining| remaining < quantity as u64) {
+            return Err(NftError::MaxSupplyReached);
+        }
+        let treasury: Address = env.storage().instance().get(&DataKey::Treasury).ok_or(NftError::NoTreasury)?;
+        let cost = phase.price.checked_mul(quantity as i128).ok_or(NftError::InvalidQuantity)?;
+
+        env.storage().persistent().set(&DataKey::PhaseMinted(index, buyer.clone()), &total);
+        if cost > 0 {
+            token::Client::new(&env, &phase.payment_token).transfer(&buyer, &treasury, &cost);
+        }
+
+        let uri: String = env.storage().instance().get(&DataKey::BaseUri).ok_or(NftError::NotInitialized)?;
+        let mut token_ids = Vec::new(&env);
+        for _ in 0..quantity {
+            token_ids.push_back(Self::mint_to(&env, &buyer, uri.clone(), false)?);
+        }
+        env.events().publish((symbol_short!("buy"), buyer), (index, quantity, cost));
+        Ok(token_ids)
+    }
+
+    /// Get the configured sale phases
+    pub fn get_sale_phases(env: Env) -> Vec<SalePhase> {
+        env.storage().instance().get(&DataKey::SalePhases).unwrap_or_else(|| Vec::new(&env))
+    }
+
+    /// Get the index of the sale phase open now, if any
+    pub fn active_phase(env: Env) -> Option<u32> {
+        let now = env.ledger().timestamp();
+        Self::get_sale_phases(env.clone())
+            .iter()
+            .position(|phase| phase.start_time <= now && now < phase.end_time)
+            .map(|index| index as u32)
+    }
+
+    /// Get how many tokens `buyer` bought in phase `index`
+    pub fn phase_minted(env: Env, index: u32, buyer: Address) -> u32 {
+        env.storage().persistent().get(&DataKey::PhaseMinted(index, buyer)).unwrap_or(0)
+    }
+
+    /// Get the address that receives sale proceeds, if set
+    pub fn get_treasury(env: Env) -> Option<Address> {
+        env.storage().instance().get(&DataKey::Treasury)
+    }
+
+    /// Destroy an NFT. Only its owner may burn it, soulbound or not.
+    pub fn burn(env: Env, owner: Address, token_id: u64) -> Result<(), NftError> {
+        owner.require_auth();
+        Self::ensure_not_paused(&env)?;
+        if Self::get_owner(env.clone(), token_id)? != owner {
+            return Err(NftError::NotOwner);
+        }
+
+        let storage = env.storage().persistent();
+        storage.remove(&DataKey::Owner(token_id));
+        storage.remove(&DataKey::Metadata(token_id));
+        storage.remove(&DataKey::Approval(token_id));
+        storage.remove(&DataKey::TokenRoyalty(token_id));
+        storage.remove(&DataKey::Soulbound(token_id));
+        storage.remove(&DataKey::MetadataFrozen(token_id));
+        Self::remove_owned(&env, &owner, token_id);
+
+        env.events().publish((symbol_short!("burn"), owner, token_id), ());
+        Ok(())
+    }
+
+    /// Check whether an NFT is soulbound
+    pub fn is_soulbound(env: Env, token_id: u64) -> bool {
+        env.storage().persistent().has(&DataKey::Soulbound(token_id))
     }
 
     /// Transfer an NFT to another address
@@ -87,61 +396,244 @@ impl NftContract {
         from: Address,
         to: Address,
         token_id: u64,
-    ) {
+    ) -> Result<(), NftError> {
         from.require_auth();
-        
-        let current_owner: Address = env.storage().persistent().get(&DataKey::Owner(token_id))
-            .unwrap_or_else(|| panic!("Token does not exist"));
-            
-        assert!(current_owner == from, "Not the owner");
-        
-        env.storage().persistent().set(&DataKey::Owner(token_id), &to);
+        Self::move_token(&env, &from, &to, token_id)
+    }
+
+    /// Transfer an NFT on the owner's behalf. `spender` must be the owner,
+    /// the token's unexpired approved spender, or an operator of the owner.
+    pub fn transfer_from(
+        env: Env,
+        spender: Address,
+        from: Address,
+        to: Address,
+        token_id: u64,
+    ) -> Result<(), NftError> {
+        spender.require_auth();
+
+        let approved = spender == from
+            || Self::get_approved(env.clone(), token_id) == Some(spender.clone())
+            || Self::is_approved_for_all(env.clone(), from.clone(), spender.clone());
+        if !approved {
+            return Err(NftError::NotAuthorized);
+        }
+
+        Self::move_token(&env, &from, &to, token_id)
+    }
+
+    /// Let `spender` transfer one token until ledger `expiration` (inclusive).
+    /// Replaces any earlier approval; an expiration in the past revokes it.
+    /// The approval is cleared when the token moves.
+    pub fn approve(env: Env, owner: Address, spender: Address, token_id: u64, expiration: u32) -> Result<(), NftError> {
+        owner.require_auth();
+
+        if Self::get_owner(env.clone(), token_id)? != owner {
+            return Err(NftError::NotOwner);
+        }
+
+        let approval = Approval { spender: spender.clone(), expiration };
+        env.storage().persistent().set(&DataKey::Approval(token_id), &approval);
+        env.events().publish((symbol_short!("approve"), owner, token_id), (spender, expiration));
+        Ok(())
+    }
+
+    /// Let `operator` transfer all of `owner`'s tokens, now and later, or
+    /// revoke that with `approved = false`
+    pub fn set_approval_for_all(env: Env, owner: Address, operator: Address, approved: bool) {
+        owner.require_auth();
+
+        let key = DataKey::OperatorApproval(owner.clone(), operator.clone());
+        if approved {
+            env.storage().persistent().set(&key, &true);
+        } else {
+            env.storage().persistent().remove(&key);
+        }
+        env.events().publish((symbol_short!("appr_all"), owner, operator), approved);
+    }
+
+    /// Get the approved spender of a token, if its approval hasn't expired
+    pub fn get_approved(env: Env, token_id: u64) -> Option<Address> {
+        env.storage()
+            .persistent()
+            .get::<_, Approval>(&DataKey::Approval(token_id))
+            .filter(|approval| approval.expiration >= env.ledger().sequence())
+            .map(|approval| approval.spender)
+    }
+
+    /// Check whether `operator` may transfer all of `owner`'s tokens
+    pub fn is_approved_for_all(env: Env, owner: Address, operator: Address) -> bool {
+        env.storage()
+            .persistent()
+            .get(&DataKey::OperatorApproval(owner, operator))
+            .unwrap_or(false)
+    }
+
+    /// Freeze minting, transfers and burns, e.g. during incident response or before a reveal
+    pub fn pause(env: Env) -> Result<(), NftError> {
+        Self::require_admin(&env)?;
+        env.storage().instance().set(&DataKey::Paused, &true);
+        env.events().publish((symbol_short!("paused"),), ());
+        Ok(())
+    }
+
+    /// Resume minting, transfers and burns
+    pub fn unpause(env: Env) -> Result<(), NftError> {
+        Self::require_admin(&env)?;
+        env.storage().instance().set(&DataKey::Paused, &false);
+        env.events().publish((symbol_short!("unpaused"),), ());
+        Ok(())
+    }
+
+    /// Check whether minting, transfers and burns are frozen
+    pub fn is_paused(env: Env) -> bool {
+        env.storage().instance().get(&DataKey::Paused).unwrap_or(false)
+    }
+
+    /// Get the collection's supply cap, if it has one
+    pub fn max_supply(env: Env) -> Option<u64> {
+        env.storage().instance().get(&DataKey::MaxSupply)
+    }
+
+    /// Get how many more tokens can be minted, if the collection is capped
+    pub fn remaining_supply(env: Env) -> Option<u64> {
+        let total_supply: u64 = env.storage().instance().get(&DataKey::TotalSupply).unwrap_or(0);
+        Self::max_supply(env).map(|max| max.saturating_sub(total_supply))
     }
 
     /// Get the owner of an NFT
-    pub fn get_owner(env: Env, token_id: u64) -> Address {
+    pub fn get_owner(env: Env, token_id: u64) -> Result<Address, NftError> {
         env.storage().persistent().get(&DataKey::Owner(token_id))
-            .unwrap_or_else(|| panic!("Token does not exist"))
+            .ok_or(NftError::TokenNotFound)
+    }
+
+    /// Get the number of tokens an address owns
+    pub fn balance_of(env: Env, owner: Address) -> u32 {
+        Self::owned_count(&env, &owner)
+    }
+
+    /// Get up to `limit` of an owner's token IDs, starting at `offset`.
+    /// `limit` is capped at `MAX_PAGE_SIZE`. Order is stable until a token
+    /// leaves the owner.
+    pub fn tokens_of_owner(env: Env, owner: Address, offset: u32, limit: u32) -> Vec<u64> {
+        let storage = env.storage().persistent();
+        let len = Self::owned_count(&env, &owner);
+        let end = offset.saturating_add(limit.min(MAX_PAGE_SIZE)).min(len);
+        let mut tokens = Vec::new(&env);
+        for index in offset..end {
+            let token_id: u64 = storage.get(&DataKey::OwnedToken(owner.clone(), index)).unwrap();
+            tokens.push_back(token_id);
+        }
+        tokens
     }
 
     /// Get token metadata
-    pub fn get_metadata(env: Env, token_id: u64) -> TokenMetadata {
-        let name: String = env.storage().instance().get(&DataKey::Name).unwrap();
-        let symbol: String = env.storage().instance().get(&DataKey::Symbol).unwrap();
+    pub fn get_metadata(env: Env, token_id: u64) -> Result<TokenMetadata, NftError> {
+        let name: String = env.storage().instance().get(&DataKey::Name).ok_or(NftError::NotInitialized)?;
+        let symbol: String = env.storage().instance().get(&DataKey::Symbol).ok_or(NftError::NotInitialized)?;
         
         let token_uri: String = env.storage().persistent()
             .get(&DataKey::Metadata(token_id))
-            .unwrap_or_else(|| panic!("Token does not exist"));
+            .ok_or(NftError::TokenNotFound)?;
 
-        TokenMetadata {
+        Ok(TokenMetadata {
             name,
             symbol,
             uri: token_uri,
+        })
+    }
+
+    /// Replace a token's URI, for dynamic NFTs or corrections. `caller` must be
+    /// the admin, or the token's owner if owner updates are allowed.
+    /// Rejected once the token's metadata is frozen.
+    pub fn set_token_uri(env: Env, caller: Address, token_id: u64, uri: String) -> Result<(), NftError> {
+        caller.require_auth();
+        let owner = Self::get_owner(env.clone(), token_id)?;
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).ok_or(NftError::NotInitialized)?;
+        if caller != admin && !(caller == owner && Self::owner_uri_updates(env.clone())) {
+            return Err(NftError::NotAuthorized);
+        }
+        if Self::is_metadata_frozen(env.clone(), token_id) {
+            return Err(NftError::MetadataFrozen);
+        }
+
+        env.storage().persistent().set(&DataKey::Metadata(token_id), &uri);
+        env.events().publish((symbol_short!("uri"), token_id), uri);
+        Ok(())
+    }
+
+    /// Let token owners set their own tokens' URIs, or take that back. Admin only.
+    pub fn set_owner_uri_updates(env: Env, allowed: bool) -> Result<(), NftError> {
+        Self::require_admin(&env)?;
+        env.storage().instance().set(&DataKey::OwnerUriUpdates, &allowed);
+        env.events().publish((symbol_short!("uri_pol"),), allowed);
+        Ok(())
+    }
+
+    /// Check whether token owners may set their own tokens' URIs
+    pub fn owner_uri_updates(env: Env) -> bool {
+        env.storage().instance().get(&DataKey::OwnerUriUpdates).unwrap_or(false)
+    }
+
+    /// Permanently fix a token's URI. Admin only.
+    pub fn freeze_metadata(env: Env, token_id: u64) -> Result<(), NftError> {
+        Self::require_admin(&env)?;
+        Self::get_owner(env.clone(), token_id)?;
+        if Self::is_metadata_frozen(env.clone(), token_id) {
+            return Err(NftError::MetadataFrozen);
+        }
+
+        env.storage().persistent().set(&DataKey::MetadataFrozen(token_id), &true);
+        env.events().publish((symbol_short!("frozen"), token_id), ());
+        Ok(())
+    }
+
+    /// Permanently fix the URI of every token, including ones minted later.
+    /// Admin only.
+    pub fn freeze_all_metadata(env: Env) -> Result<(), NftError> {
+        Self::require_admin(&env)?;
+        if env.storage().instance().has(&DataKey::AllMetadataFrozen) {
+            return Err(NftError::MetadataFrozen);
         }
+
+        env.storage().instance().set(&DataKey::AllMetadataFrozen, &true);
+        env.events().publish((symbol_short!("frozen"),), ());
+        Ok(())
+    }
+
+    /// Check whether a token's URI can no longer change
+    pub fn is_metadata_frozen(env: Env, token_id: u64) -> bool {
+        env.storage().instance().has(&DataKey::AllMetadataFrozen)
+            || env.storage().persistent().has(&DataKey::MetadataFrozen(token_id))
     }
 
     /// Set global default royalty
-    pub fn set_royalty(env: Env, receiver: Address, amount: u32) {
-        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
-        admin.require_auth();
+    pub fn set_royalty(env: Env, receiver: Address, amount: u32) -> Result<(), NftError> {
+        Self::require_admin(&env)?;
         
-        assert!(amount <= 10000, "Royalty cannot exceed 100%");
+        if amount > 10000 {
+            return Err(NftError::RoyaltyTooHigh);
+        }
         
         let royalty_data = RoyaltyData { receiver, amount };
         env.storage().instance().set(&DataKey::Royalty, &royalty_data);
+        env.events().publish((symbol_short!("royalty"),), royalty_data);
+        Ok(())
     }
 
     /// Set specific royalty for a given token
-    pub fn set_token_royalty(env: Env, token_id: u64, receiver: Address, amount: u32) {
-        let owner: Address = env.storage().persistent().get(&DataKey::Owner(token_id))
-            .unwrap_or_else(|| panic!("Token does not exist"));
-            
+    pub fn set_token_royalty(env: Env, token_id: u64, receiver: Address, amount: u32) -> Result<(), NftError> {
+        let owner = Self::get_owner(env.clone(), token_id)?;
         owner.require_auth();
         
-        assert!(amount <= 10000, "Royalty cannot exceed 100%");
+        if amount > 10000 {
+            return Err(NftError::RoyaltyTooHigh);
+        }
 
         let royalty_data = RoyaltyData { receiver, amount };
         env.storage().persistent().set(&DataKey::TokenRoyalty(token_id), &royalty_data);
+        env.events().publish((symbol_short!("royalty"), token_id), royalty_data);
+        Ok(())
     }
 
     /// Get royalty details for a given token and sale price
@@ -156,7 +648,213 @@ impl NftContract {
             return (env.current_contract_address(), 0);
         };
 
-        let royalty_amount = (sale_price * royalty_data.amount as u128) / 10000;
+        // Split the price so the product can't overflow; the result is still
+        // exactly floor(sale_price * amount / 10000) since amount <= 10000
+        let bps = royalty_data.amount as u128;
+        let royalty_amount = sale_price / 10000 * bps + sale_price % 10000 * bps / 10000;
         (royalty_data.receiver, royalty_amount)
     }
+
+    /// Enable generative traits by committing the collection seed and trait
+    /// categories. Must be done once, before the first mint, so the outcome
+    /// for every token ID is fixed before anyone owns one.
+    pub fn commit_traits(env: Env, seed: BytesN<32>, categories: Vec<TraitCategory>) -> Result<(), NftError> {
+        Self::require_admin(&env)?;
+
+        if env.storage().instance().has(&DataKey::TraitSeed) {
+            return Err(NftError::TraitsAlreadyCommitted);
+        }
+        let total_supply: u64 = env.storage().instance().get(&DataKey::TotalSupply).unwrap_or(0);
+        if total_supply != 0 {
+            return Err(NftError::TraitsAfterMint);
+        }
+
+        // Each category needs one weight per value, summing to a nonzero u32
+        for category in categories.iter() {
+            if category.values.is_empty() || category.values.len() != category.weights.len() {
+                return Err(NftError::InvalidTraitWeights);
+            }
+            let total_weight = category
+                .weights
+                .iter()
+                .try_fold(0u32, |sum, w| sum.checked_add(w))
+                .ok_or(NftError::InvalidTraitWeights)?;
+            if total_weight == 0 {
+                return Err(NftError::InvalidTraitWeights);
+            }
+        }
+
+        env.storage().instance().set(&DataKey::TraitSeed, &seed);
+        env.storage().instance().set(&DataKey::TraitCategories, &categories);
+        env.events().publish((symbol_short!("traits"),), seed);
+        Ok(())
+    }
+
+    /// Get the committed collection seed, if generative traits are enabled
+    pub fn get_trait_seed(env: Env) -> Option<BytesN<32>> {
+        env.storage().instance().get(&DataKey::TraitSeed)
+    }
+
+    /// Derive a token's traits from the collection seed and its token ID.
+    /// Category `i` rolls `sha256(seed || token_id || i)` against its weights,
+    /// so anyone can recompute and verify the result.
+    pub fn traits(env: Env, token_id: u64) -> Result<Vec<Trait>, NftError> {
+        if !env.storage().persistent().has(&DataKey::Owner(token_id)) {
+            return Err(NftError::TokenNotFound);
+        }
+        let seed: BytesN<32> = env
+            .storage()
+            .instance()
+            .get(&DataKey::TraitSeed)
+            .ok_or(NftError::TraitsNotCommitted)?;
+        let categories: Vec<TraitCategory> =
+            env.storage().instance().get(&DataKey::TraitCategories).unwrap();
+
+        let mut traits = Vec::new(&env);
+        for (index, category) in categories.iter().enumerate() {
+            let mut preimage = Bytes::from_array(&env, &seed.to_array());
+            preimage.extend_from_array(&token_id.to_be_bytes());
+            preimage.extend_from_array(&(index as u32).to_be_bytes());
+            let digest = env.crypto().sha256(&preimage).to_array();
+
+            let total_weight: u32 = category.weights.iter().sum();
+            let mut roll = u32::from_be_bytes([digest[0], digest[1], digest[2], digest[3]]) % total_weight;
+            let mut chosen = 0;
+            for (i, weight) in category.weights.iter().enumerate() {
+                if roll < weight {
+                    chosen = i as u32;
+                    break;
+                }
+                roll -= weight;
+            }
+
+            traits.push_back(Trait {
+                name: category.name.clone(),
+                value: category.values.get(chosen).unwrap(),
+            });
+        }
+        Ok(traits)
+    }
+
+    fn require_admin(env: &Env) -> Result<Address, NftError> {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).ok_or(NftError::NotInitialized)?;
+        admin.require_auth();
+        Ok(admin)
+    }
+
+    /// Issue the next token ID to `to`, within the max supply
+    fn mint_to(env: &Env, to: &Address, uri: String, soulbound: bool) -> Result<u64, NftError> {
+        Self::ensure_not_paused(env)?;
+
+        if Self::remaining_supply(env.clone()) == Some(0) {
+            return Err(NftError::MaxSupplyReached);
+        }
+
+        let mut total_supply: u64 = env.storage().instance().get(&DataKey::TotalSupply).unwrap_or(0);
+        let token_id = total_supply + 1;
+        
+        total_supply = token_id;
+        env.storage().instance().set(&DataKey::TotalSupply, &total_supply);
+
+        env.storage().persistent().set(&DataKey::Owner(token_id), to);
+        env.storage().persistent().set(&DataKey::Metadata(token_id), &uri);
+        if soulbound {
+            env.storage().persistent().set(&DataKey::Soulbound(token_id), &true);
+        }
+        Self::add_owned(env, to, token_id);
+
+        env.events().publish((symbol_short!("mint"), to.clone(), token_id), uri);
+        Ok(token_id)
+    }
+
+    /// Get how many tokens `claimant` may claim: their direct allowlist limit,
+    /// or else `limit` once `proof` places their leaf under `merkle_root`
+    fn allowance(
+        env: &Env,
+        claimant: &Address,
+        merkle_root: Option<BytesN<32>>,
+        limit: u32,
+        proof: &Vec<BytesN<32>>,
+    ) -> Result<u32, NftError> {
+        if let Some(direct) = env.storage().persistent().get(&DataKey::ClaimLimit(claimant.clone())) {
+            return Ok(direct);
+        }
+        let root = merkle_root.ok_or(NftError::NotEligible)?;
+        let mut leaf = claimant.clone().to_xdr(env);
+        leaf.extend_from_array(&limit.to_be_bytes());
+        let mut node: BytesN<32> = env.crypto().sha256(&leaf).into();
+        for sibling in proof.iter() {
+            let (left, right) = if node <= sibling { (node, sibling) } else { (sibling, node) };
+            let mut pair = Bytes::from_array(env, &left.to_array());
+            pair.extend_from_array(&right.to_array());
+            node = env.crypto().sha256(&pair).into();
+        }
+        if node != root {
+            return Err(NftError::NotEligible);
+        }
+        Ok(limit)
+    }
+
+    fn ensure_not_paused(env: &Env) -> Result<(), NftError> {
+        if Self::is_paused(env.clone()) {
+            return Err(NftError::Paused);
+        }
+        Ok(())
+    }
+
+    /// Move a token from its current owner `from` to `to`, clearing its approval
+    fn move_token(env: &Env, from: &Address, to: &Address, token_id: u64) -> Result<(), NftError> {
+        Self::ensure_not_paused(env)?;
+        if Self::get_owner(env.clone(), token_id)? != *from {
+            return Err(NftError::NotOwner);
+        }
+        if Self::is_soulbound(env.clone(), token_id) {
+            return Err(NftError::Soulbound);
+        }
+
+        env.storage().persistent().set(&DataKey::Owner(token_id), to);
+        env.storage().persistent().remove(&DataKey::Approval(token_id));
+        Self::remove_owned(env, from, token_id);
+        Self::add_owned(env, to, token_id);
+        env.events().publish((symbol_short!("transfer"), from.clone(), to.clone(), token_id), ());
+        Ok(())
+    }
+
+    fn owned_count(env: &Env, owner: &Address) -> u32 {
+        env.storage()
+            .persistent()
+            .get(&DataKey::OwnedCount(owner.clone()))
+            .unwrap_or(0)
+    }
+
+    /// Append a token to its owner's index
+    fn add_owned(env: &Env, owner: &Address, token_id: u64) {
+        let storage = env.storage().persistent();
+        let index = Self::owned_count(env, owner);
+        storage.set(&DataKey::OwnedToken(owner.clone(), index), &token_id);
+        storage.set(&DataKey::OwnedIndex(token_id), &index);
+        storage.set(&DataKey::OwnedCount(owner.clone()), &(index + 1));
+    }
+
+    /// Drop a token from its owner's index, moving the last entry into its slot
+    fn remove_owned(env: &Env, owner: &Address, token_id: u64) {
+        let storage = env.storage().persistent();
+        let Some(index) = storage.get::<_, u32>(&DataKey::OwnedIndex(token_id)) else {
+            return;
+        };
+        let last = Self::owned_count(env, owner) - 1;
+        if index < last {
+            let last_key = DataKey::OwnedToken(owner.clone(), last);
+            let moved: u64 = storage.get(&last_key).unwrap();
+            storage.set(&DataKey::OwnedToken(owner.clone(), index), &moved);
+            storage.set(&DataKey::OwnedIndex(moved), &index);
+        }
+        storage.remove(&DataKey::OwnedToken(owner.clone(), last));
+        storage.remove(&DataKey::OwnedIndex(token_id));
+        if last == 0 {
+            storage.remove(&DataKey::OwnedCount(owner.clone()));
+        } else {
+            storage.set(&DataKey::OwnedCount(owner.clone()), &last);
+        }
+    }
 }
@@ -0,0 +1,343 @@
+// Randomized calls against every state-changing entrypoint with boundary
+// values (extreme token IDs, prices, quantities, windows and ledger times).
+// Every failure must surface as an `NftError`; a trap from an overflow or
+// unwrap means an entrypoint is missing a check.
+use nft_contract::{ClaimAllowance, ClaimConfig, NftContract, NftContractClient, NftError, SalePhase, TraitCategory};
+use soroban_sdk::{
+    testutils::{Address as _, Ledger as _},
+    token::StellarAssetClient,
+    xdr::ToXdr,
+    vec, Address, BytesN, Env, InvokeError, String, Vec,
+};
+
+const ROUNDS: u64 = 24;
+/// Highest ledger sequence a round reaches, under the default minimum entry TTL
+const MAX_SEQUENCE: u32 = 4_000;
+const STEPS: u32 = 60;
+
+// xorshift64: deterministic, so a failing seed reproduces exactly
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: u64) -> u64 {
+        self.next() % n
+    }
+
+    fn flag(&mut self) -> bool {
+        self.below(2) == 0
+    }
+
+    fn price(&mut self) -> i128 {
+        match self.below(6) {
+            0 => 0,
+            1 => -1,
+            2 => i128::MAX,
+            _ => self.below(200) as i128,
+        }
+    }
+
+    fn count(&mut self) -> u32 {
+        match self.below(5) {
+            0 => 0,
+            1 => u32::MAX,
+            _ => 1 + self.below(3) as u32,
+        }
+    }
+
+    fn bps(&mut self) -> u32 {
+        match self.below(5) {
+            0 => 0,
+            1 => u32::MAX,
+            2 => 10_001,
+            _ => self.below(10_001) as u32,
+        }
+    }
+
+    fn time(&mut self) -> u64 {
+        match self.below(5) {
+            0 => 0,
+            1 => u64::MAX,
+            2 => u64::MAX - 1,
+            _ => self.below(10_000),
+        }
+    }
+}
+
+struct Harness<'a> {
+    env: &'a Env,
+    client: NftContractClient<'a>,
+    payment: Address,
+    users: [Address; 3],
+    outsider: Address,
+    rng: Rng,
+}
+
+// Ok, or a typed contract error. `InvokeError::Abort` is a trap.
+fn assert_typed<T, C>(call: &str, result: Result<Result<T, C>, Result<NftError, InvokeError>>) {
+    if let Err(Err(err)) = result {
+        panic!("{call} trapped instead of returning an NftError: {err:?}");
+    }
+}
+
+impl Harness<'_> {
+    fn address(&mut self) -> Address {
+        match self.rng.below(4) {
+            3 => self.outsider.clone(),
+            i => self.users[i as usize].clone(),
+        }
+    }
+
+    fn token_id(&mut self) -> u64 {
+        match self.rng.below(5) {
+            0 => u64::MAX,
+            1 => 0,
+            _ => 1 + self.rng.below(6),
+        }
+    }
+
+    fn uri(&mut self) -> String {
+        String::from_str(self.env, if self.rng.flag() { "ipfs://a" } else { "" })
+    }
+
+    // Root of a one-leaf tree, so a claimant with the right limit passes an empty proof
+    fn root(&mut self) -> Option<BytesN<32>> {
+        if self.rng.below(3) == 0 {
+            return None;
+        }
+        let (claimant, limit) = (self.users[0].clone(), self.rng.count());
+        let mut leaf = claimant.to_xdr(self.env);
+        leaf.extend_from_array(&limit.to_be_bytes());
+        Some(self.env.crypto().sha256(&leaf).into())
+    }
+
+    // Usually a window open now, so claims and sales get through
+    fn window(&mut self) -> (u64, u64) {
+        if self.rng.below(4) == 0 {
+            return (self.rng.time(), self.rng.time());
+        }
+        let now = self.env.ledger().timestamp();
+        (now.saturating_sub(self.rng.below(500)), now.saturating_add(1 + self.rng.below(2_000)))
+    }
+
+    fn proof(&mut self) -> Vec<BytesN<32>> {
+        let mut proof = Vec::new(self.env);
+        for _ in 0..self.rng.below(3) {
+            proof.push_back(BytesN::from_array(self.env, &[self.rng.below(4) as u8; 32]));
+        }
+        proof
+    }
+
+    fn step(&mut self) {
+        match self.rng.below(28) {
+            0..=2 => {
+                let (to, uri) = (self.address(), self.uri());
+                if self.rng.below(4) == 0 {
+                    assert_typed("mint_soulbound", self.client.try_mint_soulbound(&to, &uri));
+                } else {
+                    assert_typed("mint", self.client.try_mint(&to, &uri));
+                }
+            }
+            3 => {
+                let (start_time, end_time) = self.window();
+                let config = ClaimConfig { start_time, end_time, merkle_root: self.root() };
+                assert_typed("set_claim_config", self.client.try_set_claim_config(&config));
+            }
+            4 => {
+                let mut allowances = Vec::new(self.env);
+                for _ in 0..self.rng.below(3) {
+                    allowances.push_back(ClaimAllowance { claimant: self.address(), limit: self.rng.count() });
+                }
+                assert_typed("set_claim_limits", self.client.try_set_claim_limits(&allowances));
+            }
+            5 | 6 => {
+                let (claimant, limit, proof) = (self.address(), self.rng.count(), self.proof());
+                assert_typed("claim", self.client.try_claim(&claimant, &limit, &proof));
+            }
+            7 => {
+                let mut phases = Vec::new(self.env);
+                for _ in 0..self.rng.below(3) {
+                    let (start_time, end_time) = self.window();
+                    phases.push_back(SalePhase {
+                        price: self.rng.price(),
+                        payment_token: self.payment.clone(),
+                        max_per_wallet: self.rng.count(),
+                        start_time,
+                        end_time,
+                        allowlist_only: self.rng.flag(),
+                    });
+                }
+                assert_typed("set_sale_phases", self.client.try_set_sale_phases(&phases));
+            }
+            8 => {
+                let treasury = self.address();
+                assert_typed("set_treasury", self.client.try_set_treasury(&treasury));
+            }
+            9 | 10 => {
+                let (buyer, quantity) = (self.address(), self.rng.count());
+                let (limit, proof) = (self.rng.count(), self.proof());
+                assert_typed("buy_mint", self.client.try_buy_mint(&buyer, &quantity, &limit, &proof));
+            }
+            11 => {
+                let (owner, id) = (self.address(), self.token_id());
+                assert_typed("burn", self.client.try_burn(&owner, &id));
+            }
+            12 | 13 => {
+                let (from, to, id) = (self.address(), self.address(), self.token_id());
+                assert_typed("transfer", self.client.try_transfer(&from, &to, &id));
+            }
+            14 => {
+                let (spender, from, to, id) = (self.address(), self.address(), self.address(), self.token_id());
+                assert_typed("transfer_from", self.client.try_transfer_from(&spender, &from, &to, &id));
+            }
+            15 => {
+                let (owner, spender, id) = (self.address(), self.address(), self.token_id());
+                let expiration = if self.rng.flag() { u32::MAX } else { self.rng.below(5_000) as u32 };
+                assert_typed("approve", self.client.try_approve(&owner, &spender, &id, &expiration));
+                let (owner, operator, approved) = (self.address(), self.address(), self.rng.flag());
+                self.client.set_approval_for_all(&owner, &operator, &approved);
+            }
+            16 => {
+                if self.rng.below(3) == 0 {
+                    assert_typed("pause", self.client.try_pause());
+                } else {
+                    assert_typed("unpause", self.client.try_unpause());
+                }
+            }
+            17 => {
+                let (caller, id, uri) = (self.address(), self.token_id(), self.uri());
+                assert_typed("set_token_uri", self.client.try_set_token_uri(&caller, &id, &uri));
+                let allowed = self.rng.flag();
+                assert_typed("set_owner_uri_updates", self.client.try_set_owner_uri_updates(&allowed));
+            }
+            18 => {
+                let id = self.token_id();
+                assert_typed("freeze_metadata", self.client.try_freeze_metadata(&id));
+                if self.rng.below(8) == 0 {
+                    assert_typed("freeze_all_metadata", self.client.try_freeze_all_metadata());
+                }
+            }
+            19 => {
+                let (receiver, amount) = (self.address(), self.rng.bps());
+                assert_typed("set_royalty", self.client.try_set_royalty(&receiver, &amount));
+                let (id, receiver, amount) = (self.token_id(), self.address(), self.rng.bps());
+                assert_typed("set_token_royalty", self.client.try_set_token_royalty(&id, &receiver, &amount));
+            }
+            20 => {
+                let seed = BytesN::from_array(self.env, &[self.rng.below(4) as u8; 32]);
+                let mut categories = Vec::new(self.env);
+                for _ in 0..self.rng.below(3) {
+                    let mut values = Vec::new(self.env);
+                    let mut weights = Vec::new(self.env);
+                    for _ in 0..self.rng.below(3) {
+                        values.push_back(String::from_str(self.env, "v"));
+                        weights.push_back(self.rng.bps());
+                    }
+                    if self.rng.below(4) == 0 {
+                        weights.push_back(u32::MAX);
+                    }
+                    categories.push_back(TraitCategory { name: String::from_str(self.env, "c"), values, weights });
+                }
+                assert_typed("commit_traits", self.client.try_commit_traits(&seed, &categories));
+            }
+            21 => {
+                let id = self.token_id();
+                assert_typed("get_owner", self.client.try_get_owner(&id));
+                assert_typed("get_metadata", self.client.try_get_metadata(&id));
+                assert_typed("traits", self.client.try_traits(&id));
+                // Infallible views: a trap fails the test on its own
+                let sale_price = if self.rng.flag() { u128::MAX } else { self.rng.below(10_000) as u128 };
+                self.client.get_royalty(&id, &sale_price);
+                let (owner, offset, limit) = (self.address(), self.rng.count(), self.rng.count());
+                self.client.tokens_of_owner(&owner, &offset, &limit);
+                self.client.balance_of(&owner);
+            }
+            _ => {
+                // Time and sequence only move forward, as on a real ledger; time
+                // jumps may be large. Sequence stays below the minimum entry TTL
+                // so storage isn't archived.
+                let timestamp = match self.rng.below(16) {
+                    0 => u64::MAX,
+                    _ => self.env.ledger().timestamp().saturating_add(self.rng.below(300)),
+                };
+                let step = self.rng.below(300) as u32;
+                let sequence = self.env.ledger().sequence().saturating_add(step).min(MAX_SEQUENCE);
+                self.env.ledger().with_mut(|li| {
+                    li.timestamp = timestamp;
+                    li.sequence_number = sequence;
+                });
+            }
+        }
+    }
+}
+
+#[test]
+fn fuzz_entrypoints_fail_with_typed_errors() {
+    for seed in 1..=ROUNDS {
+        let env = Env::default();
+        env.mock_all_auths();
+        env.cost_estimate().budget().reset_unlimited();
+
+        let contract_id = env.register(NftContract, ());
+        let client = NftContractClient::new(&env, &contract_id);
+        let admin = Address::generate(&env);
+        // Half the rounds run with a small supply cap
+        let max_supply = (seed % 2 == 0).then_some(4);
+        let name = String::from_str(&env, "Fuzz");
+        client.initialize(&admin, &name, &name, &String::from_str(&env, "ipfs://base/"), &max_supply);
+
+        let payment = env.register_stellar_asset_contract_v2(Address::generate(&env)).address();
+        let users = [Address::generate(&env), Address::generate(&env), Address::generate(&env)];
+        let outsider = Address::generate(&env);
+        let minter = StellarAssetClient::new(&env, &payment);
+        for holder in users.iter().chain([&outsider]) {
+            minter.mint(holder, &1_000);
+        }
+
+        // Claims and a public sale open from the start; steps may reconfigure them
+        client.set_claim_limits(&vec![&env, ClaimAllowance { claimant: users[1].clone(), limit: 2 }]);
+        client.set_claim_config(&ClaimConfig { start_time: 0, end_time: 100_000, merkle_root: None });
+        client.set_treasury(&admin);
+        let phase = SalePhase {
+            price: 10,
+            payment_token: payment.clone(),
+            max_per_wallet: 3,
+            start_time: 0,
+            end_time: 100_000,
+            allowlist_only: false,
+        };
+        client.set_sale_phases(&vec![&env, phase]);
+
+        let mut harness = Harness { env: &env, client, payment, users, outsider, rng: Rng(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15)) };
+        for _ in 0..STEPS {
+            harness.step();
+        }
+    }
+}
+
+#[test]
+fn fuzz_initialize_fails_with_typed_errors() {
+    let mut rng = Rng(0xDEAD_BEEF);
+    for _ in 0..16 {
+        let env = Env::default();
+        env.mock_all_auths();
+        let contract_id = env.register(NftContract, ());
+        let client = NftContractClient::new(&env, &contract_id);
+
+        let (admin, name) = (Address::generate(&env), String::from_str(&env, "Fuzz"));
+        let max_supply = match rng.below(3) {
+            0 => None,
+            1 => Some(0),
+            _ => Some(u64::MAX),
+        };
+        assert_typed("initialize", client.try_initialize(&admin, &name, &name, &name, &max_supply));
+        // A second call must be refused, not trap
+        assert_typed("initialize", client.try_initialize(&admin, &name, &name, &name, &max_supply));
+    }
+}
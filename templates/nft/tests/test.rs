@@ -1,13 +1,16 @@
 #![cfg(test)]
 
-use nft_contract::{NftContract, NftContractClient};
-use soroban_sdk::{testutils::Address as _, Address, Env, String};
+use nft_contract::{ClaimAllowance, ClaimConfig, NftContract, NftContractClient, NftError, SalePhase, TraitCategory, MAX_PAGE_SIZE};
+use soroban_sdk::{
+    symbol_short, testutils::{Address as _, Events, Ledger}, token, vec, xdr::ToXdr, Address, Bytes, BytesN, Env, FromVal,
+    IntoVal, String,
+};
 
 // --- Test Helpers ---
 
 fn setup<'a>(env: &'a Env) -> (NftContractClient<'a>, Address) {
     env.mock_all_auths();
-    let contract_id = env.register_contract(None, NftContract);
+    let contract_id = env.register(NftContract, ());
     let client = NftContractClient::new(env, &contract_id);
     let admin = Address::generate(env);
     client.initialize(
@@ -15,11 +18,12 @@ fn setup<'a>(env: &'a Env) -> (NftContractClient<'a>, Address) {
         &String::from_str(env, "My NFT"),
         &String::from_str(env, "MNFT"),
         &String::from_str(env, "ipfs://base/"),
+        &None,
     );
     (client, admin)
 }
 
-fn mint_token<'a>(env: &'a Env, client: &NftContractClient, to: &Address, uri: &str) -> u64 {
+fn mint_token(env: &Env, client: &NftContractClient, to: &Address, uri: &str) -> u64 {
     client.mint(to, &String::from_str(env, uri))
 }
 
@@ -29,7 +33,7 @@ fn mint_token<'a>(env: &'a Env, client: &NftContractClient, to: &Address, uri: &
 fn test_initialize_contract() {
     let env = Env::default();
     env.mock_all_auths();
-    let contract_id = env.register_contract(None, NftContract);
+    let contract_id = env.register(NftContract, ());
     let client = NftContractClient::new(&env, &contract_id);
     let admin = Address::generate(&env);
 
@@ -38,6 +42,7 @@ fn test_initialize_contract() {
         &String::from_str(&env, "Cool NFTs"),
         &String::from_str(&env, "CNFT"),
         &String::from_str(&env, "ipfs://base/"),
+        &None,
     );
 
     // Mint to verify contract is initialized (admin can mint)
@@ -47,7 +52,7 @@ fn test_initialize_contract() {
 }
 
 #[test]
-#[should_panic(expected = "Already initialized")]
+#[should_panic(expected = "Error(Contract, #1)")]
 fn test_initialize_already_initialized_panics() {
     let env = Env::default();
     let (client, admin) = setup(&env);
@@ -58,6 +63,7 @@ fn test_initialize_already_initialized_panics() {
         &String::from_str(&env, "Dup"),
         &String::from_str(&env, "DUP"),
         &String::from_str(&env, "ipfs://dup/"),
+        &None,
     );
 }
 
@@ -103,6 +109,41 @@ fn test_mint_total_supply_increments() {
     assert_eq!(id3, 3);
 }
 
+#[test]
+fn test_mint_stops_at_max_supply() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(NftContract, ());
+    let client = NftContractClient::new(&env, &contract_id);
+    client.initialize(
+        &Address::generate(&env),
+        &String::from_str(&env, "Limited"),
+        &String::from_str(&env, "LTD"),
+        &String::from_str(&env, "ipfs://base/"),
+        &Some(2),
+    );
+    let user = Address::generate(&env);
+
+    assert_eq!(client.max_supply(), Some(2));
+    assert_eq!(client.remaining_supply(), Some(2));
+    mint_token(&env, &client, &user, "ipfs://token/1");
+    mint_token(&env, &client, &user, "ipfs://token/2");
+    assert_eq!(client.remaining_supply(), Some(0));
+    assert_eq!(
+        client.try_mint(&user, &String::from_str(&env, "ipfs://token/3")),
+        Err(Ok(NftError::MaxSupplyReached))
+    );
+}
+
+#[test]
+fn test_uncapped_supply() {
+    let env = Env::default();
+    let (client, _) = setup(&env);
+
+    assert_eq!(client.max_supply(), None);
+    assert_eq!(client.remaining_supply(), None);
+}
+
 // --- Transfers ---
 
 #[test]
@@ -137,7 +178,7 @@ fn test_transfer_updates_owner() {
 }
 
 #[test]
-#[should_panic(expected = "Not the owner")]
+#[should_panic(expected = "Error(Contract, #4)")]
 fn test_transfer_by_non_owner_panics() {
     let env = Env::default();
     let (client, _) = setup(&env);
@@ -152,7 +193,7 @@ fn test_transfer_by_non_owner_panics() {
 }
 
 #[test]
-#[should_panic(expected = "Token does not exist")]
+#[should_panic(expected = "Error(Contract, #3)")]
 fn test_transfer_nonexistent_token_panics() {
     let env = Env::default();
     let (client, _) = setup(&env);
@@ -163,6 +204,387 @@ fn test_transfer_nonexistent_token_panics() {
     client.transfer(&alice, &bob, &999u64);
 }
 
+// --- Approvals ---
+
+#[test]
+fn test_approved_spender_can_transfer_from() {
+    let env = Env::default();
+    let (client, _) = setup(&env);
+    let alice = Address::generate(&env);
+    let market = Address::generate(&env);
+    let bob = Address::generate(&env);
+
+    let id = mint_token(&env, &client, &alice, "ipfs://token/1");
+    client.approve(&alice, &market, &id, &100);
+    assert_eq!(client.get_approved(&id), Some(market.clone()));
+
+    client.transfer_from(&market, &alice, &bob, &id);
+    assert_eq!(client.get_owner(&id), bob);
+    // The approval doesn't survive the transfer
+    assert_eq!(client.get_approved(&id), None);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #5)")]
+fn test_expired_approval_cannot_transfer_from() {
+    let env = Env::default();
+    let (client, _) = setup(&env);
+    let alice = Address::generate(&env);
+    let market = Address::generate(&env);
+
+    let id = mint_token(&env, &client, &alice, "ipfs://token/1");
+    client.approve(&alice, &market, &id, &100);
+    env.ledger().with_mut(|li| li.sequence_number = 101);
+    assert_eq!(client.get_approved(&id), None);
+
+    client.transfer_from(&market, &alice, &market, &id);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #4)")]
+fn test_approve_by_non_owner_panics() {
+    let env = Env::default();
+    let (client, _) = setup(&env);
+    let alice = Address::generate(&env);
+    let mallory = Address::generate(&env);
+
+    let id = mint_token(&env, &client, &alice, "ipfs://token/1");
+    client.approve(&mallory, &mallory, &id, &100);
+}
+
+#[test]
+fn test_operator_can_transfer_all_until_revoked() {
+    let env = Env::default();
+    let (client, _) = setup(&env);
+    let alice = Address::generate(&env);
+    let escrow = Address::generate(&env);
+    let bob = Address::generate(&env);
+
+    let id1 = mint_token(&env, &client, &alice, "ipfs://token/1");
+    let id2 = mint_token(&env, &client, &alice, "ipfs://token/2");
+    client.set_approval_for_all(&alice, &escrow, &true);
+    assert!(client.is_approved_for_all(&alice, &escrow));
+
+    client.transfer_from(&escrow, &alice, &bob, &id1);
+    assert_eq!(client.get_owner(&id1), bob);
+
+    client.set_approval_for_all(&alice, &escrow, &false);
+    assert!(!client.is_approved_for_all(&alice, &escrow));
+    assert_eq!(client.try_transfer_from(&escrow, &alice, &bob, &id2), Err(Ok(NftError::NotAuthorized)));
+}
+
+// --- Events ---
+
+#[test]
+fn test_mint_and_transfer_emit_events() {
+    let env = Env::default();
+    let (client, _) = setup(&env);
+    let alice = Address::generate(&env);
+    let bob = Address::generate(&env);
+
+    let id = mint_token(&env, &client, &alice, "ipfs://token/1");
+    let (_, topics, data) = env.events().all().last().unwrap();
+    assert_eq!(topics, (symbol_short!("mint"), alice.clone(), id).into_val(&env));
+    assert_eq!(String::from_val(&env, &data), String::from_str(&env, "ipfs://token/1"));
+
+    client.transfer(&alice, &bob, &id);
+    let (_, topics, _) = env.events().all().last().unwrap();
+    assert_eq!(topics, (symbol_short!("transfer"), alice.clone(), bob.clone(), id).into_val(&env));
+}
+
+#[test]
+fn test_approvals_emit_events() {
+    let env = Env::default();
+    let (client, _) = setup(&env);
+    let alice = Address::generate(&env);
+    let market = Address::generate(&env);
+
+    let id = mint_token(&env, &client, &alice, "ipfs://token/1");
+    client.approve(&alice, &market, &id, &100);
+    let (_, topics, data) = env.events().all().last().unwrap();
+    assert_eq!(topics, (symbol_short!("approve"), alice.clone(), id).into_val(&env));
+    assert_eq!(<(Address, u32)>::from_val(&env, &data), (market.clone(), 100));
+
+    client.set_approval_for_all(&alice, &market, &true);
+    let (_, topics, data) = env.events().all().last().unwrap();
+    assert_eq!(topics, (symbol_short!("appr_all"), alice.clone(), market.clone()).into_val(&env));
+    assert!(bool::from_val(&env, &data));
+}
+
+// --- Pausing ---
+
+#[test]
+fn test_pause_freezes_mint_and_transfers() {
+    let env = Env::default();
+    let (client, _) = setup(&env);
+    let alice = Address::generate(&env);
+    let bob = Address::generate(&env);
+    let id = mint_token(&env, &client, &alice, "ipfs://token/1");
+
+    client.pause();
+    assert!(client.is_paused());
+    assert_eq!(client.try_mint(&alice, &String::from_str(&env, "ipfs://token/2")), Err(Ok(NftError::Paused)));
+    assert_eq!(client.try_transfer(&alice, &bob, &id), Err(Ok(NftError::Paused)));
+    assert_eq!(client.try_transfer_from(&alice, &alice, &bob, &id), Err(Ok(NftError::Paused)));
+
+    client.unpause();
+    assert!(!client.is_paused());
+    client.transfer(&alice, &bob, &id);
+    assert_eq!(client.get_owner(&id), bob);
+}
+
+// --- Paid Mint Phases ---
+
+fn setup_sale<'a>(env: &'a Env, client: &NftContractClient) -> (token::Client<'a>, Address) {
+    let payment = env.register_stellar_asset_contract_v2(Address::generate(env)).address();
+    let treasury = Address::generate(env);
+    client.set_treasury(&treasury);
+    client.set_sale_phases(&vec![
+        env,
+        SalePhase {
+            price: 50,
+            payment_token: payment.clone(),
+            max_per_wallet: 1,
+            start_time: 100,
+            end_time: 200,
+            allowlist_only: true,
+        },
+        SalePhase {
+            price: 80,
+            payment_token: payment.clone(),
+            max_per_wallet: 3,
+            start_time: 200,
+            end_time: 300,
+            allowlist_only: false,
+        },
+    ]);
+    (token::Client::new(env, &payment), treasury)
+}
+
+#[test]
+fn test_buy_mint_presale_and_public() {
+    let env = Env::default();
+    let (client, _) = setup(&env);
+    let (payment, treasury) = setup_sale(&env, &client);
+    let alice = Address::generate(&env);
+    let bob = Address::generate(&env);
+    token::StellarAssetClient::new(&env, &payment.address).mint(&alice, &1000);
+    token::StellarAssetClient::new(&env, &payment.address).mint(&bob, &1000);
+    client.set_claim_limits(&vec![&env, ClaimAllowance { claimant: alice.clone(), limit: 1 }]);
+
+    assert_eq!(client.try_buy_mint(&alice, &1, &0, &vec![&env]), Err(Ok(NftError::SaleNotActive)));
+
+    // Presale: allowlist only, one each
+    env.ledger().with_mut(|li| li.timestamp = 100);
+    assert_eq!(client.active_phase(), Some(0));
+    assert_eq!(client.try_buy_mint(&bob, &1, &0, &vec![&env]), Err(Ok(NftError::NotEligible)));
+    let ids = client.buy_mint(&alice, &1, &0, &vec![&env]);
+    assert_eq!(client.get_owner(&ids.get(0).unwrap()), alice);
+    assert_eq!(client.try_buy_mint(&alice, &1, &0, &vec![&env]), Err(Ok(NftError::WalletLimitReached)));
+
+    // Public: anyone, three each
+    env.ledger().with_mut(|li| li.timestamp = 250);
+    let ids = client.buy_mint(&bob, &3, &0, &vec![&env]);
+    assert_eq!(ids.len(), 3);
+    assert_eq!(client.balance_of(&bob), 3);
+    assert_eq!(client.phase_minted(&1, &bob), 3);
+    assert_eq!(client.try_buy_mint(&bob, &1, &0, &vec![&env]), Err(Ok(NftError::WalletLimitReached)));
+
+    assert_eq!(payment.balance(&alice), 1000 - 50);
+    assert_eq!(payment.balance(&bob), 1000 - 3 * 80);
+    assert_eq!(payment.balance(&treasury), 50 + 3 * 80);
+}
+
+#[test]
+fn test_buy_mint_respects_max_supply() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(NftContract, ());
+    let client = NftContractClient::new(&env, &contract_id);
+    client.initialize(
+        &Address::generate(&env),
+        &String::from_str(&env, "Limited"),
+        &String::from_str(&env, "LTD"),
+        &String::from_str(&env, "ipfs://base/"),
+        &Some(2),
+    );
+    let (payment, _) = setup_sale(&env, &client);
+    let bob = Address::generate(&env);
+    token::StellarAssetClient::new(&env, &payment.address).mint(&bob, &1000);
+
+    env.ledger().with_mut(|li| li.timestamp = 200);
+    assert_eq!(client.try_buy_mint(&bob, &3, &0, &vec![&env]), Err(Ok(NftError::MaxSupplyReached)));
+    assert_eq!(client.try_buy_mint(&bob, &0, &0, &vec![&env]), Err(Ok(NftError::InvalidQuantity)));
+    client.buy_mint(&bob, &2, &0, &vec![&env]);
+    assert_eq!(payment.balance(&bob), 1000 - 2 * 80);
+}
+
+#[test]
+fn test_overlapping_sale_phases_rejected() {
+    let env = Env::default();
+    let (client, _) = setup(&env);
+    let payment = Address::generate(&env);
+    let phase = |start_time: u64, end_time: u64| SalePhase {
+        price: 10,
+        payment_token: payment.clone(),
+        max_per_wallet: 1,
+        start_time,
+        end_time,
+        allowlist_only: false,
+    };
+
+    let overlapping = vec![&env, phase(100, 200), phase(150, 300)];
+    assert_eq!(client.try_set_sale_phases(&overlapping), Err(Ok(NftError::InvalidSalePhases)));
+    let empty_window = vec![&env, phase(100, 100)];
+    assert_eq!(client.try_set_sale_phases(&empty_window), Err(Ok(NftError::InvalidSalePhases)));
+}
+
+// --- Soulbound and Burning ---
+
+#[test]
+fn test_soulbound_token_cannot_transfer() {
+    let env = Env::default();
+    let (client, _) = setup(&env);
+    let alice = Address::generate(&env);
+    let bob = Address::generate(&env);
+
+    let id = client.mint_soulbound(&alice, &String::from_str(&env, "ipfs://badge/1"));
+    assert!(client.is_soulbound(&id));
+    assert_eq!(client.try_transfer(&alice, &bob, &id), Err(Ok(NftError::Soulbound)));
+
+    client.set_approval_for_all(&alice, &bob, &true);
+    assert_eq!(client.try_transfer_from(&bob, &alice, &bob, &id), Err(Ok(NftError::Soulbound)));
+}
+
+#[test]
+fn test_holder_can_burn() {
+    let env = Env::default();
+    let (client, _) = setup(&env);
+    let alice = Address::generate(&env);
+
+    let badge = client.mint_soulbound(&alice, &String::from_str(&env, "ipfs://badge/1"));
+    let id = mint_token(&env, &client, &alice, "ipfs://token/2");
+    assert!(!client.is_soulbound(&id));
+
+    client.burn(&alice, &badge);
+    let (_, topics, _) = env.events().all().last().unwrap();
+    assert_eq!(topics, (symbol_short!("burn"), alice.clone(), badge).into_val(&env));
+    assert_eq!(client.try_get_owner(&badge), Err(Ok(NftError::TokenNotFound)));
+    assert!(!client.is_soulbound(&badge));
+    assert_eq!(client.tokens_of_owner(&alice, &0, &10), vec![&env, id]);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #4)")]
+fn test_burn_by_non_owner_panics() {
+    let env = Env::default();
+    let (client, _) = setup(&env);
+    let alice = Address::generate(&env);
+    let bob = Address::generate(&env);
+
+    let id = mint_token(&env, &client, &alice, "ipfs://token/1");
+    client.burn(&bob, &id);
+}
+
+#[test]
+fn test_buy_mint_presale_with_merkle_proof() {
+    let env = Env::default();
+    let (client, _) = setup(&env);
+    let (payment, _) = setup_sale(&env, &client);
+    let carol = Address::generate(&env);
+    token::StellarAssetClient::new(&env, &payment.address).mint(&carol, &1000);
+
+    // Single-leaf tree: the root is carol's leaf and the proof is empty
+    let mut preimage = carol.clone().to_xdr(&env);
+    preimage.extend_from_array(&1u32.to_be_bytes());
+    client.set_claim_config(&ClaimConfig {
+        start_time: 100,
+        end_time: 200,
+        merkle_root: Some(env.crypto().sha256(&preimage).into()),
+    });
+    env.ledger().with_mut(|li| li.timestamp = 100);
+
+    assert_eq!(client.try_buy_mint(&carol, &1, &2, &vec![&env]), Err(Ok(NftError::NotEligible)));
+    let ids = client.buy_mint(&carol, &1, &1, &vec![&env]);
+    assert_eq!(client.get_owner(&ids.get(0).unwrap()), carol);
+    // Buying in the presale doesn't use up the claim allowance
+    assert_eq!(client.claimed(&carol), 0);
+}
+
+// --- Claims ---
+
+fn claim_window(env: &Env, client: &NftContractClient, merkle_root: Option<BytesN<32>>) {
+    client.set_claim_config(&ClaimConfig { start_time: 100, end_time: 200, merkle_root });
+    env.ledger().with_mut(|li| li.timestamp = 100);
+}
+
+#[test]
+fn test_direct_allowlist_claim() {
+    let env = Env::default();
+    let (client, _) = setup(&env);
+    let alice = Address::generate(&env);
+    claim_window(&env, &client, None);
+    client.set_claim_limits(&vec![&env, ClaimAllowance { claimant: alice.clone(), limit: 2 }]);
+    assert_eq!(client.claim_limit(&alice), 2);
+
+    let id = client.claim(&alice, &0, &vec![&env]);
+    assert_eq!(client.get_owner(&id), alice);
+    assert_eq!(client.get_metadata(&id).uri, String::from_str(&env, "ipfs://base/"));
+    client.claim(&alice, &0, &vec![&env]);
+    assert_eq!(client.claimed(&alice), 2);
+    assert_eq!(client.try_claim(&alice, &0, &vec![&env]), Err(Ok(NftError::ClaimLimitReached)));
+
+    let outsider = Address::generate(&env);
+    assert_eq!(client.try_claim(&outsider, &5, &vec![&env]), Err(Ok(NftError::NotEligible)));
+}
+
+#[test]
+fn test_merkle_claim() {
+    let env = Env::default();
+    let (client, _) = setup(&env);
+    let alice = Address::generate(&env);
+    let bob = Address::generate(&env);
+
+    // Two-leaf tree: root = sha256(sorted(leaf_alice, leaf_bob))
+    let leaf = |addr: &Address, limit: u32| -> BytesN<32> {
+        let mut preimage = addr.clone().to_xdr(&env);
+        preimage.extend_from_array(&limit.to_be_bytes());
+        env.crypto().sha256(&preimage).into()
+    };
+    let (a, b) = (leaf(&alice, 1), leaf(&bob, 3));
+    let (lo, hi) = if a <= b { (a.clone(), b.clone()) } else { (b.clone(), a.clone()) };
+    let mut pair = Bytes::from_array(&env, &lo.to_array());
+    pair.extend_from_array(&hi.to_array());
+    claim_window(&env, &client, Some(env.crypto().sha256(&pair).into()));
+
+    // Claiming a bigger limit than the leaf commits to fails the proof
+    assert_eq!(client.try_claim(&alice, &3, &vec![&env, b.clone()]), Err(Ok(NftError::NotEligible)));
+    client.claim(&alice, &1, &vec![&env, b]);
+    assert_eq!(client.try_claim(&alice, &1, &vec![&env, leaf(&bob, 3)]), Err(Ok(NftError::ClaimLimitReached)));
+    client.claim(&bob, &3, &vec![&env, a]);
+    assert_eq!(client.balance_of(&bob), 1);
+}
+
+#[test]
+fn test_claim_outside_window() {
+    let env = Env::default();
+    let (client, _) = setup(&env);
+    let alice = Address::generate(&env);
+    assert_eq!(client.try_claim(&alice, &0, &vec![&env]), Err(Ok(NftError::ClaimNotActive)));
+
+    claim_window(&env, &client, None);
+    client.set_claim_limits(&vec![&env, ClaimAllowance { claimant: alice.clone(), limit: 1 }]);
+    env.ledger().with_mut(|li| li.timestamp = 99);
+    assert_eq!(client.try_claim(&alice, &0, &vec![&env]), Err(Ok(NftError::ClaimNotActive)));
+    env.ledger().with_mut(|li| li.timestamp = 200);
+    assert_eq!(client.try_claim(&alice, &0, &vec![&env]), Err(Ok(NftError::ClaimNotActive)));
+
+    assert_eq!(
+        client.try_set_claim_config(&ClaimConfig { start_time: 200, end_time: 200, merkle_root: None }),
+        Err(Ok(NftError::InvalidClaimWindow))
+    );
+}
+
 // --- Metadata ---
 
 #[test]
@@ -181,7 +603,7 @@ fn test_get_metadata_correct_uri() {
 }
 
 #[test]
-#[should_panic(expected = "Token does not exist")]
+#[should_panic(expected = "Error(Contract, #3)")]
 fn test_get_metadata_nonexistent_token_panics() {
     let env = Env::default();
     let (client, _) = setup(&env);
@@ -205,6 +627,71 @@ fn test_metadata_after_transfer_unchanged() {
     assert_eq!(meta.uri, String::from_str(&env, uri));
 }
 
+#[test]
+fn test_admin_sets_token_uri() {
+    let env = Env::default();
+    let (client, admin) = setup(&env);
+    let user = Address::generate(&env);
+    let id = mint_token(&env, &client, &user, "ipfs://token/1");
+
+    let uri = String::from_str(&env, "ipfs://token/1-fixed");
+    client.set_token_uri(&admin, &id, &uri);
+    let (_, topics, data) = env.events().all().last().unwrap();
+    assert_eq!(topics, (symbol_short!("uri"), id).into_val(&env));
+    assert_eq!(String::from_val(&env, &data), uri);
+    assert_eq!(client.get_metadata(&id).uri, uri);
+
+    // Owners need the collection policy to allow it
+    let owner_uri = String::from_str(&env, "ipfs://token/1-owner");
+    assert_eq!(client.try_set_token_uri(&user, &id, &owner_uri), Err(Ok(NftError::NotAuthorized)));
+    client.set_owner_uri_updates(&true);
+    client.set_token_uri(&user, &id, &owner_uri);
+    assert_eq!(client.get_metadata(&id).uri, owner_uri);
+
+    let stranger = Address::generate(&env);
+    assert_eq!(client.try_set_token_uri(&stranger, &id, &uri), Err(Ok(NftError::NotAuthorized)));
+}
+
+#[test]
+fn test_frozen_metadata_rejects_uri_updates() {
+    let env = Env::default();
+    let (client, admin) = setup(&env);
+    let user = Address::generate(&env);
+    let id1 = mint_token(&env, &client, &user, "ipfs://token/1");
+    let id2 = mint_token(&env, &client, &user, "ipfs://token/2");
+    let uri = String::from_str(&env, "ipfs://changed");
+
+    client.freeze_metadata(&id1);
+    assert_eq!(client.try_set_token_uri(&admin, &id1, &uri), Err(Ok(NftError::MetadataFrozen)));
+    client.set_token_uri(&admin, &id2, &uri);
+
+    client.freeze_all_metadata();
+    assert_eq!(client.try_set_token_uri(&admin, &id2, &uri), Err(Ok(NftError::MetadataFrozen)));
+    assert_eq!(client.get_metadata(&id1).uri, String::from_str(&env, "ipfs://token/1"));
+}
+
+#[test]
+fn test_freeze_metadata() {
+    let env = Env::default();
+    let (client, _) = setup(&env);
+    let user = Address::generate(&env);
+    let id1 = mint_token(&env, &client, &user, "ipfs://token/1");
+    let id2 = mint_token(&env, &client, &user, "ipfs://token/2");
+
+    client.freeze_metadata(&id1);
+    assert!(client.is_metadata_frozen(&id1));
+    assert!(!client.is_metadata_frozen(&id2));
+    assert_eq!(client.try_freeze_metadata(&id1), Err(Ok(NftError::MetadataFrozen)));
+    assert_eq!(client.try_freeze_metadata(&999), Err(Ok(NftError::TokenNotFound)));
+
+    // Freezing everything covers tokens minted afterwards too
+    client.freeze_all_metadata();
+    let id3 = mint_token(&env, &client, &user, "ipfs://token/3");
+    assert!(client.is_metadata_frozen(&id2));
+    assert!(client.is_metadata_frozen(&id3));
+    assert_eq!(client.try_freeze_all_metadata(), Err(Ok(NftError::MetadataFrozen)));
+}
+
 // --- Royalties ---
 
 #[test]
@@ -247,7 +734,7 @@ fn test_token_royalty_overrides_global() {
 }
 
 #[test]
-#[should_panic(expected = "Royalty cannot exceed 100%")]
+#[should_panic(expected = "Error(Contract, #6)")]
 fn test_royalty_exceeds_100_percent_panics() {
     let env = Env::default();
     let (client, _) = setup(&env);
@@ -284,12 +771,203 @@ fn test_royalty_with_zero_sale_price() {
     assert_eq!(amount, 0u128); // 5% of 0 = 0
 }
 
+#[test]
+fn test_royalty_with_max_sale_price() {
+    let env = Env::default();
+    let (client, _) = setup(&env);
+    let user = Address::generate(&env);
+    let receiver = Address::generate(&env);
+
+    let id = mint_token(&env, &client, &user, "ipfs://token/1");
+    client.set_royalty(&receiver, &10000u32);
+    let (_, amount) = client.get_royalty(&id, &u128::MAX);
+    assert_eq!(amount, u128::MAX);
+
+    client.set_royalty(&receiver, &500u32);
+    let (_, amount) = client.get_royalty(&id, &u128::MAX);
+    assert_eq!(amount, u128::MAX / 20);
+}
+
 // --- Ownership ---
 
 #[test]
-#[should_panic(expected = "Token does not exist")]
+#[should_panic(expected = "Error(Contract, #3)")]
 fn test_get_owner_nonexistent_token_panics() {
     let env = Env::default();
     let (client, _) = setup(&env);
     client.get_owner(&999u64);
 }
+
+// --- Owner Enumeration ---
+
+#[test]
+fn test_balance_and_tokens_of_owner() {
+    let env = Env::default();
+    let (client, _) = setup(&env);
+    let alice = Address::generate(&env);
+    let bob = Address::generate(&env);
+
+    let id1 = mint_token(&env, &client, &alice, "ipfs://token/1");
+    let id2 = mint_token(&env, &client, &alice, "ipfs://token/2");
+    let id3 = mint_token(&env, &client, &alice, "ipfs://token/3");
+    assert_eq!(client.balance_of(&alice), 3);
+    assert_eq!(client.tokens_of_owner(&alice, &0, &10), vec![&env, id1, id2, id3]);
+    assert_eq!(client.tokens_of_owner(&alice, &1, &1), vec![&env, id2]);
+    assert_eq!(client.tokens_of_owner(&alice, &5, &10), vec![&env]);
+
+    // The last token fills the transferred one's slot
+    client.transfer(&alice, &bob, &id1);
+    assert_eq!(client.balance_of(&alice), 2);
+    assert_eq!(client.tokens_of_owner(&alice, &0, &10), vec![&env, id3, id2]);
+    assert_eq!(client.balance_of(&bob), 1);
+    assert_eq!(client.tokens_of_owner(&bob, &0, &10), vec![&env, id1]);
+}
+
+#[test]
+fn test_owner_index_tracks_moved_slots() {
+    let env = Env::default();
+    let (client, _) = setup(&env);
+    let alice = Address::generate(&env);
+    let bob = Address::generate(&env);
+
+    let id1 = mint_token(&env, &client, &alice, "ipfs://token/1");
+    let id2 = mint_token(&env, &client, &alice, "ipfs://token/2");
+    let id3 = mint_token(&env, &client, &alice, "ipfs://token/3");
+
+    // id3 moves into id1's slot, and must still be found there when it leaves
+    client.burn(&alice, &id1);
+    client.transfer(&alice, &bob, &id3);
+    assert_eq!(client.tokens_of_owner(&alice, &0, &10), vec![&env, id2]);
+    client.transfer(&alice, &bob, &id2);
+    assert_eq!(client.balance_of(&alice), 0);
+    assert_eq!(client.tokens_of_owner(&bob, &0, &10), vec![&env, id3, id2]);
+}
+
+#[test]
+fn test_tokens_of_owner_caps_page_size() {
+    let env = Env::default();
+    let (client, _) = setup(&env);
+    let alice = Address::generate(&env);
+    for _ in 0..MAX_PAGE_SIZE + 1 {
+        mint_token(&env, &client, &alice, "ipfs://token");
+    }
+
+    assert_eq!(client.tokens_of_owner(&alice, &0, &u32::MAX).len(), MAX_PAGE_SIZE);
+    assert_eq!(client.tokens_of_owner(&alice, &MAX_PAGE_SIZE, &u32::MAX).len(), 1);
+}
+
+#[test]
+fn test_balance_of_unknown_owner_is_zero() {
+    let env = Env::default();
+    let (client, _) = setup(&env);
+    let nobody = Address::generate(&env);
+
+    assert_eq!(client.balance_of(&nobody), 0);
+    assert_eq!(client.tokens_of_owner(&nobody, &0, &10).len(), 0);
+}
+
+// --- Generative Traits ---
+
+fn trait_categories(env: &Env) -> soroban_sdk::Vec<TraitCategory> {
+    vec![
+        env,
+        TraitCategory {
+            name: String::from_str(env, "Background"),
+            values: vec![env, String::from_str(env, "Plain"), String::from_str(env, "Cosmic")],
+            weights: vec![env, 1, 1],
+        },
+        TraitCategory {
+            name: String::from_str(env, "Eyes"),
+            values: vec![env, String::from_str(env, "Normal"), String::from_str(env, "Laser")],
+            weights: vec![env, 0, 1],
+        },
+    ]
+}
+
+#[test]
+fn test_traits_are_deterministic_and_verifiable() {
+    let env = Env::default();
+    let (client, _) = setup(&env);
+    let seed = BytesN::from_array(&env, &[9u8; 32]);
+    client.commit_traits(&seed, &trait_categories(&env));
+    let user = Address::generate(&env);
+    let id = mint_token(&env, &client, &user, "ipfs://1");
+
+    let traits = client.traits(&id);
+    assert_eq!(traits, client.traits(&id));
+    assert_eq!(traits.len(), 2);
+    assert_eq!(traits.get(0).unwrap().name, String::from_str(&env, "Background"));
+
+    // Recompute the Background roll off-chain style
+    let mut preimage = Bytes::from_array(&env, &[9u8; 32]);
+    preimage.extend_from_array(&id.to_be_bytes());
+    preimage.extend_from_array(&0u32.to_be_bytes());
+    let digest = env.crypto().sha256(&preimage).to_array();
+    let roll = u32::from_be_bytes([digest[0], digest[1], digest[2], digest[3]]) % 2;
+    let expected = if roll == 0 { "Plain" } else { "Cosmic" };
+    assert_eq!(traits.get(0).unwrap().value, String::from_str(&env, expected));
+    assert_eq!(client.get_trait_seed(), Some(seed));
+}
+
+#[test]
+fn test_traits_respect_weights() {
+    let env = Env::default();
+    let (client, _) = setup(&env);
+    client.commit_traits(&BytesN::from_array(&env, &[3u8; 32]), &trait_categories(&env));
+    let user = Address::generate(&env);
+
+    let mut cosmic = 0;
+    for _ in 0..20 {
+        let id = mint_token(&env, &client, &user, "ipfs://x");
+        let traits = client.traits(&id);
+        // A zero-weight value is never rolled
+        assert_eq!(traits.get(1).unwrap().value, String::from_str(&env, "Laser"));
+        if traits.get(0).unwrap().value == String::from_str(&env, "Cosmic") {
+            cosmic += 1;
+        }
+    }
+    assert!(cosmic > 0 && cosmic < 20);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #8)")]
+fn test_commit_traits_after_mint_panics() {
+    let env = Env::default();
+    let (client, _) = setup(&env);
+    mint_token(&env, &client, &Address::generate(&env), "ipfs://1");
+    client.commit_traits(&BytesN::from_array(&env, &[1u8; 32]), &trait_categories(&env));
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #7)")]
+fn test_commit_traits_twice_panics() {
+    let env = Env::default();
+    let (client, _) = setup(&env);
+    client.commit_traits(&BytesN::from_array(&env, &[1u8; 32]), &trait_categories(&env));
+    client.commit_traits(&BytesN::from_array(&env, &[2u8; 32]), &trait_categories(&env));
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #9)")]
+fn test_commit_traits_mismatched_weights_panics() {
+    let env = Env::default();
+    let (client, _) = setup(&env);
+    let categories = vec![
+        &env,
+        TraitCategory {
+            name: String::from_str(&env, "Hat"),
+            values: vec![&env, String::from_str(&env, "Cap")],
+            weights: vec![&env, 1, 2],
+        },
+    ];
+    client.commit_traits(&BytesN::from_array(&env, &[1u8; 32]), &categories);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #10)")]
+fn test_traits_without_commit_panics() {
+    let env = Env::default();
+    let (client, _) = setup(&env);
+    let id = mint_token(&env, &client, &Address::generate(&env), "ipfs://1");
+    client.traits(&id);
+}
@@ -1,16 +1,133 @@
 #![no_std]
-use soroban_sdk::{contract, contractimpl, contracttype, token, Address, Env, log};
+use soroban_sdk::{contract, contracterror, contractimpl, contracttype, symbol_short, token, xdr::ToXdr, Address, Bytes, BytesN, Env, Vec};
+
+pub mod math;
+use math::mul_div;
+
+/// Slash amounts are given in basis points of the position (10_000 = 100%)
+const MAX_SLASH_BPS: u32 = 10_000;
+
+/// Reward multipliers are given in basis points (10_000 = 1x)
+const BASE_MULTIPLIER_BPS: u32 = 10_000;
+
+/// Reward rates are fixed-point with 18 decimals: a rate of `RATE_SCALE`
+/// pays 1 reward unit per staked unit per second
+pub const RATE_SCALE: i128 = 1_000_000_000_000_000_000;
+
+/// Seconds in a 365-day year, for APR figures
+const SECONDS_PER_YEAR: i128 = 31_536_000;
+
+/// Assumed seconds per ledger when converting lock times to TTLs
+pub const LEDGER_SECONDS: u64 = 5;
+
+/// Ledgers a position outlives its lock end, and config its last use (~30 days)
+pub const POSITION_TTL_BUFFER: u32 = 518_400;
+
+/// Most lock tiers the admin may configure
+const MAX_TIERS: u32 = 10;
+
+/// Vesting shares are given in basis points of a claim (10_000 = 100%)
+const VESTING_BPS: u32 = 10_000;
+
+/// Referral bonuses are given in basis points of the referee's rewards
+const REFERRAL_BPS: u32 = 10_000;
+
+/// Most upcoming epochs one `set_schedule` call may add
+const MAX_EPOCHS: u32 = 20;
+
+/// Most users one `claim_for_many` call may settle
+const MAX_BATCH_CLAIMS: u32 = 50;
+
+/// Errors returned by the contract
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[repr(u32)]
+pub enum StakingError {
+    AlreadyInitialized = 1,
+    NotInitialized = 2,
+    Paused = 3,
+    Locked = 4,
+    InsufficientStake = 5,   // Unstake amount is zero or above the position
+    NoRewards = 6,
+    Overflow = 7,
+    InvalidAmount = 8,
+    NegativeRate = 9,
+    NoPosition = 10,
+    BudgetExhausted = 11,
+    TooManyEpochs = 12,
+    InvalidSchedule = 13,    // Epochs not in the future or not ascending
+    TooManyTiers = 14,
+    InvalidTier = 15,        // Zero multiplier or durations not ascending
+    NoEmergency = 16,
+    EmergencyActive = 17,    // Rewards are frozen
+    NotSlasher = 18,
+    InvalidSlashBps = 19,
+    EvidenceUsed = 20,
+    NothingToSlash = 21,
+    NoSlashDestination = 22,
+    NotPendingAdmin = 23,
+    NotOperator = 24,        // Caller is neither the operator nor the admin
+    UserCapExceeded = 25,
+    TotalCapExceeded = 26,
+    NotAllowed = 27,         // Allowlist is on and the user isn't on it
+    InvalidProof = 28,
+    InvalidVesting = 29,     // instant_bps above 10_000 or zero duration
+    NothingVested = 30,
+    PositionExists = 31,     // Transfer recipient already has a position
+    NotPositionOwner = 32,   // Caller neither owns nor is approved for the position
+    LockNotExtended = 33,    // The new lock wouldn't end later than the current one
+    InvalidReferrer = 34,    // Self-referral, or the user already has another referrer
+    InvalidReferralBps = 35,
+    CooldownRequired = 36,   // Cooldown mode is on; use request_unstake
+    CooldownActive = 37,     // The unstake request isn't withdrawable yet
+    NoUnstakeRequest = 38,
+    BatchTooLarge = 39,
+}
 
 /// Storage keys for the contract
 #[contracttype]
 #[derive(Clone)]
 pub enum DataKey {
     Admin,
+    PendingAdmin,            // Proposed by transfer_admin until accepted
+    Operator,                // Runs routine actions: rates, schedule, pause
     Token,
-    RewardRate,   // Reward units per staked unit per second (scaled)
-    IsPaused,
+    ScheduleLen,  // Number of emission epochs, past and upcoming
+    ScheduleEpoch(u32), // EpochRecord by index, ascending by start_time
+    Pause,        // PauseFlags: which user actions are halted
     Position(Address),
     TotalStaked,
+    Slasher,
+    SlashDestination,
+    SlashCount,
+    Slash(u32),              // Slash record by sequential ID
+    SlashEvidence(BytesN<32>), // Evidence hashes already acted on
+    PositionSlashes(Address),  // Vec<u32>: slash IDs against a user's position
+    Tiers,                   // Vec<LockTier>, ascending by min_duration
+    Emergency,               // Set by the admin to allow emergency_withdraw
+    StakeCaps,               // StakeCaps enforced on stake
+    AllowlistOn,             // When true, only allowed addresses can stake
+    Allowed(Address),        // Added by the admin or by a Merkle proof
+    AllowlistRoot,           // Merkle root of sha256(xdr(address)) leaves
+    RewardVesting,           // RewardVesting applied to claims, if set
+    Vesting(Address),        // VestingEntry: a user's unfinished vesting
+    TotalVesting,            // Rewards owed to vesting entries, reserved from the budget
+    VoteWeighting,           // When true, votes carry the lock multiplier until lock end
+    VoteCheckpointCount(Address), // Number of checkpoints of a user's position
+    VoteCheckpoint(Address, u32), // A user's position from the checkpoint's timestamp on
+    TotalCheckpointCount,    // Number of TotalStaked checkpoints
+    TotalCheckpoint(u32),    // TotalStaked from the checkpoint's timestamp on
+    NextPositionId,          // Next position token ID to mint
+    PositionId(Address),     // Position token ID held by an address
+    PositionOwner(u64),      // Holder of a position token ID
+    PositionApproval(u64),   // Address approved to transfer a position token
+    ReferralBps,             // Bonus referrers earn on their referees' rewards
+    Referrer(Address),       // Who referred a user, set on their first referred stake
+    ReferralStats(Address),  // ReferralStats of a referrer
+    TotalReferral,           // Unclaimed referral bonuses, reserved from the budget
+    Cooldown,                // Exit notice in seconds; unstaking is two-step when set
+    UnstakeRequest(Address), // UnstakeRequest awaiting withdraw
+    TotalPendingUnstake,     // Requested but unwithdrawn stake, reserved from the budget
 }
 
 /// User's staking data
@@ -21,6 +138,112 @@ pub struct StakingPosition {
     pub lock_end_time: u64,
     pub last_accrual_time: u64,
     pub accumulated_rewards: i128,
+    pub multiplier_bps: u32,      // Reward multiplier earned until lock_end_time
+    pub auto_compound: bool,      // Restake rewards whenever they accrue
+}
+
+/// Emission epoch: from `start_time` until the next epoch starts, rewards
+/// accrue at `rate` units per staked unit per second (scaled)
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Epoch {
+    pub start_time: u64,
+    pub rate: i128,
+}
+
+/// An epoch as stored. `reward_per_token` is the reward per staked unit
+/// (scaled) emitted by all earlier epochs, so accrual looks up the epochs it
+/// spans instead of walking the whole schedule.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct EpochRecord {
+    pub start_time: u64,
+    pub rate: i128,
+    pub reward_per_token: i128,
+}
+
+/// Reward multiplier for locks of at least `min_duration` seconds
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct LockTier {
+    pub min_duration: u64,
+    pub multiplier_bps: u32,
+}
+
+/// Optional limits on new stake; `None` means uncapped
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct StakeCaps {
+    pub max_stake_per_user: Option<i128>,
+    pub max_total_staked: Option<i128>,
+}
+
+/// How claims vest: `instant_bps` of each claim is paid at once, the rest
+/// unlocks linearly over `duration` seconds
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct RewardVesting {
+    pub instant_bps: u32,
+    pub duration: u64,
+}
+
+/// A user's vesting rewards, unlocking linearly from `start_time` to `end_time`.
+/// Each claim folds into it; see `start_vesting`.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct VestingEntry {
+    pub total: i128,
+    pub released: i128,
+    pub start_time: u64,
+    pub end_time: u64,
+}
+
+/// Snapshot of staked amount from `timestamp` until the next checkpoint.
+/// Total-stake checkpoints leave the multiplier at 1x and `lock_end_time` at 0.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Checkpoint {
+    pub timestamp: u64,
+    pub amount: i128,
+    pub multiplier_bps: u32,
+    pub lock_end_time: u64,
+}
+
+/// Independent switches for user actions; `true` halts that action
+#[contracttype]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PauseFlags {
+    pub staking: bool,   // stake, extend_lock, relock, compound
+    pub unstaking: bool, // unstake, request_unstake, withdraw
+    pub claiming: bool,  // claim_rewards, claim_vested, claim_referral_rewards, compound
+}
+
+/// Stake taken out of a position and waiting out the cooldown
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct UnstakeRequest {
+    pub amount: i128,
+    pub available_at: u64,
+}
+
+/// A referrer's running totals
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReferralStats {
+    pub referees: u32,
+    pub total_earned: i128,
+    pub unclaimed: i128,
+}
+
+/// On-chain record of a slash
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct SlashRecord {
+    pub user: Address,
+    pub amount: i128,
+    pub evidence_hash: BytesN<32>,
+    pub destination: Address,
+    pub timestamp: u64,
 }
 
 #[contract]
@@ -29,144 +252,1092 @@ pub struct StakingContract;
 #[contractimpl]
 impl StakingContract {
     /// Initialize the contract with an admin, staking token, and base reward rate
-    pub fn initialize(env: Env, admin: Address, token: Address, reward_rate: i128) {
-        if env.storage().persistent().has(&DataKey::Admin) {
-            panic!("Contract already initialized");
+    /// The rate opens the first emission epoch; see `set_schedule` for later ones.
+    pub fn initialize(env: Env, admin: Address, token: Address, reward_rate: i128) -> Result<(), StakingError> {
+        Self::extend_instance_ttl(&env);
+        if env.storage().instance().has(&DataKey::Admin) {
+            return Err(StakingError::AlreadyInitialized);
         }
-        env.storage().persistent().set(&DataKey::Admin, &admin);
-        env.storage().persistent().set(&DataKey::Token, &token);
-        env.storage().persistent().set(&DataKey::RewardRate, &reward_rate);
-        env.storage().persistent().set(&DataKey::IsPaused, &false);
-        env.storage().persistent().set(&DataKey::TotalStaked, &0i128);
+        if reward_rate < 0 {
+            return Err(StakingError::NegativeRate);
+        }
+        let first = Epoch { start_time: env.ledger().timestamp(), rate: reward_rate };
+        env.storage().instance().set(&DataKey::Admin, &admin);
+        env.storage().instance().set(&DataKey::Token, &token);
+        Self::push_epoch(&env, first)?;
+        env.storage().instance().set(&DataKey::Pause, &PauseFlags::default());
+        env.storage().instance().set(&DataKey::TotalStaked, &0i128);
+        Ok(())
     }
 
     /// Stake assets for a specific duration (in seconds)
-    /// Reward multipliers can be applied based on length (handled by caller or via specific tiers)
-    pub fn stake(env: Env, user: Address, amount: i128, lock_duration: u64) {
+    /// The whole position earns the multiplier of the highest tier its remaining
+    /// lock qualifies for, until the lock ends; 1x after that or without tiers.
+    pub fn stake(env: Env, user: Address, amount: i128, lock_duration: u64) -> Result<(), StakingError> {
+        Self::extend_instance_ttl(&env);
         user.require_auth();
-        Self::ensure_not_paused(&env);
+        Self::stake_from(&env, &user, &user, amount, lock_duration)
+    }
 
-        if amount <= 0 {
-            panic!("Staking amount must be greater than zero");
+    /// Stake `amount` from `sponsor` into `beneficiary`'s position, for
+    /// launchpads and payroll. The beneficiary owns the stake. A sponsor may
+    /// top up within the beneficiary's current lock on their own; opening a
+    /// lock, pushing one back, or a top-up that would lower the position's
+    /// multiplier (as restaking late in a lock does) also needs the
+    /// beneficiary's auth.
+    pub fn stake_for(env: Env, sponsor: Address, beneficiary: Address, amount: i128, lock_duration: u64) -> Result<(), StakingError> {
+        Self::extend_instance_ttl(&env);
+        sponsor.require_auth();
+        let now = env.ledger().timestamp();
+        let lock_end = now.checked_add(lock_duration).ok_or(StakingError::Overflow)?;
+        let (current_lock_end, current_multiplier) = Self::get_position(env.clone(), beneficiary.clone())
+            .filter(|position| position.amount > 0)
+            .map(|position| (position.lock_end_time, position.multiplier_bps))
+            .unwrap_or((0, BASE_MULTIPLIER_BPS));
+        let moves_lock = lock_duration > 0 && lock_end > current_lock_end;
+        let new_multiplier = Self::tier_multiplier(&env, lock_end.max(current_lock_end).saturating_sub(now));
+        // A second require_auth for the same address would trap
+        if (moves_lock || new_multiplier < current_multiplier) && beneficiary != sponsor {
+            beneficiary.require_auth();
         }
+        Self::stake_from(&env, &sponsor, &beneficiary, amount, lock_duration)?;
+        env.events().publish((symbol_short!("sponsor"), sponsor), (beneficiary, amount));
+        Ok(())
+    }
 
-        // Transfer tokens from user to contract
-        let token_addr = Self::get_token(&env);
-        let client = token::Client::new(&env, &token_addr);
-        client.transfer(&user, &env.current_contract_address(), &amount);
+    /// Pay the pending rewards of each listed user to that user, as
+    /// `claim_rewards` would. Anyone may call. Users with nothing to claim are
+    /// skipped, and so is everyone left once the reward budget runs out.
+    /// Returns the total paid.
+    pub fn claim_for_many(env: Env, users: Vec<Address>) -> Result<i128, StakingError> {
+        Self::extend_instance_ttl(&env);
+        if users.len() > MAX_BATCH_CLAIMS {
+            return Err(StakingError::BatchTooLarge);
+        }
+        Self::ensure_no_emergency(&env)?;
+        Self::ensure_not_paused(&env, |flags| flags.claiming)?;
+
+        let mut total: i128 = 0;
+        for user in users.iter() {
+            match Self::claim(&env, &user) {
+                Ok(paid) => total += paid,
+                Err(StakingError::NoPosition | StakingError::NoRewards) => continue,
+                Err(StakingError::BudgetExhausted) => break,
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(total)
+    }
+
+    /// Move `amount` from `from` into `user`'s position
+    fn stake_from(env: &Env, from: &Address, user: &Address, amount: i128, lock_duration: u64) -> Result<(), StakingError> {
+        Self::ensure_not_paused(env, |flags| flags.staking)?;
+
+        if amount <= 0 {
+            return Err(StakingError::InvalidAmount);
+        }
+        if !Self::is_allowed(env.clone(), user.clone()) {
+            return Err(StakingError::NotAllowed);
+        }
 
         let mut position = Self::get_position(env.clone(), user.clone()).unwrap_or(StakingPosition {
             amount: 0,
             lock_end_time: 0,
             last_accrual_time: env.ledger().timestamp(),
             accumulated_rewards: 0,
+            multiplier_bps: BASE_MULTIPLIER_BPS,
+            auto_compound: false,
         });
 
-        // Accrue pending rewards before adding new stake
+        let caps = Self::get_stake_caps(env.clone());
+        let total_staked = env.storage().instance().get::<_, i128>(&DataKey::TotalStaked).unwrap_or(0);
+        if caps.max_stake_per_user.is_some_and(|max| position.amount.saturating_add(amount) > max) {
+            return Err(StakingError::UserCapExceeded);
+        }
+        if caps.max_total_staked.is_some_and(|max| total_staked.saturating_add(amount) > max) {
+            return Err(StakingError::TotalCapExceeded);
+        }
+
+        // Accrue pending rewards before adding new stake, while the reward
+        // budget doesn't yet count the incoming tokens
         if position.amount > 0 {
-            position.accumulated_rewards = Self::calculate_pending_rewards(&env, &position);
+            Self::accrue(env, user, &mut position)?;
         }
-        
-        position.amount += amount;
+
+        // Transfer tokens from the staker or sponsor to contract
+        let token_addr = Self::get_token(env)?;
+        let client = token::Client::new(env, &token_addr);
+        client.transfer(from, &env.current_contract_address(), &amount);
+
+        position.amount = position.amount.checked_add(amount).ok_or(StakingError::Overflow)?;
         position.last_accrual_time = env.ledger().timestamp();
         
         // Update lock time if new lock is longer than existing
-        let new_lock_end = env.ledger().timestamp().checked_add(lock_duration).expect("Time overflow");
+        let new_lock_end = env.ledger().timestamp().checked_add(lock_duration).ok_or(StakingError::Overflow)?;
         if new_lock_end > position.lock_end_time {
             position.lock_end_time = new_lock_end;
         }
+        position.multiplier_bps = Self::tier_multiplier(env, position.lock_end_time - env.ledger().timestamp());
 
         // Update global state
-        let total_staked = env.storage().persistent().get::<_, i128>(&DataKey::TotalStaked).unwrap_or(0);
-        env.storage().persistent().set(&DataKey::TotalStaked, &(total_staked + amount));
+        Self::adjust_total_staked(env, amount);
         
-        env.storage().persistent().set(&DataKey::Position(user.clone()), &position);
+        if !env.storage().persistent().has(&DataKey::PositionId(user.clone())) {
+            Self::mint_position_id(env, user);
+        }
+        Self::save_position(env, user, &position);
         
-        log!(&env, "Staked amount: {}, user: {}, lock_end: {}", amount, user, position.lock_end_time);
+        env.events().publish((symbol_short!("stake"), user.clone()), (amount, position.lock_end_time, position.multiplier_bps));
+        Ok(())
     }
 
     /// Unstake assets. Only possible after lock_end_time has passed.
-    pub fn unstake(env: Env, user: Address, amount: i128) {
+    /// With a cooldown set, use `request_unstake` and `withdraw` instead.
+    pub fn unstake(env: Env, user: Address, amount: i128) -> Result<(), StakingError> {
+        Self::extend_instance_ttl(&env);
         user.require_auth();
-        
-        let mut position = Self::get_position(env.clone(), user.clone()).expect("No staking position found");
-        
-        if amount <= 0 || amount > position.amount {
-            panic!("Invalid unstake amount");
+        Self::ensure_not_paused(&env, |flags| flags.unstaking)?;
+        if Self::get_cooldown(env.clone()) > 0 {
+            return Err(StakingError::CooldownRequired);
         }
 
-        if env.ledger().timestamp() < position.lock_end_time {
-            panic!("Assets are currently locked until {} (current time: {})", position.lock_end_time, env.ledger().timestamp());
-        }
-
-        // Final accrual before withdrawal
-        position.accumulated_rewards = Self::calculate_pending_rewards(&env, &position);
-        position.last_accrual_time = env.ledger().timestamp();
-        
-        position.amount -= amount;
+        Self::remove_stake(&env, &user, amount)?;
 
         // Transfer tokens back to user
-        let token_addr = Self::get_token(&env);
+        let token_addr = Self::get_token(&env)?;
         let client = token::Client::new(&env, &token_addr);
         client.transfer(&env.current_contract_address(), &user, &amount);
 
-        // Update global state
-        let total_staked = env.storage().persistent().get::<_, i128>(&DataKey::TotalStaked).unwrap_or(0);
-        env.storage().persistent().set(&DataKey::TotalStaked, &(total_staked - amount));
+        env.events().publish((symbol_short!("unstake"), user), amount);
+        Ok(())
+    }
 
-        if position.amount == 0 && position.accumulated_rewards == 0 {
-            env.storage().persistent().remove(&DataKey::Position(user.clone()));
-        } else {
-            env.storage().persistent().set(&DataKey::Position(user.clone()), &position);
+    /// Start the cooldown on `amount` of an unlocked position. The amount stops
+    /// earning rewards now and can be withdrawn once the cooldown passes.
+    /// Another request adds to the pending amount and restarts the cooldown.
+    pub fn request_unstake(env: Env, user: Address, amount: i128) -> Result<UnstakeRequest, StakingError> {
+        Self::extend_instance_ttl(&env);
+        user.require_auth();
+        Self::ensure_not_paused(&env, |flags| flags.unstaking)?;
+        Self::remove_stake(&env, &user, amount)?;
+
+        let key = DataKey::UnstakeRequest(user.clone());
+        let pending = env.storage().persistent().get::<_, UnstakeRequest>(&key).map(|request| request.amount).unwrap_or(0);
+        let available_at = env.ledger().timestamp().checked_add(Self::get_cooldown(env.clone())).ok_or(StakingError::Overflow)?;
+        let request = UnstakeRequest { amount: pending + amount, available_at };
+        env.storage().persistent().set(&key, &request);
+        let total_pending = env.storage().instance().get::<_, i128>(&DataKey::TotalPendingUnstake).unwrap_or(0);
+        env.storage().instance().set(&DataKey::TotalPendingUnstake, &(total_pending + amount));
+
+        env.events().publish((symbol_short!("unstk_req"), user), (amount, available_at));
+        Ok(request)
+    }
+
+    /// Withdraw a pending unstake request once its cooldown has passed (at
+    /// once during an emergency). Returns the amount withdrawn.
+    pub fn withdraw(env: Env, user: Address) -> Result<i128, StakingError> {
+        Self::extend_instance_ttl(&env);
+        user.require_auth();
+        Self::ensure_not_paused(&env, |flags| flags.unstaking)?;
+        let key = DataKey::UnstakeRequest(user.clone());
+        let request = env.storage().persistent().get::<_, UnstakeRequest>(&key).ok_or(StakingError::NoUnstakeRequest)?;
+        if env.ledger().timestamp() < request.available_at && !Self::is_emergency(env.clone()) {
+            return Err(StakingError::CooldownActive);
         }
 
-        log!(&env, "Unstaked amount: {}, user: {}", amount, user);
+        env.storage().persistent().remove(&key);
+        let total_pending = env.storage().instance().get::<_, i128>(&DataKey::TotalPendingUnstake).unwrap_or(0);
+        env.storage().instance().set(&DataKey::TotalPendingUnstake, &(total_pending - request.amount));
+
+        let token_addr = Self::get_token(&env)?;
+        token::Client::new(&env, &token_addr).transfer(&env.current_contract_address(), &user, &request.amount);
+
+        env.events().publish((symbol_short!("unstake"), user), request.amount);
+        Ok(request.amount)
+    }
+
+    /// Admin: Set the exit notice in seconds. Zero turns cooldown mode off and
+    /// brings back one-step `unstake`; pending requests keep their timers.
+    pub fn set_cooldown(env: Env, seconds: u64) -> Result<(), StakingError> {
+        Self::extend_instance_ttl(&env);
+        Self::ensure_admin(&env)?;
+        env.storage().instance().set(&DataKey::Cooldown, &seconds);
+        env.events().publish((symbol_short!("cooldown"),), seconds);
+        Ok(())
+    }
+
+    /// View: Exit notice in seconds; zero when cooldown mode is off
+    pub fn get_cooldown(env: Env) -> u64 {
+        env.storage().instance().get(&DataKey::Cooldown).unwrap_or(0)
+    }
+
+    /// View: A user's pending unstake request, if any
+    pub fn get_unstake_request(env: Env, user: Address) -> Option<UnstakeRequest> {
+        env.storage().persistent().get(&DataKey::UnstakeRequest(user))
+    }
+
+    /// Stake like `stake`, crediting `referrer` with a bonus on this user's
+    /// rewards from now on. A user's referrer is set once and can't change.
+    pub fn stake_with_referrer(env: Env, user: Address, amount: i128, lock_duration: u64, referrer: Address) -> Result<(), StakingError> {
+        if referrer == user {
+            return Err(StakingError::InvalidReferrer);
+        }
+        let key = DataKey::Referrer(user.clone());
+        match env.storage().persistent().get::<_, Address>(&key) {
+            Some(existing) if existing != referrer => return Err(StakingError::InvalidReferrer),
+            Some(_) => {}
+            None => {
+                env.storage().persistent().set(&key, &referrer);
+                let mut stats = Self::get_referral_stats(env.clone(), referrer.clone());
+                stats.referees += 1;
+                Self::save_referral_stats(&env, &referrer, &stats);
+                env.events().publish((symbol_short!("referred"), user.clone()), referrer);
+            }
+        }
+        Self::stake(env, user, amount, lock_duration)
+    }
+
+    /// Pay out a referrer's unclaimed bonuses. Returns the amount paid.
+    pub fn claim_referral_rewards(env: Env, referrer: Address) -> Result<i128, StakingError> {
+        Self::extend_instance_ttl(&env);
+        referrer.require_auth();
+        Self::ensure_no_emergency(&env)?;
+        Self::ensure_not_paused(&env, |flags| flags.claiming)?;
+
+        let mut stats = Self::get_referral_stats(env.clone(), referrer.clone());
+        let paid = stats.unclaimed;
+        if paid <= 0 {
+            return Err(StakingError::NoRewards);
+        }
+        stats.unclaimed = 0;
+        Self::save_referral_stats(&env, &referrer, &stats);
+        let total_referral = env.storage().instance().get::<_, i128>(&DataKey::TotalReferral).unwrap_or(0);
+        env.storage().instance().set(&DataKey::TotalReferral, &(total_referral - paid));
+
+        let token_addr = Self::get_token(&env)?;
+        token::Client::new(&env, &token_addr).transfer(&env.current_contract_address(), &referrer, &paid);
+
+        env.events().publish((symbol_short!("ref_claim"), referrer), paid);
+        Ok(paid)
+    }
+
+    /// Admin: Set the referral bonus, in basis points of referees' rewards
+    pub fn set_referral_bps(env: Env, bps: u32) -> Result<(), StakingError> {
+        Self::extend_instance_ttl(&env);
+        Self::ensure_admin(&env)?;
+        if bps > REFERRAL_BPS {
+            return Err(StakingError::InvalidReferralBps);
+        }
+        env.storage().instance().set(&DataKey::ReferralBps, &bps);
+        env.events().publish((symbol_short!("ref_bps"),), bps);
+        Ok(())
+    }
+
+    /// View: Get the referral bonus in basis points
+    pub fn get_referral_bps(env: Env) -> u32 {
+        env.storage().instance().get(&DataKey::ReferralBps).unwrap_or(0)
+    }
+
+    /// View: Who referred `user`, if anyone
+    pub fn get_referrer(env: Env, user: Address) -> Option<Address> {
+        env.storage().persistent().get(&DataKey::Referrer(user))
+    }
+
+    /// View: Referee count and bonuses of a referrer
+    pub fn get_referral_stats(env: Env, referrer: Address) -> ReferralStats {
+        env.storage().persistent().get(&DataKey::ReferralStats(referrer)).unwrap_or(ReferralStats {
+            referees: 0,
+            total_earned: 0,
+            unclaimed: 0,
+        })
+    }
+
+    /// Push the lock end back by `extra_duration` seconds (counted from now if
+    /// the lock already ended) and earn the multiplier the longer lock reaches
+    pub fn extend_lock(env: Env, user: Address, extra_duration: u64) -> Result<(), StakingError> {
+        Self::extend_instance_ttl(&env);
+        let now = env.ledger().timestamp();
+        let position = Self::require_position(&env, &user)?;
+        let lock_end = position.lock_end_time.max(now).checked_add(extra_duration).ok_or(StakingError::Overflow)?;
+        Self::set_lock(&env, &user, position, lock_end)
+    }
+
+    /// Restart the lock at `new_duration` seconds from now. The new lock must
+    /// end later than the current one; the multiplier follows the new lock.
+    pub fn relock(env: Env, user: Address, new_duration: u64) -> Result<(), StakingError> {
+        Self::extend_instance_ttl(&env);
+        let now = env.ledger().timestamp();
+        let position = Self::require_position(&env, &user)?;
+        let lock_end = now.checked_add(new_duration).ok_or(StakingError::Overflow)?;
+        Self::set_lock(&env, &user, position, lock_end)
     }
 
     /// Claim accrued rewards without unstaking
-    pub fn claim_rewards(env: Env, user: Address) {
+    /// Pays at most the reward budget; anything beyond it stays accrued for a later claim.
+    /// With reward vesting on, only the instant share is paid; the rest starts vesting.
+    pub fn claim_rewards(env: Env, user: Address) -> Result<(), StakingError> {
+        Self::extend_instance_ttl(&env);
         user.require_auth();
 
-        let mut position = Self::get_position(env.clone(), user.clone()).expect("No staking position found");
+        Self::ensure_no_emergency(&env)?;
+        Self::ensure_not_paused(&env, |flags| flags.claiming)?;
+        Self::claim(&env, &user)?;
+        Ok(())
+    }
+
+    /// Pay out `user`'s pending rewards, up to the reward budget. Returns the amount paid,
+    /// including any share that starts vesting.
+    fn claim(env: &Env, user: &Address) -> Result<i128, StakingError> {
+        let mut position = Self::require_position(env, user)?;
         
-        let total_rewards = Self::calculate_pending_rewards(&env, &position);
+        let total_rewards = Self::calculate_pending_rewards(env, &position)?;
         if total_rewards <= 0 {
-            panic!("No rewards to claim");
+            return Err(StakingError::NoRewards);
+        }
+        let paid = total_rewards.min(Self::get_reward_budget(env.clone())?);
+        if paid <= 0 {
+            return Err(StakingError::BudgetExhausted);
         }
 
-        position.accumulated_rewards = 0;
+        let vesting = env.storage().instance().get::<_, RewardVesting>(&DataKey::RewardVesting);
+        let instant = match vesting {
+            Some(vesting) => {
+                let instant = mul_div(env, paid, vesting.instant_bps as i128, VESTING_BPS as i128).ok_or(StakingError::Overflow)?;
+                // Whatever had already vested is paid along with the instant share
+                instant + Self::start_vesting(env, user, paid - instant, vesting.duration)?
+            }
+            None => paid,
+        };
+
+        position.accumulated_rewards = total_rewards - paid;
         position.last_accrual_time = env.ledger().timestamp();
-        env.storage().persistent().set(&DataKey::Position(user.clone()), &position);
+        Self::save_or_close_position(env, user, &position);
+
+        if instant > 0 {
+            let token_addr = Self::get_token(env)?;
+            let client = token::Client::new(env, &token_addr);
+            client.transfer(&env.current_contract_address(), user, &instant);
+        }
+        Self::credit_referrer(env, user, paid)?;
+
+        env.events().publish((symbol_short!("claim"), user.clone()), paid);
+        Ok(paid)
+    }
+
+    /// Pay out everything vested so far. Returns the amount paid.
+    pub fn claim_vested(env: Env, user: Address) -> Result<i128, StakingError> {
+        Self::extend_instance_ttl(&env);
+        user.require_auth();
+        Self::ensure_no_emergency(&env)?;
+        Self::ensure_not_paused(&env, |flags| flags.claiming)?;
 
-        let token_addr = Self::get_token(&env);
+        let Some(mut entry) = Self::get_vesting(env.clone(), user.clone()) else {
+            return Err(StakingError::NothingVested);
+        };
+        let unlocked = Self::vested_amount(&env, &entry, env.ledger().timestamp())?;
+        let released = unlocked - entry.released;
+        if released <= 0 {
+            return Err(StakingError::NothingVested);
+        }
+
+        let key = DataKey::Vesting(user.clone());
+        entry.released = unlocked;
+        if entry.released < entry.total {
+            env.storage().persistent().set(&key, &entry);
+        } else {
+            env.storage().persistent().remove(&key);
+        }
+        let total_vesting = env.storage().instance().get::<_, i128>(&DataKey::TotalVesting).unwrap_or(0);
+        env.storage().instance().set(&DataKey::TotalVesting, &(total_vesting - released));
+
+        let token_addr = Self::get_token(&env)?;
+        token::Client::new(&env, &token_addr).transfer(&env.current_contract_address(), &user, &released);
+
+        env.events().publish((symbol_short!("vested"), user), released);
+        Ok(released)
+    }
+
+    /// Admin: Make claims vest, or pay them in full again with `None`.
+    /// Rewards already vesting keep their schedule.
+    pub fn set_reward_vesting(env: Env, vesting: Option<RewardVesting>) -> Result<(), StakingError> {
+        Self::extend_instance_ttl(&env);
+        Self::ensure_admin(&env)?;
+        match &vesting {
+            Some(config) => {
+                if config.instant_bps > VESTING_BPS || config.duration == 0 {
+                    return Err(StakingError::InvalidVesting);
+                }
+                env.storage().instance().set(&DataKey::RewardVesting, config);
+            }
+            None => env.storage().instance().remove(&DataKey::RewardVesting),
+        }
+        env.events().publish((symbol_short!("vesting"),), vesting);
+        Ok(())
+    }
+
+    /// View: Get the reward vesting config, if claims vest
+    pub fn get_reward_vesting(env: Env) -> Option<RewardVesting> {
+        env.storage().instance().get(&DataKey::RewardVesting)
+    }
+
+    /// View: A user's vesting schedule, if any rewards are still vesting
+    pub fn get_vesting(env: Env, user: Address) -> Option<VestingEntry> {
+        env.storage().persistent().get(&DataKey::Vesting(user))
+    }
+
+    /// View: Amount `claim_vested` would pay right now
+    pub fn get_claimable_vested(env: Env, user: Address) -> Result<i128, StakingError> {
+        match Self::get_vesting(env.clone(), user) {
+            Some(entry) => Ok(Self::vested_amount(&env, &entry, env.ledger().timestamp())? - entry.released),
+            None => Ok(0),
+        }
+    }
+
+    /// Restake pending rewards as principal, up to the reward budget.
+    /// The lock and multiplier are unchanged. Returns the amount compounded.
+    pub fn compound(env: Env, user: Address) -> Result<i128, StakingError> {
+        Self::extend_instance_ttl(&env);
+        user.require_auth();
+        Self::ensure_no_emergency(&env)?;
+        Self::ensure_not_paused(&env, |flags| flags.staking || flags.claiming)?;
+
+        let mut position = Self::require_position(&env, &user)?;
+        position.accumulated_rewards = Self::calculate_pending_rewards(&env, &position)?;
+        position.last_accrual_time = env.ledger().timestamp();
+        if position.accumulated_rewards <= 0 {
+            return Err(StakingError::NoRewards);
+        }
+
+        let compounded = Self::compound_rewards(&env, &user, &mut position)?;
+        if compounded <= 0 {
+            return Err(StakingError::BudgetExhausted);
+        }
+        Self::save_position(&env, &user, &position);
+        Ok(compounded)
+    }
+
+    /// Opt in or out of compounding rewards automatically whenever the
+    /// position accrues (on `stake` and `unstake`)
+    pub fn set_auto_compound(env: Env, user: Address, enabled: bool) -> Result<(), StakingError> {
+        Self::extend_instance_ttl(&env);
+        user.require_auth();
+
+        let mut position = Self::require_position(&env, &user)?;
+        position.auto_compound = enabled;
+        Self::save_position(&env, &user, &position);
+
+        env.events().publish((symbol_short!("auto_cmp"), user), enabled);
+        Ok(())
+    }
+
+    /// Add `amount` of the staking token to the reward budget
+    pub fn fund_rewards(env: Env, from: Address, amount: i128) -> Result<(), StakingError> {
+        Self::extend_instance_ttl(&env);
+        from.require_auth();
+        if amount <= 0 {
+            return Err(StakingError::InvalidAmount);
+        }
+
+        let token_addr = Self::get_token(&env)?;
         let client = token::Client::new(&env, &token_addr);
-        client.transfer(&env.current_contract_address(), &user, &total_rewards);
+        client.transfer(&from, &env.current_contract_address(), &amount);
+
+        env.events().publish((symbol_short!("funded"), from), amount);
+        Ok(())
+    }
+
+    /// Admin: Propose `new_admin`; the change takes effect once they call `accept_admin`
+    pub fn transfer_admin(env: Env, new_admin: Address) -> Result<(), StakingError> {
+        Self::extend_instance_ttl(&env);
+        Self::ensure_admin(&env)?;
+        env.storage().instance().set(&DataKey::PendingAdmin, &new_admin);
+        env.events().publish((symbol_short!("adm_prop"),), new_admin);
+        Ok(())
+    }
+
+    /// Complete an admin transfer; must be called by the proposed admin
+    pub fn accept_admin(env: Env, new_admin: Address) -> Result<(), StakingError> {
+        Self::extend_instance_ttl(&env);
+        new_admin.require_auth();
+        let pending = env.storage().instance().get::<_, Address>(&DataKey::PendingAdmin);
+        if pending != Some(new_admin.clone()) {
+            return Err(StakingError::NotPendingAdmin);
+        }
+        env.storage().instance().set(&DataKey::Admin, &new_admin);
+        env.storage().instance().remove(&DataKey::PendingAdmin);
+        env.events().publish((symbol_short!("admin"),), new_admin);
+        Ok(())
+    }
 
-        log!(&env, "Claimed rewards: {}, user: {}", total_rewards, user);
+    /// Admin: Appoint the operator for routine actions (rate updates,
+    /// schedule, pause). The admin can still perform them too.
+    pub fn set_operator(env: Env, operator: Address) -> Result<(), StakingError> {
+        Self::extend_instance_ttl(&env);
+        Self::ensure_admin(&env)?;
+        env.storage().instance().set(&DataKey::Operator, &operator);
+        env.events().publish((symbol_short!("operator"),), operator);
+        Ok(())
     }
 
-    /// Admin: Update the global reward rate
-    pub fn update_reward_rate(env: Env, new_rate: i128) {
-        Self::ensure_admin(&env);
+    /// Operator: Update the global reward rate
+    /// Starts a new epoch at the current time and drops any upcoming ones.
+    pub fn update_reward_rate(env: Env, caller: Address, new_rate: i128) -> Result<(), StakingError> {
+        Self::extend_instance_ttl(&env);
+        Self::ensure_operator(&env, &caller)?;
         if new_rate < 0 {
-            panic!("Reward rate cannot be negative");
+            return Err(StakingError::NegativeRate);
+        }
+        let now = env.ledger().timestamp();
+        Self::truncate_schedule(&env, now);
+        Self::push_epoch(&env, Epoch { start_time: now, rate: new_rate })?;
+        env.events().publish((symbol_short!("rate"),), new_rate);
+        Ok(())
+    }
+
+    /// Operator: Replace the upcoming emission epochs, e.g. to step emissions
+    /// down over time. Epochs must start in the future, in strictly ascending
+    /// order; epochs already started are kept so accrued rewards don't change.
+    pub fn set_schedule(env: Env, caller: Address, epochs: Vec<Epoch>) -> Result<(), StakingError> {
+        Self::extend_instance_ttl(&env);
+        Self::ensure_operator(&env, &caller)?;
+        if epochs.len() > MAX_EPOCHS {
+            return Err(StakingError::TooManyEpochs);
+        }
+        let now = env.ledger().timestamp();
+        let mut previous = now;
+        for epoch in epochs.iter() {
+            if epoch.start_time <= previous {
+                return Err(StakingError::InvalidSchedule);
+            }
+            if epoch.rate < 0 {
+                return Err(StakingError::NegativeRate);
+            }
+            previous = epoch.start_time;
+        }
+        Self::truncate_schedule(&env, now.saturating_add(1));
+        for epoch in epochs.iter() {
+            Self::push_epoch(&env, epoch)?;
+        }
+        env.events().publish((symbol_short!("schedule"),), epochs);
+        Ok(())
+    }
+
+    /// Admin: Replace the lock tiers. Tiers must be in strictly ascending
+    /// `min_duration` order; an empty list turns multipliers off.
+    /// Positions pick up the new tiers the next time they stake.
+    pub fn set_tiers(env: Env, tiers: Vec<LockTier>) -> Result<(), StakingError> {
+        Self::extend_instance_ttl(&env);
+        Self::ensure_admin(&env)?;
+        if tiers.len() > MAX_TIERS {
+            return Err(StakingError::TooManyTiers);
+        }
+        let mut previous: Option<u64> = None;
+        for tier in tiers.iter() {
+            if tier.multiplier_bps == 0 || previous.is_some_and(|min| tier.min_duration <= min) {
+                return Err(StakingError::InvalidTier);
+            }
+            previous = Some(tier.min_duration);
+        }
+        env.storage().instance().set(&DataKey::Tiers, &tiers);
+        env.events().publish((symbol_short!("tiers"),), tiers);
+        Ok(())
+    }
+
+    /// Operator: Pause staking; unstaking and claiming are left as they are
+    pub fn pause(env: Env, caller: Address) -> Result<(), StakingError> {
+        Self::extend_instance_ttl(&env);
+        Self::ensure_operator(&env, &caller)?;
+        let flags = PauseFlags { staking: true, ..Self::get_pause_flags(env.clone()) };
+        env.storage().instance().set(&DataKey::Pause, &flags);
+        env.events().publish((symbol_short!("paused"),), ());
+        Ok(())
+    }
+
+    /// Operator: Unpause staking
+    pub fn unpause(env: Env, caller: Address) -> Result<(), StakingError> {
+        Self::extend_instance_ttl(&env);
+        Self::ensure_operator(&env, &caller)?;
+        let flags = PauseFlags { staking: false, ..Self::get_pause_flags(env.clone()) };
+        env.storage().instance().set(&DataKey::Pause, &flags);
+        env.events().publish((symbol_short!("unpaused"),), ());
+        Ok(())
+    }
+
+    /// Admin: Set the staking, unstaking and claiming switches independently,
+    /// e.g. halt deposits during a migration while withdrawals stay open
+    pub fn set_pause_flags(env: Env, flags: PauseFlags) -> Result<(), StakingError> {
+        Self::extend_instance_ttl(&env);
+        Self::ensure_admin(&env)?;
+        env.storage().instance().set(&DataKey::Pause, &flags);
+        env.events().publish((symbol_short!("pause"),), flags);
+        Ok(())
+    }
+
+    /// View: Which user actions are paused
+    pub fn get_pause_flags(env: Env) -> PauseFlags {
+        env.storage().instance().get(&DataKey::Pause).unwrap_or_default()
+    }
+
+    /// Admin: Cap how much one user, and all users together, may stake.
+    /// Only `stake` is checked; compounding can grow a position past the cap.
+    pub fn set_stake_caps(env: Env, caps: StakeCaps) -> Result<(), StakingError> {
+        Self::extend_instance_ttl(&env);
+        Self::ensure_admin(&env)?;
+        if caps.max_stake_per_user.is_some_and(|max| max <= 0) || caps.max_total_staked.is_some_and(|max| max <= 0) {
+            return Err(StakingError::InvalidAmount);
+        }
+        env.storage().instance().set(&DataKey::StakeCaps, &caps);
+        env.events().publish((symbol_short!("caps"),), caps);
+        Ok(())
+    }
+
+    /// View: Get the stake caps
+    pub fn get_stake_caps(env: Env) -> StakeCaps {
+        env.storage().instance().get(&DataKey::StakeCaps).unwrap_or(StakeCaps {
+            max_stake_per_user: None,
+            max_total_staked: None,
+        })
+    }
+
+    /// View: Most `user` can stake right now under both caps; `i128::MAX` when uncapped
+    pub fn get_remaining_capacity(env: Env, user: Address) -> i128 {
+        let caps = Self::get_stake_caps(env.clone());
+        let mut remaining = i128::MAX;
+        if let Some(max) = caps.max_stake_per_user {
+            let staked = Self::get_position(env.clone(), user).map(|position| position.amount).unwrap_or(0);
+            remaining = remaining.min(max - staked);
+        }
+        if let Some(max) = caps.max_total_staked {
+            let total_staked = env.storage().instance().get::<_, i128>(&DataKey::TotalStaked).unwrap_or(0);
+            remaining = remaining.min(max - total_staked);
+        }
+        remaining.max(0)
+    }
+
+    /// Admin: Turn allowlist mode on or off. While on, only addresses added
+    /// with `set_allowed` or proven with `prove_allowed` can stake.
+    pub fn set_allowlist(env: Env, enabled: bool) -> Result<(), StakingError> {
+        Self::extend_instance_ttl(&env);
+        Self::ensure_admin(&env)?;
+        env.storage().instance().set(&DataKey::AllowlistOn, &enabled);
+        env.events().publish((symbol_short!("allowlist"),), enabled);
+        Ok(())
+    }
+
+    /// Admin: Add `account` to, or remove it from, the allowlist
+    pub fn set_allowed(env: Env, account: Address, allowed: bool) -> Result<(), StakingError> {
+        Self::extend_instance_ttl(&env);
+        Self::ensure_admin(&env)?;
+        if allowed {
+            env.storage().persistent().set(&DataKey::Allowed(account.clone()), &true);
+        } else {
+            env.storage().persistent().remove(&DataKey::Allowed(account.clone()));
+        }
+        env.events().publish((symbol_short!("allowed"), account), allowed);
+        Ok(())
+    }
+
+    /// Move the position token `position_id` — and the whole position behind
+    /// it, lock and accrued rewards included — from `from` to `to`. `spender`
+    /// must be `from` or the address approved for the token; `to` must not
+    /// already hold a position.
+    pub fn transfer_position(env: Env, spender: Address, from: Address, to: Address, position_id: u64) -> Result<(), StakingError> {
+        Self::extend_instance_ttl(&env);
+        spender.require_auth();
+        if Self::owner_of(env.clone(), position_id) != Some(from.clone()) {
+            return Err(StakingError::NotPositionOwner);
+        }
+        let approved = env.storage().persistent().get::<_, Address>(&DataKey::PositionApproval(position_id));
+        if spender != from && Some(spender) != approved {
+            return Err(StakingError::NotPositionOwner);
+        }
+        if env.storage().persistent().has(&DataKey::Position(to.clone())) {
+            return Err(StakingError::PositionExists);
+        }
+        if !Self::is_allowed(env.clone(), to.clone()) {
+            return Err(StakingError::NotAllowed);
         }
-        env.storage().persistent().set(&DataKey::RewardRate, &new_rate);
-        log!(&env, "Reward rate updated to: {}", new_rate);
+
+        let mut position = Self::require_position(&env, &from)?;
+        env.storage().persistent().remove(&DataKey::Position(from.clone()));
+        env.storage().persistent().remove(&DataKey::PositionId(from.clone()));
+        env.storage().persistent().remove(&DataKey::PositionApproval(position_id));
+        env.storage().persistent().set(&DataKey::PositionId(to.clone()), &position_id);
+        env.storage().persistent().set(&DataKey::PositionOwner(position_id), &to);
+        Self::save_position(&env, &to, &position);
+
+        position.amount = 0;
+        Self::checkpoint_votes(&env, &from, &position);
+
+        env.events().publish((symbol_short!("pos_xfer"), from, to), position_id);
+        Ok(())
+    }
+
+    /// Approve `spender` to transfer `owner`'s position token, e.g. to a lending
+    /// contract holding it as collateral; `None` clears the approval
+    pub fn approve_position(env: Env, owner: Address, spender: Option<Address>, position_id: u64) -> Result<(), StakingError> {
+        Self::extend_instance_ttl(&env);
+        owner.require_auth();
+        if Self::owner_of(env.clone(), position_id) != Some(owner.clone()) {
+            return Err(StakingError::NotPositionOwner);
+        }
+        match &spender {
+            Some(spender) => env.storage().persistent().set(&DataKey::PositionApproval(position_id), spender),
+            None => env.storage().persistent().remove(&DataKey::PositionApproval(position_id)),
+        }
+        env.events().publish((symbol_short!("pos_appr"), owner), (spender, position_id));
+        Ok(())
+    }
+
+    /// View: Holder of a position token, if it exists
+    pub fn owner_of(env: Env, position_id: u64) -> Option<Address> {
+        env.storage().persistent().get(&DataKey::PositionOwner(position_id))
+    }
+
+    /// View: Position token ID held by `user`, if they have a position
+    pub fn get_position_id(env: Env, user: Address) -> Option<u64> {
+        env.storage().persistent().get(&DataKey::PositionId(user))
+    }
+
+    /// View: Address approved to transfer a position token, if any
+    pub fn get_position_approval(env: Env, position_id: u64) -> Option<Address> {
+        env.storage().persistent().get(&DataKey::PositionApproval(position_id))
+    }
+
+    /// Admin: Set the Merkle root for large allowlists, or clear it with `None`.
+    /// Leaves are `sha256(xdr(address))`; pairs hash in sorted order.
+    pub fn set_allowlist_root(env: Env, root: Option<BytesN<32>>) -> Result<(), StakingError> {
+        Self::extend_instance_ttl(&env);
+        Self::ensure_admin(&env)?;
+        match &root {
+            Some(root) => env.storage().instance().set(&DataKey::AllowlistRoot, root),
+            None => env.storage().instance().remove(&DataKey::AllowlistRoot),
+        }
+        env.events().publish((symbol_short!("allow_rt"),), root);
+        Ok(())
+    }
+
+    /// Add `user` to the allowlist with a Merkle proof against the current root.
+    /// Anyone can submit the proof; the entry stays until the admin removes it.
+    pub fn prove_allowed(env: Env, user: Address, proof: Vec<BytesN<32>>) -> Result<(), StakingError> {
+        Self::extend_instance_ttl(&env);
+        let root = env.storage().instance().get::<_, BytesN<32>>(&DataKey::AllowlistRoot).ok_or(StakingError::InvalidProof)?;
+        let mut node: BytesN<32> = env.crypto().sha256(&user.clone().to_xdr(&env)).into();
+        for sibling in proof.iter() {
+            let (left, right) = if node <= sibling { (node, sibling) } else { (sibling, node) };
+            let mut pair = Bytes::from_array(&env, &left.to_array());
+            pair.extend_from_array(&right.to_array());
+            node = env.crypto().sha256(&pair).into();
+        }
+        if node != root {
+            return Err(StakingError::InvalidProof);
+        }
+        env.storage().persistent().set(&DataKey::Allowed(user.clone()), &true);
+        env.events().publish((symbol_short!("allowed"), user), true);
+        Ok(())
+    }
+
+    /// View: Whether `user` may stake (always true with the allowlist off)
+    pub fn is_allowed(env: Env, user: Address) -> bool {
+        let enabled = env.storage().instance().get::<_, bool>(&DataKey::AllowlistOn).unwrap_or(false);
+        !enabled || env.storage().persistent().has(&DataKey::Allowed(user))
+    }
+
+    /// Admin: Flag or clear an emergency. While flagged, stakers may pull
+    /// their principal with `emergency_withdraw` and rewards can't be paid out.
+    pub fn set_emergency(env: Env, enabled: bool) -> Result<(), StakingError> {
+        Self::extend_instance_ttl(&env);
+        Self::ensure_admin(&env)?;
+        env.storage().instance().set(&DataKey::Emergency, &enabled);
+        env.events().publish((symbol_short!("emergency"),), enabled);
+        Ok(())
+    }
+
+    /// View: Whether the admin has flagged an emergency
+    pub fn is_emergency(env: Env) -> bool {
+        env.storage().instance().get(&DataKey::Emergency).unwrap_or(false)
+    }
+
+    /// Withdraw the full principal right away during an emergency, ignoring
+    /// the lock and forfeiting all accrued rewards. Returns the amount withdrawn.
+    pub fn emergency_withdraw(env: Env, user: Address) -> Result<i128, StakingError> {
+        Self::extend_instance_ttl(&env);
+        user.require_auth();
+        if !Self::is_emergency(env.clone()) {
+            return Err(StakingError::NoEmergency);
+        }
+
+        let mut position = Self::require_position(&env, &user)?;
+        env.storage().persistent().remove(&DataKey::Position(user.clone()));
+        Self::burn_position_id(&env, &user);
+        let withdrawn = position.amount;
+        position.amount = 0;
+        Self::checkpoint_votes(&env, &user, &position);
+
+        Self::adjust_total_staked(&env, -withdrawn);
+
+        if withdrawn > 0 {
+            let token_addr = Self::get_token(&env)?;
+            let client = token::Client::new(&env, &token_addr);
+            client.transfer(&env.current_contract_address(), &user, &withdrawn);
+        }
+
+        env.events().publish((symbol_short!("emrg_wdr"), user), withdrawn);
+        Ok(withdrawn)
+    }
+
+    /// Admin: Authorize `slasher` to slash positions, sending slashed funds to `destination`
+    pub fn set_slasher(env: Env, slasher: Address, destination: Address) -> Result<(), StakingError> {
+        Self::extend_instance_ttl(&env);
+        Self::ensure_admin(&env)?;
+        env.storage().instance().set(&DataKey::Slasher, &slasher);
+        env.storage().instance().set(&DataKey::SlashDestination, &destination);
+        env.events().publish((symbol_short!("slasher"),), (slasher, destination));
+        Ok(())
+    }
+
+    /// Slasher or Admin: Slash `bps` basis points of a user's stake, and of any
+    /// pending unstake request, for the misbehaviour proven by `evidence_hash`.
+    /// Returns the amount slashed. Rewards accrued up to now are kept, and a
+    /// position left with neither stake nor rewards is closed. Each evidence
+    /// hash can be used once.
+    pub fn slash(env: Env, slasher: Address, user: Address, bps: u32, evidence_hash: BytesN<32>) -> Result<i128, StakingError> {
+        Self::extend_instance_ttl(&env);
+        slasher.require_auth();
+        let admin = env.storage().instance().get::<_, Address>(&DataKey::Admin).ok_or(StakingError::NotInitialized)?;
+        let authorized = env.storage().instance().get::<_, Address>(&DataKey::Slasher);
+        if slasher != admin && Some(slasher) != authorized {
+            return Err(StakingError::NotSlasher);
+        }
+
+        if bps == 0 || bps > MAX_SLASH_BPS {
+            return Err(StakingError::InvalidSlashBps);
+        }
+
+        let evidence_key = DataKey::SlashEvidence(evidence_hash.clone());
+        if env.storage().persistent().has(&evidence_key) {
+            return Err(StakingError::EvidenceUsed);
+        }
+
+        // Stake already requested for withdrawal is still bonded until it's
+        // paid out, so it's slashed at the same rate
+        let position = Self::get_position(env.clone(), user.clone());
+        let request_key = DataKey::UnstakeRequest(user.clone());
+        let request = env.storage().persistent().get::<_, UnstakeRequest>(&request_key);
+        if position.is_none() && request.is_none() {
+            return Err(StakingError::NoPosition);
+        }
+        let staked = position.as_ref().map_or(0, |position| position.amount);
+        let pending = request.as_ref().map_or(0, |request| request.amount);
+        let staked_slash = mul_div(&env, staked, bps as i128, MAX_SLASH_BPS as i128).ok_or(StakingError::Overflow)?;
+        let pending_slash = mul_div(&env, pending, bps as i128, MAX_SLASH_BPS as i128).ok_or(StakingError::Overflow)?;
+        let slashed = staked_slash + pending_slash;
+        if slashed <= 0 {
+            return Err(StakingError::NothingToSlash);
+        }
+        let destination = env.storage().instance().get::<_, Address>(&DataKey::SlashDestination).ok_or(StakingError::NoSlashDestination)?;
+
+        if let Some(mut position) = position {
+            // Settle rewards earned on the full stake before reducing it
+            position.accumulated_rewards = Self::calculate_pending_rewards(&env, &position)?;
+            position.last_accrual_time = env.ledger().timestamp();
+            position.amount -= staked_slash;
+            Self::save_or_close_position(&env, &user, &position);
+            Self::adjust_total_staked(&env, -staked_slash);
+        }
+        if let Some(mut request) = request {
+            request.amount -= pending_slash;
+            if request.amount == 0 {
+                env.storage().persistent().remove(&request_key);
+            } else {
+                env.storage().persistent().set(&request_key, &request);
+            }
+            let total_pending = env.storage().instance().get::<_, i128>(&DataKey::TotalPendingUnstake).unwrap_or(0);
+            env.storage().instance().set(&DataKey::TotalPendingUnstake, &(total_pending - pending_slash));
+        }
+
+        let token_addr = Self::get_token(&env)?;
+        let client = token::Client::new(&env, &token_addr);
+        client.transfer(&env.current_contract_address(), &destination, &slashed);
+
+        let slash_id = env.storage().instance().get::<_, u32>(&DataKey::SlashCount).unwrap_or(0);
+        let record = SlashRecord {
+            user: user.clone(),
+            amount: slashed,
+            evidence_hash,
+            destination,
+            timestamp: env.ledger().timestamp(),
+        };
+        env.storage().persistent().set(&DataKey::Slash(slash_id), &record);
+        env.storage().instance().set(&DataKey::SlashCount, &(slash_id + 1));
+        env.storage().persistent().set(&evidence_key, &slash_id);
+
+        let history_key = DataKey::PositionSlashes(user.clone());
+        let mut history = env.storage().persistent().get::<_, Vec<u32>>(&history_key).unwrap_or(Vec::new(&env));
+        history.push_back(slash_id);
+        env.storage().persistent().set(&history_key, &history);
+
+        env.events().publish((symbol_short!("slash"), user), (slashed, slash_id));
+        Ok(slashed)
     }
 
-    /// Admin: Pause the contract deposit/staking functions
-    pub fn pause(env: Env) {
-        Self::ensure_admin(&env);
-        env.storage().persistent().set(&DataKey::IsPaused, &true);
-        log!(&env, "Contract paused");
+    /// View: Get the current admin
+    pub fn get_admin(env: Env) -> Option<Address> {
+        env.storage().instance().get(&DataKey::Admin)
     }
 
-    /// Admin: Unpause the contract
-    pub fn unpause(env: Env) {
-        Self::ensure_admin(&env);
-        env.storage().persistent().set(&DataKey::IsPaused, &false);
-        log!(&env, "Contract unpaused");
+    /// View: Get the proposed admin awaiting acceptance, if any
+    pub fn get_pending_admin(env: Env) -> Option<Address> {
+        env.storage().instance().get(&DataKey::PendingAdmin)
+    }
+
+    /// View: Get the operator, if any
+    pub fn get_operator(env: Env) -> Option<Address> {
+        env.storage().instance().get(&DataKey::Operator)
+    }
+
+    /// View: Get the authorized slasher, if any
+    pub fn get_slasher(env: Env) -> Option<Address> {
+        env.storage().instance().get(&DataKey::Slasher)
+    }
+
+    /// View: Get a slash record by ID
+    pub fn get_slash(env: Env, slash_id: u32) -> Option<SlashRecord> {
+        env.storage().persistent().get(&DataKey::Slash(slash_id))
+    }
+
+    /// View: Slashes against a user's position, oldest first
+    pub fn get_position_slashes(env: Env, user: Address) -> Vec<SlashRecord> {
+        let ids = env.storage().persistent().get::<_, Vec<u32>>(&DataKey::PositionSlashes(user)).unwrap_or(Vec::new(&env));
+        let mut records = Vec::new(&env);
+        for id in ids.iter() {
+            if let Some(record) = env.storage().persistent().get(&DataKey::Slash(id)) {
+                records.push_back(record);
+            }
+        }
+        records
+    }
+
+    /// View: Get the number of slashes recorded
+    pub fn get_slash_count(env: Env) -> u32 {
+        env.storage().instance().get(&DataKey::SlashCount).unwrap_or(0)
+    }
+
+    /// View: Fixed-point scale of reward rates (`RATE_SCALE` = 1 unit per staked unit per second)
+    pub fn get_rate_scale(_env: Env) -> i128 {
+        RATE_SCALE
+    }
+
+    /// View: Reward rate of the epoch in effect now
+    pub fn get_current_rate(env: Env) -> i128 {
+        Self::epoch_at(&env, env.ledger().timestamp()).map(|epoch| epoch.rate).unwrap_or(0)
+    }
+
+    /// View: Epochs that haven't started yet, ascending by start time
+    pub fn get_upcoming_epochs(env: Env) -> Vec<Epoch> {
+        let started = Self::epochs_before(&env, env.ledger().timestamp().saturating_add(1));
+        let mut upcoming = Vec::new(&env);
+        for index in started..Self::schedule_len(&env) {
+            if let Some(epoch) = env.storage().persistent().get::<_, EpochRecord>(&DataKey::ScheduleEpoch(index)) {
+                upcoming.push_back(Epoch { start_time: epoch.start_time, rate: epoch.rate });
+            }
+        }
+        upcoming
+    }
+
+    /// View: Get the configured lock tiers, ascending by duration
+    pub fn get_tiers(env: Env) -> Vec<LockTier> {
+        env.storage().instance().get(&DataKey::Tiers).unwrap_or(Vec::new(&env))
+    }
+
+    /// View: Tokens available to pay rewards: the contract's balance beyond
+    /// the staked principal it owes back to stakers
+    pub fn get_reward_budget(env: Env) -> Result<i128, StakingError> {
+        let token_addr = Self::get_token(&env)?;
+        let balance = token::Client::new(&env, &token_addr).balance(&env.current_contract_address());
+        let total_staked = env.storage().instance().get::<_, i128>(&DataKey::TotalStaked).unwrap_or(0);
+        let total_vesting = env.storage().instance().get::<_, i128>(&DataKey::TotalVesting).unwrap_or(0);
+        let total_referral = env.storage().instance().get::<_, i128>(&DataKey::TotalReferral).unwrap_or(0);
+        let total_pending = env.storage().instance().get::<_, i128>(&DataKey::TotalPendingUnstake).unwrap_or(0);
+        Ok((balance - total_staked - total_vesting - total_referral - total_pending).max(0))
+    }
+
+    /// Admin: Weight votes by the lock multiplier (until each lock ends) or count
+    /// staked amounts 1:1. Applies to current and past lookups alike.
+    pub fn set_vote_weighting(env: Env, enabled: bool) -> Result<(), StakingError> {
+        Self::extend_instance_ttl(&env);
+        Self::ensure_admin(&env)?;
+        env.storage().instance().set(&DataKey::VoteWeighting, &enabled);
+        env.events().publish((symbol_short!("vote_wt"),), enabled);
+        Ok(())
+    }
+
+    /// View: Voting power of `user` now: their staked amount, tier-weighted
+    /// if vote weighting is on
+    pub fn get_votes(env: Env, user: Address) -> i128 {
+        let now = env.ledger().timestamp();
+        Self::get_past_votes(env, user, now)
+    }
+
+    /// View: Voting power of `user` as of `timestamp`, from their checkpoints
+    pub fn get_past_votes(env: Env, user: Address, timestamp: u64) -> i128 {
+        let count_key = DataKey::VoteCheckpointCount(user.clone());
+        match Self::checkpoint_at(&env, &count_key, |index| DataKey::VoteCheckpoint(user.clone(), index), timestamp) {
+            Some(checkpoint) => Self::checkpoint_votes_at(&env, &checkpoint, timestamp),
+            None => 0,
+        }
+    }
+
+    /// View: Total staked as of `timestamp`, unweighted
+    pub fn get_past_total_staked(env: Env, timestamp: u64) -> i128 {
+        Self::checkpoint_at(&env, &DataKey::TotalCheckpointCount, DataKey::TotalCheckpoint, timestamp)
+            .map(|checkpoint| checkpoint.amount)
+            .unwrap_or(0)
+    }
+
+    /// View: Same as `get_votes`, under the name token clients use, so this
+    /// contract can be set as the voting template's `voting_token`
+    pub fn balance(env: Env, id: Address) -> i128 {
+        Self::get_votes(env, id)
+    }
+
+    /// View: Annual rate, in basis points, a lock of `lock_duration` seconds
+    /// earns at the current emission rate and its tier multiplier. Rates are
+    /// per staked unit, so this doesn't change with the total staked.
+    pub fn current_apr(env: Env, lock_duration: u64) -> Result<i128, StakingError> {
+        let multiplier = Self::tier_multiplier(&env, lock_duration) as i128;
+        let yearly = Self::get_current_rate(env.clone())
+            .checked_mul(SECONDS_PER_YEAR)
+            .ok_or(StakingError::Overflow)?;
+        mul_div(&env, yearly, multiplier, RATE_SCALE).ok_or(StakingError::Overflow)
+    }
+
+    /// View: Rewards a new stake of `amount` locked for `lock_duration` seconds
+    /// would earn by the end of its lock, following the emission schedule
+    /// (including upcoming epochs). Ignores the reward budget.
+    pub fn project_rewards(env: Env, amount: i128, lock_duration: u64) -> Result<i128, StakingError> {
+        if amount <= 0 {
+            return Err(StakingError::InvalidAmount);
+        }
+        let now = env.ledger().timestamp();
+        let lock_end = now.checked_add(lock_duration).ok_or(StakingError::Overflow)?;
+        let position = StakingPosition {
+            amount,
+            lock_end_time: lock_end,
+            last_accrual_time: now,
+            accumulated_rewards: 0,
+            multiplier_bps: Self::tier_multiplier(&env, lock_duration),
+            auto_compound: false,
+        };
+        Self::accrued_until(&env, &position, lock_end)
+    }
+
+    /// Extend the TTL of `user`'s position and its related entries to the lock
+    /// end plus `POSITION_TTL_BUFFER`. Anyone may call. Returns the TTL in ledgers.
+    pub fn bump_position(env: Env, user: Address) -> Result<u32, StakingError> {
+        Self::extend_instance_ttl(&env);
+        let position = Self::require_position(&env, &user)?;
+        Ok(Self::extend_position_ttl(&env, &user, &position))
     }
 
     /// View: Get staking position of a user
@@ -175,49 +1346,504 @@ impl StakingContract {
     }
 
     /// View: Get current pending rewards for a user (unclaimed)
-    pub fn get_pending_rewards(env: Env, user: Address) -> i128 {
+    pub fn get_pending_rewards(env: Env, user: Address) -> Result<i128, StakingError> {
         let position = Self::get_position(env.clone(), user.clone()).unwrap_or(StakingPosition {
             amount: 0,
             lock_end_time: 0,
             last_accrual_time: 0,
             accumulated_rewards: 0,
+            multiplier_bps: BASE_MULTIPLIER_BPS,
+            auto_compound: false,
         });
         Self::calculate_pending_rewards(&env, &position)
     }
 
     // Helper functions
 
-    fn calculate_pending_rewards(env: &Env, position: &StakingPosition) -> i128 {
-        let now = env.ledger().timestamp();
+    fn calculate_pending_rewards(env: &Env, position: &StakingPosition) -> Result<i128, StakingError> {
+        Self::accrued_until(env, position, env.ledger().timestamp())
+    }
+
+    /// Rewards `position` will have accrued by `now`, following the emission schedule
+    fn accrued_until(env: &Env, position: &StakingPosition, now: u64) -> Result<i128, StakingError> {
         if now <= position.last_accrual_time || position.amount == 0 {
-            return position.accumulated_rewards;
+            return Ok(position.accumulated_rewards);
         }
 
-        let reward_rate = env.storage().persistent().get::<_, i128>(&DataKey::RewardRate).unwrap_or(0);
-        let elapsed_time = (now - position.last_accrual_time) as i128;
+        // Reward per token emitted over [last_accrual_time, now): time still
+        // under lock earns the tier multiplier, the rest earns 1x
+        let start = position.last_accrual_time;
+        let boost_end = position.lock_end_time.clamp(start, now);
+        let at_start = Self::reward_per_token_at(env, start)?;
+        let at_boost_end = Self::reward_per_token_at(env, boost_end)?;
+        let at_now = Self::reward_per_token_at(env, now)?;
+        let weighted_rate_time = (at_boost_end - at_start)
+            .checked_mul(position.multiplier_bps as i128)
+            .and_then(|boosted| {
+                (at_now - at_boost_end)
+                    .checked_mul(BASE_MULTIPLIER_BPS as i128)
+                    .and_then(|base| base.checked_add(boosted))
+            })
+            .ok_or(StakingError::Overflow)?;
+
+        // Linear Reward Accrual: rewards = amount * rate * time * multiplier / scale
+        let newly_accrued = mul_div(env, position.amount, weighted_rate_time, BASE_MULTIPLIER_BPS as i128 * RATE_SCALE)
+            .ok_or(StakingError::Overflow)?;
+
+        position.accumulated_rewards.checked_add(newly_accrued).ok_or(StakingError::Overflow)
+    }
+
+    /// Bring a position's rewards up to now, compounding them if it opted in
+    fn accrue(env: &Env, user: &Address, position: &mut StakingPosition) -> Result<(), StakingError> {
+        position.accumulated_rewards = Self::calculate_pending_rewards(env, position)?;
+        position.last_accrual_time = env.ledger().timestamp();
+        if position.auto_compound && position.accumulated_rewards > 0 {
+            Self::compound_rewards(env, user, position)?;
+        }
+        Ok(())
+    }
+
+    /// Move accrued rewards into principal, capped by the reward budget so
+    /// the added principal is backed by tokens the contract holds
+    fn compound_rewards(env: &Env, user: &Address, position: &mut StakingPosition) -> Result<i128, StakingError> {
+        let compounded = position.accumulated_rewards.min(Self::get_reward_budget(env.clone())?);
+        if compounded <= 0 {
+            return Ok(0);
+        }
+        position.accumulated_rewards -= compounded;
+        position.amount += compounded;
+
+        Self::adjust_total_staked(env, compounded);
+        Self::credit_referrer(env, user, compounded)?;
+
+        env.events().publish((symbol_short!("compound"), user.clone()), compounded);
+        Ok(compounded)
+    }
+
+    /// Credit `user`'s referrer with their bonus on `rewards` the user just
+    /// realized, capped by what's left of the reward budget
+    fn credit_referrer(env: &Env, user: &Address, rewards: i128) -> Result<(), StakingError> {
+        let Some(referrer) = Self::get_referrer(env.clone(), user.clone()) else {
+            return Ok(());
+        };
+        let bps = Self::get_referral_bps(env.clone());
+        let bonus = mul_div(env, rewards, bps as i128, REFERRAL_BPS as i128)
+            .ok_or(StakingError::Overflow)?
+            .min(Self::get_reward_budget(env.clone())?);
+        if bonus <= 0 {
+            return Ok(());
+        }
+
+        let mut stats = Self::get_referral_stats(env.clone(), referrer.clone());
+        stats.total_earned += bonus;
+        stats.unclaimed += bonus;
+        Self::save_referral_stats(env, &referrer, &stats);
+        let total_referral = env.storage().instance().get::<_, i128>(&DataKey::TotalReferral).unwrap_or(0);
+        env.storage().instance().set(&DataKey::TotalReferral, &(total_referral + bonus));
+
+        env.events().publish((symbol_short!("ref_bonus"), referrer), (user.clone(), bonus));
+        Ok(())
+    }
+
+    /// Move a position's lock end to `lock_end`, settling rewards at the old
+    /// multiplier first
+    fn set_lock(env: &Env, user: &Address, mut position: StakingPosition, lock_end: u64) -> Result<(), StakingError> {
+        user.require_auth();
+        Self::ensure_not_paused(env, |flags| flags.staking)?;
+        let now = env.ledger().timestamp();
+        if lock_end <= position.lock_end_time.max(now) {
+            return Err(StakingError::LockNotExtended);
+        }
+
+        Self::accrue(env, user, &mut position)?;
+        position.lock_end_time = lock_end;
+        position.multiplier_bps = Self::tier_multiplier(env, lock_end - now);
+        Self::save_position(env, user, &position);
+
+        env.events().publish((symbol_short!("relock"), user.clone()), (lock_end, position.multiplier_bps));
+        Ok(())
+    }
+
+    /// Store `user`'s position, checkpoint its votes and keep it and the
+    /// contract config from being archived
+    fn save_position(env: &Env, user: &Address, position: &StakingPosition) {
+        env.storage().persistent().set(&DataKey::Position(user.clone()), position);
+        Self::checkpoint_votes(env, user, position);
+        Self::extend_position_ttl(env, user, position);
+    }
+
+    /// Extend the position's entries, and the user's other entries, to its lock
+    /// end plus `POSITION_TTL_BUFFER`; the shared total-stake history by the
+    /// buffer. Returns the position TTL.
+    fn extend_position_ttl(env: &Env, user: &Address, position: &StakingPosition) -> u32 {
+        let max_ttl = env.storage().max_ttl();
+        let remaining = (position.lock_end_time.saturating_sub(env.ledger().timestamp()) / LEDGER_SECONDS)
+            .min(u32::MAX as u64) as u32;
+        let extend_to = remaining.saturating_add(POSITION_TTL_BUFFER).min(max_ttl);
+
+        let storage = env.storage().persistent();
+        let vote_count = storage.get::<_, u32>(&DataKey::VoteCheckpointCount(user.clone())).unwrap_or(0);
+        let user_keys = [
+            DataKey::Position(user.clone()),
+            DataKey::VoteCheckpointCount(user.clone()),
+            DataKey::VoteCheckpoint(user.clone(), vote_count.saturating_sub(1)),
+            DataKey::PositionId(user.clone()),
+            DataKey::UnstakeRequest(user.clone()),
+            DataKey::Vesting(user.clone()),
+            DataKey::Referrer(user.clone()),
+            DataKey::ReferralStats(user.clone()),
+            DataKey::Allowed(user.clone()),
+            DataKey::PositionSlashes(user.clone()),
+        ];
+        for key in user_keys.iter() {
+            if storage.has(key) {
+                storage.extend_ttl(key, extend_to, extend_to);
+            }
+        }
+        if let Some(position_id) = storage.get::<_, u64>(&DataKey::PositionId(user.clone())) {
+            for key in [DataKey::PositionOwner(position_id), DataKey::PositionApproval(position_id)] {
+                if storage.has(&key) {
+                    storage.extend_ttl(&key, extend_to, extend_to);
+                }
+            }
+        }
+
+        // Epochs the position's next accrual reads: the ones its last accrual
+        // and lock end fall in, the current one and everything upcoming
+        let epoch_index = |time: u64| Self::epochs_before(env, time.saturating_add(1)).saturating_sub(1);
+        let current = epoch_index(env.ledger().timestamp());
+        let touched = [epoch_index(position.last_accrual_time), epoch_index(position.lock_end_time)];
+        for index in touched.into_iter().chain(current..Self::schedule_len(env)) {
+            let key = DataKey::ScheduleEpoch(index);
+            if storage.has(&key) {
+                storage.extend_ttl(&key, extend_to, extend_to);
+            }
+        }
+
+        let shared_ttl = POSITION_TTL_BUFFER.min(max_ttl);
+        let total_count = storage.get::<_, u32>(&DataKey::TotalCheckpointCount).unwrap_or(0);
+        for key in [DataKey::TotalCheckpointCount, DataKey::TotalCheckpoint(total_count.saturating_sub(1))] {
+            if storage.has(&key) {
+                storage.extend_ttl(&key, shared_ttl, shared_ttl);
+            }
+        }
+        extend_to
+    }
+
+    /// Extend the instance, which holds the config and running totals, by
+    /// `POSITION_TTL_BUFFER`. Every state-changing entrypoint calls this once.
+    fn extend_instance_ttl(env: &Env) {
+        let ttl = POSITION_TTL_BUFFER.min(env.storage().max_ttl());
+        env.storage().instance().extend_ttl(ttl, ttl);
+    }
+
+    /// Take `amount` out of an unlocked position, settling its rewards first
+    fn remove_stake(env: &Env, user: &Address, amount: i128) -> Result<(), StakingError> {
+        let mut position = Self::require_position(env, user)?;
         
-        // Linear Reward Accrual: rewards = amount * rate * time
-        // Note: In production, rate should be scaled to handle decimals (e.g. rate per 10^7 units)
-        let newly_accrued = position.amount
-            .checked_mul(reward_rate).expect("Multiplication overflow")
-            .checked_mul(elapsed_time).expect("Time calculation overflow");
+        if amount <= 0 || amount > position.amount {
+            return Err(StakingError::InsufficientStake);
+        }
+
+        if env.ledger().timestamp() < position.lock_end_time {
+            return Err(StakingError::Locked);
+        }
 
-        position.accumulated_rewards.checked_add(newly_accrued).expect("Total rewards overflow")
+        // Final accrual before withdrawal
+        Self::accrue(env, user, &mut position)?;
+
+        position.amount -= amount;
+
+        // Update global state
+        Self::adjust_total_staked(env, -amount);
+
+        Self::save_or_close_position(env, user, &position);
+        Ok(())
     }
 
-    fn ensure_admin(env: &Env) {
-        let admin = env.storage().persistent().get::<_, Address>(&DataKey::Admin).expect("No admin set");
+    /// Store a position, or delete it and burn its token once it holds
+    /// neither stake nor unclaimed rewards. A fully unstaked position with
+    /// rewards left stays open until the claim that pays them out.
+    fn save_or_close_position(env: &Env, user: &Address, position: &StakingPosition) {
+        if position.amount == 0 && position.accumulated_rewards == 0 {
+            env.storage().persistent().remove(&DataKey::Position(user.clone()));
+            Self::burn_position_id(env, user);
+            Self::checkpoint_votes(env, user, position);
+        } else {
+            Self::save_position(env, user, position);
+        }
+    }
+
+    /// Issue the next position token ID to `user`
+    fn mint_position_id(env: &Env, user: &Address) {
+        let position_id = env.storage().instance().get::<_, u64>(&DataKey::NextPositionId).unwrap_or(1);
+        env.storage().instance().set(&DataKey::NextPositionId, &(position_id + 1));
+        env.storage().persistent().set(&DataKey::PositionId(user.clone()), &position_id);
+        env.storage().persistent().set(&DataKey::PositionOwner(position_id), user);
+        env.events().publish((symbol_short!("pos_mint"), user.clone()), position_id);
+    }
+
+    /// Retire `user`'s position token once their position is closed
+    fn burn_position_id(env: &Env, user: &Address) {
+        let key = DataKey::PositionId(user.clone());
+        if let Some(position_id) = env.storage().persistent().get::<_, u64>(&key) {
+            env.storage().persistent().remove(&key);
+            env.storage().persistent().remove(&DataKey::PositionOwner(position_id));
+            env.storage().persistent().remove(&DataKey::PositionApproval(position_id));
+            env.events().publish((symbol_short!("pos_burn"), user.clone()), position_id);
+        }
+    }
+
+    /// Store a referrer's stats. The referrer may hold no position of their
+    /// own to keep the entry alive, so it is extended here.
+    fn save_referral_stats(env: &Env, referrer: &Address, stats: &ReferralStats) {
+        let key = DataKey::ReferralStats(referrer.clone());
+        let ttl = POSITION_TTL_BUFFER.min(env.storage().max_ttl());
+        env.storage().persistent().set(&key, stats);
+        env.storage().persistent().extend_ttl(&key, ttl, ttl);
+    }
+
+    /// Apply `delta` to TotalStaked and checkpoint the new total
+    fn adjust_total_staked(env: &Env, delta: i128) {
+        let total_staked = env.storage().instance().get::<_, i128>(&DataKey::TotalStaked).unwrap_or(0) + delta;
+        env.storage().instance().set(&DataKey::TotalStaked, &total_staked);
+        let checkpoint = Checkpoint {
+            timestamp: env.ledger().timestamp(),
+            amount: total_staked,
+            multiplier_bps: BASE_MULTIPLIER_BPS,
+            lock_end_time: 0,
+        };
+        Self::push_checkpoint(env, &DataKey::TotalCheckpointCount, DataKey::TotalCheckpoint, checkpoint);
+    }
+
+    /// Record the position's stake for vote lookups from now on, unless it
+    /// matches the latest checkpoint
+    fn checkpoint_votes(env: &Env, user: &Address, position: &StakingPosition) {
+        let count_key = DataKey::VoteCheckpointCount(user.clone());
+        let entry_key = |index| DataKey::VoteCheckpoint(user.clone(), index);
+        if Self::latest_checkpoint(env, &count_key, entry_key).is_some_and(|last| {
+            last.amount == position.amount
+                && last.multiplier_bps == position.multiplier_bps
+                && last.lock_end_time == position.lock_end_time
+        }) {
+            return;
+        }
+        let checkpoint = Checkpoint {
+            timestamp: env.ledger().timestamp(),
+            amount: position.amount,
+            multiplier_bps: position.multiplier_bps,
+            lock_end_time: position.lock_end_time,
+        };
+        Self::push_checkpoint(env, &count_key, entry_key, checkpoint);
+    }
+
+    /// Most recent checkpoint of the history counted under `count_key`
+    fn latest_checkpoint(env: &Env, count_key: &DataKey, entry_key: impl Fn(u32) -> DataKey) -> Option<Checkpoint> {
+        let count = env.storage().persistent().get::<_, u32>(count_key).unwrap_or(0);
+        if count == 0 {
+            return None;
+        }
+        env.storage().persistent().get(&entry_key(count - 1))
+    }
+
+    /// Append a checkpoint under its own key, replacing the last one if it has
+    /// the same timestamp. Each write touches one entry however long the history.
+    fn push_checkpoint(env: &Env, count_key: &DataKey, entry_key: impl Fn(u32) -> DataKey, checkpoint: Checkpoint) {
+        let storage = env.storage().persistent();
+        let mut count = storage.get::<_, u32>(count_key).unwrap_or(0);
+        let replaces_last = count > 0
+            && storage
+                .get::<_, Checkpoint>(&entry_key(count - 1))
+                .is_some_and(|last| last.timestamp == checkpoint.timestamp);
+        if !replaces_last {
+            count += 1;
+            storage.set(count_key, &count);
+        }
+        storage.set(&entry_key(count - 1), &checkpoint);
+    }
+
+    /// Latest checkpoint at or before `timestamp` (binary search over indices)
+    fn checkpoint_at(env: &Env, count_key: &DataKey, entry_key: impl Fn(u32) -> DataKey, timestamp: u64) -> Option<Checkpoint> {
+        let storage = env.storage().persistent();
+        let (mut low, mut high) = (0, storage.get::<_, u32>(count_key).unwrap_or(0));
+        while low < high {
+            let mid = (low + high) / 2;
+            if storage.get::<_, Checkpoint>(&entry_key(mid))?.timestamp <= timestamp {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+        if low == 0 { None } else { storage.get(&entry_key(low - 1)) }
+    }
+
+    fn checkpoint_votes_at(env: &Env, checkpoint: &Checkpoint, timestamp: u64) -> i128 {
+        let weighted = env.storage().instance().get::<_, bool>(&DataKey::VoteWeighting).unwrap_or(false);
+        if weighted && timestamp < checkpoint.lock_end_time {
+            mul_div(env, checkpoint.amount, checkpoint.multiplier_bps as i128, BASE_MULTIPLIER_BPS as i128).unwrap_or(i128::MAX)
+        } else {
+            checkpoint.amount
+        }
+    }
+
+    /// Fold `amount` of a claim into `user`'s vesting schedule. The part
+    /// already unlocked is settled and returned for payout; the still-locked
+    /// part and `amount` restart vesting now, ending at their remaining
+    /// durations averaged by amount, so a small claim barely moves the end.
+    fn start_vesting(env: &Env, user: &Address, amount: i128, duration: u64) -> Result<i128, StakingError> {
+        if amount <= 0 {
+            return Ok(0);
+        }
+        let now = env.ledger().timestamp();
+        let (settled, locked, locked_for) = match Self::get_vesting(env.clone(), user.clone()) {
+            Some(entry) => {
+                let unlocked = Self::vested_amount(env, &entry, now)?;
+                (unlocked - entry.released, entry.total - unlocked, entry.end_time.saturating_sub(now))
+            }
+            None => (0, 0, 0),
+        };
+        let total = locked.checked_add(amount).ok_or(StakingError::Overflow)?;
+        let weighted_duration = mul_div(env, locked, locked_for as i128, total)
+            .zip(mul_div(env, amount, duration as i128, total))
+            .map(|(old, new)| old + new)
+            .ok_or(StakingError::Overflow)?;
+        let end_time = now.checked_add(weighted_duration as u64).ok_or(StakingError::Overflow)?;
+        let entry = VestingEntry { total, released: 0, start_time: now, end_time };
+        env.storage().persistent().set(&DataKey::Vesting(user.clone()), &entry);
+
+        let total_vesting = env.storage().instance().get::<_, i128>(&DataKey::TotalVesting).unwrap_or(0);
+        env.storage().instance().set(&DataKey::TotalVesting, &(total_vesting + amount - settled));
+
+        env.events().publish((symbol_short!("vest"), user.clone()), (amount, end_time));
+        Ok(settled)
+    }
+
+    /// Portion of `entry` unlocked by `now`, including what was already released
+    fn vested_amount(env: &Env, entry: &VestingEntry, now: u64) -> Result<i128, StakingError> {
+        if now >= entry.end_time {
+            return Ok(entry.total);
+        }
+        let elapsed = now.saturating_sub(entry.start_time) as i128;
+        let duration = (entry.end_time - entry.start_time) as i128;
+        mul_div(env, entry.total, elapsed, duration).ok_or(StakingError::Overflow)
+    }
+
+    fn schedule_len(env: &Env) -> u32 {
+        env.storage().instance().get(&DataKey::ScheduleLen).unwrap_or(0)
+    }
+
+    /// Number of epochs that start before `time` (binary search)
+    fn epochs_before(env: &Env, time: u64) -> u32 {
+        let (mut low, mut high) = (0, Self::schedule_len(env));
+        while low < high {
+            let mid = (low + high) / 2;
+            let starts_before = env
+                .storage()
+                .persistent()
+                .get::<_, EpochRecord>(&DataKey::ScheduleEpoch(mid))
+                .is_some_and(|epoch| epoch.start_time < time);
+            if starts_before {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+        low
+    }
+
+    /// Epoch in effect at `time`, if the schedule had started by then
+    fn epoch_at(env: &Env, time: u64) -> Option<EpochRecord> {
+        let index = Self::epochs_before(env, time.saturating_add(1)).checked_sub(1)?;
+        env.storage().persistent().get(&DataKey::ScheduleEpoch(index))
+    }
+
+    /// Reward per staked unit (scaled) emitted from the first epoch until `time`
+    fn reward_per_token_at(env: &Env, time: u64) -> Result<i128, StakingError> {
+        match Self::epoch_at(env, time) {
+            Some(epoch) => epoch.rate
+                .checked_mul((time - epoch.start_time) as i128)
+                .and_then(|emitted| emitted.checked_add(epoch.reward_per_token))
+                .ok_or(StakingError::Overflow),
+            None => Ok(0),
+        }
+    }
+
+    /// Drop the epochs starting at or after `time`
+    fn truncate_schedule(env: &Env, time: u64) {
+        let kept = Self::epochs_before(env, time);
+        for index in kept..Self::schedule_len(env) {
+            env.storage().persistent().remove(&DataKey::ScheduleEpoch(index));
+        }
+        env.storage().instance().set(&DataKey::ScheduleLen, &kept);
+    }
+
+    /// Append `epoch`, carrying forward the reward per token emitted before it
+    fn push_epoch(env: &Env, epoch: Epoch) -> Result<(), StakingError> {
+        let len = Self::schedule_len(env);
+        let reward_per_token = match len.checked_sub(1) {
+            Some(last) => {
+                let previous = env.storage().persistent().get::<_, EpochRecord>(&DataKey::ScheduleEpoch(last)).ok_or(StakingError::NotInitialized)?;
+                previous.rate
+                    .checked_mul((epoch.start_time - previous.start_time) as i128)
+                    .and_then(|emitted| emitted.checked_add(previous.reward_per_token))
+                    .ok_or(StakingError::Overflow)?
+            }
+            None => 0,
+        };
+        let record = EpochRecord { start_time: epoch.start_time, rate: epoch.rate, reward_per_token };
+        env.storage().persistent().set(&DataKey::ScheduleEpoch(len), &record);
+        env.storage().instance().set(&DataKey::ScheduleLen, &(len + 1));
+        Ok(())
+    }
+
+    /// Multiplier of the highest tier a lock of `lock_duration` seconds reaches
+    fn tier_multiplier(env: &Env, lock_duration: u64) -> u32 {
+        let tiers = env.storage().instance().get::<_, Vec<LockTier>>(&DataKey::Tiers).unwrap_or(Vec::new(env));
+        let mut multiplier = BASE_MULTIPLIER_BPS;
+        for tier in tiers.iter() {
+            if lock_duration >= tier.min_duration {
+                multiplier = tier.multiplier_bps;
+            }
+        }
+        multiplier
+    }
+
+    fn require_position(env: &Env, user: &Address) -> Result<StakingPosition, StakingError> {
+        Self::get_position(env.clone(), user.clone()).ok_or(StakingError::NoPosition)
+    }
+
+    fn ensure_admin(env: &Env) -> Result<(), StakingError> {
+        let admin = env.storage().instance().get::<_, Address>(&DataKey::Admin).ok_or(StakingError::NotInitialized)?;
         admin.require_auth();
+        Ok(())
+    }
+
+    /// `caller` must be the operator or the admin, and authorize the call
+    fn ensure_operator(env: &Env, caller: &Address) -> Result<(), StakingError> {
+        caller.require_auth();
+        let admin = env.storage().instance().get::<_, Address>(&DataKey::Admin).ok_or(StakingError::NotInitialized)?;
+        let operator = env.storage().instance().get::<_, Address>(&DataKey::Operator);
+        if *caller != admin && Some(caller.clone()) != operator {
+            return Err(StakingError::NotOperator);
+        }
+        Ok(())
+    }
+
+    fn ensure_not_paused(env: &Env, paused: impl Fn(&PauseFlags) -> bool) -> Result<(), StakingError> {
+        if paused(&Self::get_pause_flags(env.clone())) {
+            return Err(StakingError::Paused);
+        }
+        Ok(())
     }
 
-    fn ensure_not_paused(env: &Env) {
-        let paused = env.storage().persistent().get::<_, bool>(&DataKey::IsPaused).unwrap_or(false);
-        if paused {
-            panic!("Contract is currently paused");
+    fn ensure_no_emergency(env: &Env) -> Result<(), StakingError> {
+        if Self::is_emergency(env.clone()) {
+            return Err(StakingError::EmergencyActive);
         }
+        Ok(())
     }
 
-    fn get_token(env: &Env) -> Address {
-        env.storage().persistent().get::<_, Address>(&DataKey::Token).expect("Token not configured")
+    fn get_token(env: &Env) -> Result<Address, StakingError> {
+        env.storage().instance().get::<_, Address>(&DataKey::Token).ok_or(StakingError::NotInitialized)
     }
 }
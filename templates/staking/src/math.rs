@@ -0,0 +1,41 @@
+//! Fixed-point helpers for the contract's scaled arithmetic: reward rates
+//! (`RATE_SCALE`), basis-point shares and vesting fractions.
+
+use soroban_sdk::{Env, U256};
+
+/// Direction to round a quotient that doesn't divide evenly
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Rounding {
+    /// Toward zero; use for amounts paid out
+    Down,
+    /// Away from zero; use for amounts owed to the contract
+    Up,
+}
+
+/// `a * b / denominator`, rounded down. See [`mul_div_rounding`].
+pub fn mul_div(env: &Env, a: i128, b: i128, denominator: i128) -> Option<i128> {
+    mul_div_rounding(env, a, b, denominator, Rounding::Down)
+}
+
+/// `a * b / denominator`, rounded as `rounding` says, with the product taken
+/// in 256 bits so it can't overflow. `None` if the result doesn't fit in an
+/// `i128` or any input is out of range (negative operands, non-positive
+/// denominator).
+pub fn mul_div_rounding(env: &Env, a: i128, b: i128, denominator: i128, rounding: Rounding) -> Option<i128> {
+    if a < 0 || b < 0 || denominator <= 0 {
+        return None;
+    }
+    let (quotient, exact) = match a.checked_mul(b) {
+        Some(product) => (product / denominator, product % denominator == 0),
+        None => {
+            let product = U256::from_u128(env, a as u128).mul(&U256::from_u128(env, b as u128));
+            let denominator = U256::from_u128(env, denominator as u128);
+            let exact = product.rem_euclid(&denominator) == U256::from_u32(env, 0);
+            (i128::try_from(product.div(&denominator).to_u128()?).ok()?, exact)
+        }
+    };
+    match rounding {
+        Rounding::Up if !exact => quotient.checked_add(1),
+        _ => Some(quotient),
+    }
+}
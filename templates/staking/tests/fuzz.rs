@@ -0,0 +1,348 @@
+// Randomized calls against every state-changing entrypoint with boundary
+// values (extreme amounts, rates, lock durations, basis points and ledger
+// times). Every failure must surface as a `StakingError`; a trap from an
+// overflow or unwrap means an entrypoint is missing a check.
+use soroban_sdk::{
+    testutils::{Address as _, Ledger as _},
+    token, Address, BytesN, Env, InvokeError, Vec,
+};
+use staking_contract::{
+    Epoch, LockTier, PauseFlags, RewardVesting, StakeCaps, StakingContract,
+    StakingContractClient, StakingError, RATE_SCALE,
+};
+
+const ROUNDS: u64 = 24;
+/// Highest ledger sequence a round reaches, under the default minimum entry TTL
+const MAX_SEQUENCE: u32 = 4_000;
+const STEPS: u32 = 60;
+
+// xorshift64: deterministic, so a failing seed reproduces exactly
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: u64) -> u64 {
+        self.next() % n
+    }
+
+    fn flag(&mut self) -> bool {
+        self.below(2) == 0
+    }
+
+    fn amount(&mut self) -> i128 {
+        match self.below(7) {
+            0 => 0,
+            1 => -1,
+            2 => i128::MAX,
+            3 => i128::MIN,
+            4 => i128::MAX / 2 + 1,
+            _ => self.below(2_000) as i128,
+        }
+    }
+
+    fn rate(&mut self) -> i128 {
+        match self.below(5) {
+            0 => 0,
+            1 => -1,
+            2 => i128::MAX,
+            _ => self.below(10) as i128 * RATE_SCALE / 100,
+        }
+    }
+
+    fn bps(&mut self) -> u32 {
+        match self.below(5) {
+            0 => 0,
+            1 => u32::MAX,
+            2 => 10_001,
+            _ => self.below(10_001) as u32,
+        }
+    }
+
+    fn time(&mut self) -> u64 {
+        match self.below(5) {
+            0 => 0,
+            1 => u64::MAX,
+            2 => u64::MAX - 1,
+            _ => self.below(10_000),
+        }
+    }
+}
+
+struct Harness<'a> {
+    env: &'a Env,
+    client: StakingContractClient<'a>,
+    admin: Address,
+    users: [Address; 3],
+    outsider: Address,
+    rng: Rng,
+}
+
+// Ok, or a typed contract error. `InvokeError::Abort` is a trap.
+fn assert_typed<T, C>(call: &str, result: Result<Result<T, C>, Result<StakingError, InvokeError>>) {
+    if let Err(Err(err)) = result {
+        panic!("{call} trapped instead of returning a StakingError: {err:?}");
+    }
+}
+
+impl Harness<'_> {
+    fn address(&mut self) -> Address {
+        match self.rng.below(5) {
+            3 => self.outsider.clone(),
+            4 => self.admin.clone(),
+            i => self.users[i as usize].clone(),
+        }
+    }
+
+    fn users(&mut self) -> Vec<Address> {
+        let mut users = Vec::new(self.env);
+        for _ in 0..self.rng.below(4) {
+            let user = self.address();
+            users.push_back(user);
+        }
+        users
+    }
+
+    fn position_id(&mut self) -> u64 {
+        match self.rng.below(4) {
+            0 => u64::MAX,
+            _ => self.rng.below(6),
+        }
+    }
+
+    fn step(&mut self) {
+        match self.rng.below(32) {
+            0..=3 => {
+                let (user, amount, lock) = (self.address(), self.rng.amount(), self.rng.time());
+                assert_typed("stake", self.client.try_stake(&user, &amount, &lock));
+            }
+            4 => {
+                let (sponsor, user) = (self.address(), self.address());
+                let (amount, lock) = (self.rng.amount(), self.rng.time());
+                assert_typed("stake_for", self.client.try_stake_for(&sponsor, &user, &amount, &lock));
+            }
+            5 => {
+                let (user, referrer) = (self.address(), self.address());
+                let (amount, lock) = (self.rng.amount(), self.rng.time());
+                assert_typed(
+                    "stake_with_referrer",
+                    self.client.try_stake_with_referrer(&user, &amount, &lock, &referrer),
+                );
+            }
+            6 | 7 => {
+                let (user, amount) = (self.address(), self.rng.amount());
+                assert_typed("unstake", self.client.try_unstake(&user, &amount));
+            }
+            8 => {
+                let (user, amount) = (self.address(), self.rng.amount());
+                assert_typed("request_unstake", self.client.try_request_unstake(&user, &amount));
+                let user = self.address();
+                assert_typed("withdraw", self.client.try_withdraw(&user));
+            }
+            9 | 10 => {
+                let user = self.address();
+                assert_typed("claim_rewards", self.client.try_claim_rewards(&user));
+                let user = self.address();
+                assert_typed("claim_vested", self.client.try_claim_vested(&user));
+            }
+            11 => {
+                let user = self.address();
+                assert_typed("compound", self.client.try_compound(&user));
+                let (user, enabled) = (self.address(), self.rng.flag());
+                assert_typed("set_auto_compound", self.client.try_set_auto_compound(&user, &enabled));
+            }
+            12 => {
+                let users = self.users();
+                assert_typed("claim_for_many", self.client.try_claim_for_many(&users));
+            }
+            13 => {
+                let referrer = self.address();
+                assert_typed("claim_referral_rewards", self.client.try_claim_referral_rewards(&referrer));
+                let bps = self.rng.bps();
+                assert_typed("set_referral_bps", self.client.try_set_referral_bps(&bps));
+            }
+            14 => {
+                let (user, extra) = (self.address(), self.rng.time());
+                assert_typed("extend_lock", self.client.try_extend_lock(&user, &extra));
+                let (user, duration) = (self.address(), self.rng.time());
+                assert_typed("relock", self.client.try_relock(&user, &duration));
+            }
+            15 => {
+                let (from, amount) = (self.address(), self.rng.amount());
+                assert_typed("fund_rewards", self.client.try_fund_rewards(&from, &amount));
+            }
+            16 => {
+                let (caller, rate) = (self.address(), self.rng.rate());
+                assert_typed("update_reward_rate", self.client.try_update_reward_rate(&caller, &rate));
+            }
+            17 => {
+                let caller = self.address();
+                let mut epochs = Vec::new(self.env);
+                for _ in 0..self.rng.below(3) {
+                    epochs.push_back(Epoch { start_time: self.rng.time(), rate: self.rng.rate() });
+                }
+                assert_typed("set_schedule", self.client.try_set_schedule(&caller, &epochs));
+            }
+            18 => {
+                let mut tiers = Vec::new(self.env);
+                for _ in 0..self.rng.below(3) {
+                    tiers.push_back(LockTier { min_duration: self.rng.time(), multiplier_bps: self.rng.bps() });
+                }
+                assert_typed("set_tiers", self.client.try_set_tiers(&tiers));
+            }
+            19 => {
+                let flags = PauseFlags {
+                    staking: self.rng.flag(),
+                    unstaking: self.rng.flag(),
+                    claiming: self.rng.flag(),
+                };
+                assert_typed("set_pause_flags", self.client.try_set_pause_flags(&flags));
+                let caller = self.address();
+                if self.rng.flag() {
+                    assert_typed("pause", self.client.try_pause(&caller));
+                } else {
+                    assert_typed("unpause", self.client.try_unpause(&caller));
+                }
+            }
+            20 => {
+                let caps = StakeCaps {
+                    max_stake_per_user: self.rng.flag().then(|| self.rng.amount()),
+                    max_total_staked: self.rng.flag().then(|| self.rng.amount()),
+                };
+                assert_typed("set_stake_caps", self.client.try_set_stake_caps(&caps));
+            }
+            21 => {
+                let enabled = self.rng.below(4) == 0;
+                assert_typed("set_allowlist", self.client.try_set_allowlist(&enabled));
+                let (account, allowed) = (self.address(), self.rng.flag());
+                assert_typed("set_allowed", self.client.try_set_allowed(&account, &allowed));
+            }
+            22 => {
+                let (spender, from, to) = (self.address(), self.address(), self.address());
+                let id = self.position_id();
+                assert_typed("transfer_position", self.client.try_transfer_position(&spender, &from, &to, &id));
+            }
+            23 => {
+                let owner = self.address();
+                let spender = self.rng.flag().then(|| self.address());
+                let id = self.position_id();
+                assert_typed("approve_position", self.client.try_approve_position(&owner, &spender, &id));
+            }
+            24 => {
+                let enabled = self.rng.below(4) == 0;
+                assert_typed("set_emergency", self.client.try_set_emergency(&enabled));
+                let user = self.address();
+                assert_typed("emergency_withdraw", self.client.try_emergency_withdraw(&user));
+            }
+            25 => {
+                let (slasher, user, bps) = (self.address(), self.address(), self.rng.bps());
+                let evidence = BytesN::from_array(self.env, &[self.rng.below(4) as u8; 32]);
+                assert_typed("slash", self.client.try_slash(&slasher, &user, &bps, &evidence));
+            }
+            26 => {
+                let vesting = self.rng.flag().then(|| RewardVesting {
+                    instant_bps: self.rng.bps(),
+                    duration: self.rng.time(),
+                });
+                assert_typed("set_reward_vesting", self.client.try_set_reward_vesting(&vesting));
+            }
+            27 => {
+                let seconds = self.rng.time();
+                assert_typed("set_cooldown", self.client.try_set_cooldown(&seconds));
+            }
+            28 => {
+                let user = self.address();
+                assert_typed("bump_position", self.client.try_bump_position(&user));
+                let lock = self.rng.time();
+                assert_typed("current_apr", self.client.try_current_apr(&lock));
+                let (amount, lock) = (self.rng.amount(), self.rng.time());
+                assert_typed("project_rewards", self.client.try_project_rewards(&amount, &lock));
+                assert_typed("get_pending_rewards", self.client.try_get_pending_rewards(&user));
+                assert_typed("get_claimable_vested", self.client.try_get_claimable_vested(&user));
+            }
+            29 => {
+                let new_admin = self.address();
+                assert_typed("transfer_admin", self.client.try_transfer_admin(&new_admin));
+                let candidate = self.address();
+                assert_typed("accept_admin", self.client.try_accept_admin(&candidate));
+            }
+            _ => {
+                // Time and sequence only move forward, as on a real ledger; time
+                // jumps may be large. Sequence stays below the minimum entry TTL
+                // so storage isn't archived.
+                let timestamp = match self.rng.below(4) {
+                    0 => u64::MAX,
+                    _ => self.env.ledger().timestamp().saturating_add(self.rng.below(100_000)),
+                };
+                let step = self.rng.below(300) as u32;
+                let sequence = self.env.ledger().sequence().saturating_add(step).min(MAX_SEQUENCE);
+                self.env.ledger().with_mut(|li| {
+                    li.timestamp = timestamp;
+                    li.sequence_number = sequence;
+                });
+            }
+        }
+    }
+}
+
+#[test]
+fn fuzz_entrypoints_fail_with_typed_errors() {
+    for seed in 1..=ROUNDS {
+        let env = Env::default();
+        env.mock_all_auths();
+        env.budget().reset_unlimited();
+
+        let admin = Address::generate(&env);
+        let token_id = env
+            .register_stellar_asset_contract_v2(Address::generate(&env))
+            .address();
+        let contract_id = env.register_contract(None, StakingContract);
+        let client = StakingContractClient::new(&env, &contract_id);
+        client.initialize(&admin, &token_id, &(RATE_SCALE / 100));
+
+        let users = [Address::generate(&env), Address::generate(&env), Address::generate(&env)];
+        let outsider = Address::generate(&env);
+        let minter = token::StellarAssetClient::new(&env, &token_id);
+        for holder in users.iter().chain([&admin, &outsider]) {
+            minter.mint(holder, &10_000);
+        }
+        client.fund_rewards(&admin, &5_000);
+        client.set_slasher(&admin, &outsider);
+
+        let mut harness = Harness {
+            env: &env,
+            client,
+            admin,
+            users,
+            outsider,
+            rng: Rng(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15)),
+        };
+        for _ in 0..STEPS {
+            harness.step();
+        }
+    }
+}
+
+#[test]
+fn fuzz_initialize_fails_with_typed_errors() {
+    let mut rng = Rng(0xDEAD_BEEF);
+    for _ in 0..32 {
+        let env = Env::default();
+        env.mock_all_auths();
+        let contract_id = env.register_contract(None, StakingContract);
+        let client = StakingContractClient::new(&env, &contract_id);
+
+        let (admin, token) = (Address::generate(&env), Address::generate(&env));
+        let rate = rng.rate();
+        let timestamp = rng.time();
+        env.ledger().with_mut(|li| li.timestamp = timestamp);
+        assert_typed("initialize", client.try_initialize(&admin, &token, &rate));
+        // A second call must be refused, not trap
+        assert_typed("initialize", client.try_initialize(&admin, &token, &rate));
+    }
+}
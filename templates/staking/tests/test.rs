@@ -1,14 +1,15 @@
 #![cfg(test)]
 
-use staking_contract::{StakingContract, StakingContractClient};
-use soroban_sdk::{testutils::{Address as _, Ledger}, token, Address, Env};
+use staking_contract::{Epoch, LockTier, PauseFlags, ReferralStats, RewardVesting, StakeCaps, StakingError, UnstakeRequest, RATE_SCALE, StakingContract, StakingContractClient};
+use staking_contract::math::{mul_div, mul_div_rounding, Rounding};
+use soroban_sdk::{symbol_short, testutils::{storage::{Instance as _, Persistent as _}, Address as _, Events, Ledger}, token, vec, xdr::ToXdr, Address, Bytes, BytesN, Env, FromVal, IntoVal};
 
-fn setup_test(env: &Env) -> (Address, Address, Address, token::Client, StakingContractClient) {
+fn setup_test(env: &Env) -> (Address, Address, Address, token::Client<'_>, StakingContractClient<'_>) {
     env.mock_all_auths();
     let admin = Address::generate(env);
     let user = Address::generate(env);
     let token_admin = Address::generate(env);
-    let token_id = env.register_stellar_asset_contract(token_admin);
+    let token_id = env.register_stellar_asset_contract_v2(token_admin).address();
     let token = token::Client::new(env, &token_id);
     let contract_id = env.register_contract(None, StakingContract);
     let client = StakingContractClient::new(env, &contract_id);
@@ -24,7 +25,7 @@ fn mint_tokens(env: &Env, token_id: &Address, to: &Address, amount: i128) {
 fn test_initialization() {
     let env = Env::default();
     let (admin, _, token_id, _, client) = setup_test(&env);
-    client.initialize(&admin, &token_id, &10i128);
+    client.initialize(&admin, &token_id, &(10 * RATE_SCALE));
     assert!(client.get_position(&Address::generate(&env)).is_none());
 }
 
@@ -32,7 +33,7 @@ fn test_initialization() {
 fn test_staking_and_rewards() {
     let env = Env::default();
     let (admin, user, token_id, token, client) = setup_test(&env);
-    client.initialize(&admin, &token_id, &1i128);
+    client.initialize(&admin, &token_id, &RATE_SCALE);
     mint_tokens(&env, &token_id, &user, 1000);
     
     client.stake(&user, &100i128, &100u64);
@@ -51,11 +52,11 @@ fn test_staking_and_rewards() {
 }
 
 #[test]
-#[should_panic(expected = "Assets are currently locked")]
+#[should_panic(expected = "Error(Contract, #4)")]
 fn test_unstake_locked_fail() {
     let env = Env::default();
     let (admin, user, token_id, _, client) = setup_test(&env);
-    client.initialize(&admin, &token_id, &1i128);
+    client.initialize(&admin, &token_id, &RATE_SCALE);
     mint_tokens(&env, &token_id, &user, 1000);
     client.stake(&user, &500i128, &1000u64);
     env.ledger().with_mut(|li| li.timestamp += 500);
@@ -85,7 +86,7 @@ fn test_unstake_partial_and_full() {
 fn test_successive_staking_lock_extension() {
     let env = Env::default();
     let (admin, user, token_id, _, client) = setup_test(&env);
-    client.initialize(&admin, &token_id, &1i128);
+    client.initialize(&admin, &token_id, &RATE_SCALE);
     mint_tokens(&env, &token_id, &user, 2000);
 
     client.stake(&user, &500i128, &100u64);
@@ -104,7 +105,7 @@ fn test_multi_user_distribution() {
     let (admin, user1, token_id, _, client) = setup_test(&env);
     let user2 = Address::generate(&env);
     
-    client.initialize(&admin, &token_id, &1i128);
+    client.initialize(&admin, &token_id, &RATE_SCALE);
     mint_tokens(&env, &token_id, &user1, 1000);
     mint_tokens(&env, &token_id, &user2, 1000);
 
@@ -118,13 +119,13 @@ fn test_multi_user_distribution() {
 }
 
 #[test]
-#[should_panic(expected = "Contract is currently paused")]
+#[should_panic(expected = "Error(Contract, #3)")]
 fn test_pause_staking() {
     let env = Env::default();
     let (admin, user, token_id, _, client) = setup_test(&env);
-    client.initialize(&admin, &token_id, &1i128);
+    client.initialize(&admin, &token_id, &RATE_SCALE);
     mint_tokens(&env, &token_id, &user, 1000);
-    client.pause();
+    client.pause(&admin);
     client.stake(&user, &100i128, &100u64);
 }
 
@@ -132,7 +133,7 @@ fn test_pause_staking() {
 fn test_admin_update_rate() {
     let env = Env::default();
     let (admin, user, token_id, _, client) = setup_test(&env);
-    client.initialize(&admin, &token_id, &1i128);
+    client.initialize(&admin, &token_id, &RATE_SCALE);
     mint_tokens(&env, &token_id, &user, 1000);
     client.stake(&user, &100i128, &100u64);
     
@@ -143,18 +144,18 @@ fn test_admin_update_rate() {
     mint_tokens(&env, &token_id, &user, 1);
     client.stake(&user, &1i128, &0u64);
     
-    client.update_reward_rate(&5i128);
+    client.update_reward_rate(&admin, &(5 * RATE_SCALE));
     env.ledger().with_mut(|li| li.timestamp += 10);
     // 1000 + (101 * 5 * 10) = 1000 + 5050 = 6050
     assert_eq!(client.get_pending_rewards(&user), 6050);
 }
 
 #[test]
-#[should_panic(expected = "Staking amount must be greater than zero")]
+#[should_panic(expected = "Error(Contract, #8)")]
 fn test_stake_zero_fail() {
     let env = Env::default();
     let (admin, user, token_id, _, client) = setup_test(&env);
-    client.initialize(&admin, &token_id, &1i128);
+    client.initialize(&admin, &token_id, &RATE_SCALE);
     client.stake(&user, &0i128, &100u64);
 }
 
@@ -162,7 +163,7 @@ fn test_stake_zero_fail() {
 fn test_large_values_no_overflow() {
     let env = Env::default();
     let (admin, user, token_id, _, client) = setup_test(&env);
-    client.initialize(&admin, &token_id, &1000i128); 
+    client.initialize(&admin, &token_id, &(1000 * RATE_SCALE)); 
     
     let large_mount = 1_000_000_000_000_000_000i128; 
     mint_tokens(&env, &token_id, &user, large_mount);
@@ -172,3 +173,1159 @@ fn test_large_values_no_overflow() {
     let rewards = client.get_pending_rewards(&user);
     assert!(rewards > 0);
 }
+
+fn setup_slashing(env: &Env) -> (Address, Address, Address, token::Client<'_>, StakingContractClient<'_>, Address, Address) {
+    let (admin, user, token_id, token, client) = setup_test(env);
+    let slasher = Address::generate(env);
+    let treasury = Address::generate(env);
+    client.initialize(&admin, &token_id, &RATE_SCALE);
+    client.set_slasher(&slasher, &treasury);
+    mint_tokens(env, &token_id, &user, 1000);
+    client.stake(&user, &1000i128, &100u64);
+    (admin, user, token_id, token, client, slasher, treasury)
+}
+
+#[test]
+fn test_slash_routes_funds_and_records_evidence() {
+    let env = Env::default();
+    let (_, user, _, token, client, slasher, treasury) = setup_slashing(&env);
+    env.ledger().with_mut(|li| li.timestamp = 10);
+
+    let evidence = BytesN::from_array(&env, &[1u8; 32]);
+    let slashed = client.slash(&slasher, &user, &2500u32, &evidence);
+
+    assert_eq!(slashed, 250);
+    assert_eq!(token.balance(&treasury), 250);
+    let position = client.get_position(&user).unwrap();
+    assert_eq!(position.amount, 750);
+    // Rewards earned on the full stake before the slash are kept
+    assert_eq!(position.accumulated_rewards, 10_000);
+
+    assert_eq!(client.get_slash_count(), 1);
+    let record = client.get_slash(&0).unwrap();
+    assert_eq!(record.user, user);
+    assert_eq!(record.amount, 250);
+    assert_eq!(record.evidence_hash, evidence);
+    assert_eq!(record.destination, treasury);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #18)")]
+fn test_slash_unauthorized_fail() {
+    let env = Env::default();
+    let (_, user, _, _, client, _, _) = setup_slashing(&env);
+    client.slash(&Address::generate(&env), &user, &100u32, &BytesN::from_array(&env, &[1u8; 32]));
+}
+
+#[test]
+fn test_admin_slash_builds_position_history() {
+    let env = Env::default();
+    let (admin, user, _, token, client, slasher, treasury) = setup_slashing(&env);
+    client.slash(&slasher, &user, &1000u32, &BytesN::from_array(&env, &[1u8; 32]));
+    env.ledger().with_mut(|li| li.timestamp = 5);
+    client.slash(&admin, &user, &5000u32, &BytesN::from_array(&env, &[2u8; 32]));
+
+    assert_eq!(token.balance(&treasury), 100 + 450);
+    let history = client.get_position_slashes(&user);
+    assert_eq!(history.len(), 2);
+    assert_eq!(history.get(0).unwrap().amount, 100);
+    assert_eq!(history.get(1).unwrap().amount, 450);
+    assert_eq!(history.get(1).unwrap().timestamp, 5);
+    assert_eq!(client.get_position_slashes(&Address::generate(&env)).len(), 0);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #20)")]
+fn test_slash_evidence_reuse_fail() {
+    let env = Env::default();
+    let (_, user, _, _, client, slasher, _) = setup_slashing(&env);
+    let evidence = BytesN::from_array(&env, &[1u8; 32]);
+    client.slash(&slasher, &user, &100u32, &evidence);
+    client.slash(&slasher, &user, &100u32, &evidence);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #19)")]
+fn test_slash_over_full_stake_fail() {
+    let env = Env::default();
+    let (_, user, _, _, client, slasher, _) = setup_slashing(&env);
+    client.slash(&slasher, &user, &10_001u32, &BytesN::from_array(&env, &[1u8; 32]));
+}
+
+#[test]
+fn test_slash_reaches_pending_unstake_request() {
+    let env = Env::default();
+    let (_, user, _, token, client, slasher, treasury) = setup_slashing(&env);
+    client.set_cooldown(&10u64);
+    env.ledger().with_mut(|li| li.timestamp = 100);
+    client.request_unstake(&user, &400i128);
+
+    // Half of the 600 still staked and half of the 400 on its way out
+    let slashed = client.slash(&slasher, &user, &5000u32, &BytesN::from_array(&env, &[1u8; 32]));
+    assert_eq!(slashed, 500);
+    assert_eq!(token.balance(&treasury), 500);
+    assert_eq!(client.get_position(&user).unwrap().amount, 300);
+    assert_eq!(client.get_unstake_request(&user).unwrap().amount, 200);
+
+    env.ledger().with_mut(|li| li.timestamp = 110);
+    assert_eq!(client.withdraw(&user), 200);
+    assert_eq!(token.balance(&user), 200);
+}
+
+#[test]
+fn test_full_slash_closes_position() {
+    let env = Env::default();
+    let (_, user, _, _, client, slasher, _) = setup_slashing(&env);
+    let position_id = client.get_position_id(&user).unwrap();
+
+    // Nothing accrued yet, so nothing is left once the stake is gone
+    client.slash(&slasher, &user, &10_000u32, &BytesN::from_array(&env, &[1u8; 32]));
+    assert!(client.get_position(&user).is_none());
+    assert_eq!(client.get_position_id(&user), None);
+    assert_eq!(client.owner_of(&position_id), None);
+}
+
+fn setup_tiers(env: &Env) -> (Address, token::Client<'_>, StakingContractClient<'_>) {
+    let (admin, user, token_id, token, client) = setup_test(env);
+    client.initialize(&admin, &token_id, &RATE_SCALE);
+    client.set_tiers(&vec![
+        env,
+        LockTier { min_duration: 100, multiplier_bps: 10_000 },
+        LockTier { min_duration: 200, multiplier_bps: 15_000 },
+        LockTier { min_duration: 300, multiplier_bps: 30_000 },
+    ]);
+    mint_tokens(env, &token_id, &user, 1000);
+    (user, token, client)
+}
+
+#[test]
+fn test_tier_multiplier_applies_until_lock_end() {
+    let env = Env::default();
+    let (user, _, client) = setup_tiers(&env);
+    client.stake(&user, &100i128, &300u64);
+    assert_eq!(client.get_position(&user).unwrap().multiplier_bps, 30_000);
+
+    env.ledger().with_mut(|li| li.timestamp = 100);
+    assert_eq!(client.get_pending_rewards(&user), 30_000);
+
+    // 300s at 3x, then 100s at 1x once the lock has ended
+    env.ledger().with_mut(|li| li.timestamp = 400);
+    assert_eq!(client.get_pending_rewards(&user), 90_000 + 10_000);
+}
+
+#[test]
+fn test_tier_reevaluated_on_restake() {
+    let env = Env::default();
+    let (user, _, client) = setup_tiers(&env);
+    client.stake(&user, &100i128, &200u64);
+    assert_eq!(client.get_position(&user).unwrap().multiplier_bps, 15_000);
+
+    // Only 50s of lock left, below every tier
+    env.ledger().with_mut(|li| li.timestamp = 150);
+    client.stake(&user, &100i128, &0u64);
+    let position = client.get_position(&user).unwrap();
+    assert_eq!(position.multiplier_bps, 10_000);
+    assert_eq!(position.accumulated_rewards, 100 * 150 * 3 / 2);
+}
+
+#[test]
+fn test_no_tiers_means_base_rate() {
+    let env = Env::default();
+    let (admin, user, token_id, _, client) = setup_test(&env);
+    client.initialize(&admin, &token_id, &RATE_SCALE);
+    assert_eq!(client.get_tiers().len(), 0);
+    mint_tokens(&env, &token_id, &user, 1000);
+    client.stake(&user, &100i128, &1_000u64);
+    assert_eq!(client.get_position(&user).unwrap().multiplier_bps, 10_000);
+}
+
+#[test]
+fn test_get_tiers_returns_configuration() {
+    let env = Env::default();
+    let (_, _, client) = setup_tiers(&env);
+    let tiers = client.get_tiers();
+    assert_eq!(tiers.len(), 3);
+    assert_eq!(tiers.get(2).unwrap(), LockTier { min_duration: 300, multiplier_bps: 30_000 });
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #15)")]
+fn test_set_tiers_unsorted_fail() {
+    let env = Env::default();
+    let (admin, _, token_id, _, client) = setup_test(&env);
+    client.initialize(&admin, &token_id, &RATE_SCALE);
+    client.set_tiers(&vec![
+        &env,
+        LockTier { min_duration: 200, multiplier_bps: 15_000 },
+        LockTier { min_duration: 100, multiplier_bps: 10_000 },
+    ]);
+}
+
+#[test]
+fn test_stake_unstake_and_claim_emit_events() {
+    let env = Env::default();
+    let (admin, user, token_id, _, client) = setup_test(&env);
+    client.initialize(&admin, &token_id, &RATE_SCALE);
+    mint_tokens(&env, &token_id, &user, 1000);
+    mint_tokens(&env, &token_id, &client.address, 1000);
+
+    client.stake(&user, &100i128, &10u64);
+    let (_, topics, data) = env.events().all().last().unwrap();
+    assert_eq!(topics, (symbol_short!("stake"), user.clone()).into_val(&env));
+    assert_eq!(<(i128, u64, u32)>::from_val(&env, &data), (100, 10, 10_000));
+
+    env.ledger().with_mut(|li| li.timestamp = 10);
+    client.claim_rewards(&user);
+    let (_, topics, data) = env.events().all().last().unwrap();
+    assert_eq!(topics, (symbol_short!("claim"), user.clone()).into_val(&env));
+    assert_eq!(i128::from_val(&env, &data), 1000);
+
+    client.unstake(&user, &40i128);
+    let (_, topics, data) = env.events().all().last().unwrap();
+    assert_eq!(topics, (symbol_short!("unstake"), user.clone()).into_val(&env));
+    assert_eq!(i128::from_val(&env, &data), 40);
+}
+
+#[test]
+fn test_admin_actions_emit_events() {
+    let env = Env::default();
+    let (admin, _, token_id, _, client) = setup_test(&env);
+    client.initialize(&admin, &token_id, &RATE_SCALE);
+
+    client.update_reward_rate(&admin, &(7 * RATE_SCALE));
+    let (_, topics, data) = env.events().all().last().unwrap();
+    assert_eq!(topics, (symbol_short!("rate"),).into_val(&env));
+    assert_eq!(i128::from_val(&env, &data), 7 * RATE_SCALE);
+
+    client.pause(&admin);
+    let (_, topics, _) = env.events().all().last().unwrap();
+    assert_eq!(topics, (symbol_short!("paused"),).into_val(&env));
+
+    client.unpause(&admin);
+    let (_, topics, _) = env.events().all().last().unwrap();
+    assert_eq!(topics, (symbol_short!("unpaused"),).into_val(&env));
+}
+
+#[test]
+fn test_fund_rewards_grows_budget() {
+    let env = Env::default();
+    let (admin, user, token_id, token, client) = setup_test(&env);
+    client.initialize(&admin, &token_id, &RATE_SCALE);
+    mint_tokens(&env, &token_id, &user, 1000);
+    mint_tokens(&env, &token_id, &admin, 500);
+
+    client.stake(&user, &400i128, &0u64);
+    assert_eq!(client.get_reward_budget(), 0);
+
+    client.fund_rewards(&admin, &500i128);
+    assert_eq!(token.balance(&client.address), 900);
+    assert_eq!(client.get_reward_budget(), 500);
+}
+
+#[test]
+fn test_claim_limited_to_budget() {
+    let env = Env::default();
+    let (admin, user, token_id, token, client) = setup_test(&env);
+    client.initialize(&admin, &token_id, &RATE_SCALE);
+    mint_tokens(&env, &token_id, &user, 1000);
+    mint_tokens(&env, &token_id, &admin, 1000);
+
+    client.stake(&user, &100i128, &0u64);
+    client.fund_rewards(&admin, &600i128);
+    env.ledger().with_mut(|li| li.timestamp = 10);
+
+    // 1000 accrued, only 600 funded: the rest stays owed and principal is untouched
+    client.claim_rewards(&user);
+    assert_eq!(token.balance(&user), 900 + 600);
+    assert_eq!(client.get_pending_rewards(&user), 400);
+    assert_eq!(client.get_reward_budget(), 0);
+    assert_eq!(token.balance(&client.address), 100);
+
+    client.fund_rewards(&admin, &400i128);
+    client.claim_rewards(&user);
+    assert_eq!(token.balance(&user), 900 + 1000);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #11)")]
+fn test_claim_with_empty_budget_fail() {
+    let env = Env::default();
+    let (admin, user, token_id, _, client) = setup_test(&env);
+    client.initialize(&admin, &token_id, &RATE_SCALE);
+    mint_tokens(&env, &token_id, &user, 1000);
+    client.stake(&user, &100i128, &0u64);
+    env.ledger().with_mut(|li| li.timestamp = 10);
+    client.claim_rewards(&user);
+}
+
+#[test]
+fn test_compound_restakes_rewards() {
+    let env = Env::default();
+    let (admin, user, token_id, token, client) = setup_test(&env);
+    client.initialize(&admin, &token_id, &RATE_SCALE);
+    mint_tokens(&env, &token_id, &user, 1000);
+    mint_tokens(&env, &token_id, &client.address, 5000);
+
+    client.stake(&user, &100i128, &50u64);
+    env.ledger().with_mut(|li| li.timestamp = 10);
+    assert_eq!(client.compound(&user), 1000);
+
+    let position = client.get_position(&user).unwrap();
+    assert_eq!(position.amount, 1100);
+    assert_eq!(position.accumulated_rewards, 0);
+    assert_eq!(position.lock_end_time, 50);
+    assert_eq!(client.get_reward_budget(), 4000);
+    assert_eq!(token.balance(&user), 900);
+
+    // The compounded principal earns from now on
+    env.ledger().with_mut(|li| li.timestamp = 11);
+    assert_eq!(client.get_pending_rewards(&user), 1100);
+}
+
+#[test]
+fn test_compound_capped_by_budget() {
+    let env = Env::default();
+    let (admin, user, token_id, _, client) = setup_test(&env);
+    client.initialize(&admin, &token_id, &RATE_SCALE);
+    mint_tokens(&env, &token_id, &user, 1000);
+    mint_tokens(&env, &token_id, &client.address, 300);
+
+    client.stake(&user, &100i128, &0u64);
+    env.ledger().with_mut(|li| li.timestamp = 10);
+    assert_eq!(client.compound(&user), 300);
+    let position = client.get_position(&user).unwrap();
+    assert_eq!((position.amount, position.accumulated_rewards), (400, 700));
+}
+
+#[test]
+fn test_auto_compound_on_accrual() {
+    let env = Env::default();
+    let (admin, user, token_id, _, client) = setup_test(&env);
+    client.initialize(&admin, &token_id, &RATE_SCALE);
+    mint_tokens(&env, &token_id, &user, 1000);
+    mint_tokens(&env, &token_id, &client.address, 5000);
+
+    client.stake(&user, &100i128, &0u64);
+    client.set_auto_compound(&user, &true);
+    env.ledger().with_mut(|li| li.timestamp = 10);
+
+    client.stake(&user, &50i128, &0u64);
+    let position = client.get_position(&user).unwrap();
+    assert!(position.auto_compound);
+    assert_eq!((position.amount, position.accumulated_rewards), (1150, 0));
+
+    client.set_auto_compound(&user, &false);
+    env.ledger().with_mut(|li| li.timestamp = 20);
+    client.unstake(&user, &150i128);
+    let position = client.get_position(&user).unwrap();
+    assert_eq!((position.amount, position.accumulated_rewards), (1000, 11_500));
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #6)")]
+fn test_compound_without_rewards_fail() {
+    let env = Env::default();
+    let (admin, user, token_id, _, client) = setup_test(&env);
+    client.initialize(&admin, &token_id, &RATE_SCALE);
+    mint_tokens(&env, &token_id, &user, 1000);
+    client.stake(&user, &100i128, &0u64);
+    client.compound(&user);
+}
+
+#[test]
+fn test_emergency_withdraw_ignores_lock_and_forfeits_rewards() {
+    let env = Env::default();
+    let (admin, user, token_id, token, client) = setup_test(&env);
+    client.initialize(&admin, &token_id, &RATE_SCALE);
+    mint_tokens(&env, &token_id, &user, 1000);
+    mint_tokens(&env, &token_id, &client.address, 5000);
+
+    client.stake(&user, &400i128, &1_000u64);
+    env.ledger().with_mut(|li| li.timestamp = 10);
+    client.set_emergency(&true);
+    assert!(client.is_emergency());
+
+    assert_eq!(client.emergency_withdraw(&user), 400);
+    assert_eq!(token.balance(&user), 1000);
+    assert!(client.get_position(&user).is_none());
+    assert_eq!(client.get_pending_rewards(&user), 0);
+    assert_eq!(client.get_reward_budget(), 5000);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #16)")]
+fn test_emergency_withdraw_without_emergency_fail() {
+    let env = Env::default();
+    let (admin, user, token_id, _, client) = setup_test(&env);
+    client.initialize(&admin, &token_id, &RATE_SCALE);
+    mint_tokens(&env, &token_id, &user, 1000);
+    client.stake(&user, &400i128, &1_000u64);
+    client.emergency_withdraw(&user);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #17)")]
+fn test_claim_during_emergency_fail() {
+    let env = Env::default();
+    let (admin, user, token_id, _, client) = setup_test(&env);
+    client.initialize(&admin, &token_id, &RATE_SCALE);
+    mint_tokens(&env, &token_id, &user, 1000);
+    mint_tokens(&env, &token_id, &client.address, 5000);
+    client.stake(&user, &400i128, &0u64);
+    env.ledger().with_mut(|li| li.timestamp = 10);
+    client.set_emergency(&true);
+    client.claim_rewards(&user);
+}
+
+#[test]
+fn test_emission_schedule_steps_down() {
+    let env = Env::default();
+    let (admin, user, token_id, _, client) = setup_test(&env);
+    client.initialize(&admin, &token_id, &(4 * RATE_SCALE));
+    mint_tokens(&env, &token_id, &user, 1000);
+    client.stake(&user, &100i128, &0u64);
+
+    client.set_schedule(&admin, &vec![
+        &env,
+        Epoch { start_time: 10, rate: 2 * RATE_SCALE },
+        Epoch { start_time: 20, rate: RATE_SCALE },
+    ]);
+    assert_eq!(client.get_current_rate(), 4 * RATE_SCALE);
+    assert_eq!(client.get_upcoming_epochs().len(), 2);
+
+    // 10s at 4, 10s at 2, 5s at 1
+    env.ledger().with_mut(|li| li.timestamp = 25);
+    assert_eq!(client.get_pending_rewards(&user), 100 * (40 + 20 + 5));
+    assert_eq!(client.get_current_rate(), RATE_SCALE);
+    assert_eq!(client.get_upcoming_epochs().len(), 0);
+}
+
+#[test]
+fn test_rate_update_not_retroactive() {
+    let env = Env::default();
+    let (admin, user, token_id, _, client) = setup_test(&env);
+    client.initialize(&admin, &token_id, &(3 * RATE_SCALE));
+    mint_tokens(&env, &token_id, &user, 1000);
+    client.stake(&user, &100i128, &0u64);
+    client.set_schedule(&admin, &vec![&env, Epoch { start_time: 50, rate: RATE_SCALE }]);
+
+    env.ledger().with_mut(|li| li.timestamp = 10);
+    client.update_reward_rate(&admin, &(5 * RATE_SCALE));
+    assert_eq!(client.get_upcoming_epochs().len(), 0);
+
+    env.ledger().with_mut(|li| li.timestamp = 20);
+    assert_eq!(client.get_pending_rewards(&user), 100 * (30 + 50));
+    assert_eq!(client.get_current_rate(), 5 * RATE_SCALE);
+}
+
+#[test]
+fn test_many_rate_changes_keep_accrual_cheap() {
+    let env = Env::default();
+    let (admin, user, token_id, _, client) = setup_test(&env);
+    client.initialize(&admin, &token_id, &RATE_SCALE);
+    mint_tokens(&env, &token_id, &user, 1000);
+    client.stake(&user, &100i128, &0u64);
+
+    // Alternate 2x and 1x every second; each call must fit the default budget
+    for t in 1..=300u64 {
+        env.budget().reset_default();
+        env.ledger().with_mut(|li| li.timestamp = t);
+        client.update_reward_rate(&admin, &(RATE_SCALE * (t % 2 + 1) as i128));
+    }
+    env.budget().reset_default();
+    env.ledger().with_mut(|li| li.timestamp = 301);
+    assert_eq!(client.get_pending_rewards(&user), 100 * (1 + 150 * 2 + 150));
+    env.budget().reset_default();
+    client.stake(&user, &100i128, &0u64);
+    assert_eq!(client.get_position(&user).unwrap().accumulated_rewards, 100 * 451);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #13)")]
+fn test_schedule_past_epoch_fail() {
+    let env = Env::default();
+    let (admin, _, token_id, _, client) = setup_test(&env);
+    env.ledger().with_mut(|li| li.timestamp = 100);
+    client.initialize(&admin, &token_id, &RATE_SCALE);
+    client.set_schedule(&admin, &vec![&env, Epoch { start_time: 100, rate: RATE_SCALE }]);
+}
+
+#[test]
+fn test_errors_are_typed() {
+    let env = Env::default();
+    let (admin, user, token_id, _, client) = setup_test(&env);
+    assert_eq!(client.try_pause(&admin), Err(Ok(StakingError::NotInitialized)));
+    client.initialize(&admin, &token_id, &RATE_SCALE);
+    assert_eq!(client.try_initialize(&admin, &token_id, &RATE_SCALE), Err(Ok(StakingError::AlreadyInitialized)));
+    assert_eq!(client.try_unstake(&user, &1i128), Err(Ok(StakingError::NoPosition)));
+    assert_eq!(client.try_update_reward_rate(&admin, &-RATE_SCALE), Err(Ok(StakingError::NegativeRate)));
+}
+
+#[test]
+fn test_two_step_admin_transfer() {
+    let env = Env::default();
+    let (admin, user, token_id, _, client) = setup_test(&env);
+    client.initialize(&admin, &token_id, &RATE_SCALE);
+    let new_admin = Address::generate(&env);
+
+    client.transfer_admin(&new_admin);
+    assert_eq!(client.get_admin(), Some(admin));
+    assert_eq!(client.get_pending_admin(), Some(new_admin.clone()));
+    assert_eq!(client.try_accept_admin(&user), Err(Ok(StakingError::NotPendingAdmin)));
+
+    client.accept_admin(&new_admin);
+    assert_eq!(client.get_admin(), Some(new_admin.clone()));
+    assert_eq!(client.get_pending_admin(), None);
+    let (_, topics, _) = env.events().all().last().unwrap();
+    assert_eq!(topics, (symbol_short!("admin"),).into_val(&env));
+}
+
+#[test]
+fn test_operator_runs_routine_actions() {
+    let env = Env::default();
+    let (admin, user, token_id, _, client) = setup_test(&env);
+    client.initialize(&admin, &token_id, &RATE_SCALE);
+    let operator = Address::generate(&env);
+    client.set_operator(&operator);
+    assert_eq!(client.get_operator(), Some(operator.clone()));
+
+    client.update_reward_rate(&operator, &(3 * RATE_SCALE));
+    assert_eq!(client.get_current_rate(), 3 * RATE_SCALE);
+    client.pause(&operator);
+    client.unpause(&admin);
+    assert_eq!(client.try_pause(&user), Err(Ok(StakingError::NotOperator)));
+}
+
+#[test]
+fn test_fractional_rate_accrues() {
+    let env = Env::default();
+    let (admin, user, token_id, _, client) = setup_test(&env);
+    // ~5% APR: 0.05 / 31_536_000 per second, in fixed point
+    let rate = 5 * RATE_SCALE / 100 / 31_536_000 + 1;
+    client.initialize(&admin, &token_id, &rate);
+    assert_eq!(client.get_rate_scale(), RATE_SCALE);
+
+    let stake = 1_000_000_000_000i128;
+    mint_tokens(&env, &token_id, &user, stake);
+    client.stake(&user, &stake, &0u64);
+    env.ledger().with_mut(|li| li.timestamp = 31_536_000);
+    let rewards = client.get_pending_rewards(&user);
+    assert_eq!(rewards, stake * rate * 31_536_000 / RATE_SCALE);
+    assert_eq!(rewards / 1_000_000_000, 50); // 5% of the stake
+}
+
+#[test]
+fn test_mul_div() {
+    let env = Env::default();
+    assert_eq!(mul_div(&env, 10, 20, 3), Some(66));
+    assert_eq!(mul_div(&env, 0, i128::MAX, 7), Some(0));
+    // Intermediate product overflows i128, result fits
+    assert_eq!(mul_div(&env, i128::MAX, 4, 8), Some(i128::MAX / 2));
+    assert_eq!(mul_div(&env, i128::MAX, i128::MAX, i128::MAX), Some(i128::MAX));
+    assert_eq!(mul_div(&env, i128::MAX, 2, 1), None);
+    assert_eq!(mul_div(&env, -1, 2, 1), None);
+    assert_eq!(mul_div(&env, 1, 2, 0), None);
+}
+
+#[test]
+fn test_mul_div_rounding() {
+    let env = Env::default();
+    assert_eq!(mul_div_rounding(&env, 10, 20, 3, Rounding::Down), Some(66));
+    assert_eq!(mul_div_rounding(&env, 10, 20, 3, Rounding::Up), Some(67));
+    // Exact quotients don't round up
+    assert_eq!(mul_div_rounding(&env, 10, 30, 3, Rounding::Up), Some(100));
+    assert_eq!(mul_div_rounding(&env, 0, 5, 3, Rounding::Up), Some(0));
+    // 256-bit path: remainder detected past the i128 product
+    assert_eq!(mul_div_rounding(&env, i128::MAX, 4, 8, Rounding::Up), Some(i128::MAX / 2 + 1));
+    assert_eq!(mul_div_rounding(&env, i128::MAX, 2, 2, Rounding::Up), Some(i128::MAX));
+    // Rounding up past i128::MAX doesn't fit
+    assert_eq!(mul_div_rounding(&env, i128::MAX, 3, 2, Rounding::Up), None);
+    assert_eq!(mul_div_rounding(&env, 1, -2, 1, Rounding::Up), None);
+
+    // Up is Down plus one exactly when the division leaves a remainder
+    for a in [1i128, 7, 999, RATE_SCALE, i128::MAX / 3] {
+        for b in [1i128, 3, 10_000, RATE_SCALE] {
+            for d in [1i128, 2, 7, 10_000, RATE_SCALE * 3] {
+                let Some(down) = mul_div_rounding(&env, a, b, d, Rounding::Down) else {
+                    continue;
+                };
+                let up = mul_div_rounding(&env, a, b, d, Rounding::Up).unwrap();
+                let exact = a.checked_mul(b).map(|p| p % d == 0);
+                match exact {
+                    Some(true) => assert_eq!(up, down),
+                    Some(false) => assert_eq!(up, down + 1),
+                    None => assert!(up == down || up == down + 1),
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_stake_caps() {
+    let env = Env::default();
+    let (admin, user, token_id, _, client) = setup_test(&env);
+    client.initialize(&admin, &token_id, &RATE_SCALE);
+    let other = Address::generate(&env);
+    mint_tokens(&env, &token_id, &user, 1000);
+    mint_tokens(&env, &token_id, &other, 1000);
+    assert_eq!(client.get_remaining_capacity(&user), i128::MAX);
+
+    let caps = StakeCaps { max_stake_per_user: Some(400), max_total_staked: Some(600) };
+    client.set_stake_caps(&caps);
+    assert_eq!(client.get_stake_caps(), caps);
+
+    client.stake(&user, &300i128, &0u64);
+    assert_eq!(client.get_remaining_capacity(&user), 100);
+    assert_eq!(client.try_stake(&user, &101i128, &0u64), Err(Ok(StakingError::UserCapExceeded)));
+
+    assert_eq!(client.get_remaining_capacity(&other), 300);
+    client.stake(&other, &300i128, &0u64);
+    assert_eq!(client.get_remaining_capacity(&user), 0);
+    assert_eq!(client.try_stake(&user, &1i128, &0u64), Err(Ok(StakingError::TotalCapExceeded)));
+}
+
+#[test]
+fn test_allowlist_gates_staking() {
+    let env = Env::default();
+    let (admin, user, token_id, _, client) = setup_test(&env);
+    client.initialize(&admin, &token_id, &RATE_SCALE);
+    mint_tokens(&env, &token_id, &user, 1000);
+    client.set_allowlist(&true);
+
+    assert!(!client.is_allowed(&user));
+    assert_eq!(client.try_stake(&user, &100i128, &0u64), Err(Ok(StakingError::NotAllowed)));
+    client.set_allowed(&user, &true);
+    client.stake(&user, &100i128, &0u64);
+
+    client.set_allowed(&user, &false);
+    assert_eq!(client.try_stake(&user, &100i128, &0u64), Err(Ok(StakingError::NotAllowed)));
+    client.set_allowlist(&false);
+    assert!(client.is_allowed(&user));
+}
+
+#[test]
+fn test_allowlist_merkle_proof() {
+    let env = Env::default();
+    let (admin, user, token_id, _, client) = setup_test(&env);
+    client.initialize(&admin, &token_id, &RATE_SCALE);
+    client.set_allowlist(&true);
+
+    // Two-leaf tree: root = sha256(sorted(leaf_user, leaf_other))
+    let other = Address::generate(&env);
+    let leaf = |addr: &Address| -> BytesN<32> { env.crypto().sha256(&addr.clone().to_xdr(&env)).into() };
+    let (a, b) = (leaf(&user), leaf(&other));
+    let (lo, hi) = if a <= b { (a.clone(), b.clone()) } else { (b.clone(), a.clone()) };
+    let mut pair = Bytes::from_array(&env, &lo.to_array());
+    pair.extend_from_array(&hi.to_array());
+    let root: BytesN<32> = env.crypto().sha256(&pair).into();
+    client.set_allowlist_root(&Some(root));
+
+    let outsider = Address::generate(&env);
+    assert_eq!(client.try_prove_allowed(&outsider, &vec![&env, a.clone()]), Err(Ok(StakingError::InvalidProof)));
+    client.prove_allowed(&user, &vec![&env, b]);
+    assert!(client.is_allowed(&user));
+    assert!(!client.is_allowed(&other));
+}
+
+#[test]
+fn test_claim_vests_rewards() {
+    let env = Env::default();
+    let (admin, user, token_id, token, client) = setup_test(&env);
+    client.initialize(&admin, &token_id, &RATE_SCALE);
+    mint_tokens(&env, &token_id, &user, 1000);
+    mint_tokens(&env, &token_id, &client.address, 10_000);
+    client.set_reward_vesting(&Some(RewardVesting { instant_bps: 2500, duration: 100 }));
+    client.stake(&user, &100i128, &0u64);
+
+    // 4000 earned: 1000 now, 3000 vesting from t=40 to t=140
+    env.ledger().with_mut(|li| li.timestamp = 40);
+    client.claim_rewards(&user);
+    assert_eq!(token.balance(&user), 900 + 1000);
+    assert!(client.get_vesting(&user).is_some());
+    // Unvested rewards are reserved from the budget
+    assert_eq!(client.get_reward_budget(), 10_000 - 4000);
+
+    env.ledger().with_mut(|li| li.timestamp = 90);
+    assert_eq!(client.get_claimable_vested(&user), 1500);
+    assert_eq!(client.claim_vested(&user), 1500);
+    assert_eq!(client.try_claim_vested(&user), Err(Ok(StakingError::NothingVested)));
+
+    env.ledger().with_mut(|li| li.timestamp = 200);
+    assert_eq!(client.claim_vested(&user), 1500);
+    assert_eq!(token.balance(&user), 900 + 4000);
+    assert_eq!(client.get_vesting(&user), None);
+}
+
+#[test]
+fn test_invalid_reward_vesting() {
+    let env = Env::default();
+    let (admin, _, token_id, _, client) = setup_test(&env);
+    client.initialize(&admin, &token_id, &RATE_SCALE);
+    let over = RewardVesting { instant_bps: 10_001, duration: 100 };
+    assert_eq!(client.try_set_reward_vesting(&Some(over)), Err(Ok(StakingError::InvalidVesting)));
+    let instant = RewardVesting { instant_bps: 5000, duration: 0 };
+    assert_eq!(client.try_set_reward_vesting(&Some(instant)), Err(Ok(StakingError::InvalidVesting)));
+}
+
+#[test]
+fn test_votes_checkpointed() {
+    let env = Env::default();
+    let (admin, user, token_id, _, client) = setup_test(&env);
+    client.initialize(&admin, &token_id, &0i128);
+    mint_tokens(&env, &token_id, &user, 1000);
+
+    env.ledger().with_mut(|li| li.timestamp = 10);
+    client.stake(&user, &300i128, &0u64);
+    env.ledger().with_mut(|li| li.timestamp = 20);
+    client.stake(&user, &200i128, &0u64);
+    env.ledger().with_mut(|li| li.timestamp = 30);
+    client.unstake(&user, &400i128);
+
+    assert_eq!(client.get_votes(&user), 100);
+    assert_eq!(client.balance(&user), 100);
+    assert_eq!(client.get_past_votes(&user, &5), 0);
+    assert_eq!(client.get_past_votes(&user, &10), 300);
+    assert_eq!(client.get_past_votes(&user, &25), 500);
+    assert_eq!(client.get_past_total_staked(&15), 300);
+    assert_eq!(client.get_past_total_staked(&30), 100);
+}
+
+#[test]
+fn test_long_checkpoint_history() {
+    let env = Env::default();
+    let (admin, user, token_id, _, client) = setup_test(&env);
+    client.initialize(&admin, &token_id, &0i128);
+    mint_tokens(&env, &token_id, &user, 1000);
+
+    // Well past what a single Vec entry could hold; each call must still
+    // fit the default budget on its own
+    for t in 1..=480u64 {
+        env.budget().reset_default();
+        env.ledger().with_mut(|li| li.timestamp = t * 10);
+        if t % 2 == 1 {
+            client.stake(&user, &1i128, &0u64);
+        } else {
+            client.unstake(&user, &1i128);
+        }
+    }
+    client.stake(&user, &5i128, &0u64);
+
+    assert_eq!(client.get_votes(&user), 5);
+    assert_eq!(client.get_past_votes(&user, &5), 0);
+    assert_eq!(client.get_past_votes(&user, &3015), 1);
+    assert_eq!(client.get_past_votes(&user, &3020), 0);
+    assert_eq!(client.get_past_total_staked(&4799), 1);
+    assert_eq!(client.get_past_total_staked(&4800), 5);
+}
+
+#[test]
+fn test_votes_weighted_until_lock_end() {
+    let env = Env::default();
+    let (admin, user, token_id, _, client) = setup_test(&env);
+    client.initialize(&admin, &token_id, &0i128);
+    mint_tokens(&env, &token_id, &user, 1000);
+    client.set_tiers(&vec![&env, LockTier { min_duration: 100, multiplier_bps: 20_000 }]);
+    client.stake(&user, &100i128, &100u64);
+
+    assert_eq!(client.get_votes(&user), 100);
+    client.set_vote_weighting(&true);
+    assert_eq!(client.get_votes(&user), 200);
+    assert_eq!(client.get_past_votes(&user, &99), 200);
+    env.ledger().with_mut(|li| li.timestamp = 100);
+    assert_eq!(client.get_votes(&user), 100);
+}
+
+#[test]
+fn test_position_token_lifecycle() {
+    let env = Env::default();
+    let (admin, user, token_id, _, client) = setup_test(&env);
+    client.initialize(&admin, &token_id, &RATE_SCALE);
+    mint_tokens(&env, &token_id, &user, 1000);
+    let buyer = Address::generate(&env);
+
+    client.stake(&user, &100i128, &50u64);
+    client.stake(&user, &100i128, &50u64);
+    let id = client.get_position_id(&user).unwrap();
+    assert_eq!(client.owner_of(&id), Some(user.clone()));
+
+    env.ledger().with_mut(|li| li.timestamp = 10);
+    client.transfer_position(&user, &user, &buyer, &id);
+    assert!(client.get_position(&user).is_none());
+    let position = client.get_position(&buyer).unwrap();
+    assert_eq!(position.amount, 200);
+    assert_eq!(position.lock_end_time, 50);
+    assert_eq!(client.get_pending_rewards(&buyer), 2000);
+    assert_eq!(client.owner_of(&id), Some(buyer.clone()));
+    assert_eq!(client.get_votes(&user), 0);
+    assert_eq!(client.get_votes(&buyer), 200);
+
+    // Burned on full unstake
+    env.ledger().with_mut(|li| li.timestamp = 50);
+    mint_tokens(&env, &token_id, &client.address, 10_000);
+    client.claim_rewards(&buyer);
+    client.unstake(&buyer, &200i128);
+    assert_eq!(client.owner_of(&id), None);
+    assert_eq!(client.get_position_id(&buyer), None);
+}
+
+#[test]
+fn test_position_burned_when_last_rewards_claimed() {
+    let env = Env::default();
+    let (admin, user, token_id, _, client) = setup_test(&env);
+    client.initialize(&admin, &token_id, &RATE_SCALE);
+    mint_tokens(&env, &token_id, &user, 1000);
+    mint_tokens(&env, &token_id, &admin, 1000);
+    client.stake(&user, &100i128, &0u64);
+    let id = client.get_position_id(&user).unwrap();
+
+    // Unstaking everything leaves the unclaimed rewards, and the token, in place
+    env.ledger().with_mut(|li| li.timestamp = 10);
+    client.unstake(&user, &100i128);
+    assert_eq!(client.get_position(&user).unwrap().accumulated_rewards, 1000);
+    assert_eq!(client.owner_of(&id), Some(user.clone()));
+
+    // A claim the budget only partly covers keeps it open
+    client.fund_rewards(&admin, &400);
+    client.claim_rewards(&user);
+    assert_eq!(client.owner_of(&id), Some(user.clone()));
+
+    client.fund_rewards(&admin, &600);
+    client.claim_rewards(&user);
+    assert!(client.get_position(&user).is_none());
+    assert_eq!(client.owner_of(&id), None);
+    assert_eq!(client.get_position_id(&user), None);
+}
+
+#[test]
+fn test_position_transfer_by_approved_spender() {
+    let env = Env::default();
+    let (admin, user, token_id, _, client) = setup_test(&env);
+    client.initialize(&admin, &token_id, &RATE_SCALE);
+    mint_tokens(&env, &token_id, &user, 1000);
+    let (lender, other) = (Address::generate(&env), Address::generate(&env));
+    client.stake(&user, &100i128, &0u64);
+    let id = client.get_position_id(&user).unwrap();
+
+    assert_eq!(client.try_transfer_position(&lender, &user, &lender, &id), Err(Ok(StakingError::NotPositionOwner)));
+    client.approve_position(&user, &Some(lender.clone()), &id);
+    assert_eq!(client.get_position_approval(&id), Some(lender.clone()));
+
+    mint_tokens(&env, &token_id, &other, 100);
+    client.stake(&other, &100i128, &0u64);
+    assert_eq!(client.try_transfer_position(&lender, &user, &other, &id), Err(Ok(StakingError::PositionExists)));
+
+    client.transfer_position(&lender, &user, &lender, &id);
+    assert_eq!(client.owner_of(&id), Some(lender));
+    assert_eq!(client.get_position_approval(&id), None);
+}
+
+#[test]
+fn test_extend_lock_raises_multiplier() {
+    let env = Env::default();
+    let (admin, user, token_id, _, client) = setup_test(&env);
+    client.initialize(&admin, &token_id, &RATE_SCALE);
+    mint_tokens(&env, &token_id, &user, 1000);
+    client.set_tiers(&vec![&env, LockTier { min_duration: 100, multiplier_bps: 20_000 }]);
+    client.stake(&user, &100i128, &50u64);
+    assert_eq!(client.get_position(&user).unwrap().multiplier_bps, 10_000);
+
+    env.ledger().with_mut(|li| li.timestamp = 10);
+    client.extend_lock(&user, &60u64);
+    let position = client.get_position(&user).unwrap();
+    assert_eq!(position.lock_end_time, 110);
+    assert_eq!(position.multiplier_bps, 20_000);
+    // 10s at 1x before the extension, 10s at 2x after
+    env.ledger().with_mut(|li| li.timestamp = 20);
+    assert_eq!(client.get_pending_rewards(&user), 100 * (10 + 20));
+}
+
+#[test]
+fn test_relock() {
+    let env = Env::default();
+    let (admin, user, token_id, _, client) = setup_test(&env);
+    client.initialize(&admin, &token_id, &RATE_SCALE);
+    mint_tokens(&env, &token_id, &user, 1000);
+    client.stake(&user, &100i128, &50u64);
+
+    env.ledger().with_mut(|li| li.timestamp = 10);
+    assert_eq!(client.try_relock(&user, &40u64), Err(Ok(StakingError::LockNotExtended)));
+    assert_eq!(client.try_extend_lock(&user, &0u64), Err(Ok(StakingError::LockNotExtended)));
+
+    // An expired lock restarts from now
+    env.ledger().with_mut(|li| li.timestamp = 80);
+    client.relock(&user, &30u64);
+    assert_eq!(client.get_position(&user).unwrap().lock_end_time, 110);
+    assert_eq!(client.try_unstake(&user, &100i128), Err(Ok(StakingError::Locked)));
+}
+
+#[test]
+fn test_referral_bonus() {
+    let env = Env::default();
+    let (admin, user, token_id, token, client) = setup_test(&env);
+    client.initialize(&admin, &token_id, &RATE_SCALE);
+    mint_tokens(&env, &token_id, &user, 1000);
+    mint_tokens(&env, &token_id, &client.address, 10_000);
+    let referrer = Address::generate(&env);
+    client.set_referral_bps(&1000u32);
+
+    client.stake_with_referrer(&user, &100i128, &0u64, &referrer);
+    assert_eq!(client.get_referrer(&user), Some(referrer.clone()));
+    assert_eq!(client.try_stake_with_referrer(&user, &100i128, &0u64, &admin), Err(Ok(StakingError::InvalidReferrer)));
+
+    env.ledger().with_mut(|li| li.timestamp = 20);
+    client.claim_rewards(&user);
+    assert_eq!(client.get_referral_stats(&referrer), ReferralStats { referees: 1, total_earned: 200, unclaimed: 200 });
+    // The bonus is reserved from the budget until claimed
+    assert_eq!(client.get_reward_budget(), 10_000 - 2000 - 200);
+
+    assert_eq!(client.claim_referral_rewards(&referrer), 200);
+    assert_eq!(token.balance(&referrer), 200);
+    assert_eq!(client.try_claim_referral_rewards(&referrer), Err(Ok(StakingError::NoRewards)));
+}
+
+#[test]
+fn test_self_referral_fail() {
+    let env = Env::default();
+    let (admin, user, token_id, _, client) = setup_test(&env);
+    client.initialize(&admin, &token_id, &RATE_SCALE);
+    mint_tokens(&env, &token_id, &user, 1000);
+    assert_eq!(client.try_stake_with_referrer(&user, &100i128, &0u64, &user), Err(Ok(StakingError::InvalidReferrer)));
+    assert_eq!(client.try_set_referral_bps(&10_001u32), Err(Ok(StakingError::InvalidReferralBps)));
+}
+
+#[test]
+fn test_apr_and_projection() {
+    let env = Env::default();
+    let (admin, _, token_id, _, client) = setup_test(&env);
+    // ~3.15% a year: 10^-9 units per staked unit per second
+    let rate = RATE_SCALE / 1_000_000_000;
+    client.initialize(&admin, &token_id, &rate);
+    client.set_tiers(&vec![&env, LockTier { min_duration: 1000, multiplier_bps: 15_000 }]);
+
+    assert_eq!(client.current_apr(&0u64), 315);
+    assert_eq!(client.current_apr(&1000u64), 473);
+
+    // 1000s boosted at 1.5x
+    let amount = 1_000_000_000_000i128;
+    assert_eq!(client.project_rewards(&amount, &1000u64), 1_500_000);
+
+    // Projections follow upcoming epochs
+    client.set_schedule(&admin, &vec![&env, Epoch { start_time: 500, rate: 0 }]);
+    assert_eq!(client.project_rewards(&amount, &1000u64), 750_000);
+}
+
+#[test]
+fn test_unstake_cooldown() {
+    let env = Env::default();
+    let (admin, user, token_id, token, client) = setup_test(&env);
+    client.initialize(&admin, &token_id, &RATE_SCALE);
+    mint_tokens(&env, &token_id, &user, 1000);
+    client.set_cooldown(&100u64);
+    client.stake(&user, &500i128, &0u64);
+
+    env.ledger().with_mut(|li| li.timestamp = 10);
+    assert_eq!(client.try_unstake(&user, &100i128), Err(Ok(StakingError::CooldownRequired)));
+    let request = client.request_unstake(&user, &200i128);
+    assert_eq!(request, UnstakeRequest { amount: 200, available_at: 110 });
+    assert_eq!(client.get_position(&user).unwrap().amount, 300);
+
+    // The pending amount no longer earns
+    env.ledger().with_mut(|li| li.timestamp = 20);
+    assert_eq!(client.get_pending_rewards(&user), 500 * 10 + 300 * 10);
+    assert_eq!(client.try_withdraw(&user), Err(Ok(StakingError::CooldownActive)));
+
+    env.ledger().with_mut(|li| li.timestamp = 110);
+    assert_eq!(client.withdraw(&user), 200);
+    assert_eq!(token.balance(&user), 700);
+    assert_eq!(client.get_unstake_request(&user), None);
+    assert_eq!(client.try_withdraw(&user), Err(Ok(StakingError::NoUnstakeRequest)));
+}
+
+#[test]
+fn test_position_ttl_covers_lock() {
+    let env = Env::default();
+    let (admin, user, token_id, _, client) = setup_test(&env);
+    client.initialize(&admin, &token_id, &RATE_SCALE);
+    mint_tokens(&env, &token_id, &user, 1000);
+    let lock = 100 * 24 * 3600;
+    client.stake(&user, &100i128, &lock);
+
+    let expected = (lock / 5) as u32 + 518_400;
+    let ttl = env.as_contract(&client.address, || {
+        env.storage().persistent().get_ttl(&staking_contract::DataKey::Position(user.clone()))
+    });
+    assert_eq!(ttl, expected);
+
+    env.ledger().with_mut(|li| {
+        li.timestamp = lock;
+        li.sequence_number += 1000;
+    });
+    assert_eq!(client.bump_position(&user), 518_400);
+    assert_eq!(client.try_bump_position(&admin), Err(Ok(StakingError::NoPosition)));
+}
+
+#[test]
+fn test_config_and_referral_entries_extended() {
+    let env = Env::default();
+    let (admin, user, token_id, _, client) = setup_test(&env);
+    let referrer = Address::generate(&env);
+    client.initialize(&admin, &token_id, &RATE_SCALE);
+    client.set_cooldown(&3600);
+    mint_tokens(&env, &token_id, &user, 1000);
+    client.stake_with_referrer(&user, &100i128, &0, &referrer);
+
+    env.as_contract(&client.address, || {
+        assert!(env.storage().instance().has(&staking_contract::DataKey::Cooldown));
+        assert!(env.storage().instance().get_ttl() >= 518_400);
+        let referrer_ttl = env.storage().persistent().get_ttl(&staking_contract::DataKey::Referrer(user.clone()));
+        assert!(referrer_ttl >= 518_400);
+        let stats_ttl = env.storage().persistent().get_ttl(&staking_contract::DataKey::ReferralStats(referrer.clone()));
+        assert_eq!(stats_ttl, 518_400);
+    });
+}
+
+#[test]
+fn test_granular_pause_flags() {
+    let env = Env::default();
+    let (admin, user, token_id, token, client) = setup_test(&env);
+    client.initialize(&admin, &token_id, &RATE_SCALE);
+    mint_tokens(&env, &token_id, &user, 1000);
+    mint_tokens(&env, &token_id, &client.address, 10_000);
+    client.stake(&user, &200i128, &0);
+    env.ledger().with_mut(|li| li.timestamp += 10);
+
+    // Deposits halted for a migration; exits and claims stay open
+    client.pause(&admin);
+    assert_eq!(client.get_pause_flags(), PauseFlags { staking: true, unstaking: false, claiming: false });
+    assert_eq!(client.try_stake(&user, &100i128, &0), Err(Ok(StakingError::Paused)));
+    assert_eq!(client.try_compound(&user), Err(Ok(StakingError::Paused)));
+    client.claim_rewards(&user);
+    client.unstake(&user, &50i128);
+
+    let flags = PauseFlags { staking: false, unstaking: true, claiming: true };
+    client.set_pause_flags(&flags);
+    let (_, topics, data) = env.events().all().last().unwrap();
+    assert_eq!(topics, (symbol_short!("pause"),).into_val(&env));
+    assert_eq!(PauseFlags::from_val(&env, &data), flags);
+
+    env.ledger().with_mut(|li| li.timestamp += 10);
+    assert_eq!(client.try_unstake(&user, &50i128), Err(Ok(StakingError::Paused)));
+    assert_eq!(client.try_claim_rewards(&user), Err(Ok(StakingError::Paused)));
+    client.stake(&user, &100i128, &0);
+
+    client.set_pause_flags(&PauseFlags::default());
+    client.unstake(&user, &250i128);
+    assert_eq!(token.balance(&user), 1000 + 10 * 200);
+}
+
+#[test]
+fn test_stake_for_beneficiary() {
+    let env = Env::default();
+    let (admin, user, token_id, token, client) = setup_test(&env);
+    client.initialize(&admin, &token_id, &RATE_SCALE);
+    let sponsor = Address::generate(&env);
+    mint_tokens(&env, &token_id, &sponsor, 1000);
+    mint_tokens(&env, &token_id, &user, 1000);
+
+    client.stake_for(&sponsor, &user, &300i128, &1000);
+    let (_, topics, data) = env.events().all().last().unwrap();
+    assert_eq!(topics, (symbol_short!("sponsor"), sponsor.clone()).into_val(&env));
+    assert_eq!(<(Address, i128)>::from_val(&env, &data), (user.clone(), 300));
+    // Opening the lock needed the beneficiary's consent
+    assert!(env.auths().iter().any(|(signer, _)| *signer == user));
+    assert_eq!(token.balance(&sponsor), 700);
+    assert_eq!(client.get_position(&user).unwrap().amount, 300);
+
+    // Topping up within the lock doesn't; pushing it back does
+    client.stake_for(&sponsor, &user, &100i128, &500);
+    assert!(!env.auths().iter().any(|(signer, _)| *signer == user));
+    client.stake_for(&sponsor, &user, &100i128, &2000);
+    assert!(env.auths().iter().any(|(signer, _)| *signer == user));
+
+    // The beneficiary owns the stake
+    env.ledger().with_mut(|li| li.timestamp = 2000);
+    client.unstake(&user, &500i128);
+    assert_eq!(token.balance(&user), 1500);
+}
+
+#[test]
+fn test_stake_for_lowering_multiplier_needs_beneficiary() {
+    let env = Env::default();
+    let (user, token, client) = setup_tiers(&env);
+    let sponsor = Address::generate(&env);
+    mint_tokens(&env, &token.address, &sponsor, 1000);
+    client.stake(&user, &100i128, &300u64);
+
+    // 250s left only earns 1.5x; a sponsor can't impose that on their own
+    env.ledger().with_mut(|li| li.timestamp = 50);
+    client.stake_for(&sponsor, &user, &1i128, &0);
+    assert!(env.auths().iter().any(|(signer, _)| *signer == user));
+    assert_eq!(client.get_position(&user).unwrap().multiplier_bps, 15_000);
+}
+
+#[test]
+fn test_claim_for_many() {
+    let env = Env::default();
+    let (admin, user, token_id, token, client) = setup_test(&env);
+    client.initialize(&admin, &token_id, &RATE_SCALE);
+    let user2 = Address::generate(&env);
+    let idle = Address::generate(&env);
+    mint_tokens(&env, &token_id, &user, 1000);
+    mint_tokens(&env, &token_id, &user2, 1000);
+    mint_tokens(&env, &token_id, &client.address, 250);
+    client.stake(&user, &100i128, &0);
+    client.stake(&user2, &100i128, &0);
+    env.ledger().with_mut(|li| li.timestamp = 2);
+
+    // 200 accrued each; the budget covers the first in full and part of the second
+    let paid = client.claim_for_many(&vec![&env, idle.clone(), user.clone(), user2.clone()]);
+    assert_eq!(paid, 250);
+    assert_eq!(token.balance(&user), 900 + 200);
+    assert_eq!(token.balance(&user2), 900 + 50);
+
+    // Budget exhausted: later users are skipped rather than failing the batch
+    assert_eq!(client.claim_for_many(&vec![&env, user2.clone()]), 0);
+
+    let mut too_many = vec![&env];
+    for _ in 0..51 {
+        too_many.push_back(idle.clone());
+    }
+    assert_eq!(client.try_claim_for_many(&too_many), Err(Ok(StakingError::BatchTooLarge)));
+}
+
+#[test]
+fn test_vesting_claims_fold_into_one_schedule() {
+    let env = Env::default();
+    let (admin, user, token_id, token, client) = setup_test(&env);
+    client.initialize(&admin, &token_id, &RATE_SCALE);
+    mint_tokens(&env, &token_id, &user, 1000);
+    mint_tokens(&env, &token_id, &client.address, 100_000);
+    client.set_reward_vesting(&Some(RewardVesting { instant_bps: 5000, duration: 100 }));
+    client.stake(&user, &100i128, &0);
+
+    // 1000 earned: 500 now, 500 vesting over [10, 110)
+    env.ledger().with_mut(|li| li.timestamp = 10);
+    client.claim_rewards(&user);
+
+    // 5000 more: 2500 now plus the 250 already unlocked. The locked 250 (50s
+    // left) and the new 2500 (100s) vest together until 60 + 4 + 90.
+    env.ledger().with_mut(|li| li.timestamp = 60);
+    assert_eq!(client.claim_for_many(&vec![&env, user.clone()]), 5000);
+    assert_eq!(token.balance(&user), 900 + 500 + 2500 + 250);
+    let entry = client.get_vesting(&user).unwrap();
+    assert_eq!((entry.total, entry.start_time, entry.end_time), (2750, 60, 154));
+
+    // Others claiming on the user's behalf never lock them out
+    for t in 61..=90u64 {
+        env.ledger().with_mut(|li| li.timestamp = t);
+        client.claim_for_many(&vec![&env, user.clone()]);
+    }
+    env.ledger().with_mut(|li| li.timestamp = 91);
+    client.claim_rewards(&user);
+
+    env.ledger().with_mut(|li| li.timestamp = 10_000);
+    client.claim_vested(&user);
+    assert_eq!(client.get_vesting(&user), None);
+    assert_eq!(token.balance(&user), 900 + 9100);
+}
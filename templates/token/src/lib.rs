@@ -6,6 +6,7 @@
 //! - Token transfers between addresses
 //! - Balance queries
 //! - Total supply tracking
+//! - Irreversible renouncement of admin rights and minting
 //!
 //! Template: token
 //! Category: token
@@ -21,6 +22,8 @@ enum DataKey {
     Admin,
     TotalSupply,
     Balance(Address),
+    AdminRenounced,
+    MintingFinished,
 }
 
 #[contract]
@@ -45,6 +48,34 @@ impl TokenContract {
             .expect("not initialized")
     }
 
+    /// Whether the admin has permanently given up its privileges
+    pub fn is_admin_renounced(env: Env) -> bool {
+        env.storage()
+            .instance()
+            .get(&DataKey::AdminRenounced)
+            .unwrap_or(false)
+    }
+
+    /// Whether minting has been permanently disabled
+    pub fn is_minting_finished(env: Env) -> bool {
+        env.storage()
+            .instance()
+            .get(&DataKey::MintingFinished)
+            .unwrap_or(false)
+    }
+
+    /// Permanently give up all admin privileges (admin only, irreversible)
+    pub fn renounce_admin(env: Env, admin: Address) {
+        Self::require_admin(&env, &admin);
+        env.storage().instance().set(&DataKey::AdminRenounced, &true);
+    }
+
+    /// Permanently disable minting, fixing the supply (admin only, irreversible)
+    pub fn finish_minting(env: Env, admin: Address) {
+        Self::require_admin(&env, &admin);
+        env.storage().instance().set(&DataKey::MintingFinished, &true);
+    }
+
     /// Get the total supply of tokens
     pub fn total_supply(env: Env) -> i128 {
         env.storage()
@@ -64,10 +95,9 @@ impl TokenContract {
     /// Mint tokens to an address (admin only)
     pub fn mint(env: Env, admin: Address, to: Address, amount: i128) {
         // Verify the caller is the admin
-        let contract_admin = Self::admin(env.clone());
-        admin.require_auth();
-        if admin != contract_admin {
-            panic!("not admin");
+        Self::require_admin(&env, &admin);
+        if Self::is_minting_finished(env.clone()) {
+            panic!("minting finished");
         }
 
         // Validate amount
@@ -161,4 +191,15 @@ impl TokenContract {
             .instance()
             .set(&DataKey::TotalSupply, &new_supply);
     }
+
+    fn require_admin(env: &Env, admin: &Address) {
+        let contract_admin = Self::admin(env.clone());
+        admin.require_auth();
+        if *admin != contract_admin {
+            panic!("not admin");
+        }
+        if Self::is_admin_renounced(env.clone()) {
+            panic!("admin renounced");
+        }
+    }
 }
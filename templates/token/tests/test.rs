@@ -5,7 +5,7 @@ use token_contract::{TokenContract, TokenContractClient};
 
 fn setup<'a>(env: &'a Env) -> (TokenContractClient<'a>, Address, Address, Address, Address) {
     env.mock_all_auths();
-    let contract_id = env.register_contract(None, TokenContract);
+    let contract_id = env.register(TokenContract, ());
     let client = TokenContractClient::new(env, &contract_id);
 
     let admin = Address::generate(env);
@@ -198,3 +198,67 @@ fn test_max_supply_overflow_protection() {
     });
     assert!(res.is_err());
 }
+
+#[test]
+fn test_finish_minting_fixes_supply() {
+    let env = Env::default();
+    let (client, admin, alice, _, _) = setup(&env);
+
+    client.mint(&admin, &alice, &1000);
+    assert!(!client.is_minting_finished());
+    client.finish_minting(&admin);
+
+    assert!(client.is_minting_finished());
+    assert_eq!(client.total_supply(), 1000);
+}
+
+#[test]
+#[should_panic(expected = "minting finished")]
+fn test_mint_after_finish_minting_fails() {
+    let env = Env::default();
+    let (client, admin, alice, _, _) = setup(&env);
+    client.finish_minting(&admin);
+    client.mint(&admin, &alice, &1);
+}
+
+#[test]
+#[should_panic(expected = "not admin")]
+fn test_non_admin_cannot_finish_minting() {
+    let env = Env::default();
+    let (client, _admin, alice, _, _) = setup(&env);
+    client.finish_minting(&alice);
+}
+
+#[test]
+#[should_panic(expected = "admin renounced")]
+fn test_mint_after_renounce_admin_fails() {
+    let env = Env::default();
+    let (client, admin, alice, _, _) = setup(&env);
+    client.renounce_admin(&admin);
+    assert!(client.is_admin_renounced());
+    client.mint(&admin, &alice, &1);
+}
+
+#[test]
+#[should_panic(expected = "admin renounced")]
+fn test_renounce_admin_is_irreversible() {
+    let env = Env::default();
+    let (client, admin, _, _, _) = setup(&env);
+    client.renounce_admin(&admin);
+    client.finish_minting(&admin);
+}
+
+#[test]
+fn test_holders_unaffected_by_renounce_admin() {
+    let env = Env::default();
+    let (client, admin, alice, bob, _) = setup(&env);
+
+    client.mint(&admin, &alice, &500);
+    client.renounce_admin(&admin);
+
+    client.transfer(&alice, &bob, &200);
+    client.burn(&bob, &50);
+    assert_eq!(client.balance(&alice), 300);
+    assert_eq!(client.balance(&bob), 150);
+    assert_eq!(client.total_supply(), 450);
+}
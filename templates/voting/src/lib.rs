@@ -7,6 +7,8 @@
 //! - Quorum requirements and threshold logic
 //! - Vote delegation
 //! - Proposal status tracking and execution
+//! - Governance-approved contract upgrades with versioned storage migration
+//! - Treasury spending proposals with a Moloch-style ragequit window
 //!
 //! Template: voting
 //! Category: voting
@@ -15,12 +17,16 @@
 #![no_std]
 
 use soroban_sdk::{
-    contract, contractimpl, contracttype, token, Address, Env, String, Vec,
+    contract, contractimpl, contracttype, token, Address, BytesN, Env, String, Symbol, Vec,
 };
 
 /// Maximum description length for proposals
 const MAX_DESCRIPTION_LENGTH: u32 = 500;
 
+/// Storage layout version written by this build of the contract.
+/// Bump this and add a step to `migrate` whenever the layout changes.
+const STORAGE_VERSION: u32 = 1;
+
 /// Proposal statuses
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -68,6 +74,15 @@ pub struct VoteRecord {
     pub timestamp: u64,
 }
 
+/// Treasury payout authorized by a treasury proposal
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TreasuryTransfer {
+    pub token: Address,
+    pub recipient: Address,
+    pub amount: i128,
+}
+
 /// Governance configuration
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -90,6 +105,11 @@ pub enum StorageKey {
     VoterPower(Address),         // Cached voting power
     Delegation(Address),         // Delegator -> Delegate mapping
     DelegatedPower(Address),     // Total delegated power to an address
+    UpgradeTarget(u64),          // Proposal ID -> WASM hash it authorizes
+    StorageVersion,              // Layout version of persisted state
+    TreasuryTransfer(u64),       // Proposal ID -> payout it authorizes
+    RagequitDeadline(u64),       // Proposal ID -> last ragequit timestamp, set when it passes
+    RagequitShares(u64, Address), // (proposal_id, member) -> shares already burned in ragequit
 }
 
 #[contract]
@@ -133,6 +153,9 @@ impl VotingContract {
 
         env.storage().instance().set(&StorageKey::Config, &config);
         env.storage().instance().set(&StorageKey::ProposalCount, &0u64);
+        env.storage()
+            .instance()
+            .set(&StorageKey::StorageVersion, &STORAGE_VERSION);
     }
 
     /// Create a new proposal
@@ -243,7 +266,7 @@ impl VotingContract {
         // Record vote
         let vote_record = VoteRecord {
             voter: voter.clone(),
-            vote_type: vote_type.clone(),
+            vote_type,
             voting_power,
             timestamp: current_time,
         };
@@ -360,11 +383,9 @@ impl VotingContract {
         let quorum_met = total_votes >= config.quorum_threshold;
 
         // Check if proposal passed
-        let yes_percentage = if total_votes > 0 {
-            (proposal.yes_votes * 100) / total_votes
-        } else {
-            0
-        };
+        let yes_percentage = (proposal.yes_votes * 100)
+            .checked_div(total_votes)
+            .unwrap_or(0);
 
         let threshold_met = yes_percentage >= config.pass_threshold_percent as u128;
 
@@ -372,6 +393,19 @@ impl VotingContract {
         if quorum_met && threshold_met {
             proposal.status = ProposalStatus::Passed;
             proposal.executed = true;
+
+            // Dissenters get a full voting period from finalization, however
+            // long after the vote that happens
+            if env
+                .storage()
+                .instance()
+                .has(&StorageKey::TreasuryTransfer(proposal_id))
+            {
+                env.storage().instance().set(
+                    &StorageKey::RagequitDeadline(proposal_id),
+                    &(current_time + config.voting_period),
+                );
+            }
         } else {
             proposal.status = ProposalStatus::Rejected;
         }
@@ -504,4 +538,339 @@ impl VotingContract {
             .instance()
             .set(&StorageKey::Proposal(proposal_id), &proposal);
     }
+
+    /// Create a proposal that, once passed, authorizes upgrading this
+    /// contract to `new_wasm_hash`
+    ///
+    /// # Arguments
+    /// * `proposer` - Address creating the proposal
+    /// * `description` - Proposal description
+    /// * `new_wasm_hash` - Hash of the already-uploaded WASM to upgrade to
+    ///
+    /// # Returns
+    /// * `u64` - The proposal ID
+    pub fn create_upgrade_proposal(
+        env: Env,
+        proposer: Address,
+        description: String,
+        new_wasm_hash: BytesN<32>,
+    ) -> u64 {
+        let proposal_id = Self::create_proposal(env.clone(), proposer, description);
+        env.storage()
+            .instance()
+            .set(&StorageKey::UpgradeTarget(proposal_id), &new_wasm_hash);
+        proposal_id
+    }
+
+    /// Upgrade the contract code to the WASM approved by a passed upgrade proposal
+    ///
+    /// The proposal is marked `Executed` so the same approval cannot be
+    /// replayed. Call `migrate` afterwards to bring storage up to date.
+    ///
+    /// # Arguments
+    /// * `caller` - Address calling the function
+    /// * `proposal_id` - ID of the passed upgrade proposal
+    /// * `new_wasm_hash` - WASM hash; must match the one voted on
+    pub fn upgrade(env: Env, caller: Address, proposal_id: u64, new_wasm_hash: BytesN<32>) {
+        caller.require_auth();
+
+        let mut proposal: Proposal = env
+            .storage()
+            .instance()
+            .get(&StorageKey::Proposal(proposal_id))
+            .expect("Proposal does not exist");
+
+        let approved_hash: BytesN<32> = env
+            .storage()
+            .instance()
+            .get(&StorageKey::UpgradeTarget(proposal_id))
+            .expect("Not an upgrade proposal");
+
+        assert!(
+            proposal.status == ProposalStatus::Passed,
+            "Proposal has not passed"
+        );
+        assert!(approved_hash == new_wasm_hash, "WASM hash does not match proposal");
+
+        proposal.status = ProposalStatus::Executed;
+        env.storage()
+            .instance()
+            .set(&StorageKey::Proposal(proposal_id), &proposal);
+
+        env.deployer().update_current_contract_wasm(new_wasm_hash);
+    }
+
+    /// Migrate persisted state to the layout expected by the current code
+    ///
+    /// Deployments created before storage versioning are treated as version 0.
+    /// Only the admin can migrate, and only when the stored version is behind.
+    pub fn migrate(env: Env) {
+        let config: GovernanceConfig = env
+            .storage()
+            .instance()
+            .get(&StorageKey::Config)
+            .expect("Contract not initialized");
+        config.admin.require_auth();
+
+        let stored_version = Self::get_storage_version(env.clone());
+        assert!(stored_version < STORAGE_VERSION, "Storage already up to date");
+
+        // Version 0 -> 1: no layout changes, only starts tracking the version.
+        // Add a step here for every future STORAGE_VERSION bump.
+
+        env.storage()
+            .instance()
+            .set(&StorageKey::StorageVersion, &STORAGE_VERSION);
+    }
+
+    /// Get the storage layout version of the persisted state
+    ///
+    /// # Returns
+    /// * `u32` - Stored layout version (0 for pre-versioning deployments)
+    pub fn get_storage_version(env: Env) -> u32 {
+        env.storage()
+            .instance()
+            .get(&StorageKey::StorageVersion)
+            .unwrap_or(0)
+    }
+
+    /// Get the WASM hash authorized by an upgrade proposal
+    ///
+    /// # Arguments
+    /// * `proposal_id` - ID of the proposal
+    ///
+    /// # Returns
+    /// * `Option<BytesN<32>>` - The approved hash, or None for ordinary proposals
+    pub fn get_upgrade_target(env: Env, proposal_id: u64) -> Option<BytesN<32>> {
+        env.storage()
+            .instance()
+            .get(&StorageKey::UpgradeTarget(proposal_id))
+    }
+
+    /// Create a proposal that, once passed, pays `amount` of `token` from
+    /// the contract's treasury to `recipient`
+    ///
+    /// After the proposal passes, members who voted No or did not vote get
+    /// a grace period of one voting period, counted from when
+    /// `execute_proposal` finalizes it, to `ragequit` before the payout can
+    /// be executed.
+    ///
+    /// # Arguments
+    /// * `proposer` - Address creating the proposal
+    /// * `description` - Proposal description
+    /// * `token` - Treasury token to pay out
+    /// * `recipient` - Address receiving the payout
+    /// * `amount` - Amount to pay out
+    ///
+    /// # Returns
+    /// * `u64` - The proposal ID
+    pub fn create_treasury_proposal(
+        env: Env,
+        proposer: Address,
+        description: String,
+        token: Address,
+        recipient: Address,
+        amount: i128,
+    ) -> u64 {
+        assert!(amount > 0, "Amount must be > 0");
+
+        let proposal_id = Self::create_proposal(env.clone(), proposer, description);
+        let transfer = TreasuryTransfer {
+            token,
+            recipient,
+            amount,
+        };
+        env.storage()
+            .instance()
+            .set(&StorageKey::TreasuryTransfer(proposal_id), &transfer);
+        proposal_id
+    }
+
+    /// Pay out a passed treasury proposal once its ragequit window has closed
+    ///
+    /// # Arguments
+    /// * `caller` - Address calling the function
+    /// * `proposal_id` - ID of the passed treasury proposal
+    pub fn execute_treasury_proposal(env: Env, caller: Address, proposal_id: u64) {
+        caller.require_auth();
+
+        let mut proposal: Proposal = env
+            .storage()
+            .instance()
+            .get(&StorageKey::Proposal(proposal_id))
+            .expect("Proposal does not exist");
+
+        let transfer: TreasuryTransfer = env
+            .storage()
+            .instance()
+            .get(&StorageKey::TreasuryTransfer(proposal_id))
+            .expect("Not a treasury proposal");
+
+        assert!(
+            proposal.status == ProposalStatus::Passed,
+            "Proposal has not passed"
+        );
+        assert!(
+            env.ledger().timestamp() > Self::ragequit_deadline(&env, &proposal),
+            "Ragequit window still open"
+        );
+
+        proposal.status = ProposalStatus::Executed;
+        env.storage()
+            .instance()
+            .set(&StorageKey::Proposal(proposal_id), &proposal);
+
+        token::Client::new(&env, &transfer.token).transfer(
+            &env.current_contract_address(),
+            &transfer.recipient,
+            &transfer.amount,
+        );
+    }
+
+    /// Exit with a pro-rata share of the treasury before a passed treasury
+    /// proposal is executed
+    ///
+    /// Only members who voted No on the proposal can ragequit, up to the
+    /// voting power they cast, and only during the grace period after the
+    /// proposal is finalized. Non-voters can't: shares are transferable, so
+    /// their power may have come from a supporter. The member's `shares` of
+    /// the voting token are burned, and
+    /// for each listed treasury token they receive
+    /// `balance * shares / total_supply`. Each token may be listed once.
+    /// The voting token must expose `total_supply` and `burn`, as the token
+    /// template does.
+    ///
+    /// # Arguments
+    /// * `member` - Address exiting
+    /// * `proposal_id` - ID of the passed treasury proposal being dissented from
+    /// * `shares` - Amount of voting token to burn
+    /// * `tokens` - Treasury tokens to withdraw a share of
+    pub fn ragequit(
+        env: Env,
+        member: Address,
+        proposal_id: u64,
+        shares: i128,
+        tokens: Vec<Address>,
+    ) {
+        member.require_auth();
+
+        assert!(shares > 0, "Shares must be > 0");
+
+        let proposal: Proposal = env
+            .storage()
+            .instance()
+            .get(&StorageKey::Proposal(proposal_id))
+            .expect("Proposal does not exist");
+
+        assert!(
+            env.storage()
+                .instance()
+                .has(&StorageKey::TreasuryTransfer(proposal_id)),
+            "Not a treasury proposal"
+        );
+        assert!(
+            proposal.status == ProposalStatus::Passed,
+            "Proposal has not passed"
+        );
+        assert!(
+            env.ledger().timestamp() <= Self::ragequit_deadline(&env, &proposal),
+            "Ragequit window closed"
+        );
+
+        // Shares move freely, so only power recorded as a No vote is known
+        // to have dissented; a supporter's shares moved to a fresh address
+        // must not get out
+        let record = env
+            .storage()
+            .instance()
+            .get::<StorageKey, VoteRecord>(&StorageKey::Vote(proposal_id, member.clone()))
+            .filter(|record| record.vote_type == VoteType::No)
+            .expect("Only dissenting members can ragequit");
+
+        // Delegated power may have been cast in favour on the member's behalf
+        assert!(
+            !env.storage()
+                .instance()
+                .has(&StorageKey::Delegation(member.clone())),
+            "Delegated members cannot ragequit"
+        );
+
+        let config: GovernanceConfig = env
+            .storage()
+            .instance()
+            .get(&StorageKey::Config)
+            .expect("Contract not initialized");
+
+        let voting_token = token::Client::new(&env, &config.voting_token);
+        assert!(voting_token.balance(&member) >= shares, "Insufficient shares");
+        let exited_key = StorageKey::RagequitShares(proposal_id, member.clone());
+        let exited: i128 = env.storage().instance().get(&exited_key).unwrap_or(0);
+        assert!(
+            (exited + shares) as u128 <= record.voting_power,
+            "Shares exceed dissenting vote"
+        );
+        env.storage().instance().set(&exited_key, &(exited + shares));
+
+        // Payouts are priced against the supply before this member's burn
+        let total_supply: i128 = env.invoke_contract(
+            &config.voting_token,
+            &Symbol::new(&env, "total_supply"),
+            Vec::new(&env),
+        );
+        voting_token.burn(&member, &shares);
+
+        // A repeated token would pay out a share of what's left again
+        for (index, token_address) in tokens.iter().enumerate() {
+            assert!(
+                tokens.first_index_of(&token_address) == Some(index as u32),
+                "Duplicate treasury token"
+            );
+        }
+
+        let treasury = env.current_contract_address();
+        for token_address in tokens.iter() {
+            let treasury_token = token::Client::new(&env, &token_address);
+            let payout = treasury_token
+                .balance(&treasury)
+                .checked_mul(shares)
+                .expect("Payout overflow")
+                / total_supply;
+            if payout > 0 {
+                treasury_token.transfer(&treasury, &member, &payout);
+            }
+        }
+    }
+
+    /// Get the payout authorized by a treasury proposal
+    ///
+    /// # Arguments
+    /// * `proposal_id` - ID of the proposal
+    ///
+    /// # Returns
+    /// * `Option<TreasuryTransfer>` - The payout, or None for other proposals
+    pub fn get_treasury_transfer(env: Env, proposal_id: u64) -> Option<TreasuryTransfer> {
+        env.storage()
+            .instance()
+            .get(&StorageKey::TreasuryTransfer(proposal_id))
+    }
+
+    /// Last timestamp at which dissenters can ragequit a passed proposal
+    ///
+    /// Proposals that passed before the deadline was recorded fall back to
+    /// one voting period after voting ended.
+    fn ragequit_deadline(env: &Env, proposal: &Proposal) -> u64 {
+        if let Some(deadline) = env
+            .storage()
+            .instance()
+            .get(&StorageKey::RagequitDeadline(proposal.id))
+        {
+            return deadline;
+        }
+        let config: GovernanceConfig = env
+            .storage()
+            .instance()
+            .get(&StorageKey::Config)
+            .expect("Contract not initialized");
+        proposal.end_time + config.voting_period
+    }
 }
@@ -14,10 +14,12 @@
 #![cfg(test)]
 
 use soroban_sdk::{
-    testutils::{Address as _, Ledger, LedgerInfo}, token, Address, Env, String,
+    testutils::{Address as _, Ledger, LedgerInfo}, token, vec, Address, BytesN, Env, String,
 };
 
-use voting_contract::{ProposalStatus, VoteType, VotingContract, VotingContractClient};
+use voting_contract::{
+    ProposalStatus, StorageKey, TreasuryTransfer, VoteType, VotingContract, VotingContractClient,
+};
 
 /// Helper function to create and initialize a mock token contract
 fn create_token_contract<'a>(env: &Env, admin: &Address) -> (Address, token::StellarAssetClient<'a>) {
@@ -39,7 +41,7 @@ fn test_initialize_contract() {
     let admin = Address::generate(&env);
     let (token_address, _token) = create_token_contract(&env, &admin);
     
-    let contract_id = env.register_contract(None, VotingContract);
+    let contract_id = env.register(VotingContract, ());
     let client = VotingContractClient::new(&env, &contract_id);
 
     // Initialize the contract
@@ -65,7 +67,7 @@ fn test_initialize_invalid_threshold() {
     let admin = Address::generate(&env);
     let (token_address, _token) = create_token_contract(&env, &admin);
     
-    let contract_id = env.register_contract(None, VotingContract);
+    let contract_id = env.register(VotingContract, ());
     let client = VotingContractClient::new(&env, &contract_id);
 
     // Try to initialize with invalid threshold (> 100)
@@ -87,7 +89,7 @@ fn test_create_proposal() {
     let proposer = Address::generate(&env);
     let (token_address, _token) = create_token_contract(&env, &admin);
     
-    let contract_id = env.register_contract(None, VotingContract);
+    let contract_id = env.register(VotingContract, ());
     let client = VotingContractClient::new(&env, &contract_id);
 
     // Initialize
@@ -108,7 +110,7 @@ fn test_create_proposal() {
     assert_eq!(proposal.no_votes, 0);
     assert_eq!(proposal.abstain_votes, 0);
     assert_eq!(proposal.status, ProposalStatus::Active);
-    assert_eq!(proposal.executed, false);
+    assert!(!proposal.executed);
 }
 
 #[test]
@@ -124,7 +126,7 @@ fn test_vote_on_proposal() {
     // Mint tokens to voter (voting power)
     mint_tokens(&token, &admin, &voter, 100);
 
-    let contract_id = env.register_contract(None, VotingContract);
+    let contract_id = env.register(VotingContract, ());
     let client = VotingContractClient::new(&env, &contract_id);
 
     // Initialize
@@ -157,7 +159,7 @@ fn test_cannot_vote_twice() {
     
     mint_tokens(&token, &admin, &voter, 100);
 
-    let contract_id = env.register_contract(None, VotingContract);
+    let contract_id = env.register(VotingContract, ());
     let client = VotingContractClient::new(&env, &contract_id);
 
     client.initialize(&admin, &token_address, &50, &51, &86400);
@@ -185,7 +187,7 @@ fn test_vote_delegation() {
     // Mint tokens to delegator
     mint_tokens(&token, &admin, &delegator, 200);
 
-    let contract_id = env.register_contract(None, VotingContract);
+    let contract_id = env.register(VotingContract, ());
     let client = VotingContractClient::new(&env, &contract_id);
 
     client.initialize(&admin, &token_address, &50, &51, &86400);
@@ -214,7 +216,7 @@ fn test_execute_passed_proposal() {
     mint_tokens(&token, &admin, &voter1, 600);
     mint_tokens(&token, &admin, &voter2, 400);
 
-    let contract_id = env.register_contract(None, VotingContract);
+    let contract_id = env.register(VotingContract, ());
     let client = VotingContractClient::new(&env, &contract_id);
 
     // Initialize with 500 quorum and 51% threshold
@@ -246,7 +248,7 @@ fn test_execute_passed_proposal() {
     // Verify proposal passed (quorum met: 1000 >= 500, yes%: 60% >= 51%)
     let proposal = client.get_proposal(&proposal_id);
     assert_eq!(proposal.status, ProposalStatus::Passed);
-    assert_eq!(proposal.executed, true);
+    assert!(proposal.executed);
 }
 
 #[test]
@@ -262,7 +264,7 @@ fn test_execute_rejected_proposal_no_quorum() {
     // Mint only small amount - won't meet quorum
     mint_tokens(&token, &admin, &voter, 100);
 
-    let contract_id = env.register_contract(None, VotingContract);
+    let contract_id = env.register(VotingContract, ());
     let client = VotingContractClient::new(&env, &contract_id);
 
     // Initialize with high quorum requirement
@@ -307,7 +309,7 @@ fn test_execute_rejected_proposal_threshold_not_met() {
     mint_tokens(&token, &admin, &voter1, 400);
     mint_tokens(&token, &admin, &voter2, 600);
 
-    let contract_id = env.register_contract(None, VotingContract);
+    let contract_id = env.register(VotingContract, ());
     let client = VotingContractClient::new(&env, &contract_id);
 
     // Initialize with 51% threshold
@@ -348,7 +350,7 @@ fn test_cancel_proposal() {
     let proposer = Address::generate(&env);
     let (token_address, _token) = create_token_contract(&env, &admin);
     
-    let contract_id = env.register_contract(None, VotingContract);
+    let contract_id = env.register(VotingContract, ());
     let client = VotingContractClient::new(&env, &contract_id);
 
     client.initialize(&admin, &token_address, &1000, &51, &86400);
@@ -376,7 +378,7 @@ fn test_cannot_cancel_others_proposal() {
     let other = Address::generate(&env);
     let (token_address, _token) = create_token_contract(&env, &admin);
     
-    let contract_id = env.register_contract(None, VotingContract);
+    let contract_id = env.register(VotingContract, ());
     let client = VotingContractClient::new(&env, &contract_id);
 
     client.initialize(&admin, &token_address, &1000, &51, &86400);
@@ -400,7 +402,7 @@ fn test_abstain_vote() {
     
     mint_tokens(&token, &admin, &voter, 100);
 
-    let contract_id = env.register_contract(None, VotingContract);
+    let contract_id = env.register(VotingContract, ());
     let client = VotingContractClient::new(&env, &contract_id);
 
     client.initialize(&admin, &token_address, &50, &51, &86400);
@@ -427,7 +429,7 @@ fn test_proposal_count_increments() {
     let proposer = Address::generate(&env);
     let (token_address, _token) = create_token_contract(&env, &admin);
     
-    let contract_id = env.register_contract(None, VotingContract);
+    let contract_id = env.register(VotingContract, ());
     let client = VotingContractClient::new(&env, &contract_id);
 
     client.initialize(&admin, &token_address, &1000, &51, &86400);
@@ -459,7 +461,7 @@ fn test_vote_without_tokens() {
     
     // Don't mint any tokens to voter
 
-    let contract_id = env.register_contract(None, VotingContract);
+    let contract_id = env.register(VotingContract, ());
     let client = VotingContractClient::new(&env, &contract_id);
 
     client.initialize(&admin, &token_address, &50, &51, &86400);
@@ -486,7 +488,7 @@ fn test_vote_delegated_power() {
     mint_tokens(&token, &admin, &delegator, 300);
     mint_tokens(&token, &admin, &delegate, 200);
 
-    let contract_id = env.register_contract(None, VotingContract);
+    let contract_id = env.register(VotingContract, ());
     let client = VotingContractClient::new(&env, &contract_id);
 
     client.initialize(&admin, &token_address, &50, &51, &86400);
@@ -519,7 +521,7 @@ fn test_change_delegation() {
 
     mint_tokens(&token, &admin, &delegator, 150);
 
-    let contract_id = env.register_contract(None, VotingContract);
+    let contract_id = env.register(VotingContract, ());
     let client = VotingContractClient::new(&env, &contract_id);
 
     client.initialize(&admin, &token_address, &50, &51, &86400);
@@ -550,7 +552,7 @@ fn test_cannot_vote_on_expired_proposal() {
 
     mint_tokens(&token, &admin, &voter, 100);
 
-    let contract_id = env.register_contract(None, VotingContract);
+    let contract_id = env.register(VotingContract, ());
     let client = VotingContractClient::new(&env, &contract_id);
 
     // 100 seconds voting period
@@ -585,7 +587,7 @@ fn test_cannot_execute_active_proposal() {
     let proposer = Address::generate(&env);
     let (token_address, _token) = create_token_contract(&env, &admin);
 
-    let contract_id = env.register_contract(None, VotingContract);
+    let contract_id = env.register(VotingContract, ());
     let client = VotingContractClient::new(&env, &contract_id);
 
     client.initialize(&admin, &token_address, &50, &51, &86400);
@@ -612,7 +614,7 @@ fn test_execute_tied_vote() {
     mint_tokens(&token, &admin, &voter1, 500);
     mint_tokens(&token, &admin, &voter2, 500);
 
-    let contract_id = env.register_contract(None, VotingContract);
+    let contract_id = env.register(VotingContract, ());
     let client = VotingContractClient::new(&env, &contract_id);
 
     // 1000 quorum, requires >50% (51%) to pass
@@ -653,7 +655,7 @@ fn test_create_proposal_description_too_long() {
     let proposer = Address::generate(&env);
     let (token_address, _token) = create_token_contract(&env, &admin);
     
-    let contract_id = env.register_contract(None, VotingContract);
+    let contract_id = env.register(VotingContract, ());
     let client = VotingContractClient::new(&env, &contract_id);
 
     client.initialize(&admin, &token_address, &50, &51, &86400);
@@ -665,3 +667,503 @@ fn test_create_proposal_description_too_long() {
     // Should panic due to length restriction
     client.create_proposal(&proposer, &description);
 }
+
+/// Helper to move the ledger past a proposal's voting period
+fn advance_past_voting_period(env: &Env, seconds: u64) {
+    env.ledger().set(LedgerInfo {
+        timestamp: env.ledger().timestamp() + seconds,
+        protocol_version: 22,
+        sequence_number: env.ledger().sequence(),
+        network_id: Default::default(),
+        base_reserve: 10,
+        min_temp_entry_ttl: 10,
+        min_persistent_entry_ttl: 10,
+        max_entry_ttl: 3110400,
+    });
+}
+
+#[test]
+fn test_create_upgrade_proposal() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let proposer = Address::generate(&env);
+    let (token_address, _token) = create_token_contract(&env, &admin);
+
+    let contract_id = env.register(VotingContract, ());
+    let client = VotingContractClient::new(&env, &contract_id);
+
+    client.initialize(&admin, &token_address, &50, &51, &100);
+
+    let wasm_hash = BytesN::from_array(&env, &[7u8; 32]);
+    let description = String::from_str(&env, "Upgrade to v2");
+    let proposal_id = client.create_upgrade_proposal(&proposer, &description, &wasm_hash);
+
+    assert_eq!(client.get_upgrade_target(&proposal_id), Some(wasm_hash));
+    assert_eq!(client.get_proposal(&proposal_id).status, ProposalStatus::Active);
+
+    // Ordinary proposals carry no upgrade target
+    let plain_id = client.create_proposal(&proposer, &String::from_str(&env, "Plain"));
+    assert_eq!(client.get_upgrade_target(&plain_id), None);
+}
+
+#[test]
+#[should_panic(expected = "Proposal has not passed")]
+fn test_upgrade_requires_passed_proposal() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let proposer = Address::generate(&env);
+    let (token_address, _token) = create_token_contract(&env, &admin);
+
+    let contract_id = env.register(VotingContract, ());
+    let client = VotingContractClient::new(&env, &contract_id);
+
+    client.initialize(&admin, &token_address, &50, &51, &100);
+
+    let wasm_hash = BytesN::from_array(&env, &[7u8; 32]);
+    let description = String::from_str(&env, "Upgrade to v2");
+    let proposal_id = client.create_upgrade_proposal(&proposer, &description, &wasm_hash);
+
+    // Still active - upgrade must not go through
+    client.upgrade(&admin, &proposal_id, &wasm_hash);
+}
+
+#[test]
+#[should_panic(expected = "Proposal has not passed")]
+fn test_upgrade_rejected_proposal_fails() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let proposer = Address::generate(&env);
+    let voter = Address::generate(&env);
+    let (token_address, token) = create_token_contract(&env, &admin);
+    mint_tokens(&token, &admin, &voter, 1000);
+
+    let contract_id = env.register(VotingContract, ());
+    let client = VotingContractClient::new(&env, &contract_id);
+
+    client.initialize(&admin, &token_address, &500, &51, &100);
+
+    let wasm_hash = BytesN::from_array(&env, &[7u8; 32]);
+    let description = String::from_str(&env, "Upgrade to v2");
+    let proposal_id = client.create_upgrade_proposal(&proposer, &description, &wasm_hash);
+
+    client.vote(&voter, &proposal_id, &VoteType::No);
+    advance_past_voting_period(&env, 101);
+    client.execute_proposal(&admin, &proposal_id);
+    assert_eq!(client.get_proposal(&proposal_id).status, ProposalStatus::Rejected);
+
+    client.upgrade(&admin, &proposal_id, &wasm_hash);
+}
+
+#[test]
+#[should_panic(expected = "WASM hash does not match proposal")]
+fn test_upgrade_hash_mismatch_fails() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let proposer = Address::generate(&env);
+    let voter = Address::generate(&env);
+    let (token_address, token) = create_token_contract(&env, &admin);
+    mint_tokens(&token, &admin, &voter, 1000);
+
+    let contract_id = env.register(VotingContract, ());
+    let client = VotingContractClient::new(&env, &contract_id);
+
+    client.initialize(&admin, &token_address, &500, &51, &100);
+
+    let wasm_hash = BytesN::from_array(&env, &[7u8; 32]);
+    let description = String::from_str(&env, "Upgrade to v2");
+    let proposal_id = client.create_upgrade_proposal(&proposer, &description, &wasm_hash);
+
+    client.vote(&voter, &proposal_id, &VoteType::Yes);
+    advance_past_voting_period(&env, 101);
+    client.execute_proposal(&admin, &proposal_id);
+
+    // Passed, but the caller tries to swap in different code
+    let other_hash = BytesN::from_array(&env, &[9u8; 32]);
+    client.upgrade(&admin, &proposal_id, &other_hash);
+}
+
+#[test]
+#[should_panic(expected = "Not an upgrade proposal")]
+fn test_upgrade_with_plain_proposal_fails() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let proposer = Address::generate(&env);
+    let voter = Address::generate(&env);
+    let (token_address, token) = create_token_contract(&env, &admin);
+    mint_tokens(&token, &admin, &voter, 1000);
+
+    let contract_id = env.register(VotingContract, ());
+    let client = VotingContractClient::new(&env, &contract_id);
+
+    client.initialize(&admin, &token_address, &500, &51, &100);
+
+    let proposal_id = client.create_proposal(&proposer, &String::from_str(&env, "Plain"));
+    client.vote(&voter, &proposal_id, &VoteType::Yes);
+    advance_past_voting_period(&env, 101);
+    client.execute_proposal(&admin, &proposal_id);
+
+    client.upgrade(&admin, &proposal_id, &BytesN::from_array(&env, &[7u8; 32]));
+}
+
+#[test]
+fn test_initialize_sets_storage_version() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let (token_address, _token) = create_token_contract(&env, &admin);
+
+    let contract_id = env.register(VotingContract, ());
+    let client = VotingContractClient::new(&env, &contract_id);
+
+    client.initialize(&admin, &token_address, &50, &51, &100);
+
+    assert_eq!(client.get_storage_version(), 1);
+}
+
+#[test]
+fn test_migrate_legacy_storage() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let (token_address, _token) = create_token_contract(&env, &admin);
+
+    let contract_id = env.register(VotingContract, ());
+    let client = VotingContractClient::new(&env, &contract_id);
+
+    client.initialize(&admin, &token_address, &50, &51, &100);
+
+    // Simulate a deployment created before storage versioning existed
+    env.as_contract(&contract_id, || {
+        env.storage().instance().remove(&StorageKey::StorageVersion);
+    });
+    assert_eq!(client.get_storage_version(), 0);
+
+    client.migrate();
+    assert_eq!(client.get_storage_version(), 1);
+}
+
+#[test]
+#[should_panic(expected = "Storage already up to date")]
+fn test_migrate_current_storage_fails() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let (token_address, _token) = create_token_contract(&env, &admin);
+
+    let contract_id = env.register(VotingContract, ());
+    let client = VotingContractClient::new(&env, &contract_id);
+
+    client.initialize(&admin, &token_address, &50, &51, &100);
+    client.migrate();
+}
+
+// --- Ragequit ---
+
+/// Minimal share token with a queryable total supply, standing in for the
+/// token template as the voting token.
+mod share_token {
+    use soroban_sdk::{contract, contractimpl, contracttype, Address, Env};
+
+    #[contracttype]
+    enum DataKey {
+        TotalSupply,
+        Balance(Address),
+    }
+
+    #[contract]
+    pub struct ShareToken;
+
+    #[contractimpl]
+    impl ShareToken {
+        pub fn mint(env: Env, to: Address, amount: i128) {
+            let balance = Self::balance(env.clone(), to.clone());
+            env.storage().instance().set(&DataKey::Balance(to), &(balance + amount));
+            let supply = Self::total_supply(env.clone());
+            env.storage().instance().set(&DataKey::TotalSupply, &(supply + amount));
+        }
+
+        pub fn burn(env: Env, from: Address, amount: i128) {
+            from.require_auth();
+            let balance = Self::balance(env.clone(), from.clone());
+            assert!(balance >= amount, "insufficient balance");
+            env.storage().instance().set(&DataKey::Balance(from), &(balance - amount));
+            let supply = Self::total_supply(env.clone());
+            env.storage().instance().set(&DataKey::TotalSupply, &(supply - amount));
+        }
+
+        pub fn transfer(env: Env, from: Address, to: Address, amount: i128) {
+            from.require_auth();
+            let balance = Self::balance(env.clone(), from.clone());
+            assert!(balance >= amount, "insufficient balance");
+            env.storage().instance().set(&DataKey::Balance(from), &(balance - amount));
+            let to_balance = Self::balance(env.clone(), to.clone());
+            env.storage().instance().set(&DataKey::Balance(to), &(to_balance + amount));
+        }
+
+        pub fn balance(env: Env, id: Address) -> i128 {
+            env.storage().instance().get(&DataKey::Balance(id)).unwrap_or(0)
+        }
+
+        pub fn total_supply(env: Env) -> i128 {
+            env.storage().instance().get(&DataKey::TotalSupply).unwrap_or(0)
+        }
+    }
+}
+
+/// Governance over share token with a funded treasury. Returns the client,
+/// share token, treasury token, and members holding 600 / 300 / 100 shares.
+/// A treasury proposal paying 500 to `recipient` has passed with the first
+/// member's Yes and the second member's No; the third did not vote.
+/// Voting ends at 100 and the proposal is finalized at 101, so the ragequit
+/// window runs through 201.
+#[allow(clippy::type_complexity)]
+fn setup_passed_treasury_proposal<'a>(
+    env: &'a Env,
+) -> (
+    VotingContractClient<'a>,
+    share_token::ShareTokenClient<'a>,
+    token::Client<'a>,
+    u64,
+    Address,
+    Address,
+    Address,
+) {
+    setup_treasury_proposal_finalized_at(env, 101)
+}
+
+/// As `setup_passed_treasury_proposal`, finalizing `finalize_after` seconds
+/// after the proposal was created
+#[allow(clippy::type_complexity)]
+fn setup_treasury_proposal_finalized_at<'a>(
+    env: &'a Env,
+    finalize_after: u64,
+) -> (
+    VotingContractClient<'a>,
+    share_token::ShareTokenClient<'a>,
+    token::Client<'a>,
+    u64,
+    Address,
+    Address,
+    Address,
+) {
+    env.mock_all_auths();
+
+    let admin = Address::generate(env);
+    let yes_voter = Address::generate(env);
+    let no_voter = Address::generate(env);
+    let absent = Address::generate(env);
+    let recipient = Address::generate(env);
+
+    let shares = share_token::ShareTokenClient::new(env, &env.register(share_token::ShareToken, ()));
+    shares.mint(&yes_voter, &600);
+    shares.mint(&no_voter, &300);
+    shares.mint(&absent, &100);
+
+    let contract_id = env.register(VotingContract, ());
+    let client = VotingContractClient::new(env, &contract_id);
+    client.initialize(&admin, &shares.address, &500, &51, &100);
+
+    let (treasury_address, treasury_sac) = create_token_contract(env, &admin);
+    treasury_sac.mint(&contract_id, &10_000);
+
+    let proposal_id = client.create_treasury_proposal(
+        &yes_voter,
+        &String::from_str(env, "Fund grant"),
+        &treasury_address,
+        &recipient,
+        &500,
+    );
+    client.vote(&yes_voter, &proposal_id, &VoteType::Yes);
+    client.vote(&no_voter, &proposal_id, &VoteType::No);
+    advance_past_voting_period(env, finalize_after);
+    client.execute_proposal(&admin, &proposal_id);
+
+    (
+        client,
+        shares,
+        token::Client::new(env, &treasury_address),
+        proposal_id,
+        yes_voter,
+        no_voter,
+        absent,
+    )
+}
+
+#[test]
+fn test_create_treasury_proposal() {
+    let env = Env::default();
+    let (client, _, treasury, proposal_id, _, _, _) = setup_passed_treasury_proposal(&env);
+
+    let transfer: TreasuryTransfer = client.get_treasury_transfer(&proposal_id).unwrap();
+    assert_eq!(transfer.token, treasury.address);
+    assert_eq!(transfer.amount, 500);
+    assert_eq!(client.get_proposal(&proposal_id).status, ProposalStatus::Passed);
+}
+
+#[test]
+fn test_ragequit_no_voter_receives_pro_rata_share() {
+    let env = Env::default();
+    let (client, shares, treasury, proposal_id, _, no_voter, _) =
+        setup_passed_treasury_proposal(&env);
+
+    client.ragequit(&no_voter, &proposal_id, &300, &vec![&env, treasury.address.clone()]);
+
+    // 10_000 * 300 / 1_000
+    assert_eq!(treasury.balance(&no_voter), 3_000);
+    assert_eq!(treasury.balance(&client.address), 7_000);
+    assert_eq!(shares.balance(&no_voter), 0);
+    assert_eq!(shares.total_supply(), 700);
+}
+
+#[test]
+fn test_ragequit_no_voter_can_exit_partially() {
+    let env = Env::default();
+    let (client, shares, treasury, proposal_id, _, no_voter, _) =
+        setup_passed_treasury_proposal(&env);
+
+    client.ragequit(&no_voter, &proposal_id, &50, &vec![&env, treasury.address.clone()]);
+
+    // 10_000 * 50 / 1_000
+    assert_eq!(treasury.balance(&no_voter), 500);
+    assert_eq!(shares.balance(&no_voter), 250);
+}
+
+#[test]
+#[should_panic(expected = "Only dissenting members can ragequit")]
+fn test_ragequit_non_voter_fails() {
+    let env = Env::default();
+    let (client, _, treasury, proposal_id, _, _, absent) =
+        setup_passed_treasury_proposal(&env);
+
+    client.ragequit(&absent, &proposal_id, &50, &vec![&env, treasury.address.clone()]);
+}
+
+#[test]
+#[should_panic(expected = "Only dissenting members can ragequit")]
+fn test_ragequit_with_supporter_shares_moved_to_fresh_address_fails() {
+    let env = Env::default();
+    let (client, shares, treasury, proposal_id, yes_voter, _, _) =
+        setup_passed_treasury_proposal(&env);
+
+    let fresh = Address::generate(&env);
+    shares.transfer(&yes_voter, &fresh, &600);
+    client.ragequit(&fresh, &proposal_id, &600, &vec![&env, treasury.address.clone()]);
+}
+
+#[test]
+#[should_panic(expected = "Shares exceed dissenting vote")]
+fn test_ragequit_beyond_no_vote_fails() {
+    let env = Env::default();
+    let (client, shares, treasury, proposal_id, yes_voter, no_voter, _) =
+        setup_passed_treasury_proposal(&env);
+
+    // Shares received after voting No don't add to the dissent
+    shares.transfer(&yes_voter, &no_voter, &100);
+    client.ragequit(&no_voter, &proposal_id, &200, &vec![&env, treasury.address.clone()]);
+    client.ragequit(&no_voter, &proposal_id, &101, &vec![&env, treasury.address.clone()]);
+}
+
+#[test]
+#[should_panic(expected = "Only dissenting members can ragequit")]
+fn test_ragequit_yes_voter_fails() {
+    let env = Env::default();
+    let (client, _, treasury, proposal_id, yes_voter, _, _) =
+        setup_passed_treasury_proposal(&env);
+
+    client.ragequit(&yes_voter, &proposal_id, &100, &vec![&env, treasury.address.clone()]);
+}
+
+#[test]
+#[should_panic(expected = "Ragequit window closed")]
+fn test_ragequit_after_window_fails() {
+    let env = Env::default();
+    let (client, _, treasury, proposal_id, _, no_voter, _) =
+        setup_passed_treasury_proposal(&env);
+
+    advance_past_voting_period(&env, 101);
+    client.ragequit(&no_voter, &proposal_id, &300, &vec![&env, treasury.address.clone()]);
+}
+
+#[test]
+fn test_ragequit_window_runs_from_finalization() {
+    let env = Env::default();
+    // Finalized long after voting ended at 100
+    let (client, _, treasury, proposal_id, yes_voter, no_voter, _) =
+        setup_treasury_proposal_finalized_at(&env, 1_000);
+
+    // The payout can't run in the same ledger the proposal passed
+    let result = client.try_execute_treasury_proposal(&yes_voter, &proposal_id);
+    assert!(result.is_err());
+
+    advance_past_voting_period(&env, 100);
+    client.ragequit(&no_voter, &proposal_id, &300, &vec![&env, treasury.address.clone()]);
+    assert_eq!(treasury.balance(&no_voter), 3_000);
+}
+
+#[test]
+#[should_panic(expected = "Duplicate treasury token")]
+fn test_ragequit_repeated_token_fails() {
+    let env = Env::default();
+    let (client, _, treasury, proposal_id, _, no_voter, _) =
+        setup_passed_treasury_proposal(&env);
+
+    // Listing the treasury token twice would pay 30% of the remainder again
+    client.ragequit(
+        &no_voter,
+        &proposal_id,
+        &100,
+        &vec![&env, treasury.address.clone(), treasury.address.clone()],
+    );
+}
+
+#[test]
+#[should_panic(expected = "Insufficient shares")]
+fn test_ragequit_more_than_held_fails() {
+    let env = Env::default();
+    let (client, _, treasury, proposal_id, _, no_voter, _) =
+        setup_passed_treasury_proposal(&env);
+
+    client.ragequit(&no_voter, &proposal_id, &301, &vec![&env, treasury.address.clone()]);
+}
+
+#[test]
+#[should_panic(expected = "Ragequit window still open")]
+fn test_execute_treasury_proposal_during_window_fails() {
+    let env = Env::default();
+    let (client, _, _, proposal_id, yes_voter, _, _) = setup_passed_treasury_proposal(&env);
+
+    client.execute_treasury_proposal(&yes_voter, &proposal_id);
+}
+
+#[test]
+fn test_execute_treasury_proposal_after_ragequit() {
+    let env = Env::default();
+    let (client, _, treasury, proposal_id, yes_voter, no_voter, _) =
+        setup_passed_treasury_proposal(&env);
+
+    client.ragequit(&no_voter, &proposal_id, &300, &vec![&env, treasury.address.clone()]);
+    advance_past_voting_period(&env, 101);
+    client.execute_treasury_proposal(&yes_voter, &proposal_id);
+
+    let recipient = client.get_treasury_transfer(&proposal_id).unwrap().recipient;
+    assert_eq!(treasury.balance(&recipient), 500);
+    assert_eq!(treasury.balance(&client.address), 6_500);
+    assert_eq!(client.get_proposal(&proposal_id).status, ProposalStatus::Executed);
+
+    // Nothing left to dissent from once the payout has gone out
+    let result = client.try_ragequit(&no_voter, &proposal_id, &1, &vec![&env, treasury.address.clone()]);
+    assert!(result.is_err());
+}